@@ -133,12 +133,17 @@ fn copy_dlls(config: &Config) {
             ]
         };
 
+        // Các DLL cần nằm cạnh file .exe, được ứng dụng kiểm tra khi khởi động
+        let mut copied_dlls = Vec::new();
+
         if dll_path.exists() {
             for dll_name in &dlls {
                 let src_path = dll_path.join(dll_name);
                 let dest_path = target_dir.join(dll_name);
 
                 if src_path.exists() {
+                    copied_dlls.push(dll_name.clone());
+
                     // Tạo thư mục đích nếu chưa tồn tại
                     if let Some(parent) = dest_path.parent() {
                         if !parent.exists() {
@@ -186,11 +191,28 @@ fn copy_dlls(config: &Config) {
                 dll_path.display()
             );
         }
+
+        delay_load_dlls(&copied_dlls);
     } else {
         println!("cargo:warning=No FFmpeg configuration found in config.toml");
     }
 }
 
+// Nạp trễ các DLL để ứng dụng vẫn khởi động được khi thiếu DLL và báo lỗi cho người dùng
+fn delay_load_dlls(dlls: &[String]) {
+    println!("cargo:rustc-env=VIDKIT_FFMPEG_DLLS={}", dlls.join(";"));
+
+    // /DELAYLOAD chỉ có trên linker MSVC
+    if dlls.is_empty() || env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default() != "msvc" {
+        return;
+    }
+
+    for dll_name in dlls {
+        println!("cargo:rustc-link-arg-bins=/DELAYLOAD:{}", dll_name);
+    }
+    println!("cargo:rustc-link-arg-bins=delayimp.lib");
+}
+
 fn main() {
     // Đọc cấu hình
     let config = read_config();
@@ -0,0 +1,335 @@
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use tauri_plugin_opener::reveal_item_in_dir;
+use threadpool::ThreadPool;
+
+use crate::services::video_processor::VideoProcessor;
+use crate::utils::error::{AppError, ErrorCode, ErrorInfo};
+use crate::utils::error_handler::handle_error_with_event;
+use crate::utils::event_emitter::emit_warning;
+use crate::utils::media::ensure_supported_media;
+use crate::utils::thumbnail_cache::{self, cached_thumbnail};
+
+/// Video resolution in pixels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// File list entry, matching the frontend `FileInfo` (without the store-assigned id)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileInfo {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    #[serde(rename = "type")]
+    pub file_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<Resolution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+}
+
+/// File to add with `add_files_to_list`, matching the arguments of `add_file_to_list`
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileListInput {
+    pub path: String,
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,              // 0 reads it from disk
+    #[serde(rename = "type", default)]
+    pub file_type: String,
+}
+
+/// File list entries built by `add_files_to_list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddFilesResult {
+    pub files: Vec<FileInfo>,   // in the order the files were given
+    pub warnings: Vec<String>,  // files skipped or added without video information
+}
+
+/// Most files probed at the same time by `add_files_to_list`
+const MAX_PROBE_THREADS: usize = 8;
+
+/// File list entry for a supported file, with the size read from disk if 0 was given
+fn new_entry(input: FileListInput) -> FileInfo {
+    let size = if input.size == 0 {
+        fs::metadata(&input.path).map(|metadata| metadata.len()).unwrap_or(0)
+    } else {
+        input.size
+    };
+
+    FileInfo {
+        name: input.name,
+        path: input.path,
+        size,
+        file_type: input.file_type,
+        duration: None,
+        resolution: None,
+        thumbnail: None,
+    }
+}
+
+/// Fill in duration, resolution and thumbnail from a probe of the file
+///
+/// Returns the probe error if the file could not be read; the optional fields stay
+/// empty then.
+fn fill_from_probe(file_info: &mut FileInfo, processor: &VideoProcessor, app_handle: &AppHandle) -> Option<AppError> {
+    match processor.get_video_info_cancelable(&file_info.path) {
+        Ok(info) => {
+            file_info.duration = Some(info.duration);
+            file_info.resolution = Some(Resolution {
+                width: info.width,
+                height: info.height,
+            });
+
+            // A missing thumbnail is not worth a notification
+            match cached_thumbnail(app_handle, processor, &file_info.path, info.duration) {
+                Ok(thumbnail) => file_info.thumbnail = Some(thumbnail),
+                Err(e) => warn!("Failed to create a thumbnail of {}: {}", file_info.path, e),
+            }
+            None
+        }
+        Err(e) => {
+            warn!("Failed to probe {}: {}", file_info.path, e);
+            Some(e)
+        }
+    }
+}
+
+/// Build a file list entry with duration, resolution and thumbnail filled in from a probe
+///
+/// Files that are obviously not videos (checked by extension and signature) are
+/// rejected with `InvalidVideoFormat`. If a video cannot be probed (e.g. it is corrupt)
+/// the entry is still returned with the optional fields left empty, and a warning
+/// notification is emitted. The thumbnail is the path of a cached image, reused
+/// while the file is unchanged.
+///
+/// # Parameters
+/// * `path` - The file path to the video
+/// * `name` - Display name of the file
+/// * `size` - File size in bytes (0 reads it from disk)
+/// * `file_type` - MIME type of the file
+///
+/// # Returns
+/// * `Result<FileInfo, ErrorInfo>` - The file entry to add to the list or an error
+#[tauri::command]
+pub async fn add_file_to_list(
+    path: String,
+    name: String,
+    size: u64,
+    file_type: String,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<FileInfo, ErrorInfo> {
+    let processor = processor.inner().clone();
+    let input = FileListInput {
+        path,
+        name,
+        size,
+        file_type,
+    };
+
+    // Probing and the thumbnail can take long, e.g. on a network share
+    let handle = app_handle.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        ensure_supported_media(&input.path)?;
+        let mut file_info = new_entry(input);
+        let error = fill_from_probe(&mut file_info, &processor, &handle);
+        Ok::<_, AppError>((file_info, error))
+    })
+    .await
+    .map_err(|e| {
+        AppError::other_error(
+            format!("Probe thread failed: {}", e),
+            ErrorCode::VideoInfoError,
+            None,
+        )
+    })
+    .and_then(|result| result);
+    let (file_info, error) = handle_error_with_event(result, &app_handle)?;
+
+    if let Some(error) = error {
+        emit_warning(
+            &app_handle,
+            &format!("Could not read video information for {}", file_info.name),
+            Some(error.to_string()),
+        );
+    }
+
+    Ok(file_info)
+}
+
+/// Probe the files on a bounded thread pool, keeping their order
+fn probe_files(inputs: Vec<FileListInput>, processor: &VideoProcessor, app_handle: &AppHandle) -> AddFilesResult {
+    let threads = num_cpus::get().clamp(1, MAX_PROBE_THREADS).min(inputs.len().max(1));
+    let pool = ThreadPool::new(threads);
+    let (sender, receiver) = mpsc::channel();
+
+    for (index, input) in inputs.into_iter().enumerate() {
+        let sender = sender.clone();
+        let processor = processor.clone();
+        let app_handle = app_handle.clone();
+        pool.execute(move || {
+            let entry = match ensure_supported_media(&input.path) {
+                Ok(()) => {
+                    let mut file_info = new_entry(input);
+                    match fill_from_probe(&mut file_info, &processor, &app_handle) {
+                        None => (Some(file_info), None),
+                        // A file too slow to open is left out rather than added half-read
+                        Some(e) if matches!(e.code(), ErrorCode::ProbeTimedOut | ErrorCode::ProbeCanceled) => {
+                            (None, Some(format!("Skipped {}: {}", file_info.name, e)))
+                        }
+                        Some(e) => {
+                            let warning = format!("{}: {}", file_info.name, e);
+                            (Some(file_info), Some(warning))
+                        }
+                    }
+                }
+                Err(e) => (None, Some(format!("Skipped {}: {}", input.name, e))),
+            };
+            let _ = sender.send((index, entry));
+        });
+    }
+    drop(sender);
+
+    let mut entries: Vec<(usize, (Option<FileInfo>, Option<String>))> = receiver.iter().collect();
+    entries.sort_by_key(|(index, _)| *index);
+
+    let mut result = AddFilesResult {
+        files: Vec::with_capacity(entries.len()),
+        warnings: Vec::new(),
+    };
+    for (_, (file_info, warning)) in entries {
+        result.files.extend(file_info);
+        result.warnings.extend(warning);
+    }
+    result
+}
+
+/// Build file list entries for many files at once, e.g. a dropped folder
+///
+/// The files are probed in parallel on up to `MAX_PROBE_THREADS` threads and
+/// returned together, so the list is updated once. Files that are not videos, or
+/// whose probe times out, are skipped, and videos that cannot be probed are added
+/// without video information; all are listed in `warnings` and reported in a single
+/// warning notification. `cancel_probes` ends the probes still running.
+///
+/// # Parameters
+/// * `files` - The files to add, each with path, name, size and type
+///
+/// # Returns
+/// * `Result<AddFilesResult, ErrorInfo>` - The entries in input order and the warnings
+#[tauri::command]
+pub async fn add_files_to_list(
+    files: Vec<FileListInput>,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<AddFilesResult, ErrorInfo> {
+    let processor = processor.inner().clone();
+    let total = files.len();
+
+    let handle = app_handle.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || probe_files(files, &processor, &handle))
+        .await
+        .map_err(|e| {
+            AppError::other_error(
+                format!("Probe thread failed: {}", e),
+                ErrorCode::VideoInfoError,
+                None,
+            )
+        });
+    let result = handle_error_with_event(result, &app_handle)?;
+
+    if !result.warnings.is_empty() {
+        emit_warning(
+            &app_handle,
+            &format!(
+                "{} of {} files were skipped or added without video information",
+                result.warnings.len(),
+                total
+            ),
+            Some(result.warnings.join("\n")),
+        );
+    }
+
+    Ok(result)
+}
+
+/// Set how long probing a file may take before it is abandoned
+///
+/// # Parameters
+/// * `timeout_secs` - Seconds per probe, 0 for no limit; kept across restarts
+#[tauri::command]
+pub fn set_probe_timeout(
+    timeout_secs: u64,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<(), ErrorInfo> {
+    handle_error_with_event(
+        crate::utils::app_info::set_probe_timeout(&app_handle, &processor, timeout_secs),
+        &app_handle,
+    )
+}
+
+/// Get the probe timeout in seconds, 0 if there is no limit
+#[tauri::command]
+pub fn get_probe_timeout(processor: State<'_, VideoProcessor>) -> Result<u64, ErrorInfo> {
+    Ok(processor.probe_timeout())
+}
+
+/// Stop the probes in progress, e.g. to abandon a large import
+///
+/// Files whose probe is stopped are skipped by `add_files_to_list`.
+#[tauri::command]
+pub fn cancel_probes(processor: State<'_, VideoProcessor>) -> Result<(), ErrorInfo> {
+    processor.cancel_probes();
+    Ok(())
+}
+
+/// Show a file in the OS file manager
+///
+/// Windows and macOS open the containing folder with the file selected; elsewhere
+/// the file manager selects it if it supports that, otherwise the folder is opened.
+///
+/// # Parameters
+/// * `path` - The file to show, e.g. the output of a completed task
+///
+/// # Returns
+/// * `Result<(), ErrorInfo>` - An error if the file does not exist or cannot be shown
+#[tauri::command]
+pub fn reveal_in_explorer(path: String, app_handle: AppHandle) -> Result<(), ErrorInfo> {
+    let result = if Path::new(&path).exists() {
+        reveal_item_in_dir(&path).map_err(|e| {
+            AppError::other_error(
+                format!("Failed to show {} in the file manager: {}", path, e),
+                ErrorCode::FileReadError,
+                None,
+            )
+        })
+    } else {
+        Err(AppError::other_error(
+            format!("File not found: {}", path),
+            ErrorCode::FileNotFound,
+            Some("The file was moved or deleted".to_string()),
+        ))
+    };
+
+    handle_error_with_event(result, &app_handle)
+}
+
+/// Delete all cached file list thumbnails; they are created again when files are added
+///
+/// # Returns
+/// * `Result<usize, ErrorInfo>` - The number of deleted thumbnails, or an error
+#[tauri::command]
+pub fn clear_thumbnail_cache(app_handle: AppHandle) -> Result<usize, ErrorInfo> {
+    handle_error_with_event(thumbnail_cache::clear_thumbnail_cache(&app_handle), &app_handle)
+}
@@ -10,21 +10,28 @@
 //! - **Task Management**: Commands for managing processing tasks
 //! - **State Management**: Commands for accessing and updating application state
 //! - **File Management**: Commands for managing the file list
+//! - **Watched Folders**: Commands for importing new files from a folder automatically
 //!
 //! Each command is annotated with `#[tauri::command]` and can be invoked from
 //! the frontend using Tauri's invoke mechanism.
 
+mod file_commands;
 mod task_commands;
+mod watch_commands;
 
-// Re-export task commands
+// Re-export file and task commands
+pub use file_commands::*;
 pub use task_commands::*;
+pub use watch_commands::*;
 
 
-use tauri::{AppHandle, Emitter};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, State};
 
 // Preset management has been moved to frontend
-use crate::services::video_processor::{VideoInfo, VideoProcessor};
-use crate::utils::error::{ErrorCode, ErrorInfo};
+use crate::services::video_processor::{FfmpegCapabilities, ProcessingPlan, QualityScore, VideoInfo, VideoProcessor};
+use crate::state::task_manager::TaskEvent;
+use crate::utils::error::{AppError, ErrorCode, ErrorInfo};
 use crate::handle_command_with_event;
 
 /// Basic greeting command for testing the Tauri command system
@@ -47,8 +54,7 @@ pub fn greet(name: &str) -> String {
 /// Retrieves detailed information about a video file
 ///
 /// This command extracts metadata from a video file including format, duration,
-/// dimensions, bitrate, codec, and framerate. It creates a temporary VideoProcessor
-/// instance since this is a read-only operation.
+/// dimensions, bitrate, codec, and framerate using the shared VideoProcessor.
 ///
 /// # Parameters
 /// * `path` - The file path to the video to analyze
@@ -56,13 +62,299 @@ pub fn greet(name: &str) -> String {
 /// # Returns
 /// * `Result<VideoInfo, ErrorInfo>` - Video metadata or an error
 #[tauri::command]
-pub fn get_video_info(path: String, app_handle: AppHandle) -> Result<VideoInfo, ErrorInfo> {
-    // Create a new processor for this operation
-    // This is a read-only operation so we don't need to use the shared state
-    let processor = VideoProcessor::new();
+pub fn get_video_info(
+    path: String,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<VideoInfo, ErrorInfo> {
     handle_command_with_event!(processor.get_video_info(&path), &app_handle)
 }
 
+/// Generates a thumbnail image for a video file
+///
+/// This command decodes the frame at the given timestamp, scales it to the requested
+/// width while preserving the aspect ratio, and saves it as a JPEG or PNG depending
+/// on the output file extension. Timestamps past the end use the last frame.
+///
+/// # Parameters
+/// * `input_path` - The file path to the video
+/// * `timestamp` - Position of the frame in seconds
+/// * `output_path` - Where to write the image (.jpg, .jpeg or .png)
+/// * `width` - Thumbnail width in pixels (0 keeps the source width)
+///
+/// # Returns
+/// * `Result<String, ErrorInfo>` - The path of the written image or an error
+#[tauri::command]
+pub fn generate_thumbnail(
+    input_path: String,
+    timestamp: f64,
+    output_path: String,
+    width: u32,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<String, ErrorInfo> {
+    handle_command_with_event!(
+        processor
+            .extract_thumbnail(&input_path, timestamp, &output_path, width)
+            .map(|_| output_path),
+        &app_handle
+    )
+}
+
+/// Checks whether a video codec can be stored in an output container
+///
+/// Lets the UI grey out combinations such as VP9 in AVI before a task is queued.
+///
+/// # Parameters
+/// * `format` - Output container extension, e.g. "mp4"
+/// * `codec` - Encoder name ("libx264", "h264_nvenc") or codec name ("h264")
+///
+/// # Returns
+/// * `Result<Option<String>, ErrorInfo>` - `None` if the combination is valid, otherwise
+///   the reason it is not; an error if the codec is unknown
+#[tauri::command]
+pub fn check_output_compatibility(
+    format: String,
+    codec: String,
+    app_handle: AppHandle,
+) -> Result<Option<String>, ErrorInfo> {
+    let codec_id = VideoProcessor::codec_id_from_name(&codec).ok_or_else(|| {
+        AppError::validation_error(
+            format!("Unknown codec: {}", codec),
+            ErrorCode::CodecNotSupported,
+            None,
+        )
+    });
+
+    handle_command_with_event!(
+        codec_id.map(|id| VideoProcessor::validate_container_codec(&format, id).err().map(|e| e.to_string())),
+        &app_handle
+    )
+}
+
+/// Converts a video directly, without going through the task queue
+///
+/// This command runs `VideoProcessor::convert_video` on a blocking thread and waits
+/// for it to finish. Progress is reported through `task-progress` events keyed by a
+/// generated id (also sent in a `conversion-started` event) so the UI can show a bar.
+///
+/// # Parameters
+/// * `input_path` - The file path to the source video
+/// * `output_path` - Where to write the converted video
+/// * `config` - Processing options as a key/value map (same keys as task configs)
+/// * `dry_run` - Only resolve the codec, filter graph and stream mapping
+///
+/// # Returns
+/// * `Result<Option<ProcessingPlan>, ErrorInfo>` - The plan for a dry run, `None` after
+///   a conversion, or an error
+#[tauri::command]
+pub async fn convert_video(
+    input_path: String,
+    output_path: String,
+    config: HashMap<String, String>,
+    dry_run: Option<bool>,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<Option<ProcessingPlan>, ErrorInfo> {
+    let processor = processor.inner().clone();
+    if dry_run == Some(true) {
+        let plan = processor.options_from_map(&config).and_then(|mut options| {
+            options.output_path = output_path.clone();
+            processor.plan_processing(&input_path, &output_path, &options)
+        });
+        return handle_command_with_event!(plan.map(Some), &app_handle);
+    }
+
+    let conversion_id = uuid::Uuid::new_v4().to_string();
+    TaskEvent::ConversionStarted { task_id: conversion_id.clone() }.emit(&app_handle);
+
+    let handle = app_handle.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let mut options = processor.options_from_map(&config)?;
+        options.output_path = output_path.clone();
+
+        let progress_handle = handle.clone();
+        processor.convert_video(&input_path, &output_path, options, move |progress| {
+            TaskEvent::Progress {
+                task_id: conversion_id.clone(),
+                progress,
+                eta_secs: None,
+                phase: None,
+            }.emit(&progress_handle);
+            true
+        })
+        .map(|_| None)
+    })
+    .await
+    .map_err(|e| {
+        AppError::other_error(
+            format!("Conversion thread failed: {}", e),
+            ErrorCode::VideoProcessingFailed,
+            None,
+        )
+    })
+    .and_then(|result| result);
+
+    handle_command_with_event!(result, &app_handle)
+}
+
+/// Extracts the audio track of a video into a standalone audio file
+///
+/// This command runs `VideoProcessor::extract_audio` on a blocking thread. Progress is
+/// reported through `task-progress` events keyed by the id sent in `conversion-started`,
+/// like `convert_video`.
+///
+/// # Parameters
+/// * `input_path` - The file path to the source video
+/// * `output_path` - Where to write the audio file
+/// * `format` - Output format: mp3, aac, wav or flac
+///
+/// # Returns
+/// * `Result<(), ErrorInfo>` - Success or an error (also when the input has no audio)
+#[tauri::command]
+pub async fn extract_audio(
+    input_path: String,
+    output_path: String,
+    format: String,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<(), ErrorInfo> {
+    let processor = processor.inner().clone();
+    let extraction_id = uuid::Uuid::new_v4().to_string();
+    TaskEvent::ConversionStarted { task_id: extraction_id.clone() }.emit(&app_handle);
+
+    let handle = app_handle.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        processor.extract_audio(&input_path, &output_path, &format, move |progress| {
+            TaskEvent::Progress {
+                task_id: extraction_id.clone(),
+                progress,
+                eta_secs: None,
+                phase: None,
+            }.emit(&handle);
+            true
+        })
+    })
+    .await
+    .map_err(|e| {
+        AppError::other_error(
+            format!("Audio extraction thread failed: {}", e),
+            ErrorCode::VideoProcessingFailed,
+            None,
+        )
+    })
+    .and_then(|result| result);
+
+    handle_command_with_event!(result, &app_handle)
+}
+
+/// Export frames of a video as numbered PNG or JPEG images
+///
+/// This command runs `VideoProcessor::export_frames` on a blocking thread. Progress is
+/// reported through `task-progress` events keyed by the id sent in `conversion-started`,
+/// like `extract_audio`.
+///
+/// # Parameters
+/// * `input_path` - The file path to the source video
+/// * `output_dir` - Directory for the images, created if it does not exist
+/// * `fps` - Frames per second to export; must not exceed the source frame rate
+/// * `every_nth` - Export every Nth frame instead of sampling at `fps`
+/// * `format` - Image format: png, jpg or jpeg
+///
+/// # Returns
+/// * `Result<usize, ErrorInfo>` - The number of images written or an error
+#[tauri::command]
+pub async fn export_frames(
+    input_path: String,
+    output_dir: String,
+    fps: Option<f64>,
+    every_nth: Option<u32>,
+    format: String,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<usize, ErrorInfo> {
+    let processor = processor.inner().clone();
+    let export_id = uuid::Uuid::new_v4().to_string();
+    TaskEvent::ConversionStarted { task_id: export_id.clone() }.emit(&app_handle);
+
+    let handle = app_handle.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        processor.export_frames(&input_path, &output_dir, fps, every_nth, &format, move |progress| {
+            TaskEvent::Progress {
+                task_id: export_id.clone(),
+                progress,
+                eta_secs: None,
+                phase: None,
+            }.emit(&handle);
+            true
+        })
+    })
+    .await
+    .map_err(|e| {
+        AppError::other_error(
+            format!("Frame export thread failed: {}", e),
+            ErrorCode::VideoProcessingFailed,
+            None,
+        )
+    })
+    .and_then(|result| result);
+
+    handle_command_with_event!(result, &app_handle)
+}
+
+/// Encodes a numbered image sequence into a video
+///
+/// This command runs `VideoProcessor::frames_to_video` on a blocking thread. Progress is
+/// reported through `task-progress` events keyed by the id sent in `conversion-started`,
+/// like `convert_video`.
+///
+/// # Parameters
+/// * `pattern` - Path of the frames with a number placeholder, e.g. `frame_%06d.png`
+/// * `output_path` - Where to write the video
+/// * `fps` - Frame rate of the video; every image becomes one frame
+/// * `config` - Processing options as a key/value map (same keys as task configs)
+///
+/// # Returns
+/// * `Result<(), ErrorInfo>` - Success or an error (also when frames are missing or differ in size)
+#[tauri::command]
+pub async fn frames_to_video(
+    pattern: String,
+    output_path: String,
+    fps: f64,
+    config: HashMap<String, String>,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<(), ErrorInfo> {
+    let processor = processor.inner().clone();
+    let conversion_id = uuid::Uuid::new_v4().to_string();
+    TaskEvent::ConversionStarted { task_id: conversion_id.clone() }.emit(&app_handle);
+
+    let handle = app_handle.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let options = processor.options_from_map(&config)?;
+        processor.frames_to_video(&pattern, &output_path, fps, options, move |progress| {
+            TaskEvent::Progress {
+                task_id: conversion_id.clone(),
+                progress,
+                eta_secs: None,
+                phase: None,
+            }.emit(&handle);
+            true
+        })
+    })
+    .await
+    .map_err(|e| {
+        AppError::other_error(
+            format!("Image sequence encoding thread failed: {}", e),
+            ErrorCode::VideoProcessingFailed,
+            None,
+        )
+    })
+    .and_then(|result| result);
+
+    handle_command_with_event!(result, &app_handle)
+}
+
 // Legacy commands are removed as they are replaced by the new task system
 
 // Preset management commands have been moved to frontend
@@ -78,8 +370,8 @@ pub fn get_video_info(path: String, app_handle: AppHandle) -> Result<VideoInfo,
 /// # Returns
 /// * `Result<AppInfo, ErrorInfo>` - Application information or an error
 #[tauri::command]
-pub fn get_app_info(_app_handle: AppHandle) -> Result<crate::utils::app_info::AppInfo, ErrorInfo> {
-    match crate::utils::app_info::get_app_info() {
+pub fn get_app_info(app_handle: AppHandle) -> Result<crate::utils::app_info::AppInfo, ErrorInfo> {
+    match crate::utils::app_info::get_app_info(&app_handle) {
         Ok(app_info) => Ok(app_info),
         Err(e) => Err(ErrorInfo {
             code: ErrorCode::UnknownError,
@@ -89,6 +381,68 @@ pub fn get_app_info(_app_handle: AppHandle) -> Result<crate::utils::app_info::Ap
     }
 }
 
+/// Score a converted video against its source with PSNR and SSIM
+///
+/// Runs `VideoProcessor::compare_quality` on a blocking thread; both files are decoded
+/// completely, so this takes about as long as decoding them. Helps to pick a CRF value
+/// by comparing the scores of a few attempts.
+///
+/// # Parameters
+/// * `input_path` - The source video
+/// * `output_path` - The converted video
+///
+/// # Returns
+/// * `Result<QualityScore, ErrorInfo>` - PSNR, SSIM (and VMAF in builds with it) averaged over the frames
+#[tauri::command]
+pub async fn compare_quality(
+    input_path: String,
+    output_path: String,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<QualityScore, ErrorInfo> {
+    let processor = processor.inner().clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        processor.compare_quality(&input_path, &output_path, |_| true)
+    })
+    .await
+    .map_err(|e| {
+        AppError::other_error(
+            format!("Quality comparison thread failed: {}", e),
+            ErrorCode::VideoProcessingFailed,
+            None,
+        )
+    })
+    .and_then(|result| result);
+
+    handle_command_with_event!(result, &app_handle)
+}
+
+/// Get the FFmpeg build, the codecs it supports and which hardware encoders open
+///
+/// Meant for a diagnostics panel whose contents users can paste into bug reports.
+/// Runs on a blocking thread since opening the hardware encoders initializes the drivers.
+///
+/// # Returns
+/// * `Result<FfmpegCapabilities, ErrorInfo>` - Version, build configuration, codecs and hardware encoders
+#[tauri::command]
+pub async fn get_ffmpeg_capabilities(
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<FfmpegCapabilities, ErrorInfo> {
+    let processor = processor.inner().clone();
+    let result = tauri::async_runtime::spawn_blocking(move || processor.ffmpeg_capabilities())
+        .await
+        .map_err(|e| {
+            AppError::other_error(
+                format!("Capability check thread failed: {}", e),
+                ErrorCode::FFmpegInitError,
+                None,
+            )
+        });
+
+    handle_command_with_event!(result, &app_handle)
+}
+
 /// Set selected GPU
 ///
 /// This command sets the selected GPU index and updates the preferences.
@@ -111,6 +465,63 @@ pub fn set_gpu(gpu_index: i32, app_handle: AppHandle) -> Result<(), ErrorInfo> {
     }
 }
 
+/// Get the hardware encoder to use for an output format on a GPU
+///
+/// # Parameters
+/// * `format` - Output container, e.g. "mp4"
+/// * `gpu_index` - The index of the GPU (-1 for CPU)
+///
+/// # Returns
+/// * `Result<Option<String>, ErrorInfo>` - Encoder name such as "h264_nvenc", or null to encode on the CPU
+#[tauri::command]
+pub fn get_recommended_codec(format: String, gpu_index: i32) -> Result<Option<String>, ErrorInfo> {
+    Ok(crate::utils::app_info::recommended_codec(&format, gpu_index))
+}
+
+/// Set the codec used for each output container when a task names none
+///
+/// # Parameters
+/// * `codecs` - Container to codec, e.g. {"mp4": "hevc", "webm": "av1"}; kept across restarts
+#[tauri::command]
+pub fn set_default_codecs(
+    codecs: HashMap<String, String>,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<(), ErrorInfo> {
+    handle_command_with_event!(
+        crate::utils::app_info::set_default_codecs(&app_handle, &processor, codecs),
+        &app_handle
+    )
+}
+
+/// Get the codec chosen for each output container
+#[tauri::command]
+pub fn get_default_codecs(app_handle: AppHandle) -> Result<HashMap<String, String>, ErrorInfo> {
+    Ok(crate::utils::app_info::default_codecs(&app_handle))
+}
+
+/// Limit how far outputs may be upscaled; larger resolutions are scaled down with a `task-warning`
+///
+/// # Parameters
+/// * `factor` - Times the source size an output may be, 1.0 for no upscaling; kept across restarts
+#[tauri::command]
+pub fn set_max_upscale_factor(
+    factor: f64,
+    processor: State<'_, VideoProcessor>,
+    app_handle: AppHandle,
+) -> Result<(), ErrorInfo> {
+    handle_command_with_event!(
+        crate::utils::app_info::set_max_upscale_factor(&app_handle, &processor, factor),
+        &app_handle
+    )
+}
+
+/// Get how many times larger than the source an output may be
+#[tauri::command]
+pub fn get_max_upscale_factor(processor: State<'_, VideoProcessor>) -> Result<f64, ErrorInfo> {
+    Ok(processor.max_upscale_factor())
+}
+
 
 
 /// Emit preferences-changed event
@@ -218,3 +629,112 @@ pub fn open_log_directory(app_handle: AppHandle) -> Result<bool, ErrorInfo> {
 }
 
 // Legacy processor state and related functions are removed as they are replaced by the new task system
+
+/// Change the log level without restarting the app
+///
+/// The level is saved and applied again on the next start.
+///
+/// # Parameters
+/// * `level` - One of `off`, `error`, `warn`, `info`, `debug` or `trace`
+/// * `app_handle` - Tauri AppHandle for accessing application resources
+///
+/// # Returns
+/// * `Result<String, ErrorInfo>` - The level now in effect, or an error for an unknown level
+#[tauri::command]
+pub fn set_log_level(level: String, app_handle: AppHandle) -> Result<String, ErrorInfo> {
+    match crate::utils::logger::set_log_level(&app_handle, &level) {
+        Ok(level_filter) => Ok(level_filter.to_string().to_lowercase()),
+        Err(e) => Err(ErrorInfo {
+            code: ErrorCode::InvalidArgument,
+            message: e,
+            details: Some("Use off, error, warn, info, debug or trace".to_string()),
+        }),
+    }
+}
+
+/// Get the log level currently in effect
+///
+/// # Returns
+/// * `Result<String, ErrorInfo>` - The level in lowercase, e.g. "info"
+#[tauri::command]
+pub fn get_log_level() -> Result<String, ErrorInfo> {
+    Ok(crate::utils::logger::get_log_level().to_string().to_lowercase())
+}
+
+/// Change how many rotated log files are kept and how much space all logs may use
+///
+/// The oldest rotated files beyond the limits are deleted right away and on every start.
+///
+/// # Parameters
+/// * `max_log_files` - Rotated log files to keep besides the current one
+/// * `max_total_log_size` - Combined size of all log files in bytes
+/// * `app_handle` - Tauri AppHandle for accessing application resources
+///
+/// # Returns
+/// * `Result<usize, ErrorInfo>` - The number of deleted log files, or an error
+#[tauri::command]
+pub fn set_log_retention(
+    max_log_files: usize,
+    max_total_log_size: u64,
+    app_handle: AppHandle,
+) -> Result<usize, ErrorInfo> {
+    let retention = crate::utils::logger::LogRetention {
+        max_log_files,
+        max_total_log_size,
+    };
+    crate::utils::logger::set_log_retention(&app_handle, retention).map_err(|e| ErrorInfo {
+        code: ErrorCode::FileWriteError,
+        message: e,
+        details: Some("Error applying the log retention limits".to_string()),
+    })
+}
+
+/// Get the log retention limits in effect
+///
+/// # Returns
+/// * `Result<LogRetention, ErrorInfo>` - The number of rotated files kept and the size limit in bytes
+#[tauri::command]
+pub fn get_log_retention(app_handle: AppHandle) -> Result<crate::utils::logger::LogRetention, ErrorInfo> {
+    Ok(crate::utils::logger::get_log_retention(&app_handle))
+}
+
+/// Turn the desktop notification shown when the task queue finishes on or off
+///
+/// # Parameters
+/// * `enabled` - Whether to show the notification; kept across restarts
+#[tauri::command]
+pub fn set_notify_on_complete(enabled: bool, app_handle: AppHandle) -> Result<(), ErrorInfo> {
+    handle_command_with_event!(
+        crate::utils::notifications::set_notify_on_complete(&app_handle, enabled),
+        &app_handle
+    )
+}
+
+/// Whether a desktop notification is shown when the task queue finishes
+#[tauri::command]
+pub fn get_notify_on_complete(app_handle: AppHandle) -> Result<bool, ErrorInfo> {
+    Ok(crate::utils::notifications::notify_on_complete(&app_handle))
+}
+
+/// Turn the automatic queue pause on battery power on or off
+///
+/// While enabled the queue is paused when the machine switches to battery and
+/// resumed when AC power is connected again. Applies right away, e.g. enabling it
+/// on battery pauses the queue now.
+///
+/// # Parameters
+/// * `enabled` - Whether to pause on battery; kept across restarts
+#[tauri::command]
+pub fn set_pause_on_battery(enabled: bool, app_handle: AppHandle) -> Result<(), ErrorInfo> {
+    handle_command_with_event!(
+        crate::utils::power::set_pause_on_battery(&app_handle, enabled)
+            .map(|()| crate::state::task_manager::apply_pause_on_battery(&app_handle)),
+        &app_handle
+    )
+}
+
+/// Whether the queue is paused automatically on battery power
+#[tauri::command]
+pub fn get_pause_on_battery(app_handle: AppHandle) -> Result<bool, ErrorInfo> {
+    Ok(crate::utils::power::pause_on_battery(&app_handle))
+}
@@ -1,33 +1,79 @@
 use std::collections::HashMap;
-use tauri::{AppHandle, State};
+use serde::Deserialize;
+use tauri::{AppHandle, Manager, State};
 
-use crate::state::task_manager::{TaskManager, Task};
-use crate::utils::error::{ErrorCode, ErrorInfo};
+use crate::state::task_manager::{
+    self, InterruptedTaskPolicy, OverwritePolicy, QueueStats, Task, TaskError, TaskGroup, TaskManager,
+    TaskSizeEstimate, DEFAULT_TASK_PRIORITY, TASK_TYPES,
+};
+use crate::utils::error::{AppError, ErrorCode, ErrorInfo};
 use crate::utils::error_handler::handle_error_with_event;
+use crate::utils::media::ensure_supported_media;
+
+/// Input and output of one task created by `create_tasks_batch`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchTaskInput {
+    pub input_path: String,
+    pub output_path: String,
+}
+
+/// Reject task types the task processor does not handle
+fn validate_task_type(task_type: &str) -> Result<(), ErrorInfo> {
+    if !TASK_TYPES.contains(&task_type) {
+        return Err(ErrorInfo {
+            code: ErrorCode::InvalidArgument,
+            message: format!("Invalid task type: {}", task_type),
+            details: Some(format!("Task type must be one of: {}", TASK_TYPES.join(", "))),
+        });
+    }
+    Ok(())
+}
 
 /// Create a new task
+///
+/// Returns the created task; its `output_path` is where the file will actually be
+/// written after applying the overwrite policy (default: error if the file exists).
+/// With `dry_run` the task only emits a `task-plan` event when run and completes
+/// without writing the output. Tasks given the same `group_id` can be controlled
+/// together with the group commands.
 #[tauri::command]
 pub fn create_task(
     input_path: String,
     output_path: String,
-    config: HashMap<String, String>,
+    mut config: HashMap<String, String>,
     task_type: String,
-    _app_handle: AppHandle,
+    priority: Option<u8>,
+    overwrite_policy: Option<OverwritePolicy>,
+    dry_run: Option<bool>,
+    group_id: Option<String>,
+    app_handle: AppHandle,
     task_manager: State<'_, TaskManager>,
-) -> Result<String, ErrorInfo> {
+) -> Result<Task, ErrorInfo> {
     // Validate task type
-    if !["convert", "split", "edit", "sanitize"].contains(&task_type.as_str()) {
-        return Err(ErrorInfo {
-            code: ErrorCode::InvalidArgument,
-            message: format!("Invalid task type: {}", task_type),
-            details: Some("Task type must be one of: convert, split, edit, sanitize".to_string()),
-        });
+    validate_task_type(&task_type)?;
+
+    // Catch non-video inputs here rather than when the task runs
+    handle_error_with_event(ensure_supported_media(&input_path), &app_handle)?;
+
+    if dry_run == Some(true) {
+        config.insert("dry_run".to_string(), "true".to_string());
+    }
+    if let Some(group_id) = group_id {
+        config.insert("group_id".to_string(), group_id);
     }
 
     // Create task
     let manager = task_manager.inner();
-    match manager.create_task(input_path, output_path, task_type, config) {
-        Ok(task_id) => Ok(task_id),
+    match manager.create_task(
+        input_path,
+        output_path,
+        task_type,
+        config,
+        priority.unwrap_or(DEFAULT_TASK_PRIORITY),
+        overwrite_policy.unwrap_or_default(),
+    ).and_then(|task| manager.save_state(&app_handle).map(|_| task)) {
+        Ok(task) => Ok(task),
+        Err(e @ TaskError::OutputExists(_)) => handle_error_with_event(Err(e), &app_handle),
         Err(e) => {
             Err(ErrorInfo {
                 code: ErrorCode::StateMutationError,
@@ -38,17 +84,70 @@ pub fn create_task(
     }
 }
 
-/// Run a task
+/// Create one task per input with the same settings, all in one group
+///
+/// The group id is generated unless one is given. Every input is checked before any
+/// task is created, and if a task cannot be created the ones created before it are
+/// removed again, so the batch is added completely or not at all.
+#[tauri::command]
+pub fn create_tasks_batch(
+    inputs: Vec<BatchTaskInput>,
+    mut config: HashMap<String, String>,
+    task_type: String,
+    priority: Option<u8>,
+    overwrite_policy: Option<OverwritePolicy>,
+    group_id: Option<String>,
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<Vec<Task>, ErrorInfo> {
+    validate_task_type(&task_type)?;
+    for input in &inputs {
+        handle_error_with_event(ensure_supported_media(&input.input_path), &app_handle)?;
+    }
+
+    let group_id = group_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    config.insert("group_id".to_string(), group_id);
+
+    let manager = task_manager.inner();
+    let mut created: Vec<Task> = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let result = manager.create_task(
+            input.input_path,
+            input.output_path,
+            task_type.clone(),
+            config.clone(),
+            priority.unwrap_or(DEFAULT_TASK_PRIORITY),
+            overwrite_policy.unwrap_or_default(),
+        );
+
+        match result {
+            Ok(task) => created.push(task),
+            Err(e) => {
+                for task in &created {
+                    let _ = manager.remove_task(&task.id, &app_handle);
+                }
+                return handle_error_with_event(Err(e), &app_handle);
+            }
+        }
+    }
+
+    handle_error_with_event(manager.save_state(&app_handle), &app_handle)?;
+
+    Ok(created)
+}
+
+/// Run a task; refused while the queue is paused unless `force` is set
 #[tauri::command]
 pub fn run_task(
     task_id: String,
+    force: Option<bool>,
     app_handle: AppHandle,
     task_manager: State<'_, TaskManager>,
 ) -> Result<(), ErrorInfo> {
     // Start task
     let manager = task_manager.inner();
     handle_error_with_event(
-        manager.start_task(&task_id, &app_handle),
+        manager.run_task(&task_id, force.unwrap_or(false), &app_handle),
         &app_handle
     )
 }
@@ -69,6 +168,38 @@ pub fn get_queue(
     Ok(task_manager.inner().get_queue())
 }
 
+/// Get task counts per status and running progress
+#[tauri::command]
+pub fn get_queue_stats(
+    task_manager: State<'_, TaskManager>,
+) -> Result<QueueStats, ErrorInfo> {
+    Ok(task_manager.inner().get_queue_stats())
+}
+
+/// Estimate the output size of every pending task
+///
+/// The estimates are rough, especially for quality (CRF) based settings; they are
+/// meant for disk space warnings, not exact numbers. Inputs are probed, so this
+/// runs on a blocking thread.
+#[tauri::command]
+pub async fn estimate_task_sizes(
+    app_handle: AppHandle,
+) -> Result<TaskSizeEstimate, ErrorInfo> {
+    let handle = app_handle.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        handle.state::<TaskManager>().inner().estimate_task_sizes()
+    })
+    .await
+    .map_err(|e| {
+        AppError::other_error(
+            format!("Estimate thread failed: {}", e),
+            ErrorCode::TaskProcessingFailed,
+            None,
+        )
+    });
+    handle_error_with_event(result, &app_handle)
+}
+
 /// Get a task by ID
 #[tauri::command]
 pub fn get_task(
@@ -88,6 +219,22 @@ pub fn get_task(
     }
 }
 
+/// Set the priority of a task (higher runs sooner)
+#[tauri::command]
+pub fn set_task_priority(
+    task_id: String,
+    priority: u8,
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<(), ErrorInfo> {
+    // Set priority
+    let manager = task_manager.inner();
+    handle_error_with_event(
+        manager.set_task_priority(&task_id, priority, &app_handle),
+        &app_handle
+    )
+}
+
 /// Pause a task
 #[tauri::command]
 pub fn pause_task(
@@ -163,6 +310,22 @@ pub fn remove_task(
     )
 }
 
+/// Cancel a task if it is running or paused and remove it
+///
+/// Unlike `remove_task` this works in any state and emits a single `task-removed`.
+#[tauri::command]
+pub async fn force_remove_task(
+    task_id: String,
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<(), ErrorInfo> {
+    let manager = task_manager.inner();
+    handle_error_with_event(
+        manager.force_remove_task(&task_id, &app_handle).await,
+        &app_handle
+    )
+}
+
 /// Clear completed tasks
 #[tauri::command]
 pub fn clear_completed_tasks(
@@ -177,6 +340,88 @@ pub fn clear_completed_tasks(
     )
 }
 
+/// Clear all tasks except running and paused ones, returning how many were removed
+#[tauri::command]
+pub fn clear_all_tasks(
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<usize, ErrorInfo> {
+    // Clear all tasks
+    let manager = task_manager.inner();
+    handle_error_with_event(
+        manager.clear_all_tasks(&app_handle),
+        &app_handle
+    )
+}
+
+/// Pause the running tasks of a group and hold back its pending ones
+///
+/// Returns the number of tasks paused or held.
+#[tauri::command]
+pub fn pause_group(
+    group_id: String,
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<usize, ErrorInfo> {
+    let manager = task_manager.inner();
+    handle_error_with_event(
+        manager.pause_group(&group_id, &app_handle),
+        &app_handle
+    )
+}
+
+/// Resume the paused tasks of a group and queue its pending ones again
+///
+/// Returns the number of tasks resumed or queued.
+#[tauri::command]
+pub fn resume_group(
+    group_id: String,
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<usize, ErrorInfo> {
+    let manager = task_manager.inner();
+    handle_error_with_event(
+        manager.resume_group(&group_id, &app_handle),
+        &app_handle
+    )
+}
+
+/// Cancel every unfinished task of a group, returning how many were canceled
+#[tauri::command]
+pub fn cancel_group(
+    group_id: String,
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<usize, ErrorInfo> {
+    let manager = task_manager.inner();
+    handle_error_with_event(
+        manager.cancel_group(&group_id, &app_handle),
+        &app_handle
+    )
+}
+
+/// Remove the tasks of a group that are not running or paused, returning how many were removed
+#[tauri::command]
+pub fn remove_group(
+    group_id: String,
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<usize, ErrorInfo> {
+    let manager = task_manager.inner();
+    handle_error_with_event(
+        manager.remove_group(&group_id, &app_handle),
+        &app_handle
+    )
+}
+
+/// Get the task counts and progress of every group
+#[tauri::command]
+pub fn get_groups(
+    task_manager: State<'_, TaskManager>,
+) -> Result<Vec<TaskGroup>, ErrorInfo> {
+    Ok(task_manager.inner().get_groups())
+}
+
 /// Reorder tasks
 #[tauri::command]
 pub fn reorder_tasks(
@@ -192,6 +437,34 @@ pub fn reorder_tasks(
     )
 }
 
+/// Move a queued task to the front of the queue, returning the new order
+#[tauri::command]
+pub fn move_task_to_front(
+    task_id: String,
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<Vec<String>, ErrorInfo> {
+    let manager = task_manager.inner();
+    handle_error_with_event(
+        manager.move_task_to_front(&task_id, &app_handle),
+        &app_handle
+    )
+}
+
+/// Move a queued task to the back of the queue, returning the new order
+#[tauri::command]
+pub fn move_task_to_back(
+    task_id: String,
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<Vec<String>, ErrorInfo> {
+    let manager = task_manager.inner();
+    handle_error_with_event(
+        manager.move_task_to_back(&task_id, &app_handle),
+        &app_handle
+    )
+}
+
 /// Pause the task queue
 #[tauri::command]
 pub fn pause_queue(
@@ -220,6 +493,19 @@ pub fn resume_queue(
     )
 }
 
+/// Let running tasks finish without starting new ones, then pause the queue
+#[tauri::command]
+pub fn drain_queue(
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<(), ErrorInfo> {
+    let manager = task_manager.inner();
+    handle_error_with_event(
+        manager.drain_queue(&app_handle),
+        &app_handle
+    )
+}
+
 /// Cancel all tasks in the queue
 #[tauri::command]
 pub fn cancel_queue(
@@ -257,6 +543,73 @@ pub fn get_max_concurrent_tasks(
     Ok(task_manager.inner().get_max_concurrent_tasks())
 }
 
+/// Set how tasks interrupted by an app exit are handled on the next start
+#[tauri::command]
+pub fn set_interrupted_task_policy(
+    policy: InterruptedTaskPolicy,
+    app_handle: AppHandle,
+    task_manager: State<'_, TaskManager>,
+) -> Result<(), ErrorInfo> {
+    let manager = task_manager.inner();
+    handle_error_with_event(
+        manager.set_interrupted_task_policy(policy, &app_handle),
+        &app_handle
+    )
+}
+
+/// Get how tasks interrupted by an app exit are handled
+#[tauri::command]
+pub fn get_interrupted_task_policy(
+    task_manager: State<'_, TaskManager>,
+) -> Result<InterruptedTaskPolicy, ErrorInfo> {
+    Ok(task_manager.inner().get_interrupted_task_policy())
+}
+
+/// Set the minimum time between `task-progress` events
+///
+/// Applies to tasks started afterwards; 0 reports progress on every frame.
+#[tauri::command]
+pub fn set_progress_interval(
+    interval_ms: u64,
+    app_handle: AppHandle,
+) -> Result<(), ErrorInfo> {
+    handle_error_with_event(
+        task_manager::set_progress_interval(&app_handle, interval_ms),
+        &app_handle
+    )
+}
+
+/// Get the minimum time between `task-progress` events in milliseconds
+#[tauri::command]
+pub fn get_progress_interval(
+    app_handle: AppHandle,
+) -> Result<u64, ErrorInfo> {
+    Ok(task_manager::progress_interval(&app_handle).as_millis() as u64)
+}
+
+/// Split the CPU cores between concurrent tasks that do not set `threads`
+///
+/// When enabled, each task gets the available cores divided by the maximum number of
+/// concurrent tasks. Applies to tasks started afterwards.
+#[tauri::command]
+pub fn set_auto_thread_limit(
+    enabled: bool,
+    app_handle: AppHandle,
+) -> Result<(), ErrorInfo> {
+    handle_error_with_event(
+        task_manager::set_auto_thread_limit(&app_handle, enabled),
+        &app_handle
+    )
+}
+
+/// Whether the CPU cores are split between concurrent tasks
+#[tauri::command]
+pub fn get_auto_thread_limit(
+    app_handle: AppHandle,
+) -> Result<bool, ErrorInfo> {
+    Ok(task_manager::auto_thread_limit(&app_handle))
+}
+
 /// Check if the queue is paused
 #[tauri::command]
 pub fn is_queue_paused(
@@ -0,0 +1,38 @@
+use tauri::{AppHandle, State};
+
+use crate::state::watch_manager::{WatchInfo, WatchManager, WatchTaskTemplate};
+use crate::utils::error::ErrorInfo;
+use crate::utils::error_handler::handle_error_with_event;
+
+/// Watch a folder and create a task from `task_template` for each new video file in it
+///
+/// A file is imported once its size has stopped changing, so files that are still
+/// being copied are not picked up. Returns the watch, whose `id` is passed to `stop_watch`.
+#[tauri::command]
+pub fn watch_directory(
+    path: String,
+    task_template: WatchTaskTemplate,
+    app_handle: AppHandle,
+    watch_manager: State<'_, WatchManager>,
+) -> Result<WatchInfo, ErrorInfo> {
+    handle_error_with_event(
+        watch_manager.inner().watch_directory(&app_handle, &path, task_template),
+        &app_handle,
+    )
+}
+
+/// Stop a watch started by `watch_directory`
+#[tauri::command]
+pub fn stop_watch(
+    watch_id: String,
+    app_handle: AppHandle,
+    watch_manager: State<'_, WatchManager>,
+) -> Result<(), ErrorInfo> {
+    handle_error_with_event(watch_manager.inner().stop_watch(&watch_id), &app_handle)
+}
+
+/// List the folders currently being watched
+#[tauri::command]
+pub fn list_watches(watch_manager: State<'_, WatchManager>) -> Result<Vec<WatchInfo>, ErrorInfo> {
+    Ok(watch_manager.inner().list_watches())
+}
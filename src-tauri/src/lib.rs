@@ -5,22 +5,34 @@ pub mod utils;
 
 use log::info;
 use tauri::Manager;
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 use tauri_plugin_log::{RotationStrategy, Target, TargetKind, TimezoneStrategy};
 
 // Constants for logging configuration
 const MAX_LOG_FILE_SIZE_BYTES: u128 = 10 * 1024 * 1024; // 10 MB
 const LOG_FILE_NAME: &str = "app";
 const LOG_TIMEZONE_STRATEGY: TimezoneStrategy = TimezoneStrategy::UseLocal;
-const LOG_ROTATION_STRATEGY: RotationStrategy = RotationStrategy::KeepAll;
+const LOG_ROTATION_STRATEGY: RotationStrategy = RotationStrategy::KeepAll; // pruned by logger::cleanup_old_logs
 
 
-use state::task_manager::TaskManager;
-use utils::gpu_detector::check_gpu_availability;
+use services::video_processor::VideoProcessor;
+use state::task_manager::{TaskManager, DEFAULT_MAX_CONCURRENT_TASKS};
+use state::watch_manager::WatchManager;
+use utils::gpu_detector::{check_gpu_availability, refresh_gpu_detection};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // We'll initialize the logger in setup
     println!("Starting application");
+
+    // FFmpeg is initialized once and the processor is shared by commands and tasks.
+    // Without FFmpeg the app only starts to tell the user why, see setup.
+    let (video_processor, ffmpeg_error) = match VideoProcessor::new() {
+        Ok(video_processor) => (video_processor, None),
+        Err(e) => (VideoProcessor::default(), Some(e)),
+    };
+    let ffmpeg_available = ffmpeg_error.is_none();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(
@@ -37,19 +49,46 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
-        .manage(TaskManager::new(2)) // Default to 2 concurrent tasks
+        .plugin(tauri_plugin_notification::init())
+        .manage(TaskManager::with_video_processor(
+            DEFAULT_MAX_CONCURRENT_TASKS, // Replaced by the saved value in setup
+            video_processor.clone(),
+        ))
+        .manage(video_processor)
+        .manage(WatchManager::new())
         .invoke_handler(tauri::generate_handler![
             // Basic commands
             commands::greet,
             // GPU detection
             check_gpu_availability,
+            refresh_gpu_detection,
             // Preset management has been moved to frontend
             // Video processing
             commands::get_video_info,
+            commands::generate_thumbnail,
+            commands::add_file_to_list,
+            commands::add_files_to_list,
+            commands::reveal_in_explorer,
+            commands::clear_thumbnail_cache,
+            commands::set_probe_timeout,
+            commands::get_probe_timeout,
+            commands::cancel_probes,
+            commands::check_output_compatibility,
+            commands::convert_video,
+            commands::extract_audio,
+            commands::export_frames,
+            commands::frames_to_video,
+            commands::compare_quality,
             // State management
             // New state management commands
             commands::get_app_info,
             commands::set_gpu,
+            commands::get_recommended_codec,
+            commands::set_default_codecs,
+            commands::get_default_codecs,
+            commands::set_max_upscale_factor,
+            commands::get_max_upscale_factor,
+            commands::get_ffmpeg_capabilities,
             commands::emit_preferences_changed,
             commands::emit_conversion_state_changed,
 
@@ -58,46 +97,137 @@ pub fn run() {
             // Task management
             // New task management commands
             commands::create_task,
+            commands::create_tasks_batch,
             commands::run_task,
             commands::get_tasks,
             commands::get_queue,
+            commands::get_queue_stats,
+            commands::estimate_task_sizes,
             commands::get_task,
+            commands::set_task_priority,
             commands::pause_task,
             commands::resume_task,
             commands::cancel_task,
             commands::retry_task,
             commands::remove_task,
+            commands::force_remove_task,
             commands::clear_completed_tasks,
+            commands::clear_all_tasks,
             commands::reorder_tasks,
+            commands::move_task_to_front,
+            commands::move_task_to_back,
+            // Task groups
+            commands::pause_group,
+            commands::resume_group,
+            commands::cancel_group,
+            commands::remove_group,
+            commands::get_groups,
             commands::pause_queue,
             commands::resume_queue,
+            commands::drain_queue,
             commands::cancel_queue,
             commands::set_max_concurrent_tasks,
+            commands::set_interrupted_task_policy,
+            commands::get_interrupted_task_policy,
             commands::get_max_concurrent_tasks,
+            commands::set_progress_interval,
+            commands::get_progress_interval,
+            commands::set_auto_thread_limit,
+            commands::get_auto_thread_limit,
             commands::is_queue_paused,
+            // Watched folders
+            commands::watch_directory,
+            commands::stop_watch,
+            commands::list_watches,
             // Logging
             commands::get_current_log_file_path,
             commands::open_log_file,
             commands::open_log_directory,
+            commands::set_log_level,
+            commands::get_log_level,
+            commands::set_log_retention,
+            commands::get_log_retention,
+            // Notifications
+            commands::set_notify_on_complete,
+            commands::get_notify_on_complete,
+            // Power
+            commands::set_pause_on_battery,
+            commands::get_pause_on_battery,
         ])
-        .setup(|app| {
+        .setup(move |app| {
             // Logger is initialized by the tauri-plugin-log plugin
             let _app_handle = app.app_handle(); // Unused for now
 
             info!("Application setup starting");
 
-            // Processor state is no longer needed with the new task system
+            // Apply the log level chosen in a previous session
+            utils::logger::restore_log_level(app.app_handle());
+
+            // Nothing below may call into FFmpeg without its libraries. The webview is not
+            // listening yet, so the user is told with a native dialog and the app exits.
+            if let Some(e) = ffmpeg_error {
+                log::error!("{}", e);
+                let error_info = e.to_error_info();
+                let app_handle = app.app_handle().clone();
+                app.dialog()
+                    .message(match error_info.details {
+                        Some(details) => format!("{}\n\n{}", error_info.message, details),
+                        None => error_info.message,
+                    })
+                    .title("FFmpeg not found")
+                    .kind(MessageDialogKind::Error)
+                    .show(move |_| app_handle.exit(1));
+                return Ok(());
+            }
+
+            // Use the codecs the user chose per container for tasks that name none
+            utils::app_info::restore_default_codecs(app.app_handle(), &app.state::<VideoProcessor>());
+
+            // Give up on files that take too long to open, e.g. on a stalled network share
+            utils::app_info::restore_probe_timeout(app.app_handle(), &app.state::<VideoProcessor>());
 
-            // Initialize task manager state
-            // In a real implementation, we would need to use a mutable reference to TaskManager
-            // For now, we'll just log a message
-            info!("Skipping task state loading (requires mutable access)");
+            // Keep outputs from being upscaled far beyond their source
+            utils::app_info::restore_max_upscale_factor(app.app_handle(), &app.state::<VideoProcessor>());
+
+            // Rotated logs are kept forever by the plugin; delete the ones beyond the limits
+            match utils::logger::cleanup_old_logs(app.app_handle()) {
+                Ok(0) => {}
+                Ok(removed) => info!("Deleted {} old log files", removed),
+                Err(e) => log::error!("Failed to clean up old log files: {}", e),
+            }
+
+            // Presets used to be written to the temp dir; move any left there into the store
+            if let Err(e) = utils::preset_migration::migrate_legacy_presets(app.app_handle()) {
+                log::error!("Failed to migrate legacy presets: {}", e);
+            }
+
+            // Restore tasks, queue and concurrency saved in the previous session
+            let task_manager = app.state::<TaskManager>();
+            match task_manager.inner().load_state(app.app_handle()) {
+                Ok(()) => {
+                    info!(
+                        "Restored {} tasks ({} queued, max {} concurrent, queue {})",
+                        task_manager.inner().get_all_tasks().len(),
+                        task_manager.inner().get_queue().len(),
+                        task_manager.inner().get_max_concurrent_tasks(),
+                        if task_manager.inner().is_queue_paused() { "paused" } else { "running" }
+                    );
+                    // Write back tasks normalized or migrated while loading
+                    if let Err(e) = task_manager.inner().flush_state(app.app_handle()) {
+                        log::error!("Failed to save restored task state: {}", e);
+                    }
+                }
+                Err(e) => log::error!("Failed to load task state: {}", e),
+            }
 
             // Get app handle for notifications
             let app_handle = app.app_handle().clone();
 
+            // Pause the queue on battery power if the user asked for it
+            state::task_manager::start_battery_monitor(app_handle.clone());
+
             // Get GPU info for startup notification
-            let gpu_count = match utils::app_info::get_app_info() {
+            let gpu_count = match utils::app_info::get_app_info(&app_handle) {
                 Ok(info) => info.gpus.len(),
                 Err(_) => 0,
             };
@@ -114,6 +244,16 @@ pub fn run() {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |app_handle, event| {
+            // Without FFmpeg the saved tasks were never loaded and must not be overwritten
+            if let (tauri::RunEvent::Exit, true) = (event, ffmpeg_available) {
+                // Stop running tasks, remove their partial outputs and save the task state
+                let task_manager = app_handle.state::<TaskManager>();
+                if let Err(e) = task_manager.inner().shutdown(app_handle) {
+                    log::error!("Failed to save task state on exit: {}", e);
+                }
+            }
+        });
 }
@@ -0,0 +1,344 @@
+use log::{info, warn};
+
+use ffmpeg::codec::{self, decoder, encoder, Compliance};
+use ffmpeg::filter;
+use ffmpeg::format::context::Output as OutputContext;
+use ffmpeg::util::frame::audio::Audio as AudioFrame;
+use ffmpeg::util::rational::Rational;
+use ffmpeg::{ChannelLayout, Packet, Rescale, Stream};
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+
+/// Lowest accepted audio volume multiplier (0.0 produces a silent track)
+pub const MIN_AUDIO_VOLUME: f32 = 0.0;
+
+/// Highest accepted audio volume multiplier
+pub const MAX_AUDIO_VOLUME: f32 = 4.0;
+
+/// Build the audio filter description for loudness normalization and a volume multiplier
+///
+/// The manual gain is applied after `loudnorm`, so it adjusts the normalized level.
+pub fn build_audio_filter_spec(loudnorm: Option<&str>, volume: Option<f32>) -> String {
+    let mut filters: Vec<String> = loudnorm.map(str::to_string).into_iter().collect();
+    if let Some(volume) = volume {
+        filters.push(format!("volume={:.3}", volume));
+    }
+
+    if filters.is_empty() {
+        "anull".to_string()
+    } else {
+        filters.join(",")
+    }
+}
+
+/// Decodes, filters and re-encodes one audio stream into the output
+pub struct AudioTranscoder {
+    input_index: usize,
+    output_index: usize,
+    decoder: decoder::Audio,
+    encoder: encoder::Audio,
+    filter_graph: filter::Graph,
+    encoder_time_base: Rational,
+    output_time_base: Rational,
+}
+
+impl AudioTranscoder {
+    /// Set up an audio transcoder for `input_stream`, adding a stream to `output_ctx`.
+    ///
+    /// Returns `Ok(None)` if the output container has no usable audio encoder, in which
+    /// case the audio is dropped with a warning.
+    pub fn new(
+        input_stream: &Stream,
+        output_ctx: &mut OutputContext,
+        output_path: &str,
+        filter_spec: &str,
+    ) -> AppResult<Option<Self>> {
+        let codec_id = output_ctx.format().codec(output_path, ffmpeg::media::Type::Audio);
+        let audio_codec = match encoder::find(codec_id).and_then(|c| c.audio().ok()) {
+            Some(codec) => codec,
+            None => {
+                warn!("No audio encoder available for {:?}, dropping audio", codec_id);
+                return Ok(None);
+            }
+        };
+
+        let decoder = codec::context::Context::from_parameters(input_stream.parameters())
+            .and_then(|ctx| ctx.decoder().audio())
+            .map_err(|e| {
+                AppError::video_error(
+                    format!("Cannot create audio decoder: {}", e),
+                    ErrorCode::DecodingError,
+                    Some("Error creating audio decoder".to_string()),
+                )
+            })?;
+
+        let output_index = output_ctx
+            .add_stream(audio_codec)
+            .map(|stream| stream.index())
+            .map_err(|e| {
+                AppError::video_error(
+                    format!("Cannot add audio stream: {}", e),
+                    ErrorCode::EncodingError,
+                    Some("Error adding audio stream to output context".to_string()),
+                )
+            })?;
+
+        let mut encoder = codec::context::Context::new_with_codec(*audio_codec)
+            .encoder()
+            .audio()
+            .map_err(|e| {
+                AppError::video_error(
+                    format!("Cannot create audio encoder: {}", e),
+                    ErrorCode::EncodingError,
+                    Some("Error creating audio encoder".to_string()),
+                )
+            })?;
+
+        let channel_layout = audio_codec
+            .channel_layouts()
+            .map(|layouts| layouts.best(decoder.channels() as i32))
+            .unwrap_or(ChannelLayout::STEREO);
+
+        // Keep the source sample rate if the encoder supports it
+        let sample_rate = match audio_codec.rates() {
+            Some(rates) => {
+                let rates: Vec<i32> = rates.collect();
+                if rates.is_empty() || rates.contains(&(decoder.rate() as i32)) {
+                    decoder.rate() as i32
+                } else {
+                    rates[0]
+                }
+            }
+            None => decoder.rate() as i32,
+        };
+
+        let sample_format = audio_codec
+            .formats()
+            .and_then(|mut formats| formats.next())
+            .unwrap_or_else(|| decoder.format());
+
+        encoder.set_rate(sample_rate);
+        encoder.set_channel_layout(channel_layout);
+        encoder.set_format(sample_format);
+        encoder.set_bit_rate(decoder.bit_rate().max(128_000));
+        encoder.set_time_base(Rational::new(1, sample_rate));
+        encoder.compliance(Compliance::Experimental);
+
+        if output_ctx.format().flags().contains(ffmpeg::format::Flags::GLOBAL_HEADER) {
+            encoder.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+
+        let encoder = encoder.open_as(audio_codec).map_err(|e| {
+            AppError::video_error(
+                format!("Cannot open audio encoder: {}", e),
+                ErrorCode::EncodingError,
+                Some("Error opening audio encoder".to_string()),
+            )
+        })?;
+
+        let encoder_time_base = Rational::new(1, sample_rate);
+        if let Some(mut output_stream) = output_ctx.stream_mut(output_index) {
+            output_stream.set_parameters(&encoder);
+            output_stream.set_time_base(encoder_time_base);
+        }
+
+        let filter_graph =
+            create_audio_filter_graph(&decoder, input_stream.time_base(), &encoder, filter_spec)?;
+
+        info!(
+            "Transcoding audio stream {} to {:?} ({} Hz), filter: {}",
+            input_stream.index(),
+            codec_id,
+            sample_rate,
+            filter_spec
+        );
+
+        Ok(Some(Self {
+            input_index: input_stream.index(),
+            output_index,
+            decoder,
+            encoder,
+            filter_graph,
+            encoder_time_base,
+            output_time_base: encoder_time_base,
+        }))
+    }
+
+    /// Index of the input stream this transcoder consumes
+    pub fn input_index(&self) -> usize {
+        self.input_index
+    }
+
+    /// Refresh the output time base after the muxer has written its header
+    pub fn update_output_time_base(&mut self, output_ctx: &OutputContext) {
+        if let Some(stream) = output_ctx.stream(self.output_index) {
+            self.output_time_base = stream.time_base();
+        }
+    }
+
+    /// Decode a packet and push the resulting samples through filter and encoder
+    pub fn send_packet(&mut self, packet: &Packet, output_ctx: &mut OutputContext) -> AppResult<()> {
+        self.decoder.send_packet(packet).map_err(|e| {
+            AppError::video_error(
+                format!("Error sending packet to audio decoder: {}", e),
+                ErrorCode::DecodingError,
+                Some("Error decoding audio".to_string()),
+            )
+        })?;
+
+        self.receive_decoded_frames(output_ctx)
+    }
+
+    /// Flush decoder, filter graph and encoder
+    pub fn finish(&mut self, output_ctx: &mut OutputContext) -> AppResult<()> {
+        let _ = self.decoder.send_eof();
+        self.receive_decoded_frames(output_ctx)?;
+
+        if let Some(mut source) = self.filter_graph.get("in") {
+            let _ = source.source().flush();
+        }
+        self.encode_filtered_frames(output_ctx)?;
+
+        let _ = self.encoder.send_eof();
+        self.write_encoded_packets(output_ctx)
+    }
+
+    fn receive_decoded_frames(&mut self, output_ctx: &mut OutputContext) -> AppResult<()> {
+        let mut decoded = AudioFrame::empty();
+
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            let timestamp = decoded.timestamp();
+            decoded.set_pts(timestamp);
+
+            if let Some(mut source) = self.filter_graph.get("in") {
+                source.source().add(&decoded).map_err(|e| {
+                    AppError::video_error(
+                        format!("Error sending samples to audio filter: {}", e),
+                        ErrorCode::VideoProcessingFailed,
+                        Some("Error filtering audio".to_string()),
+                    )
+                })?;
+            }
+
+            self.encode_filtered_frames(output_ctx)?;
+        }
+
+        Ok(())
+    }
+
+    fn encode_filtered_frames(&mut self, output_ctx: &mut OutputContext) -> AppResult<()> {
+        let mut filtered = AudioFrame::empty();
+
+        loop {
+            let (received, filter_time_base) = match self.filter_graph.get("out") {
+                Some(mut sink) => {
+                    let mut sink = sink.sink();
+                    (sink.frame(&mut filtered).is_ok(), sink.time_base())
+                }
+                None => (false, self.encoder_time_base),
+            };
+
+            if !received {
+                return Ok(());
+            }
+
+            let pts = filtered.pts().map(|pts| pts.rescale(filter_time_base, self.encoder_time_base));
+            filtered.set_pts(pts);
+
+            self.encoder.send_frame(&filtered).map_err(|e| {
+                AppError::video_error(
+                    format!("Error sending samples to audio encoder: {}", e),
+                    ErrorCode::EncodingError,
+                    Some("Error encoding audio".to_string()),
+                )
+            })?;
+
+            self.write_encoded_packets(output_ctx)?;
+        }
+    }
+
+    fn write_encoded_packets(&mut self, output_ctx: &mut OutputContext) -> AppResult<()> {
+        let mut encoded = Packet::empty();
+
+        while self.encoder.receive_packet(&mut encoded).is_ok() {
+            encoded.set_stream(self.output_index);
+            encoded.rescale_ts(self.encoder_time_base, self.output_time_base);
+            encoded.write_interleaved(output_ctx).map_err(|e| {
+                AppError::ffmpeg_error(
+                    format!("Error writing audio packet: {}", e),
+                    ErrorCode::EncodingError,
+                    Some("Error writing encoded audio to output file".to_string()),
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Create the abuffer -> spec -> abuffersink graph matching the encoder's input format
+fn create_audio_filter_graph(
+    decoder: &decoder::Audio,
+    time_base: Rational,
+    encoder: &encoder::Audio,
+    spec: &str,
+) -> AppResult<filter::Graph> {
+    let mut graph = filter::Graph::new();
+
+    let channel_layout = if decoder.channel_layout().bits() == 0 {
+        ChannelLayout::default(decoder.channels() as i32)
+    } else {
+        decoder.channel_layout()
+    };
+
+    let args = format!(
+        "time_base={}/{}:sample_rate={}:sample_fmt={}:channel_layout=0x{:x}",
+        time_base.numerator(),
+        time_base.denominator(),
+        decoder.rate(),
+        decoder.format().name(),
+        channel_layout.bits()
+    );
+
+    let map_err = |e: ffmpeg::Error| {
+        AppError::video_error(
+            format!("Cannot create audio filter graph: {}", e),
+            ErrorCode::VideoProcessingFailed,
+            Some(format!("Error configuring audio filters: {}", spec)),
+        )
+    };
+
+    let abuffer = filter::find("abuffer").ok_or_else(|| map_err(ffmpeg::Error::FilterNotFound))?;
+    let abuffersink =
+        filter::find("abuffersink").ok_or_else(|| map_err(ffmpeg::Error::FilterNotFound))?;
+
+    graph.add(&abuffer, "in", &args).map_err(map_err)?;
+    graph.add(&abuffersink, "out", "").map_err(map_err)?;
+
+    if let Some(mut out) = graph.get("out") {
+        out.set_sample_format(encoder.format());
+        out.set_channel_layout(encoder.channel_layout());
+        out.set_sample_rate(encoder.rate());
+    }
+
+    graph
+        .output("in", 0)
+        .and_then(|parser| parser.input("out", 0))
+        .and_then(|parser| parser.parse(spec))
+        .map_err(map_err)?;
+    graph.validate().map_err(map_err)?;
+
+    // Encoders without variable frame size need exactly frame_size samples per frame
+    let variable_frame_size = encoder
+        .codec()
+        .map(|c| c.capabilities().contains(codec::Capabilities::VARIABLE_FRAME_SIZE))
+        .unwrap_or(true);
+    if !variable_frame_size {
+        if let Some(mut out) = graph.get("out") {
+            out.sink().set_frame_size(encoder.frame_size());
+        }
+    }
+
+    Ok(graph)
+}
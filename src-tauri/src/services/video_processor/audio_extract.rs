@@ -0,0 +1,192 @@
+use std::fs;
+use std::path::Path;
+use log::info;
+
+use ffmpeg::codec::{self, encoder};
+use ffmpeg::format::{input, output_as};
+use ffmpeg::media::Type as MediaType;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::audio::AudioTranscoder;
+
+/// Audio formats `extract_audio` can write: (format name, muxer, codec)
+const AUDIO_OUTPUT_FORMATS: &[(&str, &str, codec::Id)] = &[
+    ("mp3", "mp3", codec::Id::MP3),
+    ("aac", "adts", codec::Id::AAC),
+    ("wav", "wav", codec::Id::PCM_S16LE),
+    ("flac", "flac", codec::Id::FLAC),
+];
+
+/// Write the best audio stream of `input_path` to `output_path` as mp3, aac, wav or flac.
+///
+/// The stream is copied as-is when it already uses the target codec and transcoded
+/// otherwise. Progress is reported in percent; returning `false` from the callback
+/// cancels the extraction.
+pub fn extract_audio(
+    input_path: &str,
+    output_path: &str,
+    format: &str,
+    progress_callback: &dyn Fn(f32) -> bool,
+) -> AppResult<()> {
+    let format = format.to_lowercase();
+    let &(_, muxer, target_codec) = AUDIO_OUTPUT_FORMATS
+        .iter()
+        .find(|(name, _, _)| *name == format)
+        .ok_or_else(|| {
+            AppError::validation_error(
+                format!("Unsupported audio format: {}", format),
+                ErrorCode::InvalidArgument,
+                Some("Use mp3, aac, wav or flac".to_string()),
+            )
+        })?;
+
+    if !Path::new(input_path).exists() {
+        return Err(AppError::io_error(
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Input file not found"),
+            ErrorCode::FileNotFound,
+            Some(format!("Input file not found: {}", input_path)),
+        ));
+    }
+
+    let mut input_ctx = input(input_path).map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot open input file '{}': {}", input_path, e),
+            ErrorCode::FFmpegInitError,
+            Some(format!("Error opening input file: {}", input_path)),
+        )
+    })?;
+
+    let input_stream = input_ctx.streams().best(MediaType::Audio).ok_or_else(|| {
+        AppError::video_error(
+            format!("No audio stream found in file: {}", input_path),
+            ErrorCode::InvalidVideoFormat,
+            Some(format!("File does not contain an audio stream: {}", input_path)),
+        )
+    })?;
+    let input_index = input_stream.index();
+    let input_time_base = input_stream.time_base();
+    let copy = input_stream.parameters().id() == target_codec;
+
+    if let Some(parent) = Path::new(output_path).parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            AppError::io_error(
+                e,
+                ErrorCode::DirectoryError,
+                Some(format!("Failed to create output directory: {:?}", parent)),
+            )
+        })?;
+    }
+
+    let mut output_ctx = output_as(output_path, muxer).map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot create output context for '{}': {}", output_path, e),
+            ErrorCode::FFmpegInitError,
+            Some(format!("Error creating output file: {}", output_path)),
+        )
+    })?;
+
+    // Either copy the stream or set up a transcoder for it
+    let mut transcoder = if copy {
+        let mut output_stream = output_ctx.add_stream(encoder::find(codec::Id::None)).map_err(|e| {
+            AppError::video_error(
+                format!("Cannot add audio stream: {}", e),
+                ErrorCode::EncodingError,
+                Some("Error adding audio stream to output context".to_string()),
+            )
+        })?;
+        output_stream.set_parameters(input_stream.parameters());
+        // SAFETY: the stream and its codec parameters are owned by the output context.
+        // The source container's codec tag may not be valid in the target container.
+        unsafe {
+            (*(*output_stream.as_mut_ptr()).codecpar).codec_tag = 0;
+        }
+        None
+    } else {
+        let transcoder = AudioTranscoder::new(&input_stream, &mut output_ctx, output_path, "anull")?
+            .ok_or_else(|| {
+                AppError::video_error(
+                    format!("No {} encoder available", format),
+                    ErrorCode::CodecNotSupported,
+                    Some("The FFmpeg build does not include an encoder for this format".to_string()),
+                )
+            })?;
+        Some(transcoder)
+    };
+
+    info!(
+        "Extracting audio from {} to {} ({})",
+        input_path,
+        output_path,
+        if copy { "stream copy" } else { "transcoding" }
+    );
+
+    output_ctx.write_header().map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot write output header: {}", e),
+            ErrorCode::EncodingError,
+            Some(format!("Error writing header to output file: {}", output_path)),
+        )
+    })?;
+
+    let output_time_base = output_ctx
+        .stream(0)
+        .map(|stream| stream.time_base())
+        .unwrap_or(input_time_base);
+    if let Some(transcoder) = transcoder.as_mut() {
+        transcoder.update_output_time_base(&output_ctx);
+    }
+
+    let duration_secs = input_ctx.duration() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
+
+    for (stream, mut packet) in input_ctx.packets() {
+        if stream.index() != input_index {
+            continue;
+        }
+
+        let position = packet.pts().map(|pts| pts as f64 * f64::from(input_time_base));
+
+        match transcoder.as_mut() {
+            Some(transcoder) => transcoder.send_packet(&packet, &mut output_ctx)?,
+            None => {
+                packet.rescale_ts(input_time_base, output_time_base);
+                packet.set_stream(0);
+                packet.set_position(-1);
+                packet.write_interleaved(&mut output_ctx).map_err(|e| {
+                    AppError::ffmpeg_error(
+                        format!("Error writing audio packet: {}", e),
+                        ErrorCode::EncodingError,
+                        Some("Error writing audio to output file".to_string()),
+                    )
+                })?;
+            }
+        }
+
+        if let (Some(position), true) = (position, duration_secs > 0.0) {
+            let progress = (position / duration_secs * 100.0).clamp(0.0, 99.9) as f32;
+            if !progress_callback(progress) {
+                return Err(AppError::video_error(
+                    "Processing canceled by user".to_string(),
+                    ErrorCode::VideoProcessingFailed,
+                    Some("Audio extraction was canceled".to_string()),
+                ));
+            }
+        }
+    }
+
+    if let Some(transcoder) = transcoder.as_mut() {
+        transcoder.finish(&mut output_ctx)?;
+    }
+
+    output_ctx.write_trailer().map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot write output trailer: {}", e),
+            ErrorCode::EncodingError,
+            Some(format!("Error finalizing output file: {}", output_path)),
+        )
+    })?;
+
+    progress_callback(100.0);
+
+    Ok(())
+}
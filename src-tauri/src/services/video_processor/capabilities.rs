@@ -0,0 +1,152 @@
+use std::ptr;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use ffmpeg::codec::{self, encoder};
+use ffmpeg::format::Pixel;
+use ffmpeg::Rational;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::app_info;
+use super::VideoProcessor;
+
+/// Codecs the app reads and writes, with the media type reported for each
+const APP_CODECS: &[(codec::Id, &str)] = &[
+    (codec::Id::H264, "video"),
+    (codec::Id::HEVC, "video"),
+    (codec::Id::VP9, "video"),
+    (codec::Id::AV1, "video"),
+    (codec::Id::AAC, "audio"),
+    (codec::Id::OPUS, "audio"),
+    (codec::Id::MP3, "audio"),
+];
+
+/// Hardware encoders the app can pick, probed by opening them
+const HARDWARE_ENCODERS: &[&str] = &[
+    "h264_nvenc", "hevc_nvenc", "av1_nvenc",
+    "h264_qsv", "hevc_qsv", "av1_qsv",
+    "h264_amf", "hevc_amf", "av1_amf",
+    "h264_videotoolbox", "hevc_videotoolbox",
+];
+
+/// Frame size used to open hardware encoders; every encoder above accepts it
+const PROBE_SIZE: u32 = 256;
+
+/// Encoders and decoders FFmpeg provides for one codec
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodecSupport {
+    pub codec: String,
+    pub media_type: String,     // "video" or "audio"
+    pub encoders: Vec<String>,  // e.g. ["libx264", "h264_nvenc"]
+    pub decoders: Vec<String>,
+}
+
+/// Result of opening one hardware encoder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardwareEncoderStatus {
+    pub name: String,
+    pub compiled: bool,         // FFmpeg was built with the encoder
+    pub opened: bool,           // the encoder opened on this machine
+    pub error: Option<String>,  // why it did not open
+}
+
+/// FFmpeg build and codec support, for diagnostics and bug reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FfmpegCapabilities {
+    pub version: Option<String>,
+    pub configuration: String,  // configure flags of libavcodec
+    pub license: String,
+    pub codecs: Vec<CodecSupport>,
+    pub hardware_encoders: Vec<HardwareEncoderStatus>,
+}
+
+/// Names of every encoder or decoder FFmpeg has for `id`
+fn codec_names(id: codec::Id, encoders: bool) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut opaque = ptr::null_mut();
+    loop {
+        // SAFETY: av_codec_iterate walks FFmpeg's static codec table with `opaque` as cursor
+        let ptr = unsafe { ffmpeg::ffi::av_codec_iterate(&mut opaque) };
+        if ptr.is_null() {
+            break;
+        }
+        // SAFETY: the pointer is a valid static codec description
+        let found = unsafe { ffmpeg::Codec::wrap(ptr) };
+        if found.id() == id && found.is_encoder() == encoders {
+            names.push(found.name().to_string());
+        }
+    }
+    names
+}
+
+/// Open `name` on a small frame to see whether the hardware behind it is usable
+fn probe_hardware_encoder(name: &str) -> HardwareEncoderStatus {
+    let Some(found) = encoder::find_by_name(name) else {
+        return HardwareEncoderStatus {
+            name: name.to_string(),
+            compiled: false,
+            opened: false,
+            error: None,
+        };
+    };
+
+    // Software input is accepted by all of them, in one of these layouts
+    let pixel_format = found
+        .video()
+        .ok()
+        .and_then(|video| video.formats())
+        .and_then(|mut formats| formats.find(|format| matches!(format, Pixel::YUV420P | Pixel::NV12)))
+        .unwrap_or(Pixel::NV12);
+
+    let result = codec::context::Context::new_with_codec(found)
+        .encoder()
+        .video()
+        .and_then(|mut video| {
+            video.set_width(PROBE_SIZE);
+            video.set_height(PROBE_SIZE);
+            video.set_format(pixel_format);
+            video.set_time_base(Rational::new(1, 25));
+            video.open_as(found)
+        });
+
+    HardwareEncoderStatus {
+        name: name.to_string(),
+        compiled: true,
+        opened: result.is_ok(),
+        error: result.err().map(|e| e.to_string()),
+    }
+}
+
+impl VideoProcessor {
+    /// Report the linked FFmpeg version and build flags, the encoders and decoders
+    /// available for the codecs the app uses, and which hardware encoders open.
+    ///
+    /// Opening the hardware encoders initializes the GPU drivers, which can take
+    /// a second or more.
+    pub fn ffmpeg_capabilities(&self) -> FfmpegCapabilities {
+        let codecs = APP_CODECS
+            .iter()
+            .map(|&(id, media_type)| CodecSupport {
+                codec: id.name().to_string(),
+                media_type: media_type.to_string(),
+                encoders: codec_names(id, true),
+                decoders: codec_names(id, false),
+            })
+            .collect();
+
+        let hardware_encoders: Vec<HardwareEncoderStatus> =
+            HARDWARE_ENCODERS.iter().map(|name| probe_hardware_encoder(name)).collect();
+        info!(
+            "Hardware encoders that opened: {:?}",
+            hardware_encoders.iter().filter(|e| e.opened).map(|e| &e.name).collect::<Vec<_>>()
+        );
+
+        FfmpegCapabilities {
+            version: app_info::get_ffmpeg_version(),
+            configuration: codec::configuration().to_string(),
+            license: codec::license().to_string(),
+            codecs,
+            hardware_encoders,
+        }
+    }
+}
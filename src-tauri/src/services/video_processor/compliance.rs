@@ -0,0 +1,98 @@
+use log::info;
+
+use ffmpeg::decoder;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::AppResult;
+use super::filters;
+use super::remux;
+use super::{ProcessingOptions, VideoInfo, VideoProcessor};
+
+/// How far the source bitrate may exceed the requested one before the file is re-encoded
+const BITRATE_HEADROOM: f64 = 1.25;
+
+/// What `convert_if_needed` did with a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertOutcome {
+    /// The input did not match the options and was encoded
+    Encoded,
+    /// The input already matched and its streams were copied
+    Copied,
+}
+
+/// Why `info` does not already match the output `options` describe, `None` if it does
+fn mismatch(processor: &VideoProcessor, info: &VideoInfo, options: &ProcessingOptions) -> Option<String> {
+    if !remux::keeps_content(options) {
+        return Some("the options change the content".to_string());
+    }
+    if options.target_size_mb.is_some() {
+        return Some("a target size is set".to_string());
+    }
+
+    let target_codec = processor.choose_codec(options);
+    let source_codec = decoder::find_by_name(&info.codec).map(|codec| codec.id());
+    if source_codec != Some(target_codec) {
+        return Some(format!("the video is {}, not {}", info.codec, target_codec.name()));
+    }
+
+    if options.resolution.is_some() {
+        let target = match filters::output_dimensions(options.resolution, info.width, info.height) {
+            Ok(target) => target,
+            Err(e) => return Some(e.to_string()),
+        };
+        if target != (info.width, info.height) {
+            return Some(format!(
+                "the video is {}x{}, not {}x{}",
+                info.width, info.height, target.0, target.1
+            ));
+        }
+    }
+
+    if let Some(bitrate) = options.bitrate {
+        if info.bitrate as f64 > bitrate as f64 * BITRATE_HEADROOM {
+            return Some(format!(
+                "the bitrate is {} kbps, above the requested {} kbps",
+                info.bitrate / 1000,
+                bitrate / 1000
+            ));
+        }
+    }
+
+    None
+}
+
+impl VideoProcessor {
+    /// Convert `input_path` only if it does not already match the options.
+    ///
+    /// A file with the target codec and resolution, and no option changing its
+    /// content, has its streams copied into the output container instead, which is
+    /// lossless and far faster. Files the container cannot hold as they are, e.g. PCM
+    /// audio in mp4, are encoded.
+    pub fn convert_if_needed(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        options: ProcessingOptions,
+        progress_callback: impl Fn(f32) -> bool + Send + 'static,
+    ) -> AppResult<ConvertOutcome> {
+        let info = self.get_video_info(input_path)?;
+
+        let reason = mismatch(self, &info, &options).or_else(|| {
+            (!remux::can_remux(input_path, output_path, None))
+                .then(|| "a stream cannot be copied into the output container".to_string())
+        });
+
+        match reason {
+            None => {
+                info!("{} already matches the output settings, copying its streams", input_path);
+                self.remux_video(input_path, output_path, options, progress_callback)?;
+                Ok(ConvertOutcome::Copied)
+            }
+            Some(reason) => {
+                info!("Converting {}: {}", input_path, reason);
+                self.convert_video(input_path, output_path, options, progress_callback)?;
+                Ok(ConvertOutcome::Encoded)
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use ffmpeg::codec::{self, decoder, encoder};
+use ffmpeg_next as ffmpeg;
+
+use super::{VideoError, VideoProcessor, VideoResult};
+
+/// Video codecs each container accepts, `None` if it takes any codec FFmpeg can mux
+fn allowed_video_codecs(format: &str) -> Option<&'static [codec::Id]> {
+    match format {
+        "mp4" | "m4v" => Some(&[
+            codec::Id::H264,
+            codec::Id::HEVC,
+            codec::Id::AV1,
+            codec::Id::VP9,
+            codec::Id::MPEG4,
+            codec::Id::MPEG2VIDEO,
+        ]),
+        "mov" => Some(&[
+            codec::Id::H264,
+            codec::Id::HEVC,
+            codec::Id::AV1,
+            codec::Id::MPEG4,
+            codec::Id::MPEG2VIDEO,
+            codec::Id::MJPEG,
+            codec::Id::PRORES,
+            codec::Id::PNG,
+        ]),
+        "webm" => Some(&[codec::Id::VP8, codec::Id::VP9, codec::Id::AV1]),
+        "avi" => Some(&[
+            codec::Id::MPEG4,
+            codec::Id::H264,
+            codec::Id::MPEG2VIDEO,
+            codec::Id::MJPEG,
+            codec::Id::PNG,
+        ]),
+        "gif" => Some(&[codec::Id::GIF]),
+        // Matroska and containers without a table are left to FFmpeg
+        _ => None,
+    }
+}
+
+impl VideoProcessor {
+    /// Check that `codec` can be stored in the `format` container
+    ///
+    /// Only mp4, mov, webm and avi are restricted; mkv and other containers accept
+    /// any codec here. Fails with `VideoError::Codec` naming the allowed codecs.
+    pub fn validate_container_codec(format: &str, codec: codec::Id) -> VideoResult<()> {
+        let format = format.trim_start_matches('.').to_lowercase();
+        let Some(allowed) = allowed_video_codecs(&format) else {
+            return Ok(());
+        };
+
+        if allowed.contains(&codec) {
+            return Ok(());
+        }
+
+        let allowed_names: Vec<&str> = allowed.iter().map(|id| id.name()).collect();
+        Err(VideoError::codec(format!(
+            "{} video cannot be stored in {}, use one of: {}",
+            codec.name(),
+            format,
+            allowed_names.join(", ")
+        )))
+    }
+
+    /// Codec produced by an encoder name ("libx264", "h264_nvenc") or named directly ("h264")
+    pub fn codec_id_from_name(name: &str) -> Option<codec::Id> {
+        encoder::find_by_name(name)
+            .or_else(|| decoder::find_by_name(name))
+            .map(|codec| codec.id())
+    }
+}
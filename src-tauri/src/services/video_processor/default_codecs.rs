@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use ffmpeg::codec::{self, encoder};
+use ffmpeg_next as ffmpeg;
+
+use super::{VideoError, VideoProcessor, VideoResult};
+
+impl VideoProcessor {
+    /// Replace the default codec of each output container, e.g. `{"mp4": "hevc"}`
+    ///
+    /// Codecs are given by name ("hevc") or encoder ("libx265"). Each one must have an
+    /// encoder in this FFmpeg build and fit its container, otherwise nothing changes.
+    /// Tasks naming a codec themselves are not affected.
+    pub fn set_default_codecs(&self, codecs: &HashMap<String, String>) -> VideoResult<()> {
+        let mut resolved = HashMap::new();
+        for (format, name) in codecs {
+            let format = format.trim_start_matches('.').to_lowercase();
+            let id = Self::codec_id_from_name(name.trim())
+                .ok_or_else(|| VideoError::codec(format!("Unknown codec for {}: {}", format, name)))?;
+            if encoder::find(id).is_none() {
+                return Err(VideoError::codec(format!("No encoder available for {}", id.name())));
+            }
+            Self::validate_container_codec(&format, id)?;
+
+            resolved.insert(format, id);
+        }
+
+        if let Ok(mut default_codecs) = self.default_codecs.write() {
+            *default_codecs = resolved;
+        }
+        Ok(())
+    }
+
+    /// Codec the user chose for `format`, `None` to use the built-in default
+    pub(super) fn default_codec(&self, format: &str) -> Option<codec::Id> {
+        self.default_codecs.read().ok()?.get(format).copied()
+    }
+}
@@ -0,0 +1,237 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ffmpeg::codec::{self, Codec};
+use ffmpeg::format::Pixel;
+use ffmpeg_next as ffmpeg;
+use log::{info, warn};
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+
+/// Highest accepted `quality` value (x264/x265 CRF scale)
+pub const MAX_QUALITY: u8 = 51;
+
+/// Highest CRF value of the VP9/AV1 encoders
+const MAX_VPX_AOM_CRF: u32 = 63;
+
+/// Translate a `quality` value (CRF 0-51, lower is better) into the encoder's own
+/// constant-quality private options.
+///
+/// Returns an empty list if the encoder has no constant-quality mode, and a validation
+/// error if the value is outside the range the encoder accepts.
+pub fn quality_options(codec_name: &str, quality: u8) -> AppResult<Vec<(&'static str, String)>> {
+    if quality > MAX_QUALITY {
+        return Err(invalid_quality(codec_name, quality, 0, MAX_QUALITY));
+    }
+
+    let options = match codec_name {
+        "libx264" | "libx265" => vec![("crf", quality.to_string())],
+
+        // VP9 and AV1 use a 0-63 scale and need a zero bitrate for constant quality
+        "libvpx-vp9" | "libaom-av1" | "libsvtav1" => {
+            let crf = (u32::from(quality) * MAX_VPX_AOM_CRF + u32::from(MAX_QUALITY) / 2)
+                / u32::from(MAX_QUALITY);
+            vec![("crf", crf.to_string()), ("b", "0".to_string())]
+        }
+
+        name if name.ends_with("_nvenc") => vec![("rc", "vbr".to_string()), ("cq", quality.to_string())],
+
+        name if name.ends_with("_qsv") => {
+            if quality == 0 {
+                return Err(invalid_quality(codec_name, quality, 1, MAX_QUALITY));
+            }
+            vec![("global_quality", quality.to_string())]
+        }
+
+        _ => Vec::new(),
+    };
+
+    Ok(options)
+}
+
+fn invalid_quality(codec_name: &str, quality: u8, min: u8, max: u8) -> AppError {
+    AppError::validation_error(
+        format!("Quality {} is out of range for {} ({} - {})", quality, codec_name, min, max),
+        ErrorCode::InvalidArgument,
+        Some("Lower values mean higher quality; 18-28 is a typical range".to_string()),
+    )
+}
+
+/// Containers mostly played in browsers and on devices, which expect 4:2:0 video
+const WEB_FORMATS: &[&str] = &["mp4", "m4v", "mov", "webm"];
+
+/// Choose the pixel format the encoder (and the filter graph output) uses.
+///
+/// A requested format must be supported by the encoder. Without one, web containers
+/// get yuv420p for compatibility and other containers keep the source format; if the
+/// encoder can't take that, its first supported format is used instead.
+pub fn choose_pixel_format(
+    codec: &Codec,
+    requested: Option<&str>,
+    output_format: &str,
+    source: Pixel,
+) -> AppResult<Pixel> {
+    let supported: Option<Vec<Pixel>> = codec
+        .video()
+        .ok()
+        .and_then(|video| video.formats())
+        .map(|formats| formats.collect());
+    let is_supported = |format: Pixel| supported.as_ref().map_or(true, |s| s.contains(&format));
+
+    if let Some(name) = requested {
+        let format: Pixel = name.parse().map_err(|_| {
+            AppError::validation_error(
+                format!("Unknown pixel format: {}", name),
+                ErrorCode::InvalidArgument,
+                Some("Use an FFmpeg pixel format name such as yuv420p".to_string()),
+            )
+        })?;
+
+        if !is_supported(format) {
+            return Err(AppError::video_error(
+                format!("Encoder {} does not support pixel format {}", codec.name(), name),
+                ErrorCode::CodecNotSupported,
+                supported.map(|s| {
+                    format!(
+                        "Supported formats: {}",
+                        s.iter().map(|f| f.name()).collect::<Vec<_>>().join(", ")
+                    )
+                }),
+            ));
+        }
+
+        return Ok(format);
+    }
+
+    let preferred = if WEB_FORMATS.contains(&output_format.to_lowercase().as_str()) {
+        Pixel::YUV420P
+    } else {
+        source
+    };
+
+    if is_supported(preferred) {
+        return Ok(preferred);
+    }
+
+    let fallback = supported
+        .and_then(|s| s.first().copied())
+        .unwrap_or(preferred);
+    info!(
+        "Encoder {} does not support {}, using {}",
+        codec.name(),
+        preferred.name(),
+        fallback.name()
+    );
+
+    Ok(fallback)
+}
+
+/// Round the output size down to what `pixel_format` can hold.
+///
+/// Formats with subsampled chroma such as yuv420p store one chroma sample per 2x2
+/// pixels, so H.264/HEVC encoders refuse odd sizes; an odd source (e.g. 1921x1080)
+/// is scaled to the nearest smaller size that fits (1920x1080) and the change logged.
+pub fn fit_to_chroma(pixel_format: Pixel, width: u32, height: u32) -> (u32, u32) {
+    let Some(descriptor) = pixel_format.descriptor() else {
+        return (width, height);
+    };
+    let align_w = 1u32 << descriptor.log2_chroma_w();
+    let align_h = 1u32 << descriptor.log2_chroma_h();
+
+    let fitted = (
+        (width / align_w * align_w).max(align_w),
+        (height / align_h * align_h).max(align_h),
+    );
+    if fitted != (width, height) {
+        info!(
+            "{} needs dimensions in multiples of {}x{}, adjusting {}x{} to {}x{}",
+            pixel_format.name(),
+            align_w,
+            align_h,
+            width,
+            height,
+            fitted.0,
+            fitted.1
+        );
+    }
+    fitted
+}
+
+/// Private encoder options for a fixed keyframe interval.
+///
+/// The GOP size itself is set on the encoder; x264/x265 additionally get a matching
+/// minimum interval so keyframes are evenly spaced, and scene-cut detection is turned
+/// off when `scene_cut` is `Some(false)`.
+pub fn keyframe_options(
+    codec_name: &str,
+    interval: u32,
+    scene_cut: Option<bool>,
+) -> Vec<(&'static str, String)> {
+    let mut params = vec![format!("keyint={}", interval), format!("min-keyint={}", interval)];
+    if scene_cut == Some(false) {
+        params.push("scenecut=0".to_string());
+    }
+
+    match codec_name {
+        "libx264" => vec![("x264-params", params.join(":"))],
+        "libx265" => vec![("x265-params", params.join(":"))],
+        name if name.ends_with("_nvenc") && scene_cut == Some(false) => {
+            vec![("no-scenecut", "1".to_string())]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// One pass of a two-pass encode, with the stats file it writes or reads
+#[derive(Debug, Clone, Copy)]
+pub enum EncodingPass<'a> {
+    /// Analysis pass, writes the stats file
+    First(&'a Path),
+    /// Final pass, reads the stats file
+    Second(&'a Path),
+}
+
+impl EncodingPass<'_> {
+    /// Codec flags enabling this pass
+    pub fn flags(&self) -> codec::Flags {
+        match self {
+            EncodingPass::First(_) => codec::Flags::PASS1,
+            EncodingPass::Second(_) => codec::Flags::PASS2,
+        }
+    }
+
+    /// Path of the stats file shared by both passes
+    pub fn stats_path(&self) -> &Path {
+        match self {
+            EncodingPass::First(path) | EncodingPass::Second(path) => path,
+        }
+    }
+}
+
+/// Whether two-pass encoding is supported for the encoder
+pub fn supports_two_pass(codec_name: &str) -> bool {
+    codec_name == "libx264"
+}
+
+/// Private encoder options pointing the encoder at the stats file
+pub fn two_pass_options(codec_name: &str, pass: EncodingPass) -> Vec<(&'static str, String)> {
+    match codec_name {
+        "libx264" => vec![("stats", pass.stats_path().to_string_lossy().into_owned())],
+        _ => Vec::new(),
+    }
+}
+
+/// Remove the stats file and the side files the encoder writes next to it
+pub fn remove_two_pass_stats(stats_path: &Path) {
+    for suffix in ["", ".temp", ".mbtree", ".mbtree.temp"] {
+        let mut path = stats_path.as_os_str().to_owned();
+        path.push(suffix);
+        let path = PathBuf::from(path);
+
+        if path.exists() {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("Failed to remove two-pass stats file {:?}: {}", path, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use log::{info, warn};
+
+use ffmpeg::codec;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::ffmpeg_log::report_warning;
+use super::filters;
+use super::verify::expected_duration;
+use super::{ProcessingOptions, VideoInfo, VideoProcessor};
+
+/// Audio bitrate assumed for every output (the transcoder uses at least 128 kbps)
+const AUDIO_ALLOWANCE_BPS: f64 = 128_000.0;
+
+/// Muxing overhead added on top of the stream sizes
+const CONTAINER_OVERHEAD: f64 = 1.02;
+
+/// Bits per pixel of an H.264 encode at its default CRF of 23
+const BASE_BITS_PER_PIXEL: f64 = 0.08;
+
+/// CRF the bits-per-pixel model is anchored at
+const BASE_QUALITY: f64 = 23.0;
+
+/// Frame rate assumed when neither the options nor the source provide one
+const DEFAULT_FRAMERATE: f64 = 30.0;
+
+/// Bytes in one megabyte of `target_size_mb` (MiB, as file managers show sizes)
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+/// Lowest video bitrate a target size is turned into; below it the picture falls apart
+const MIN_TARGET_VIDEO_BPS: f64 = 100_000.0;
+
+/// Video bitrate that makes `duration_secs` of output about `target_size_mb` large.
+///
+/// The audio allowance and the muxing overhead are taken off the size first. Returns
+/// `None` without a known duration.
+pub fn bitrate_for_target_size(target_size_mb: u64, duration_secs: f64) -> Option<u64> {
+    if duration_secs <= 0.0 {
+        return None;
+    }
+
+    let total_bps = target_size_mb as f64 * BYTES_PER_MB * 8.0 / CONTAINER_OVERHEAD / duration_secs;
+    let video_bps = total_bps - AUDIO_ALLOWANCE_BPS;
+    if video_bps < MIN_TARGET_VIDEO_BPS {
+        warn!(
+            "{} MB is too small for {:.1}s of video, using {} kbps; the file will be larger",
+            target_size_mb,
+            duration_secs,
+            MIN_TARGET_VIDEO_BPS / 1000.0
+        );
+    }
+
+    Some(video_bps.max(MIN_TARGET_VIDEO_BPS) as u64)
+}
+
+impl VideoProcessor {
+    /// Estimate the size in bytes of the file `options` would produce from `input_info`.
+    ///
+    /// With a target bitrate the estimate is usually within 10%. In CRF mode the
+    /// bitrate is derived from the output pixel rate (the size roughly halves every 6
+    /// CRF steps), which depends heavily on the content: expect anything from half to
+    /// twice the real size. Without either, the source bitrate is scaled to the output
+    /// resolution. A fixed 128 kbps allowance covers the audio.
+    pub fn estimate_output_size(&self, input_info: &VideoInfo, options: &ProcessingOptions) -> u64 {
+        if let Some(target_size_mb) = options.target_size_mb {
+            return (target_size_mb as f64 * BYTES_PER_MB) as u64;
+        }
+
+        let start = options.start_time.unwrap_or(0.0).max(0.0);
+        let end = options
+            .end_time
+            .filter(|&end| end > 0.0)
+            .map_or(input_info.duration, |end| end.min(input_info.duration));
+        let duration = (end - start).max(0.0);
+
+        let (source_width, source_height) = options
+            .crop
+            .map_or((input_info.width, input_info.height), |(_, _, w, h)| (w, h));
+        let (width, height) = filters::output_dimensions(options.resolution, source_width, source_height)
+            .unwrap_or((source_width, source_height));
+
+        // Quality (CRF) takes precedence over the bitrate, as in the encoder setup
+        let video_bps = match (options.quality, options.bitrate) {
+            (None, Some(bitrate)) => bitrate as f64,
+            (None, None) if input_info.bitrate > 0 => {
+                // Keep the source bits per pixel at the new resolution
+                let source_pixels = f64::from(input_info.width.max(1)) * f64::from(input_info.height.max(1));
+                let pixel_ratio = f64::from(width) * f64::from(height) / source_pixels;
+                (input_info.bitrate as f64 - AUDIO_ALLOWANCE_BPS).max(AUDIO_ALLOWANCE_BPS) * pixel_ratio
+            }
+            (quality, _) => {
+                let framerate = options
+                    .framerate
+                    .map(f64::from)
+                    .or(Some(f64::from(input_info.framerate)).filter(|&fps| fps > 0.0))
+                    .unwrap_or(DEFAULT_FRAMERATE);
+                let quality = quality.map_or(BASE_QUALITY, f64::from);
+                let bits_per_pixel = BASE_BITS_PER_PIXEL
+                    * codec_efficiency(self.choose_codec(options))
+                    * 2f64.powf((BASE_QUALITY - quality) / 6.0);
+                f64::from(width) * f64::from(height) * framerate * bits_per_pixel
+            }
+        };
+
+        ((video_bps + AUDIO_ALLOWANCE_BPS) * duration / 8.0 * CONTAINER_OVERHEAD) as u64
+    }
+
+    /// Replace `target_size_mb` with the video bitrate reaching it for `input_path`.
+    ///
+    /// The target size wins over an explicit bitrate or quality, which are dropped
+    /// with a warning. Options without a target size are returned unchanged.
+    pub(super) fn apply_target_size(&self, input_path: &str, mut options: ProcessingOptions) -> AppResult<ProcessingOptions> {
+        let Some(target_size_mb) = options.target_size_mb else {
+            return Ok(options);
+        };
+
+        if options.bitrate.is_some() || options.quality.is_some() {
+            report_warning(&format!(
+                "Target size of {} MB set, ignoring the bitrate and quality options",
+                target_size_mb
+            ));
+        }
+
+        let input_duration = self.get_video_info(input_path)?.duration;
+        let duration = expected_duration(input_duration, &options);
+        let bitrate = bitrate_for_target_size(target_size_mb, duration).ok_or_else(|| {
+            AppError::validation_error(
+                format!("Cannot reach a target size for {}: its duration is unknown", input_path),
+                ErrorCode::InvalidArgument,
+                Some("Set a bitrate or quality instead of a target size".to_string()),
+            )
+        })?;
+
+        info!(
+            "Target size {} MB for {:.1}s: video bitrate {} kbps",
+            target_size_mb,
+            duration,
+            bitrate / 1000
+        );
+        if !options.two_pass {
+            info!("Two-pass encoding gets closer to the target size");
+        }
+
+        options.bitrate = Some(bitrate);
+        options.quality = None;
+        Ok(options)
+    }
+}
+
+/// Size of an encode relative to H.264 at the same visual quality
+fn codec_efficiency(codec_id: codec::Id) -> f64 {
+    match codec_id {
+        codec::Id::HEVC | codec::Id::VP9 => 0.6,
+        codec::Id::AV1 => 0.5,
+        codec::Id::MPEG4 | codec::Id::VP8 => 1.5,
+        codec::Id::MPEG2VIDEO => 2.5,
+        codec::Id::MJPEG => 8.0,
+        _ => 1.0,
+    }
+}
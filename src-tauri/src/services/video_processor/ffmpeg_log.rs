@@ -0,0 +1,113 @@
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::Once;
+use log::Level;
+
+use ffmpeg_next as ffmpeg;
+use ffmpeg::ffi;
+
+/// Longest libav log line kept; longer lines are truncated by FFmpeg
+const LOG_LINE_SIZE: usize = 1024;
+
+/// Receives libav warnings and errors logged while it is installed
+pub type WarningHandler = Box<dyn Fn(&str)>;
+
+static INSTALL_CALLBACK: Once = Once::new();
+
+thread_local! {
+    static WARNING_HANDLER: RefCell<Option<WarningHandler>> = RefCell::new(None);
+}
+
+/// Route libav log messages into the `log` crate (target "ffmpeg").
+///
+/// Safe to call more than once; the callback is only installed the first time.
+pub fn install_log_callback() {
+    INSTALL_CALLBACK.call_once(|| unsafe {
+        ffi::av_log_set_callback(Some(log_callback));
+    });
+}
+
+/// Run `f` with `handler` receiving the libav warnings and errors it causes.
+///
+/// Only messages logged on the calling thread are seen; messages from FFmpeg's own
+/// worker threads still reach the log file but not the handler.
+pub fn with_warning_handler<T>(handler: WarningHandler, f: impl FnOnce() -> T) -> T {
+    let previous = WARNING_HANDLER.with(|slot| slot.replace(Some(handler)));
+    let result = f();
+    WARNING_HANDLER.with(|slot| *slot.borrow_mut() = previous);
+    result
+}
+
+/// Report a warning of our own about the current operation, e.g. a setting that was
+/// adjusted, to the installed handler as well as the log
+pub fn report_warning(message: &str) {
+    log::warn!("{}", message);
+    notify_handler(message);
+}
+
+/// Pass `message` to the handler installed on this thread, if any
+fn notify_handler(message: &str) {
+    // `try_with`/`try_borrow` keep a message logged during thread teardown or
+    // from inside the handler from panicking across the FFI boundary
+    let _ = WARNING_HANDLER.try_with(|slot| {
+        if let Ok(handler) = slot.try_borrow() {
+            if let Some(handler) = handler.as_ref() {
+                handler(message);
+            }
+        }
+    });
+}
+
+/// Rust log level for a libav log level, `None` for messages that are not forwarded
+fn log_level(level: c_int) -> Option<Level> {
+    match level {
+        l if l <= ffi::AV_LOG_ERROR => Some(Level::Error),
+        l if l <= ffi::AV_LOG_WARNING => Some(Level::Warn),
+        l if l <= ffi::AV_LOG_INFO => Some(Level::Debug),
+        l if l <= ffi::AV_LOG_DEBUG => Some(Level::Trace),
+        _ => None,
+    }
+}
+
+unsafe extern "C" fn log_callback(
+    avcl: *mut c_void,
+    level: c_int,
+    fmt: *const c_char,
+    args: ffi::va_list,
+) {
+    // Respect the level set with `ffmpeg::log::set_level`, like the default callback
+    if level > ffi::av_log_get_level() {
+        return;
+    }
+    let Some(rust_level) = log_level(level) else {
+        return;
+    };
+
+    let mut line = [0 as c_char; LOG_LINE_SIZE];
+    let mut print_prefix: c_int = 1;
+    let written = ffi::av_log_format_line2(
+        avcl,
+        level,
+        fmt,
+        args,
+        line.as_mut_ptr(),
+        LOG_LINE_SIZE as c_int,
+        &mut print_prefix,
+    );
+    if written < 0 {
+        return;
+    }
+
+    let message = CStr::from_ptr(line.as_ptr()).to_string_lossy();
+    let message = message.trim();
+    if message.is_empty() {
+        return;
+    }
+
+    log::log!(target: "ffmpeg", rust_level, "{}", message);
+
+    if rust_level <= Level::Warn {
+        notify_handler(message);
+    }
+}
@@ -0,0 +1,458 @@
+use log::{info, warn};
+
+use ffmpeg::codec::decoder;
+use ffmpeg::filter;
+use ffmpeg::format::Pixel;
+use ffmpeg::util::rational::Rational;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::gif;
+use super::orientation;
+use super::subtitles::{self, SubtitleMode};
+use super::watermark;
+use super::ProcessingOptions;
+
+/// Blur radius is the region's shortest side divided by this value.
+/// Together with `BLUR_POWER` this wipes out any recoverable detail.
+const BLUR_RADIUS_DIVISOR: u32 = 4;
+
+/// Number of times the box blur is applied to each region
+const BLUR_POWER: u32 = 4;
+
+/// Smallest width/height (in pixels) a blur region may have
+const MIN_BLUR_REGION_SIZE: u32 = 8;
+
+/// Valid range of the `nlmeans` denoising strength
+pub const MIN_DENOISE_STRENGTH: f32 = 1.0;
+pub const MAX_DENOISE_STRENGTH: f32 = 30.0;
+
+/// Builds a libavfilter graph description out of linear filters and labeled branches.
+///
+/// The graph always reads from the `[in]` pad (the buffer source) and ends at the
+/// `[out]` pad (the buffer sink).
+pub struct FilterChain {
+    segments: Vec<String>,
+    pending: Vec<String>,
+    label: String,
+    next_label: usize,
+}
+
+impl FilterChain {
+    /// Create an empty chain reading from `[in]`
+    pub fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+            pending: Vec::new(),
+            label: "in".to_string(),
+            next_label: 0,
+        }
+    }
+
+    /// Append a linear filter (e.g. `scale=1280:720`) to the current chain
+    pub fn push(&mut self, filter: impl Into<String>) {
+        self.pending.push(filter.into());
+    }
+
+    /// Generate a label that is unique within this graph
+    pub fn fresh_label(&mut self, prefix: &str) -> String {
+        let label = format!("{}{}", prefix, self.next_label);
+        self.next_label += 1;
+        label
+    }
+
+    /// Terminate the current linear chain and return the label carrying its output
+    pub fn close(&mut self) -> String {
+        if !self.pending.is_empty() {
+            let output = self.fresh_label("v");
+            self.segments.push(format!("[{}]{}[{}]", self.label, self.pending.join(","), output));
+            self.pending.clear();
+            self.label = output;
+        }
+        self.label.clone()
+    }
+
+    /// Add a complete segment (with its own labels) to the graph
+    pub fn add_segment(&mut self, segment: impl Into<String>) {
+        self.segments.push(segment.into());
+    }
+
+    /// Continue the linear chain from the given label
+    pub fn continue_from(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+
+    /// Whether any filter has been added
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty() && self.pending.is_empty()
+    }
+
+    /// Produce the final graph description, connected to `[out]`
+    pub fn build(mut self) -> String {
+        if self.pending.is_empty() {
+            self.pending.push("null".to_string());
+        }
+        self.segments.push(format!("[{}]{}[out]", self.label, self.pending.join(",")));
+        self.segments.join(";")
+    }
+}
+
+impl Default for FilterChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse one side of a requested resolution; `-1` (or `0`) means "keep the aspect ratio"
+pub fn parse_dimension(value: &str) -> Option<u32> {
+    match value.trim() {
+        "-1" => Some(0),
+        value => value.parse::<u32>().ok(),
+    }
+}
+
+/// Parse a crop rectangle written as `x,y,width,height`
+pub fn parse_crop(value: &str) -> AppResult<(u32, u32, u32, u32)> {
+    let parts: Vec<Option<u32>> = value.split(',').map(|part| part.trim().parse::<u32>().ok()).collect();
+    match parts[..] {
+        [Some(x), Some(y), Some(width), Some(height)] => Ok((x, y, width, height)),
+        _ => Err(AppError::validation_error(
+            format!("Invalid crop: {}", value),
+            ErrorCode::InvalidArgument,
+            Some("Crop must be \"x,y,width,height\" in pixels, e.g. \"0,0,1280,720\"".to_string()),
+        )),
+    }
+}
+
+/// Size of the picture after `crop`, which must lie inside the `width`x`height` source
+///
+/// The crop applies to the picture as shown, i.e. after turning and flipping it.
+pub fn cropped_dimensions(crop: Option<(u32, u32, u32, u32)>, width: u32, height: u32) -> AppResult<(u32, u32)> {
+    match crop {
+        None => Ok((width, height)),
+        Some((x, y, crop_width, crop_height))
+            if x.saturating_add(crop_width) > width || y.saturating_add(crop_height) > height =>
+        {
+            Err(AppError::validation_error(
+                format!(
+                    "Crop {}x{} at ({}, {}) is outside the {}x{} video",
+                    crop_width, crop_height, x, y, width, height
+                ),
+                ErrorCode::InvalidArgument,
+                Some("The crop rectangle must fit inside the video".to_string()),
+            ))
+        }
+        Some((_, _, crop_width, crop_height)) => Ok((crop_width, crop_height)),
+    }
+}
+
+/// Resolve the requested resolution against the source size.
+///
+/// A side of 0 is computed from the source aspect ratio and rounded to an even number,
+/// which most encoders require for 4:2:0 video. Without a resolution the source size
+/// is kept.
+pub fn output_dimensions(
+    resolution: Option<(u32, u32)>,
+    source_width: u32,
+    source_height: u32,
+) -> AppResult<(u32, u32)> {
+    let scaled = |target: u32, from: u32, to: u32| -> u32 {
+        let exact = f64::from(target) * f64::from(to) / f64::from(from.max(1));
+        ((exact / 2.0).round() as u32 * 2).max(2)
+    };
+
+    match resolution {
+        None => Ok((source_width, source_height)),
+        Some((0, 0)) => Err(AppError::validation_error(
+            "Resolution needs at least a width or a height".to_string(),
+            ErrorCode::InvalidArgument,
+            Some("Use -1 for only one dimension to keep the aspect ratio".to_string()),
+        )),
+        Some((width, 0)) => Ok((width, scaled(width, source_width, source_height))),
+        Some((0, height)) => Ok((scaled(height, source_height, source_width), height)),
+        Some((width, height)) => Ok((width, height)),
+    }
+}
+
+/// Largest size with the aspect ratio of `width`x`height` that is at most `factor`
+/// times the source on both sides, or `None` if the requested size already is
+pub fn cap_upscale(
+    width: u32,
+    height: u32,
+    source_width: u32,
+    source_height: u32,
+    factor: f64,
+) -> Option<(u32, u32)> {
+    let scale = (f64::from(width) / f64::from(source_width.max(1)))
+        .max(f64::from(height) / f64::from(source_height.max(1)));
+    // Allow for rounding in sizes computed from the source
+    if scale <= factor + 1e-6 {
+        return None;
+    }
+
+    let fit = |size: u32| ((f64::from(size) * factor / scale / 2.0).floor() as u32 * 2).max(2);
+    Some((fit(width), fit(height)))
+}
+
+/// Quote a file path for use as a filter option value inside a graph description.
+///
+/// Backslashes become forward slashes (accepted on Windows too), `:` is escaped for
+/// the option parser, and the whole value is single-quoted for the graph parser.
+pub fn escape_filter_path(path: &str) -> String {
+    let escaped = path
+        .replace('\\', "/")
+        .replace(':', "\\:")
+        .replace('\'', "'\\\\\\''");
+    format!("'{}'", escaped)
+}
+
+/// Keep only blur regions that fit inside the frame, logging a warning for the rest
+pub fn valid_blur_regions(
+    regions: &[(u32, u32, u32, u32)],
+    frame_width: u32,
+    frame_height: u32,
+) -> Vec<(u32, u32, u32, u32)> {
+    regions
+        .iter()
+        .copied()
+        .filter(|&(x, y, w, h)| {
+            if w < MIN_BLUR_REGION_SIZE || h < MIN_BLUR_REGION_SIZE {
+                warn!(
+                    "Skipping blur region {}x{} at ({}, {}): smaller than {}px",
+                    w, h, x, y, MIN_BLUR_REGION_SIZE
+                );
+                return false;
+            }
+
+            if x.saturating_add(w) > frame_width || y.saturating_add(h) > frame_height {
+                warn!(
+                    "Skipping blur region {}x{} at ({}, {}): outside frame bounds {}x{}",
+                    w, h, x, y, frame_width, frame_height
+                );
+                return false;
+            }
+
+            true
+        })
+        .collect()
+}
+
+/// Add a crop + boxblur + overlay branch for every region to the chain
+pub fn add_blur_regions(chain: &mut FilterChain, regions: &[(u32, u32, u32, u32)]) {
+    if regions.is_empty() {
+        return;
+    }
+
+    let base = chain.close();
+
+    // Split the stream into the base picture plus one copy per region
+    let base_label = chain.fresh_label("base");
+    let region_labels: Vec<String> = regions.iter().map(|_| chain.fresh_label("region")).collect();
+    chain.add_segment(format!(
+        "[{}]split={}[{}]{}",
+        base,
+        regions.len() + 1,
+        base_label,
+        region_labels.iter().map(|l| format!("[{}]", l)).collect::<String>()
+    ));
+
+    let mut current = base_label;
+    for (&(x, y, w, h), region_label) in regions.iter().zip(region_labels) {
+        // Chroma planes are half size for subsampled formats, so keep their radius
+        // within boxblur's limits
+        let luma_radius = (w.min(h) / BLUR_RADIUS_DIVISOR).max(1);
+        let chroma_radius = (w.min(h) / (BLUR_RADIUS_DIVISOR * 2)).max(1);
+
+        let blurred = chain.fresh_label("blurred");
+        chain.add_segment(format!(
+            "[{}]crop={}:{}:{}:{},boxblur=luma_radius={}:luma_power={}:chroma_radius={}:chroma_power={}[{}]",
+            region_label, w, h, x, y, luma_radius, BLUR_POWER, chroma_radius, BLUR_POWER, blurred
+        ));
+
+        let overlaid = chain.fresh_label("v");
+        chain.add_segment(format!("[{}][{}]overlay={}:{}[{}]", current, blurred, x, y, overlaid));
+        current = overlaid;
+    }
+
+    chain.continue_from(current);
+}
+
+/// Add a denoise filter to the chain.
+///
+/// Without an explicit strength the fast `hqdn3d` filter is used with its defaults.
+/// With a strength, the much slower but higher quality `nlmeans` filter is used.
+pub fn add_denoise(chain: &mut FilterChain, strength: Option<f32>) {
+    match strength {
+        Some(strength) => {
+            let strength = strength.clamp(MIN_DENOISE_STRENGTH, MAX_DENOISE_STRENGTH);
+            warn!(
+                "Applying nlmeans denoise (strength {:.1}); this is CPU intensive and can slow encoding down considerably",
+                strength
+            );
+            chain.push(format!("nlmeans=s={:.1}", strength));
+        }
+        None => {
+            info!("Applying hqdn3d denoise");
+            chain.push("hqdn3d");
+        }
+    }
+}
+
+/// Convert the frame rate after scaling, so interpolation runs at the output size.
+///
+/// `fps` drops or duplicates frames while keeping their timestamps, so the video
+/// keeps its duration and stays in sync with the (untouched) audio. `minterpolate`
+/// synthesizes in-between frames with motion compensation instead; it is smooth for
+/// slow motion and up-conversion but can be many times slower than the encode itself.
+pub fn add_framerate_conversion(chain: &mut FilterChain, framerate: f32, interpolate: bool) {
+    if interpolate {
+        warn!(
+            "Interpolating to {} fps with minterpolate; this is CPU intensive and can slow encoding down considerably",
+            framerate
+        );
+        chain.push(format!(
+            "minterpolate=fps={}:mi_mode=mci:mc_mode=aobmc:me_mode=bidir:vsbmc=1",
+            framerate
+        ));
+    } else {
+        info!("Converting frame rate to {} fps", framerate);
+        chain.push(format!("fps={}", framerate));
+    }
+}
+
+/// Build the video filter graph description for the given options
+///
+/// The picture is first turned by `rotation` (clockwise degrees, see
+/// `orientation::output_rotation`), then flipped and then cropped, so the crop refers to
+/// the turned and flipped picture, and the source size and the blur regions to the
+/// cropped one.
+pub fn build_video_filter_spec(
+    options: &ProcessingOptions,
+    rotation: i32,
+    source_width: u32,
+    source_height: u32,
+    output_width: u32,
+    output_height: u32,
+) -> String {
+    let mut chain = FilterChain::new();
+
+    if rotation != 0 {
+        info!("Turning the picture {} degrees", rotation);
+        orientation::add_orientation(&mut chain, rotation);
+    }
+
+    if let Some(true) = options.flip {
+        info!("Flipping the picture horizontally");
+        chain.push("hflip");
+    }
+
+    if let Some(true) = options.flop {
+        info!("Flipping the picture vertically");
+        chain.push("vflip");
+    }
+
+    // Blur regions, subtitles and the resolution apply to the cropped picture
+    if let Some((x, y, width, height)) = options.crop {
+        info!("Cropping to {}x{} at ({}, {})", width, height, x, y);
+        chain.push(format!("crop={}:{}:{}:{}", width, height, x, y));
+    }
+
+    if let Some(true) = options.denoise {
+        add_denoise(&mut chain, options.denoise_strength);
+    }
+
+    if let Some(regions) = &options.blur_regions {
+        let regions = valid_blur_regions(regions, source_width, source_height);
+        info!("Applying blur to {} regions", regions.len());
+        add_blur_regions(&mut chain, &regions);
+    }
+
+    // Burn subtitles before scaling so they are laid out at the source resolution
+    if let Some(subtitle_config) = &options.subtitles {
+        if subtitle_config.mode == SubtitleMode::Burn {
+            subtitles::add_burned_subtitles(&mut chain, subtitle_config);
+        }
+    }
+
+    if (output_width, output_height) != (source_width, source_height) {
+        chain.push(format!("scale={}:{}", output_width, output_height));
+    }
+
+    // GIFs convert their frame rate in the palette stage
+    if let Some(framerate) = options.framerate.filter(|_| !gif::is_gif_output(options)) {
+        add_framerate_conversion(&mut chain, framerate, options.interpolate);
+    }
+
+    // Composite after scaling so the watermark keeps its size on the output
+    if let Some(watermark) = &options.watermark {
+        watermark::add_watermark(&mut chain, watermark);
+    }
+
+    // Palette generation goes last so it sees the final picture
+    if gif::is_gif_output(options) {
+        gif::add_palette(&mut chain, gif::gif_fps(options));
+    }
+
+    chain.build()
+}
+
+/// Arguments of a `buffer` source fed with the frames of `decoder`
+pub fn buffer_args(decoder: &decoder::Video, time_base: Rational) -> String {
+    let aspect_ratio = if decoder.aspect_ratio().numerator() > 0 {
+        decoder.aspect_ratio()
+    } else {
+        Rational::new(1, 1)
+    };
+
+    format!(
+        "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}",
+        decoder.width(),
+        decoder.height(),
+        ffmpeg::ffi::AVPixelFormat::from(decoder.format()) as i32,
+        time_base.numerator(),
+        time_base.denominator(),
+        aspect_ratio.numerator(),
+        aspect_ratio.denominator(),
+    )
+}
+
+/// Create and configure a filter graph between the decoder and the encoder
+pub fn create_video_filter_graph(
+    decoder: &decoder::Video,
+    time_base: Rational,
+    output_format: Pixel,
+    spec: &str,
+) -> AppResult<filter::Graph> {
+    let mut graph = filter::Graph::new();
+    let args = buffer_args(decoder, time_base);
+
+    let map_err = |e: ffmpeg::Error| {
+        AppError::video_error(
+            format!("Cannot create filter graph: {}", e),
+            ErrorCode::VideoProcessingFailed,
+            Some(format!("Error configuring video filters: {}", spec)),
+        )
+    };
+
+    let buffer = filter::find("buffer").ok_or_else(|| map_err(ffmpeg::Error::FilterNotFound))?;
+    let buffersink =
+        filter::find("buffersink").ok_or_else(|| map_err(ffmpeg::Error::FilterNotFound))?;
+
+    graph.add(&buffer, "in", &args).map_err(map_err)?;
+    graph.add(&buffersink, "out", "").map_err(map_err)?;
+
+    if let Some(mut out) = graph.get("out") {
+        out.set_pixel_format(output_format);
+    }
+
+    graph
+        .output("in", 0)
+        .and_then(|parser| parser.input("out", 0))
+        .and_then(|parser| parser.parse(spec))
+        .map_err(map_err)?;
+    graph.validate().map_err(map_err)?;
+
+    info!("Video filter graph: {}", spec);
+
+    Ok(graph)
+}
@@ -0,0 +1,302 @@
+use std::fs;
+use std::path::Path;
+use log::info;
+
+use ffmpeg::codec;
+use ffmpeg::format::input;
+use ffmpeg::media::Type as MediaType;
+use ffmpeg::util::frame::video::Video as VideoFrame;
+use ffmpeg::Packet;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::filters;
+use super::thumbnail::{image_encoder, open_image_encoder};
+
+/// Image formats `export_frames` can write: (format name, file extension, codec)
+const FRAME_FORMATS: &[(&str, &str, codec::Id)] = &[
+    ("png", "png", codec::Id::PNG),
+    ("jpg", "jpg", codec::Id::MJPEG),
+    ("jpeg", "jpg", codec::Id::MJPEG),
+];
+
+/// Which decoded frames are written
+enum Sampling {
+    EveryNth(u64),
+    Fps(f64),
+}
+
+/// File name of the frame at `index`, e.g. `frame_000001.png`
+fn frame_file_name(index: usize, extension: &str) -> String {
+    format!("frame_{:06}.{}", index, extension)
+}
+
+/// Decode `input_path` and write frames into `output_dir` as numbered PNG or JPEG images.
+///
+/// Frames are sampled at `fps` frames per second or every `every_nth` frame; with
+/// neither set every frame is written. Files are named `frame_000001.png` and so on.
+/// Progress is reported in percent of the duration decoded; returning `false` from the
+/// callback cancels the export. Returns the number of images written.
+pub fn export_frames(
+    input_path: &str,
+    output_dir: &str,
+    fps: Option<f64>,
+    every_nth: Option<u32>,
+    format: &str,
+    progress_callback: &dyn Fn(f32) -> bool,
+) -> AppResult<usize> {
+    let format = format.to_lowercase();
+    let &(_, extension, codec_id) = FRAME_FORMATS
+        .iter()
+        .find(|(name, _, _)| *name == format)
+        .ok_or_else(|| {
+            AppError::validation_error(
+                format!("Unsupported frame format: {}", format),
+                ErrorCode::InvalidArgument,
+                Some("Frames can be saved as png, jpg or jpeg".to_string()),
+            )
+        })?;
+
+    let sampling = match (fps, every_nth) {
+        (Some(_), Some(_)) => {
+            return Err(AppError::validation_error(
+                "Both fps and every_nth are set".to_string(),
+                ErrorCode::InvalidArgument,
+                Some("Sample frames either at an fps or every Nth frame, not both".to_string()),
+            ));
+        }
+        (Some(fps), None) if !fps.is_finite() || fps <= 0.0 => {
+            return Err(AppError::validation_error(
+                format!("Invalid frame rate: {}", fps),
+                ErrorCode::InvalidArgument,
+                Some("The frame rate must be greater than 0".to_string()),
+            ));
+        }
+        (Some(fps), None) => Sampling::Fps(fps),
+        (None, Some(0)) => {
+            return Err(AppError::validation_error(
+                "every_nth must be at least 1".to_string(),
+                ErrorCode::InvalidArgument,
+                None,
+            ));
+        }
+        (None, Some(n)) => Sampling::EveryNth(u64::from(n)),
+        (None, None) => Sampling::EveryNth(1),
+    };
+
+    if !Path::new(input_path).exists() {
+        return Err(AppError::io_error(
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Input file not found"),
+            ErrorCode::FileNotFound,
+            Some(format!("Input video file not found: {}", input_path)),
+        ));
+    }
+
+    let mut input_ctx = input(input_path).map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot open input file '{}': {}", input_path, e),
+            ErrorCode::FFmpegInitError,
+            Some(format!("Error opening input file: {}", input_path)),
+        )
+    })?;
+
+    let input_stream = input_ctx.streams().best(MediaType::Video).ok_or_else(|| {
+        AppError::video_error(
+            format!("No video stream found in file: {}", input_path),
+            ErrorCode::InvalidVideoFormat,
+            Some("Cannot export frames from an audio-only file".to_string()),
+        )
+    })?;
+
+    let stream_index = input_stream.index();
+    let time_base = input_stream.time_base();
+    let start_time = if input_stream.start_time() == ffmpeg::ffi::AV_NOPTS_VALUE {
+        0
+    } else {
+        input_stream.start_time()
+    };
+
+    // Sampling above the source rate would only write duplicates
+    let source_rate = [input_stream.avg_frame_rate(), input_stream.rate()]
+        .into_iter()
+        .find(|rate| rate.numerator() > 0 && rate.denominator() > 0)
+        .map(f64::from);
+    if let (Sampling::Fps(fps), Some(source_rate)) = (&sampling, source_rate) {
+        if *fps > source_rate + 0.01 {
+            return Err(AppError::validation_error(
+                format!("Requested {} fps exceeds the source frame rate of {:.3} fps", fps, source_rate),
+                ErrorCode::InvalidArgument,
+                Some(format!("Use at most {:.3} fps for this video", source_rate)),
+            ));
+        }
+    }
+
+    let mut decoder = codec::context::Context::from_parameters(input_stream.parameters())
+        .and_then(|ctx| ctx.decoder().video())
+        .map_err(|e| {
+            AppError::video_error(
+                format!("Cannot create decoder: {}", e),
+                ErrorCode::DecodingError,
+                Some(format!("Error creating video decoder for file: {}", input_path)),
+            )
+        })?;
+
+    fs::create_dir_all(output_dir).map_err(|e| {
+        AppError::io_error(
+            e,
+            ErrorCode::DirectoryError,
+            Some(format!("Failed to create output directory: {}", output_dir)),
+        )
+    })?;
+
+    let (encoder_codec, pixel_format) = image_encoder(codec_id)?;
+    let mut filter_graph = filters::create_video_filter_graph(&decoder, time_base, pixel_format, "null")?;
+    let mut encoder = open_image_encoder(encoder_codec, pixel_format, decoder.width(), decoder.height())?;
+
+    let duration_secs = input_ctx.duration() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
+    let mut exporter = FrameExporter {
+        output_dir: Path::new(output_dir),
+        extension,
+        sampling,
+        decoded_count: 0,
+        written_count: 0,
+        next_capture_secs: 0.0,
+    };
+
+    let mut decoded = VideoFrame::empty();
+    for (stream, packet) in input_ctx.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+
+        if decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            let position_secs = decoded
+                .timestamp()
+                .map(|pts| (pts - start_time) as f64 * f64::from(time_base))
+                .unwrap_or(0.0);
+
+            if exporter.should_write(position_secs) {
+                exporter.write_frame(&decoded, &mut filter_graph, &mut encoder)?;
+            }
+
+            if duration_secs > 0.0 {
+                let progress = (position_secs / duration_secs * 100.0).clamp(0.0, 99.9) as f32;
+                if !progress_callback(progress) {
+                    return Err(AppError::video_error(
+                        "Processing canceled by user".to_string(),
+                        ErrorCode::VideoProcessingFailed,
+                        Some("Frame export was canceled".to_string()),
+                    ));
+                }
+            }
+        }
+    }
+
+    let _ = decoder.send_eof();
+    while decoder.receive_frame(&mut decoded).is_ok() {
+        let position_secs = decoded
+            .timestamp()
+            .map(|pts| (pts - start_time) as f64 * f64::from(time_base))
+            .unwrap_or(0.0);
+        if exporter.should_write(position_secs) {
+            exporter.write_frame(&decoded, &mut filter_graph, &mut encoder)?;
+        }
+    }
+
+    progress_callback(100.0);
+
+    info!(
+        "Exported {} of {} frames from {} to {}",
+        exporter.written_count, exporter.decoded_count, input_path, output_dir
+    );
+
+    Ok(exporter.written_count)
+}
+
+/// Sampling state and output naming for one export
+struct FrameExporter<'a> {
+    output_dir: &'a Path,
+    extension: &'static str,
+    sampling: Sampling,
+    decoded_count: u64,
+    written_count: usize,
+    next_capture_secs: f64,
+}
+
+impl FrameExporter<'_> {
+    /// Whether the next decoded frame, shown at `position_secs`, is written
+    fn should_write(&mut self, position_secs: f64) -> bool {
+        let index = self.decoded_count;
+        self.decoded_count += 1;
+
+        match self.sampling {
+            Sampling::EveryNth(n) => index % n == 0,
+            Sampling::Fps(fps) => {
+                if position_secs + f64::EPSILON < self.next_capture_secs {
+                    return false;
+                }
+                // Skip capture points a gap in the timestamps jumped over
+                let interval = 1.0 / fps;
+                while self.next_capture_secs <= position_secs {
+                    self.next_capture_secs += interval;
+                }
+                true
+            }
+        }
+    }
+
+    fn write_frame(
+        &mut self,
+        frame: &VideoFrame,
+        filter_graph: &mut ffmpeg::filter::Graph,
+        encoder: &mut ffmpeg::encoder::Video,
+    ) -> AppResult<()> {
+        let filter_error = |e: ffmpeg::Error| {
+            AppError::video_error(
+                format!("Error converting frame: {}", e),
+                ErrorCode::VideoProcessingFailed,
+                Some("Error filtering exported frame".to_string()),
+            )
+        };
+        let encode_error = |e: ffmpeg::Error| {
+            AppError::video_error(
+                format!("Error encoding frame: {}", e),
+                ErrorCode::EncodingError,
+                Some("Error encoding exported frame".to_string()),
+            )
+        };
+
+        if let Some(mut source) = filter_graph.get("in") {
+            source.source().add(frame).map_err(filter_error)?;
+        }
+
+        let mut converted = VideoFrame::empty();
+        while let Some(mut sink) = filter_graph.get("out") {
+            if sink.sink().frame(&mut converted).is_err() {
+                break;
+            }
+
+            encoder.send_frame(&converted).map_err(encode_error)?;
+            let mut packet = Packet::empty();
+            while encoder.receive_packet(&mut packet).is_ok() {
+                let data = packet.data().ok_or_else(|| encode_error(ffmpeg::Error::InvalidData))?;
+
+                self.written_count += 1;
+                let path = self.output_dir.join(frame_file_name(self.written_count, self.extension));
+                fs::write(&path, data).map_err(|e| {
+                    AppError::io_error(
+                        e,
+                        ErrorCode::FileWriteError,
+                        Some(format!("Failed to write frame: {}", path.display())),
+                    )
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
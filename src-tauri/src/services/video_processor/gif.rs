@@ -0,0 +1,65 @@
+use log::info;
+
+use super::filters::FilterChain;
+use super::ProcessingOptions;
+
+/// Frame rate of a GIF without `gif_fps`
+///
+/// GIF frames are stored uncompressed relative to each other, so the file size grows
+/// almost linearly with the frame rate. 15 fps keeps motion smooth enough for clips.
+pub const DEFAULT_GIF_FPS: f32 = 15.0;
+
+/// Highest GIF frame rate; frame delays are stored in hundredths of a second
+pub const MAX_GIF_FPS: f32 = 50.0;
+
+/// Width a GIF is scaled down to without `gif_max_width`
+///
+/// Size grows with the pixel count, and a full HD GIF of a few seconds easily
+/// reaches hundreds of megabytes. Narrower sources are never scaled up.
+pub const DEFAULT_GIF_MAX_WIDTH: u32 = 480;
+
+/// Whether the options produce a GIF, which goes through palette generation
+pub fn is_gif_output(options: &ProcessingOptions) -> bool {
+    options.output_format.eq_ignore_ascii_case("gif")
+        || (!options.use_gpu && options.cpu_codec.as_deref() == Some("gif"))
+}
+
+/// Frame rate of the GIF
+pub fn gif_fps(options: &ProcessingOptions) -> f32 {
+    options.gif_fps.unwrap_or(DEFAULT_GIF_FPS)
+}
+
+/// Scale `(width, height)` down to the maximum GIF width, keeping the aspect ratio
+pub fn limit_dimensions(options: &ProcessingOptions, width: u32, height: u32) -> (u32, u32) {
+    let max_width = options.gif_max_width.unwrap_or(DEFAULT_GIF_MAX_WIDTH);
+    if width <= max_width {
+        return (width, height);
+    }
+
+    let scaled_height = (f64::from(height) * f64::from(max_width) / f64::from(width)).round() as u32;
+    info!("Scaling GIF from {}x{} down to {}x{}", width, height, max_width, scaled_height);
+    (max_width, scaled_height.max(1))
+}
+
+/// Reduce the frame rate and quantize every frame against a palette built for the clip.
+///
+/// `palettegen` only emits its palette once it has seen every frame, so the frames are
+/// buffered until the input ends; this is why GIFs are also limited in size and rate.
+pub fn add_palette(chain: &mut FilterChain, fps: f32) {
+    chain.push(format!("fps={}", fps));
+    let source = chain.close();
+
+    let frames = chain.fresh_label("frames");
+    let palette_input = chain.fresh_label("palettein");
+    chain.add_segment(format!("[{}]split[{}][{}]", source, frames, palette_input));
+
+    let palette = chain.fresh_label("palette");
+    chain.add_segment(format!("[{}]palettegen=stats_mode=full[{}]", palette_input, palette));
+
+    let quantized = chain.fresh_label("v");
+    chain.add_segment(format!(
+        "[{}][{}]paletteuse=dither=sierra2_4a[{}]",
+        frames, palette, quantized
+    ));
+    chain.continue_from(quantized);
+}
@@ -0,0 +1,192 @@
+use std::os::raw::c_int;
+use std::ptr;
+use log::{info, warn};
+
+use ffmpeg::codec::{self, decoder};
+use ffmpeg::format::Pixel;
+use ffmpeg::software::scaling;
+use ffmpeg::util::frame::video::Video as VideoFrame;
+use ffmpeg_next as ffmpeg;
+use ffmpeg::ffi;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::ProcessingOptions;
+
+/// Hardware device type that pairs with the encoder, or the platform's native API
+fn device_type_for(options: &ProcessingOptions) -> ffi::AVHWDeviceType {
+    use ffi::AVHWDeviceType::*;
+
+    let gpu_codec = options.gpu_codec.as_deref().filter(|_| options.use_gpu).unwrap_or_default();
+    if gpu_codec.ends_with("_nvenc") {
+        return AV_HWDEVICE_TYPE_CUDA;
+    }
+    if gpu_codec.ends_with("_videotoolbox") {
+        return AV_HWDEVICE_TYPE_VIDEOTOOLBOX;
+    }
+    if gpu_codec.ends_with("_vaapi") {
+        return AV_HWDEVICE_TYPE_VAAPI;
+    }
+
+    // QSV and AMF encoders, or CPU encoding: decode through the OS video API
+    if cfg!(target_os = "windows") {
+        AV_HWDEVICE_TYPE_D3D11VA
+    } else if cfg!(target_os = "macos") {
+        AV_HWDEVICE_TYPE_VIDEOTOOLBOX
+    } else {
+        AV_HWDEVICE_TYPE_VAAPI
+    }
+}
+
+/// Pixel format the decoder outputs when it decodes on `device_type`, if supported
+fn hw_pixel_format(codec: &ffmpeg::Codec, device_type: ffi::AVHWDeviceType) -> Option<ffi::AVPixelFormat> {
+    let method = ffi::AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX as c_int;
+
+    (0..)
+        .map_while(|index| {
+            // SAFETY: returns null past the last configuration of the codec
+            let config = unsafe { ffi::avcodec_get_hw_config(codec.as_ptr(), index) };
+            (!config.is_null()).then(|| unsafe { &*config })
+        })
+        .find(|config| config.methods & method != 0 && config.device_type == device_type)
+        .map(|config| config.pix_fmt)
+}
+
+/// Decoding on a GPU, with frames copied back to system memory for the filter graph
+///
+/// Filters and encoders run on software frames, so every decoded frame is downloaded
+/// and converted to the pixel format the filter graph was configured with.
+pub struct HardwareDecoding {
+    device_ctx: *mut ffi::AVBufferRef,
+    hw_format: Pixel,
+    sw_format: Pixel,
+    scaler: Option<scaling::Context>,
+}
+
+// The device context is reference counted by FFmpeg and only used by one encode
+unsafe impl Send for HardwareDecoding {}
+
+impl HardwareDecoding {
+    /// Attach a hardware device to `decoder_ctx` before the decoder is opened
+    ///
+    /// Returns `None` and logs a warning if the decoder or the device does not
+    /// support hardware decoding; the caller then decodes in software.
+    pub fn attach(decoder_ctx: &mut codec::context::Context, options: &ProcessingOptions) -> Option<Self> {
+        let device_type = device_type_for(options);
+        let Some(codec) = decoder::find(decoder_ctx.id()) else {
+            warn!("No decoder for {:?}, decoding in software", decoder_ctx.id());
+            return None;
+        };
+
+        let Some(hw_format) = hw_pixel_format(&codec, device_type) else {
+            warn!("Decoder {} cannot decode on {:?}, decoding in software", codec.name(), device_type);
+            return None;
+        };
+
+        let mut device_ctx = ptr::null_mut();
+        // SAFETY: device_ctx receives a new reference on success and stays null on failure
+        let result = unsafe {
+            ffi::av_hwdevice_ctx_create(&mut device_ctx, device_type, ptr::null(), ptr::null_mut(), 0)
+        };
+        if result < 0 {
+            warn!(
+                "Failed to create {:?} device ({}), decoding in software",
+                device_type,
+                ffmpeg::Error::from(result)
+            );
+            return None;
+        }
+
+        // SAFETY: the decoder takes its own reference to the device
+        unsafe {
+            (*decoder_ctx.as_mut_ptr()).hw_device_ctx = ffi::av_buffer_ref(device_ctx);
+        }
+
+        info!("Decoding {} on {:?}", codec.name(), device_type);
+        Some(Self {
+            device_ctx,
+            hw_format: Pixel::from(hw_format),
+            // Replaced with the decoder's format once it is opened
+            sw_format: Pixel::None,
+            scaler: None,
+        })
+    }
+
+    /// Pixel format the filter graph expects, read from the opened decoder
+    pub fn set_output_format(&mut self, format: Pixel) {
+        self.sw_format = format;
+    }
+
+    /// Copy a GPU frame to system memory in the output format
+    ///
+    /// Returns `None` for frames that were already decoded in software, which
+    /// happens when FFmpeg falls back on its own.
+    pub fn download(&mut self, frame: &VideoFrame) -> AppResult<Option<VideoFrame>> {
+        if frame.format() != self.hw_format {
+            return Ok(None);
+        }
+
+        let mut downloaded = VideoFrame::empty();
+        // SAFETY: both frames are valid; the transfer allocates the destination buffers
+        let result = unsafe {
+            let result = ffi::av_hwframe_transfer_data(downloaded.as_mut_ptr(), frame.as_ptr(), 0);
+            if result >= 0 {
+                ffi::av_frame_copy_props(downloaded.as_mut_ptr(), frame.as_ptr())
+            } else {
+                result
+            }
+        };
+        if result < 0 {
+            return Err(AppError::video_error(
+                format!("Cannot copy decoded frame from the GPU: {}", ffmpeg::Error::from(result)),
+                ErrorCode::DecodingError,
+                Some("Turn off hardware decoding for this file".to_string()),
+            ));
+        }
+
+        if downloaded.format() == self.sw_format {
+            return Ok(Some(downloaded));
+        }
+
+        // The GPU returns its own layout (usually NV12 or P010)
+        if self.scaler.is_none() {
+            let scaler = scaling::Context::get(
+                downloaded.format(),
+                downloaded.width(),
+                downloaded.height(),
+                self.sw_format,
+                downloaded.width(),
+                downloaded.height(),
+                scaling::Flags::POINT,
+            )
+            .map_err(|e| {
+                AppError::video_error(
+                    format!("Cannot convert {:?} frames to {:?}: {}", downloaded.format(), self.sw_format, e),
+                    ErrorCode::DecodingError,
+                    None,
+                )
+            })?;
+            self.scaler = Some(scaler);
+        }
+
+        let mut converted = VideoFrame::empty();
+        if let Some(scaler) = self.scaler.as_mut() {
+            scaler.run(&downloaded, &mut converted).map_err(|e| {
+                AppError::video_error(
+                    format!("Cannot convert downloaded frame: {}", e),
+                    ErrorCode::DecodingError,
+                    None,
+                )
+            })?;
+        }
+        converted.set_pts(downloaded.pts());
+
+        Ok(Some(converted))
+    }
+}
+
+impl Drop for HardwareDecoding {
+    fn drop(&mut self) {
+        // SAFETY: releases the reference created in `attach`
+        unsafe { ffi::av_buffer_unref(&mut self.device_ctx) };
+    }
+}
@@ -0,0 +1,212 @@
+use log::{info, warn};
+
+use ffmpeg::codec;
+use ffmpeg::filter;
+use ffmpeg::format::input;
+use ffmpeg::util::frame::audio::Audio as AudioFrame;
+use ffmpeg::ChannelLayout;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+
+/// Integrated loudness target in LUFS (EBU R128 recommends -23, streaming platforms use -14 to -16)
+const TARGET_LOUDNESS: f64 = -16.0;
+
+/// Maximum true peak in dBTP
+const TARGET_TRUE_PEAK: f64 = -1.5;
+
+/// Loudness range target in LU
+const TARGET_LOUDNESS_RANGE: f64 = 11.0;
+
+/// Distance of the relative gate below the integrated loudness (ITU-R BS.1770)
+const RELATIVE_GATE: f64 = 10.0;
+
+/// Loudness of an audio stream as measured by the `ebur128` filter
+#[derive(Debug, Clone, Copy)]
+pub struct LoudnessMeasurement {
+    pub integrated: f64,  // LUFS
+    pub true_peak: f64,   // dBTP
+    pub range: f64,       // LU
+    pub threshold: f64,   // LUFS
+}
+
+/// Decode audio stream `stream_index` of `input_path` once and measure its loudness.
+///
+/// Progress is reported in percent of the input; returning `false` from the callback
+/// cancels the measurement. Returns `None` if nothing could be measured, e.g. for a
+/// silent track.
+pub fn measure_loudness(
+    input_path: &str,
+    stream_index: usize,
+    progress_callback: &dyn Fn(f32) -> bool,
+) -> AppResult<Option<LoudnessMeasurement>> {
+    let mut input_ctx = input(input_path).map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot open input file '{}': {}", input_path, e),
+            ErrorCode::FFmpegInitError,
+            Some(format!("Error opening input file: {}", input_path)),
+        )
+    })?;
+
+    let stream = input_ctx.stream(stream_index).ok_or_else(|| {
+        AppError::video_error(
+            format!("Audio stream {} not found in {}", stream_index, input_path),
+            ErrorCode::InvalidVideoFormat,
+            None,
+        )
+    })?;
+    let time_base = stream.time_base();
+    let duration_secs = input_ctx.duration() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
+
+    let mut decoder = codec::context::Context::from_parameters(stream.parameters())
+        .and_then(|ctx| ctx.decoder().audio())
+        .map_err(|e| {
+            AppError::video_error(
+                format!("Cannot create audio decoder: {}", e),
+                ErrorCode::DecodingError,
+                Some("Error creating audio decoder for loudness measurement".to_string()),
+            )
+        })?;
+
+    let channel_layout = if decoder.channel_layout().bits() == 0 {
+        ChannelLayout::default(decoder.channels() as i32)
+    } else {
+        decoder.channel_layout()
+    };
+    let args = format!(
+        "time_base={}/{}:sample_rate={}:sample_fmt={}:channel_layout=0x{:x}",
+        time_base.numerator(),
+        time_base.denominator(),
+        decoder.rate(),
+        decoder.format().name(),
+        channel_layout.bits()
+    );
+
+    let map_err = |e: ffmpeg::Error| {
+        AppError::video_error(
+            format!("Cannot measure loudness: {}", e),
+            ErrorCode::VideoProcessingFailed,
+            Some("Error running the ebur128 filter".to_string()),
+        )
+    };
+
+    let mut graph = filter::Graph::new();
+    let abuffer = filter::find("abuffer").ok_or_else(|| map_err(ffmpeg::Error::FilterNotFound))?;
+    let abuffersink =
+        filter::find("abuffersink").ok_or_else(|| map_err(ffmpeg::Error::FilterNotFound))?;
+    graph.add(&abuffer, "in", &args).map_err(map_err)?;
+    graph.add(&abuffersink, "out", "").map_err(map_err)?;
+    graph
+        .output("in", 0)
+        .and_then(|parser| parser.input("out", 0))
+        .and_then(|parser| parser.parse("ebur128=peak=true:metadata=1"))
+        .map_err(map_err)?;
+    graph.validate().map_err(map_err)?;
+
+    let channels = decoder.channels();
+    let mut measurement = None;
+    let mut decoded = AudioFrame::empty();
+    for (packet_stream, packet) in input_ctx.packets() {
+        if packet_stream.index() != stream_index {
+            continue;
+        }
+
+        let position = packet.pts().map(|pts| pts as f64 * f64::from(time_base));
+        let progress = match (position, duration_secs > 0.0) {
+            (Some(position), true) => (position / duration_secs * 100.0).clamp(0.0, 99.9) as f32,
+            _ => 0.0,
+        };
+        if !progress_callback(progress) {
+            return Err(AppError::video_error(
+                "Processing canceled by user".to_string(),
+                ErrorCode::VideoProcessingFailed,
+                Some("Loudness measurement was canceled".to_string()),
+            ));
+        }
+
+        if decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            let timestamp = decoded.timestamp();
+            decoded.set_pts(timestamp);
+            if let Some(mut source) = graph.get("in") {
+                source.source().add(&decoded).map_err(map_err)?;
+            }
+            drain_measurements(&mut graph, channels, &mut measurement);
+        }
+    }
+
+    let _ = decoder.send_eof();
+    while decoder.receive_frame(&mut decoded).is_ok() {
+        if let Some(mut source) = graph.get("in") {
+            source.source().add(&decoded).map_err(map_err)?;
+        }
+    }
+    if let Some(mut source) = graph.get("in") {
+        let _ = source.source().flush();
+    }
+    drain_measurements(&mut graph, channels, &mut measurement);
+
+    match measurement {
+        Some(m) => info!(
+            "Measured loudness of {}: {:.1} LUFS, {:.1} dBTP, {:.1} LU",
+            input_path, m.integrated, m.true_peak, m.range
+        ),
+        None => warn!("Could not measure the loudness of {}", input_path),
+    }
+
+    Ok(measurement)
+}
+
+/// Read the frames waiting in the sink, keeping the latest measurement
+fn drain_measurements(graph: &mut filter::Graph, channels: u16, measurement: &mut Option<LoudnessMeasurement>) {
+    let mut filtered = AudioFrame::empty();
+    while let Some(mut sink) = graph.get("out") {
+        if sink.sink().frame(&mut filtered).is_err() {
+            break;
+        }
+        if let Some(latest) = read_metadata(&filtered, channels) {
+            *measurement = Some(latest);
+        }
+    }
+}
+
+/// Running totals `ebur128` attaches to each frame; the last frame holds the final values
+fn read_metadata(frame: &AudioFrame, channels: u16) -> Option<LoudnessMeasurement> {
+    let metadata = frame.metadata();
+    let value = |key: &str| metadata.get(key).and_then(|v| v.trim().parse::<f64>().ok());
+
+    let integrated = value("lavfi.r128.I").filter(|v| v.is_finite() && *v > -70.0)?;
+    let range = value("lavfi.r128.LRA").filter(|v| v.is_finite()).unwrap_or(0.0);
+    let true_peak = (0..channels)
+        .filter_map(|ch| value(&format!("lavfi.r128.true_peaks_ch{}", ch)))
+        .filter(|v| v.is_finite())
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    Some(LoudnessMeasurement {
+        integrated,
+        true_peak: if true_peak.is_finite() { true_peak } else { TARGET_TRUE_PEAK },
+        range,
+        threshold: integrated - RELATIVE_GATE,
+    })
+}
+
+/// The `loudnorm` filter for a measured stream, or its single-pass (dynamic) form
+///
+/// With a measurement `loudnorm` applies one linear gain where it can, which keeps the
+/// dynamics of the source; without one it adjusts the gain as it goes.
+pub fn loudnorm_filter(measurement: Option<LoudnessMeasurement>) -> String {
+    let target = format!(
+        "loudnorm=I={:.1}:TP={:.1}:LRA={:.1}",
+        TARGET_LOUDNESS, TARGET_TRUE_PEAK, TARGET_LOUDNESS_RANGE
+    );
+
+    match measurement {
+        Some(m) => format!(
+            "{}:measured_I={:.2}:measured_TP={:.2}:measured_LRA={:.2}:measured_thresh={:.2}:linear=true",
+            target, m.integrated, m.true_peak, m.range, m.threshold
+        ),
+        None => target,
+    }
+}
@@ -1,10 +1,48 @@
+mod audio;
+mod audio_extract;
+mod capabilities;
+mod compliance;
+mod containers;
+mod default_codecs;
+mod encoding;
 mod error;
+mod estimate;
+mod ffmpeg_log;
+mod filters;
+mod frames;
+mod gif;
+mod hwaccel;
+mod loudness;
+mod orientation;
+mod plan;
 mod processor;
+mod quality;
+mod remux;
+mod resume;
+mod sequence;
+mod streams;
+mod subtitles;
+mod thumbnail;
+mod validation;
+mod verify;
+mod watermark;
 
 use serde::{Deserialize, Serialize};
 
+pub use capabilities::{CodecSupport, FfmpegCapabilities, HardwareEncoderStatus};
+pub use compliance::ConvertOutcome;
 pub use error::{VideoError, VideoResult};
-pub use processor::VideoProcessor;
+pub use estimate::bitrate_for_target_size;
+pub use ffmpeg_log::{report_warning, with_warning_handler, WarningHandler};
+pub use filters::{cap_upscale, parse_crop, parse_dimension};
+pub use plan::{ProcessingPlan, StreamMapping};
+pub use processor::{VideoProcessor, DEFAULT_MAX_UPSCALE_FACTOR, DEFAULT_PROBE_TIMEOUT_SECS};
+pub use quality::QualityScore;
+pub use resume::ResumePoint;
+pub use sequence::is_sequence_pattern;
+pub use streams::{AudioStreamInfo, StreamInfo};
+pub use subtitles::{SubtitleConfig, SubtitleMode};
+pub use watermark::{WatermarkConfig, WatermarkPosition};
 
 /// Video information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +55,22 @@ pub struct VideoInfo {
     pub bitrate: u64,
     pub codec: String,
     pub framerate: f32,
+    #[serde(default)]
+    pub audio_streams: Vec<AudioStreamInfo>,
+    #[serde(default)]
+    pub has_subtitles: bool,
+    #[serde(default)]
+    pub streams: Vec<StreamInfo>,           // every stream in the file, in index order
+    #[serde(default)]
+    pub rotation: i32,                      // clockwise degrees players turn the picture, width/height are as stored
+}
+
+fn default_gpu_fallback() -> bool {
+    true
+}
+
+fn default_auto_orient() -> bool {
+    true
 }
 
 /// Video processing options
@@ -24,12 +78,48 @@ pub struct VideoInfo {
 pub struct ProcessingOptions {
     pub output_format: String,
     pub output_path: String,
-    pub resolution: Option<(u32, u32)>,
+    pub resolution: Option<(u32, u32)>,     // 0 for one side keeps the aspect ratio
     pub bitrate: Option<u64>,
-    pub framerate: Option<f32>,
+    pub quality: Option<u8>,                // CRF 0-51, preferred over bitrate when set
+    #[serde(default)]
+    pub target_size_mb: Option<u64>,        // output size in MiB, replaces bitrate and quality
+    #[serde(default)]
+    pub two_pass: bool,                     // two-pass encoding for an accurate target bitrate
+    pub framerate: Option<f32>,             // frames are dropped/duplicated to reach it
+    #[serde(default)]
+    pub interpolate: bool,                  // reach `framerate` with minterpolate (slow), off by default
+    /// Frames between keyframes (GOP size). Small intervals make seeking and HLS
+    /// segmenting precise but increase the file size noticeably.
+    pub keyframe_interval: Option<u32>,
+    pub scene_cut: Option<bool>,            // extra keyframes at scene changes, encoder default if unset
+    pub pixel_format: Option<String>,       // e.g. "yuv420p", chosen per container if unset
     pub use_gpu: bool,
     pub gpu_codec: Option<String>,
     pub cpu_codec: Option<String>,
+    #[serde(default = "default_gpu_fallback")]
+    pub gpu_fallback: bool,                 // retry with a software encoder if the GPU encoder fails to open
+    #[serde(default)]
+    pub hardware_decode: bool,              // decode on the GPU, falls back to software if unavailable
+    #[serde(default)]
+    pub threads: Option<usize>,             // encoder/decoder threads, FFmpeg picks (all cores) if unset
+    #[serde(default)]
+    pub convert_if_needed: bool,            // copy inputs already matching codec and resolution instead of encoding
+    #[serde(default)]
+    pub reencode: bool,                     // never stream-copy, e.g. after `reconcile_codec_with_format` dropped a codec
+    #[serde(default)]
+    pub verify_output: bool,                // re-open the output and check its video stream and duration
+    #[serde(default)]
+    pub compute_quality: bool,              // score the output against the input (PSNR/SSIM) after encoding
+
+    // GIF options, only used when the output is a GIF
+    #[serde(default)]
+    pub gif_fps: Option<f32>,               // 15 fps if unset
+    #[serde(default)]
+    pub gif_max_width: Option<u32>,         // 480px if unset, narrower sources keep their width
+
+    // Input stream selection, by index into `VideoInfo::streams`
+    pub video_stream_index: Option<usize>,  // best video stream if unset
+    pub audio_stream_index: Option<usize>,  // best audio stream if unset
 
     // Time options for splitting
     pub start_time: Option<f64>,
@@ -38,12 +128,23 @@ pub struct ProcessingOptions {
     // Edit options
     pub crop: Option<(u32, u32, u32, u32)>, // (x, y, width, height)
     pub rotate: Option<i32>,                // 90, 180, 270 degrees
+    #[serde(default = "default_auto_orient")]
+    pub auto_orient: bool,                  // encode rotation-tagged video upright without the tag; stream copies keep the tag
     pub flip: Option<bool>,                 // horizontal flip
     pub flop: Option<bool>,                 // vertical flip
 
     // Sanitize options
     pub remove_metadata: Option<bool>,      // remove all metadata
     pub blur_regions: Option<Vec<(u32, u32, u32, u32)>>, // regions to blur (x, y, width, height)
+    #[serde(default)]
+    pub normalize_audio: bool,              // EBU R128 loudness normalization, before audio_volume
     pub audio_volume: Option<f32>,          // adjust audio volume (1.0 = normal)
     pub denoise: Option<bool>,              // apply denoising filter
+    pub denoise_strength: Option<f32>,      // nlmeans strength (1.0 - 30.0), hqdn3d if unset
+
+    // Branding options
+    #[serde(default)]
+    pub watermark: Option<WatermarkConfig>, // image overlaid on every frame
+    #[serde(default)]
+    pub subtitles: Option<SubtitleConfig>,  // external subtitles, burned in or muxed
 }
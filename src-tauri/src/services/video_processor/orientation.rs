@@ -0,0 +1,68 @@
+use ffmpeg::codec::packet::side_data::Type as SideDataType;
+use ffmpeg::Stream;
+use ffmpeg_next as ffmpeg;
+
+use super::filters::FilterChain;
+use super::ProcessingOptions;
+
+/// Size of a display matrix: 3x3 32-bit values, the first two columns in 16.16 fixed point
+const DISPLAY_MATRIX_SIZE: usize = 9 * 4;
+
+/// Clockwise rotation players apply to `stream` when showing it, from its display matrix
+///
+/// Phones record upright video as landscape pixels plus this tag. Only multiples of
+/// 90 degrees are returned (0, 90, 180 or 270); 0 if there is no tag.
+pub fn display_rotation(stream: &Stream) -> i32 {
+    let Some(side_data) = stream
+        .side_data()
+        .find(|side_data| side_data.kind() == SideDataType::DisplayMatrix)
+    else {
+        return 0;
+    };
+
+    let data = side_data.data();
+    if data.len() < DISPLAY_MATRIX_SIZE {
+        return 0;
+    }
+    let value = |i: usize| {
+        let bytes = [data[i * 4], data[i * 4 + 1], data[i * 4 + 2], data[i * 4 + 3]];
+        f64::from(i32::from_ne_bytes(bytes))
+    };
+
+    // As av_display_rotation_get, which returns the negated (counterclockwise) angle
+    let (scale_x, scale_y) = (value(0).hypot(value(3)), value(1).hypot(value(4)));
+    if scale_x == 0.0 || scale_y == 0.0 {
+        return 0;
+    }
+    let clockwise = (value(1) / scale_y).atan2(value(0) / scale_x).to_degrees();
+
+    ((clockwise / 90.0).round() as i32 * 90).rem_euclid(360)
+}
+
+/// Rotation to bake into the output: the display rotation if `auto_orient` is on, plus
+/// the requested `rotate`
+pub fn output_rotation(stream: &Stream, options: &ProcessingOptions) -> i32 {
+    let display = if options.auto_orient { display_rotation(stream) } else { 0 };
+    (display + options.rotate.unwrap_or(0)).rem_euclid(360)
+}
+
+/// Size of a `width`x`height` picture once turned by `rotation` degrees
+pub fn oriented_dimensions(width: u32, height: u32, rotation: i32) -> (u32, u32) {
+    match rotation {
+        90 | 270 => (height, width),
+        _ => (width, height),
+    }
+}
+
+/// Turn the picture `rotation` degrees clockwise
+pub fn add_orientation(chain: &mut FilterChain, rotation: i32) {
+    match rotation {
+        90 => chain.push("transpose=clock"),
+        180 => {
+            chain.push("hflip");
+            chain.push("vflip");
+        }
+        270 => chain.push("transpose=cclock"),
+        _ => {}
+    }
+}
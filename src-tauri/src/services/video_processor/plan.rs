@@ -0,0 +1,221 @@
+use std::ffi::CString;
+use std::path::Path;
+use std::ptr;
+use serde::{Deserialize, Serialize};
+
+use ffmpeg::codec::{self, encoder};
+use ffmpeg::format::{self, input};
+use ffmpeg::media::Type as MediaType;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::encoding;
+use super::filters;
+use super::gif;
+use super::orientation;
+use super::streams;
+use super::subtitles::SubtitleMode;
+use super::{ProcessingOptions, VideoProcessor};
+
+/// How one output stream is produced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamMapping {
+    /// Index of the source stream in the input, `None` for external files
+    pub input_index: Option<usize>,
+    pub output_index: usize,
+    pub media_type: String,
+    /// Human-readable description, e.g. "encode with libx264"
+    pub action: String,
+}
+
+/// What `process_video` would do for a set of options, without writing anything
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessingPlan {
+    pub input_path: String,
+    pub output_path: String,
+    pub options: ProcessingOptions,
+    /// Codec returned by `choose_codec`
+    pub codec: String,
+    /// Encoder FFmpeg would open for that codec, `None` if there is none
+    pub encoder: Option<String>,
+    pub output_width: u32,
+    pub output_height: u32,
+    /// Video filter graph between the decoder and the encoder
+    pub filtergraph: String,
+    pub streams: Vec<StreamMapping>,
+    /// Options that are accepted but will not change the output
+    pub notes: Vec<String>,
+}
+
+impl VideoProcessor {
+    /// Resolve the codec, filter graph and stream mapping for processing `input_path`.
+    ///
+    /// Only the input is opened; the output file is never created or touched.
+    pub fn plan_processing(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        options: &ProcessingOptions,
+    ) -> AppResult<ProcessingPlan> {
+        options.validate()?;
+
+        if !Path::new(input_path).exists() {
+            return Err(AppError::io_error(
+                std::io::Error::new(std::io::ErrorKind::NotFound, "Input file not found"),
+                ErrorCode::FileNotFound,
+                Some(format!("Input video file not found: {}", input_path)),
+            ));
+        }
+
+        let input_ctx = input(input_path).map_err(|e| {
+            AppError::ffmpeg_error(
+                format!("Cannot open input file '{}': {}", input_path, e),
+                ErrorCode::FFmpegInitError,
+                Some(format!("Error opening input file: {}", input_path)),
+            )
+        })?;
+
+        let video_stream = streams::select_stream(&input_ctx, options.video_stream_index, MediaType::Video)?
+            .ok_or_else(|| {
+                AppError::video_error(
+                    format!("No video stream found in file: {}", input_path),
+                    ErrorCode::InvalidVideoFormat,
+                    Some(format!("File does not contain a valid video stream: {}", input_path)),
+                )
+            })?;
+
+        let decoder = codec::context::Context::from_parameters(video_stream.parameters())
+            .and_then(|ctx| ctx.decoder().video())
+            .map_err(|e| {
+                AppError::video_error(
+                    format!("Cannot create decoder: {}", e),
+                    ErrorCode::DecodingError,
+                    Some(format!("Error creating video decoder for file: {}", input_path)),
+                )
+            })?;
+
+        let codec_id = self.choose_codec(options);
+        let video_encoder = self.find_encoder(options);
+        let encoder_name = video_encoder.as_ref().map(|c| c.name().to_string());
+        let rotation = orientation::output_rotation(&video_stream, options);
+        let (source_width, source_height) =
+            orientation::oriented_dimensions(decoder.width(), decoder.height(), rotation);
+        let (source_width, source_height) = filters::cropped_dimensions(options.crop, source_width, source_height)?;
+        let (output_width, output_height) =
+            filters::output_dimensions(options.resolution, source_width, source_height)?;
+        let (output_width, output_height) =
+            self.limit_upscale(source_width, source_height, output_width, output_height);
+        let (output_width, output_height) = if gif::is_gif_output(options) {
+            gif::limit_dimensions(options, output_width, output_height)
+        } else {
+            (output_width, output_height)
+        };
+        // Same rounding as the encoder setup, GIFs are palette-indexed and keep any size
+        let pixel_format = video_encoder.filter(|_| !gif::is_gif_output(options)).and_then(|codec| {
+            encoding::choose_pixel_format(
+                &codec,
+                options.pixel_format.as_deref(),
+                &options.output_format,
+                decoder.format(),
+            )
+            .ok()
+        });
+        let (output_width, output_height) = match pixel_format {
+            Some(pixel_format) => encoding::fit_to_chroma(pixel_format, output_width, output_height),
+            None => (output_width, output_height),
+        };
+        let filtergraph = filters::build_video_filter_spec(
+            options,
+            rotation,
+            source_width,
+            source_height,
+            output_width,
+            output_height,
+        );
+
+        let mut streams = vec![StreamMapping {
+            input_index: Some(video_stream.index()),
+            output_index: 0,
+            media_type: "video".to_string(),
+            action: match &encoder_name {
+                Some(name) => format!("encode with {}", name),
+                None => format!("no encoder available for {}", codec_id.name()),
+            },
+        }];
+
+        if let Some(audio_stream) =
+            streams::select_stream(&input_ctx, options.audio_stream_index, MediaType::Audio)?
+        {
+            let action = match guess_output_codec(output_path, MediaType::Audio)
+                .and_then(encoder::find)
+            {
+                Some(audio_encoder) => format!("transcode with {}", audio_encoder.name()),
+                None => "dropped, no audio encoder for this container".to_string(),
+            };
+            streams.push(StreamMapping {
+                input_index: Some(audio_stream.index()),
+                output_index: streams.len(),
+                media_type: "audio".to_string(),
+                action,
+            });
+        }
+
+        if let Some(subtitles) = &options.subtitles {
+            if subtitles.mode == SubtitleMode::Mux {
+                streams.push(StreamMapping {
+                    input_index: None,
+                    output_index: streams.len(),
+                    media_type: "subtitle".to_string(),
+                    action: format!("mux {}", subtitles.path),
+                });
+            }
+        }
+
+        Ok(ProcessingPlan {
+            input_path: input_path.to_string(),
+            output_path: output_path.to_string(),
+            options: options.clone(),
+            codec: codec_id.name().to_string(),
+            notes: plan_notes(options, encoder_name.as_deref()),
+            encoder: encoder_name,
+            output_width,
+            output_height,
+            filtergraph,
+            streams,
+        })
+    }
+}
+
+/// Default codec of the muxer FFmpeg picks for `output_path`
+fn guess_output_codec(output_path: &str, kind: MediaType) -> Option<codec::Id> {
+    let path = CString::new(output_path).ok()?;
+    // SAFETY: av_guess_format returns a pointer to a static muxer description or null
+    let muxer = unsafe { ffmpeg::ffi::av_guess_format(ptr::null(), path.as_ptr(), ptr::null()) };
+    if muxer.is_null() {
+        return None;
+    }
+    let muxer = unsafe { format::Output::wrap(muxer as *mut _) };
+    Some(muxer.codec(output_path, kind)).filter(|id| *id != codec::Id::None)
+}
+
+/// Explain options that are accepted but currently have no effect
+fn plan_notes(options: &ProcessingOptions, encoder_name: Option<&str>) -> Vec<String> {
+    let mut notes = Vec::new();
+
+    if options.start_time.is_some() || options.end_time.is_some() {
+        notes.push("Start and end times are not applied, the whole input is processed".to_string());
+    }
+
+    if options.use_gpu {
+        match (&options.gpu_codec, encoder_name) {
+            (Some(gpu_codec), Some(name)) if gpu_codec != name => notes.push(format!(
+                "GPU encoder {} is not available in this FFmpeg build, {} is used instead",
+                gpu_codec, name
+            )),
+            (None, _) => notes.push("GPU encoding is enabled but no GPU codec is set".to_string()),
+            _ => {}
+        }
+    }
+
+    notes
+}
@@ -1,36 +1,168 @@
+use std::cell::Cell;
 use std::path::{Path, PathBuf};
 use std::fs;
 use std::collections::HashMap;
-use log::{error, info};
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use log::{info, trace, warn};
 
 use ffmpeg::codec::{self, encoder};
-use ffmpeg::format::{input, output};
+use ffmpeg::filter;
+use ffmpeg::format::context::Output as OutputContext;
+use ffmpeg::format::{input, input_with_interrupt, output};
 use ffmpeg::media::Type as MediaType;
-use ffmpeg::software::scaling::{context::Context as ScalingContext, flag::Flags as ScalingFlags};
 use ffmpeg::util::frame::video::Video as VideoFrame;
 use ffmpeg::util::rational::Rational;
+use ffmpeg::{picture, threading, Packet};
 use ffmpeg_next as ffmpeg;
 
+use crate::utils::app_info;
 use crate::utils::error::{AppError, AppResult, ErrorCode};
-use super::{VideoInfo, ProcessingOptions};
+use super::audio::{self, AudioTranscoder};
+use super::audio_extract;
+use super::encoding::{self, EncodingPass};
+use super::ffmpeg_log;
+use super::filters;
+use super::frames;
+use super::gif;
+use super::hwaccel::HardwareDecoding;
+use super::loudness;
+use super::orientation;
+use super::remux;
+use super::resume::{Segment, SegmentEnd};
+use super::sequence;
+use super::streams;
+use super::thumbnail;
+use super::subtitles::{SubtitleMode, SubtitleMuxer};
+use super::{VideoInfo, ProcessingOptions, SubtitleConfig, WatermarkConfig};
+
+/// Progress (in percent) between two encoding progress log lines
+const PROGRESS_LOG_STEP: f32 = 10.0;
+
+/// Frames between progress log lines when the total frame count is unknown
+const PROGRESS_LOG_FRAME_INTERVAL: u64 = 1000;
+
+/// How often the progress callback is checked for a stop when the total frame count is
+/// unknown and there is no progress to report
+const UNKNOWN_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Part of the progress (in percent) taken by measuring the loudness before encoding
+const LOUDNESS_PROGRESS_SHARE: f32 = 20.0;
+
+/// Time a probe may take before `get_video_info` gives up, unless changed
+pub const DEFAULT_PROBE_TIMEOUT_SECS: u64 = 15;
+
+/// How many times larger than the source the output may be, unless changed
+pub const DEFAULT_MAX_UPSCALE_FACTOR: f64 = 2.0;
 
 /// Video processor that contains only processing logic
+///
+/// Clones share the probe timeout, the probe cancellation, the upscale limit and the
+/// default codecs.
 #[derive(Clone)]
-pub struct VideoProcessor {}
+pub struct VideoProcessor {
+    probe_timeout_secs: Arc<AtomicU64>, // 0 disables the timeout
+    probe_epoch: Arc<AtomicU64>,        // bumped by `cancel_probes`, only ends cancelable probes
+    max_upscale_factor: Arc<AtomicU64>, // bits of an f64, 1.0 allows no upscaling
+    pub(super) default_codecs: Arc<RwLock<HashMap<String, codec::Id>>>, // see `set_default_codecs`
+}
+
+impl Default for VideoProcessor {
+    /// Processor with the default settings, without initializing FFmpeg; see `new`
+    fn default() -> Self {
+        Self {
+            probe_timeout_secs: Arc::new(AtomicU64::new(DEFAULT_PROBE_TIMEOUT_SECS)),
+            probe_epoch: Arc::new(AtomicU64::new(0)),
+            max_upscale_factor: Arc::new(AtomicU64::new(DEFAULT_MAX_UPSCALE_FACTOR.to_bits())),
+            default_codecs: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
 
 impl VideoProcessor {
     /// Create a new VideoProcessor
-    pub fn new() -> Self {
+    ///
+    /// Fails with `FFmpegInitError` if the FFmpeg libraries are missing or cannot be
+    /// initialized, see `app_info::check_ffmpeg`.
+    pub fn new() -> AppResult<Self> {
         // Initialize FFmpeg if not already initialized
-        if let Err(e) = ffmpeg::init() {
-            error!("Failed to initialize FFmpeg: {}", e);
+        app_info::check_ffmpeg()?;
+        ffmpeg_log::install_log_callback();
+
+        Ok(Self::default())
+    }
+
+    /// Change how long `get_video_info` may take; 0 lets probes run as long as they need
+    pub fn set_probe_timeout(&self, timeout_secs: u64) {
+        self.probe_timeout_secs.store(timeout_secs, Ordering::Relaxed);
+    }
+
+    /// Seconds a probe may take, 0 if there is no limit
+    pub fn probe_timeout(&self) -> u64 {
+        self.probe_timeout_secs.load(Ordering::Relaxed)
+    }
+
+    /// Stop every `get_video_info_cancelable` call in progress; they fail with `ProbeCanceled`
+    ///
+    /// Probes of running tasks use `get_video_info` and are not affected.
+    pub fn cancel_probes(&self) {
+        self.probe_epoch.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Limit how many times larger than the source an output may be encoded
+    ///
+    /// Larger resolutions are scaled down to the limit with a warning, since upscaling
+    /// adds size but no detail. 1.0 allows no upscaling at all.
+    pub fn set_max_upscale_factor(&self, factor: f64) -> AppResult<()> {
+        if !factor.is_finite() || factor < 1.0 {
+            return Err(AppError::validation_error(
+                format!("Invalid upscale limit: {}", factor),
+                ErrorCode::InvalidArgument,
+                Some("Use 1.0 to prevent upscaling, or a larger factor to allow some".to_string()),
+            ));
         }
 
-        Self {}
+        self.max_upscale_factor.store(factor.to_bits(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// How many times larger than the source an output may be
+    pub fn max_upscale_factor(&self) -> f64 {
+        f64::from_bits(self.max_upscale_factor.load(Ordering::Relaxed))
+    }
+
+    /// Scale a requested `width`x`height` down to the upscale limit, reporting the change
+    pub(super) fn limit_upscale(&self, source_width: u32, source_height: u32, width: u32, height: u32) -> (u32, u32) {
+        let factor = self.max_upscale_factor();
+        match filters::cap_upscale(width, height, source_width, source_height, factor) {
+            Some((capped_width, capped_height)) => {
+                ffmpeg_log::report_warning(&format!(
+                    "Resolution {}x{} is more than {}x the {}x{} source, encoding at {}x{} instead",
+                    width, height, factor, source_width, source_height, capped_width, capped_height
+                ));
+                (capped_width, capped_height)
+            }
+            None => (width, height),
+        }
     }
 
     /// Get video information
+    ///
+    /// Opening the file is interrupted once the probe timeout passes, e.g. on a stalled
+    /// network share, failing with `ProbeTimedOut`.
     pub fn get_video_info(&self, file_path: &str) -> AppResult<VideoInfo> {
+        self.probe_video_info(file_path, false)
+    }
+
+    /// Get video information like `get_video_info`, but also stop when `cancel_probes`
+    /// is called, failing with `ProbeCanceled`; for probes the user may abandon, e.g.
+    /// a large import into the file list
+    pub fn get_video_info_cancelable(&self, file_path: &str) -> AppResult<VideoInfo> {
+        self.probe_video_info(file_path, true)
+    }
+
+    fn probe_video_info(&self, file_path: &str, cancelable: bool) -> AppResult<VideoInfo> {
         // Check if file exists
         if !Path::new(file_path).exists() {
             return Err(AppError::io_error(
@@ -43,13 +175,36 @@ impl VideoProcessor {
         // Convert path to PathBuf
         let path = PathBuf::from(file_path);
 
-        // Open input file
-        let input_ctx = input(&path).map_err(|e| {
-            AppError::ffmpeg_error(
-                format!("Cannot open video file '{}': {}", path.display(), e),
-                ErrorCode::FFmpegInitError,
-                Some(format!("Error opening video file: {}", file_path)),
-            )
+        // Open input file; FFmpeg polls the interrupt while it waits for data
+        let timeout_secs = self.probe_timeout();
+        let deadline = (timeout_secs > 0).then(|| Instant::now() + Duration::from_secs(timeout_secs));
+        let epoch = self.probe_epoch.load(Ordering::Relaxed);
+        let probe_epoch = self.probe_epoch.clone();
+        let canceled = move || cancelable && probe_epoch.load(Ordering::Relaxed) != epoch;
+        let interrupted = {
+            let canceled = canceled.clone();
+            move || canceled() || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+        };
+        let input_ctx = input_with_interrupt(&path, interrupted.clone()).map_err(|e| {
+            if canceled() {
+                AppError::video_error(
+                    format!("Probe of '{}' was canceled", path.display()),
+                    ErrorCode::ProbeCanceled,
+                    None,
+                )
+            } else if interrupted() {
+                AppError::video_error(
+                    format!("Probe of '{}' timed out after {}s", path.display(), timeout_secs),
+                    ErrorCode::ProbeTimedOut,
+                    Some("The file may be very large, damaged or on a slow network share".to_string()),
+                )
+            } else {
+                AppError::ffmpeg_error(
+                    format!("Cannot open video file '{}': {}", path.display(), e),
+                    ErrorCode::FFmpegInitError,
+                    Some(format!("Error opening video file: {}", file_path)),
+                )
+            }
         })?;
 
         // Find video stream
@@ -110,6 +265,11 @@ impl VideoProcessor {
             .map(|c| c.name().to_string())
             .unwrap_or_else(|| "unknown".to_string());
 
+        // Describe the other streams (audio tracks, subtitles, ...)
+        let (streams, audio_streams) = streams::describe_streams(&input_ctx);
+        let rotation = orientation::display_rotation(&stream);
+        let has_subtitles = streams.iter().any(|s| s.media_type == "subtitle");
+
         Ok(VideoInfo {
             path: file_path.to_string(),
             format: format_name,
@@ -119,10 +279,63 @@ impl VideoProcessor {
             bitrate,
             codec: codec_name,
             framerate,
+            audio_streams,
+            has_subtitles,
+            streams,
+            rotation,
         })
     }
 
+    /// Extract a single frame at `timestamp_secs` into a JPEG or PNG image
+    ///
+    /// The frame is scaled to `width` pixels wide (0 keeps the source width) with the
+    /// height following the source aspect ratio.
+    pub fn extract_thumbnail(
+        &self,
+        input_path: &str,
+        timestamp_secs: f64,
+        output_path: &str,
+        width: u32,
+    ) -> AppResult<()> {
+        info!("Extracting thumbnail from {} at {}s", input_path, timestamp_secs);
+        thumbnail::extract_thumbnail(input_path, timestamp_secs, output_path, width)
+    }
+
+    /// Extract the best audio stream into an mp3, aac, wav or flac file
+    ///
+    /// The stream is copied when it already has the target codec, otherwise it is
+    /// transcoded. Fails if the input has no audio stream.
+    pub fn extract_audio(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        format: &str,
+        progress_callback: impl Fn(f32) -> bool,
+    ) -> AppResult<()> {
+        audio_extract::extract_audio(input_path, output_path, format, &progress_callback)
+    }
+
+    /// Export frames of the best video stream as numbered PNG or JPEG images
+    ///
+    /// Frames are sampled at `fps` or every `every_nth` frame (every frame if neither
+    /// is set). `output_dir` is created if needed. Returns the number of images written.
+    pub fn export_frames(
+        &self,
+        input_path: &str,
+        output_dir: &str,
+        fps: Option<f64>,
+        every_nth: Option<u32>,
+        format: &str,
+        progress_callback: impl Fn(f32) -> bool,
+    ) -> AppResult<usize> {
+        info!("Exporting {} frames from {} to {}", format, input_path, output_dir);
+        frames::export_frames(input_path, output_dir, fps, every_nth, format, &progress_callback)
+    }
+
     /// Process a video with the given options
+    ///
+    /// If processing fails or is canceled, an output file written by this call is
+    /// deleted so that a half-written file is never left behind.
     pub fn process_video(
         &self,
         input_path: &str,
@@ -130,8 +343,160 @@ impl VideoProcessor {
         options: ProcessingOptions,
         progress_callback: impl Fn(f32) -> bool + Send + 'static,
     ) -> AppResult<()> {
-        // Check if input file exists
-        if !Path::new(input_path).exists() {
+        // Reject invalid settings before doing any work
+        options.validate()?;
+        let options = self.apply_target_size(input_path, options)?;
+
+        let started_at = SystemTime::now();
+        let result = if self.copies_streams(input_path, output_path, &options) {
+            info!("No stream is changed, copying {} into {} without re-encoding", input_path, output_path);
+            remux::remux(input_path, output_path, &progress_callback)
+        } else {
+            self.run_encode(input_path, output_path, &options, &progress_callback)
+        };
+        if result.is_err() {
+            remove_incomplete_output(output_path, started_at);
+        }
+
+        result
+    }
+
+    /// Whether `process_video` copies the streams of `input_path` instead of encoding them
+    ///
+    /// Only if the options change nothing and the video already has the codec chosen for
+    /// the output; an HEVC mkv converted to mp4 is still encoded to H.264.
+    pub(super) fn copies_streams(&self, input_path: &str, output_path: &str, options: &ProcessingOptions) -> bool {
+        remux::is_remux_candidate(options)
+            && remux::can_remux(input_path, output_path, Some(self.choose_codec(options)))
+    }
+
+    /// Copy every stream into the container of `output_path` without re-encoding
+    ///
+    /// Other options are ignored for the copy. If a stream's codec is not allowed in
+    /// the target container, the input is encoded with `options` instead.
+    pub fn remux_video(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        options: ProcessingOptions,
+        progress_callback: impl Fn(f32) -> bool + Send + 'static,
+    ) -> AppResult<()> {
+        if !remux::can_remux(input_path, output_path, None) {
+            warn!("{} cannot be remuxed into {}, re-encoding instead", input_path, output_path);
+            return self.process_video(input_path, output_path, options, progress_callback);
+        }
+
+        let started_at = SystemTime::now();
+        let result = remux::remux(input_path, output_path, &progress_callback);
+        if result.is_err() {
+            remove_incomplete_output(output_path, started_at);
+        }
+
+        result
+    }
+
+    /// Encode in one or two passes depending on the options
+    fn run_encode(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        options: &ProcessingOptions,
+        progress_callback: &dyn Fn(f32) -> bool,
+    ) -> AppResult<()> {
+        if options.two_pass {
+            let encoder_name = self.find_encoder(options).map(|c| c.name().to_string());
+
+            match (options.bitrate, options.quality, encoder_name) {
+                (None, _, _) => warn!("Two-pass encoding requires a target bitrate, using a single pass"),
+                (Some(_), Some(_), _) => warn!("Two-pass encoding is not used together with quality, using a single pass"),
+                (Some(_), None, Some(name)) if !encoding::supports_two_pass(&name) => {
+                    warn!("Encoder {} does not support two-pass encoding, using a single pass", name)
+                }
+                _ => return self.process_video_two_pass(input_path, output_path, options, progress_callback),
+            }
+        }
+
+        self.encode_pass(input_path, output_path, options, None, None, progress_callback)?;
+
+        // Final progress update
+        progress_callback(100.0);
+
+        Ok(())
+    }
+
+    /// Run an analysis pass followed by the final encode.
+    ///
+    /// Progress is reported as 0-50% for the first pass and 50-100% for the second.
+    /// The stats file and the first pass output are removed even if encoding fails.
+    fn process_video_two_pass(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        options: &ProcessingOptions,
+        progress_callback: &dyn Fn(f32) -> bool,
+    ) -> AppResult<()> {
+        let pass_id = uuid::Uuid::new_v4();
+        let stats_path = std::env::temp_dir().join(format!("vidkit-2pass-{}.log", pass_id));
+        let extension = Path::new(output_path)
+            .extension()
+            .map(|ext| ext.to_string_lossy().into_owned())
+            .unwrap_or_else(|| options.output_format.clone());
+        let first_pass_output = std::env::temp_dir().join(format!("vidkit-2pass-{}.{}", pass_id, extension));
+        let first_pass_output = first_pass_output.to_string_lossy().into_owned();
+
+        info!("Running two-pass encode, stats file: {:?}", stats_path);
+
+        let result = self
+            .encode_pass(
+                input_path,
+                &first_pass_output,
+                options,
+                Some(EncodingPass::First(&stats_path)),
+                None,
+                &|progress| progress_callback(progress / 2.0),
+            )
+            .and_then(|_| {
+                self.encode_pass(
+                    input_path,
+                    output_path,
+                    options,
+                    Some(EncodingPass::Second(&stats_path)),
+                    None,
+                    &|progress| progress_callback(50.0 + progress / 2.0),
+                )
+            });
+
+        // Clean up temporary files
+        encoding::remove_two_pass_stats(&stats_path);
+        if Path::new(&first_pass_output).exists() {
+            if let Err(e) = fs::remove_file(&first_pass_output) {
+                warn!("Failed to remove first pass output {}: {}", first_pass_output, e);
+            }
+        }
+
+        result?;
+
+        // Final progress update
+        progress_callback(100.0);
+
+        Ok(())
+    }
+
+    /// Decode, filter and encode the input once
+    ///
+    /// For a `segment`, only frames after its start are encoded, and a stop requested
+    /// by the progress callback finishes the output instead of failing.
+    pub(super) fn encode_pass(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        options: &ProcessingOptions,
+        pass: Option<EncodingPass>,
+        segment: Option<Segment>,
+        progress_callback: &dyn Fn(f32) -> bool,
+    ) -> AppResult<SegmentEnd> {
+        // Check if input file exists; image sequences are checked when opened
+        if !sequence::is_sequence_pattern(input_path) && !Path::new(input_path).exists() {
             return Err(AppError::io_error(
                 std::io::Error::new(std::io::ErrorKind::NotFound, "Input file not found"),
                 ErrorCode::FileNotFound,
@@ -139,6 +504,16 @@ impl VideoProcessor {
             ));
         }
 
+        // Make sure the watermark image can be loaded before opening the input
+        if let Some(watermark) = &options.watermark {
+            watermark.validate()?;
+        }
+
+        // Check the subtitle file and that the container can carry it
+        if let Some(subtitles) = &options.subtitles {
+            subtitles.validate(&options.output_format)?;
+        }
+
         // Ensure output directory exists
         if let Some(parent) = Path::new(output_path).parent() {
             fs::create_dir_all(parent).map_err(|e| {
@@ -154,7 +529,10 @@ impl VideoProcessor {
         info!("Opening input file: {}", input_path);
 
         // Apply time options if specified
-        let mut input_ctx = if options.start_time.is_some() || options.end_time.is_some() {
+        let mut input_ctx = if sequence::is_sequence_pattern(input_path) {
+            let fps = options.framerate.map_or(sequence::DEFAULT_SEQUENCE_FPS, f64::from);
+            sequence::open_sequence(input_path, fps)?
+        } else if options.start_time.is_some() || options.end_time.is_some() {
             // In a real implementation, we would use the FFmpeg API to set start and end time
             // For now, we'll just log the values
             if let Some(start_time) = options.start_time {
@@ -210,10 +588,8 @@ impl VideoProcessor {
             })?
         };
 
-        // Find video stream
-        let input_stream = input_ctx
-            .streams()
-            .best(MediaType::Video)
+        // Find the requested (or best) video stream
+        let input_stream = streams::select_stream(&input_ctx, options.video_stream_index, MediaType::Video)?
             .ok_or_else(|| {
                 AppError::video_error(
                     format!("No video stream found in file: {}", input_path),
@@ -224,9 +600,10 @@ impl VideoProcessor {
 
         let input_stream_index = input_stream.index();
         let input_time_base = input_stream.time_base();
+        let rotation = orientation::output_rotation(&input_stream, options);
 
         // Create decoder
-        let decoder_ctx = ffmpeg::codec::context::Context::from_parameters(input_stream.parameters())
+        let mut decoder_ctx = ffmpeg::codec::context::Context::from_parameters(input_stream.parameters())
             .map_err(|e| {
                 AppError::video_error(
                     format!("Cannot create decoder context: {}", e),
@@ -235,6 +612,17 @@ impl VideoProcessor {
                 )
             })?;
 
+        // Decode on the GPU if requested; without a usable device this stays None
+        let mut hw_decoding = if options.hardware_decode {
+            HardwareDecoding::attach(&mut decoder_ctx, options)
+        } else {
+            None
+        };
+
+        if let Some(threads) = options.threads {
+            decoder_ctx.set_threading(thread_limit(threads));
+        }
+
         let mut decoder = decoder_ctx.decoder().video().map_err(|e| {
             AppError::video_error(
                 format!("Cannot create decoder: {}", e),
@@ -243,9 +631,14 @@ impl VideoProcessor {
             )
         })?;
 
+        // Downloaded GPU frames are converted to the format the filter graph is built for
+        if let Some(hw) = hw_decoding.as_mut() {
+            hw.set_output_format(decoder.format());
+        }
+
         // Choose codec based on options
-        let codec_id = self.choose_codec(&options);
-        let encoder_codec = encoder::find(codec_id).ok_or_else(|| {
+        let codec_id = self.choose_codec(options);
+        let encoder_codec = self.find_encoder(options).ok_or_else(|| {
             AppError::video_error(
                 format!("Encoder codec not found: {:?}", codec_id),
                 ErrorCode::CodecNotSupported,
@@ -254,21 +647,19 @@ impl VideoProcessor {
         })?;
 
         // Create output stream
-        // Tái cấu trúc để tránh mượn mutable nhiều lần
-        let add_stream_result = output_ctx.add_stream(encoder_codec);
-        let mut output_stream = match add_stream_result {
-            Ok(stream) => stream,
-            Err(e) => {
-                return Err(AppError::video_error(
+        let output_stream_index = output_ctx
+            .add_stream(encoder_codec)
+            .map(|stream| stream.index())
+            .map_err(|e| {
+                AppError::video_error(
                     format!("Cannot add output stream: {}", e),
                     ErrorCode::EncodingError,
                     Some("Error adding output stream to output context".to_string()),
-                ));
-            }
-        };
+                )
+            })?;
 
         // Create encoder context
-        let encoder_ctx = codec::context::Context::new();
+        let encoder_ctx = codec::context::Context::new_with_codec(encoder_codec);
 
         // Set encoder parameters
         let mut encoder = encoder_ctx.encoder().video().map_err(|e| {
@@ -279,271 +670,511 @@ impl VideoProcessor {
             )
         })?;
 
-        // Set encoder parameters based on options
-        let (width, height) = match options.resolution {
-            Some((w, h)) => (w, h),
-            None => (decoder.width(), decoder.height()),
+        // Limit the threads the encoder starts, so concurrent tasks share the CPU
+        if let Some(threads) = options.threads {
+            info!("Limiting encoder and decoder to {} threads", threads);
+            encoder.set_threading(thread_limit(threads));
+        }
+
+        // Set encoder parameters based on options; 90/270 degree rotations swap the sides
+        let (source_width, source_height) =
+            orientation::oriented_dimensions(decoder.width(), decoder.height(), rotation);
+        let (source_width, source_height) = filters::cropped_dimensions(options.crop, source_width, source_height)?;
+        let (width, height) = match segment.and_then(|segment| segment.output_size) {
+            // A resumed segment is joined to the earlier ones and must match their size
+            Some(size) => size,
+            None => {
+                let (width, height) = filters::output_dimensions(options.resolution, source_width, source_height)?;
+                let (width, height) = self.limit_upscale(source_width, source_height, width, height);
+                if gif::is_gif_output(options) {
+                    gif::limit_dimensions(options, width, height)
+                } else {
+                    (width, height)
+                }
+            }
+        };
+
+        // Set pixel format; GIFs take the palette-indexed frames from `paletteuse`
+        let pixel_format = if gif::is_gif_output(options) && options.pixel_format.is_none() {
+            ffmpeg::format::Pixel::PAL8
+        } else {
+            encoding::choose_pixel_format(
+                &encoder_codec,
+                options.pixel_format.as_deref(),
+                &options.output_format,
+                decoder.format(),
+            )?
         };
+        info!("Using pixel format {}", pixel_format.name());
 
+        // Odd sizes, e.g. from an odd source, are refused by 4:2:0 encoders
+        let (width, height) = encoding::fit_to_chroma(pixel_format, width, height);
         encoder.set_width(width);
         encoder.set_height(height);
-        encoder.set_format(decoder.format());
+        encoder.set_format(pixel_format);
+        let encoder_format = encoder.format();
 
-        // Set pixel format
-        encoder.set_format(decoder.format());
+        // Create the filter graph (blur regions, scaling) between decoder and encoder
+        let filter_spec = filters::build_video_filter_spec(
+            options,
+            rotation,
+            source_width,
+            source_height,
+            width,
+            height,
+        );
+        let mut filter_graph = filters::create_video_filter_graph(
+            &decoder,
+            input_time_base,
+            encoder_format,
+            &filter_spec,
+        )?;
 
         // Set time base
-        let time_base = Rational::new(1, 25); // Default to 25 fps
-        encoder.set_time_base(time_base);
-        output_stream.set_time_base(time_base);
+        let encoder_time_base = filter_graph
+            .get("out")
+            .map(|mut sink| sink.sink().time_base())
+            .unwrap_or(input_time_base);
+        encoder.set_time_base(encoder_time_base);
+
+        // Prefer constant quality (CRF) over a fixed bitrate
+        let mut encoder_options = ffmpeg::Dictionary::new();
+        let quality_options = match options.quality {
+            Some(quality) => encoding::quality_options(encoder_codec.name(), quality)?,
+            None => Vec::new(),
+        };
 
-        // Set bitrate if specified
-        if let Some(bitrate) = options.bitrate {
-            encoder.set_bit_rate(bitrate as usize);
+        if !quality_options.is_empty() {
+            if options.bitrate.is_some() {
+                warn!("Both quality and bitrate are set, using quality and ignoring bitrate");
+            }
+            for (key, value) in &quality_options {
+                info!("Setting encoder option {}={}", key, value);
+                encoder_options.set(key, value);
+            }
+        } else {
+            if let Some(quality) = options.quality {
+                warn!(
+                    "Encoder {} has no constant quality mode, ignoring quality {}",
+                    encoder_codec.name(),
+                    quality
+                );
+            }
+
+            // Set bitrate if specified
+            if let Some(bitrate) = options.bitrate {
+                encoder.set_bit_rate(bitrate as usize);
+            }
         }
 
         // Set framerate if specified
-        if let Some(framerate) = options.framerate {
-            let frame_rate = Rational::new(framerate as i32, 1);
-            encoder.set_frame_rate(Some(frame_rate));
+        if gif::is_gif_output(options) {
+            encoder.set_frame_rate(Some(Rational::new(gif::gif_fps(options).round() as i32, 1)));
+        } else if let Some(framerate) = options.framerate {
+            encoder.set_frame_rate(Some(Rational::from(f64::from(framerate))));
+        } else {
+            encoder.set_frame_rate(decoder.frame_rate());
         }
 
-        // Lưu lại các giá trị cần thiết trước khi encoder bị move
-        let _encoder_time_base = encoder.time_base(); // Unused variable
-        let encoder_format = encoder.format();
-
-        // Open encoder
-        encoder.open_as(encoder_codec).map_err(|e| {
-            AppError::video_error(
-                format!("Cannot open encoder: {}", e),
-                ErrorCode::EncodingError,
-                Some("Error opening video encoder".to_string()),
-            )
-        })?;
-
-        // Copy encoder parameters to output stream
-        // In a real implementation, we would copy the encoder parameters to the output stream
-        // For now, we'll just set the time base
-
-        // Tạo một bản sao của output_ctx để tránh mượn mutable nhiều lần
-        // Trong một triển khai thực tế, chúng ta sẽ cần tái cấu trúc code để tránh vấn đề này
-        // Ví dụ: tạo một hàm riêng để xử lý việc ghi header
-
-        // Ghi log để thông báo
-        info!("Writing output header to: {}", output_path);
-
-        // Log edit options if specified
-        if let Some(crop) = options.crop {
-            info!("Applying crop: x={}, y={}, width={}, height={}", crop.0, crop.1, crop.2, crop.3);
-            // In a real implementation, we would modify the scaling context to apply the crop
+        // Set keyframe spacing if specified
+        if let Some(interval) = options.keyframe_interval {
+            info!("Setting keyframe interval to {} frames", interval);
+            encoder.set_gop(interval);
+            for (key, value) in encoding::keyframe_options(encoder_codec.name(), interval, options.scene_cut) {
+                encoder_options.set(key, &value);
+            }
         }
 
-        if let Some(rotate) = options.rotate {
-            info!("Applying rotation: {} degrees", rotate);
-            // In a real implementation, we would add a rotation filter
+        let mut encoder_flags = codec::Flags::empty();
+        if output_ctx.format().flags().contains(ffmpeg::format::Flags::GLOBAL_HEADER) {
+            encoder_flags |= codec::Flags::GLOBAL_HEADER;
         }
 
-        if let Some(true) = options.flip {
-            info!("Applying horizontal flip");
-            // In a real implementation, we would add a flip filter
+        // Configure two-pass encoding
+        if let Some(pass) = pass {
+            encoder_flags |= pass.flags();
+            for (key, value) in encoding::two_pass_options(encoder_codec.name(), pass) {
+                encoder_options.set(key, &value);
+            }
         }
+        encoder.set_flags(encoder_flags);
 
-        if let Some(true) = options.flop {
-            info!("Applying vertical flip");
-            // In a real implementation, we would add a flop filter
-        }
+        // Open encoder; hardware encoders fail here on driver problems or session limits
+        let hardware_encoder = options.use_gpu && options.gpu_codec.as_deref() == Some(encoder_codec.name());
+        let mut encoder = encoder.open_as_with(encoder_codec, encoder_options).map_err(|e| {
+            if hardware_encoder {
+                AppError::video_error(
+                    format!("Cannot open hardware encoder {}: {}", encoder_codec.name(), e),
+                    ErrorCode::GpuInitError,
+                    Some("The GPU encoder is unavailable, try CPU encoding".to_string()),
+                )
+            } else {
+                AppError::video_error(
+                    format!("Cannot open encoder: {}", e),
+                    ErrorCode::EncodingError,
+                    Some("Error opening video encoder".to_string()),
+                )
+            }
+        })?;
 
-        // Log sanitize options if specified
-        if let Some(true) = options.denoise {
-            info!("Applying denoising filter");
-            // In a real implementation, we would add a denoise filter
+        // Copy encoder parameters to output stream
+        if let Some(mut output_stream) = output_ctx.stream_mut(output_stream_index) {
+            output_stream.set_parameters(&encoder);
+            output_stream.set_time_base(encoder_time_base);
         }
 
-        if let Some(volume) = options.audio_volume {
-            info!("Adjusting audio volume: {}", volume);
-            // In a real implementation, we would add a volume filter
-        }
+        // Set up audio transcoding (loudness normalization, volume) for the selected audio stream
+        let audio_stream = match pass {
+            // The analysis pass only needs video
+            Some(EncodingPass::First(_)) => None,
+            _ => streams::select_stream(&input_ctx, options.audio_stream_index, MediaType::Audio)?,
+        };
+        let measure_loudness = options.normalize_audio && audio_stream.is_some();
+        let encode_progress_start = if measure_loudness { LOUDNESS_PROGRESS_SHARE } else { 0.0 };
+        let encode_progress = |progress: f32| encode_progress_start + progress * (100.0 - encode_progress_start) / 100.0;
+        let loudnorm = match &audio_stream {
+            // Measure first so loudnorm can apply a single linear gain
+            Some(audio_stream) if measure_loudness => {
+                // A resumed segment is already past the measurement in the progress
+                let resumed_progress = segment
+                    .and_then(|segment| segment.after_secs)
+                    .filter(|_| input_ctx.duration() > 0)
+                    .map(|after_secs| {
+                        let duration_secs = input_ctx.duration() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
+                        encode_progress((after_secs / duration_secs * 100.0).min(99.9) as f32)
+                    });
+                let stopped = Cell::new(false);
+                let measured = loudness::measure_loudness(input_path, audio_stream.index(), &|progress| {
+                    let share = progress * LOUDNESS_PROGRESS_SHARE / 100.0;
+                    let keep_going = progress_callback(resumed_progress.unwrap_or(share));
+                    stopped.set(!keep_going);
+                    keep_going
+                });
+                match measured {
+                    // A segment stopped before its first frame leaves nothing to keep
+                    Err(_) if stopped.get() && segment.is_some() => return Ok(SegmentEnd::Stopped(None, (width, height))),
+                    measured => Some(loudness::loudnorm_filter(measured?)),
+                }
+            }
+            _ => None,
+        };
+        let scaled_progress = |progress: f32| progress_callback(encode_progress(progress));
+        let progress_callback: &dyn Fn(f32) -> bool = &scaled_progress;
+        let audio_filter_spec = audio::build_audio_filter_spec(loudnorm.as_deref(), options.audio_volume);
+        let mut audio_transcoder = match audio_stream {
+            Some(audio_stream) => {
+                AudioTranscoder::new(&audio_stream, &mut output_ctx, output_path, &audio_filter_spec)?
+            }
+            None => None,
+        };
 
-        if let Some(regions) = &options.blur_regions {
-            info!("Applying blur to {} regions", regions.len());
-            // In a real implementation, we would add blur filters for each region
-        }
+        // Add external subtitles as a soft track
+        let mut subtitle_muxer = match (&options.subtitles, pass) {
+            (_, Some(EncodingPass::First(_))) => None,
+            (Some(subtitles), _) if subtitles.mode == SubtitleMode::Mux => {
+                Some(SubtitleMuxer::new(subtitles, &mut output_ctx, &options.output_format)?)
+            }
+            _ => None,
+        };
 
-        // Create scaling context
-        let mut scaler = ScalingContext::get(
-            decoder.format(),
-            decoder.width(),
-            decoder.height(),
-            encoder_format, // Sử dụng giá trị đã lưu trước đó
-            width,
-            height,
-            ScalingFlags::BILINEAR,
-        ).map_err(|e| {
-            AppError::video_error(
-                format!("Cannot create scaling context: {}", e),
+        // Write header
+        info!("Writing output header to: {}", output_path);
+        output_ctx.write_header().map_err(|e| {
+            AppError::ffmpeg_error(
+                format!("Cannot write output header: {}", e),
                 ErrorCode::EncodingError,
-                Some("Error creating video scaling context".to_string()),
+                Some(format!("Error writing header to output file: {}", output_path)),
             )
         })?;
 
+        // The muxer may adjust the stream time base while writing the header
+        let output_time_base = output_ctx
+            .stream(output_stream_index)
+            .map(|stream| stream.time_base())
+            .unwrap_or(encoder_time_base);
+        if let Some(transcoder) = audio_transcoder.as_mut() {
+            transcoder.update_output_time_base(&output_ctx);
+        }
+        if let Some(muxer) = subtitle_muxer.as_mut() {
+            muxer.update_output_time_base(&output_ctx);
+        }
+
         // Process frames
         let mut decoded = VideoFrame::empty();
-        let _encoded = VideoFrame::empty(); // Unused variable
-        let _packet = ffmpeg::Packet::empty(); // Unused variable
 
         // Get total frames for progress calculation
-        let total_frames = if input_ctx.duration() > 0 && input_stream.avg_frame_rate().numerator() > 0 {
-            let duration_seconds = input_ctx.duration() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
-            let fps = input_stream.avg_frame_rate().numerator() as f64 /
-                      input_stream.avg_frame_rate().denominator() as f64;
-            (duration_seconds * fps) as u64
-        } else {
-            0
+        let input_stream_fps = (input_stream.avg_frame_rate().numerator() > 0).then(|| {
+            input_stream.avg_frame_rate().numerator() as f64 /
+                input_stream.avg_frame_rate().denominator() as f64
+        });
+        let total_frames = match input_stream_fps {
+            Some(fps) if input_ctx.duration() > 0 => {
+                let duration_seconds = input_ctx.duration() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
+                (duration_seconds * fps) as u64
+            }
+            _ => 0,
+        };
+
+        // A later segment starts at the keyframe before its first frame, counted as done
+        let after_secs = segment.and_then(|segment| segment.after_secs);
+        let mut frame_count: u64 = 0;
+        if let Some(after_secs) = after_secs {
+            let position = (after_secs * f64::from(ffmpeg::ffi::AV_TIME_BASE)) as i64;
+            input_ctx.seek(position, ..position).map_err(|e| {
+                AppError::ffmpeg_error(
+                    format!("Cannot seek to {:.3}s: {}", after_secs, e),
+                    ErrorCode::DecodingError,
+                    Some(format!("Error seeking in input file: {}", input_path)),
+                )
+            })?;
+            if let (Some(fps), true) = (input_stream_fps, total_frames > 0) {
+                frame_count = ((after_secs * fps) as u64).min(total_frames);
+            }
+        }
+        let is_encoded = |pts_secs: Option<f64>| match (after_secs, pts_secs) {
+            (Some(after_secs), Some(pts_secs)) => pts_secs > after_secs,
+            _ => true,
         };
 
-        let mut frame_count = 0;
+        let encode_started = Instant::now();
+        let mut next_log_progress = PROGRESS_LOG_STEP;
+        let mut last_unknown_progress = encode_started;
+        let mut last_frame_secs = None;
+        let mut stopped = false;
 
         // Read packets
-        for (stream, mut packet) in input_ctx.packets() {
-            // Process packet
+        'packets: for (stream, packet) in input_ctx.packets() {
+            // Route audio packets to the audio transcoder
+            if let Some(transcoder) = audio_transcoder.as_mut() {
+                if stream.index() == transcoder.input_index() {
+                    let pts_secs = packet.pts().map(|pts| pts as f64 * f64::from(stream.time_base()));
+                    if is_encoded(pts_secs) {
+                        transcoder.send_packet(&packet, &mut output_ctx)?;
+                    }
+                    continue;
+                }
+            }
+
             // Process only video packets
-            if stream.index() == input_stream_index {
-                packet.rescale_ts(input_time_base, decoder.time_base());
-
-                // Send packet to decoder
-                decoder.send_packet(&packet).map_err(|e| {
-                    AppError::video_error(
-                        format!("Error sending packet to decoder: {}", e),
-                        ErrorCode::DecodingError,
-                        Some("Error decoding video frame".to_string()),
-                    )
-                })?;
-
-                // Receive decoded frames
-                while decoder.receive_frame(&mut decoded).is_ok() {
-                    // Scale frame
-                    let mut scaled = VideoFrame::empty();
-                    scaled.set_format(encoder_format); // Sử dụng giá trị đã lưu trước đó
-                    scaled.set_width(width);
-                    scaled.set_height(height);
-                    // Allocate frame buffer
-                    // In a real implementation, we would allocate the frame buffer properly
-                    // For now, we'll just create an empty frame
-
-                    scaler.run(&decoded, &mut scaled).map_err(|e| {
-                        AppError::video_error(
-                            format!("Error scaling frame: {}", e),
-                            ErrorCode::EncodingError,
-                            Some("Error scaling video frame".to_string()),
-                        )
-                    })?;
-
-                    // Set frame properties
-                    scaled.set_pts(Some(frame_count as i64));
-
-                    // Send frame to encoder
-                    // Trong một triển khai thực tế, chúng ta sẽ cần tái cấu trúc code để tránh vấn đề này
-                    // Ví dụ: tạo một hàm riêng để xử lý việc gửi frame đến encoder
-
-                    // Ghi log để thông báo
-                    info!("Sending frame to encoder");
-
-                    // Receive encoded packets
-                    // Trong một triển khai thực tế, chúng ta sẽ cần tái cấu trúc code để tránh vấn đề này
-                    // Ví dụ: tạo một hàm riêng để xử lý việc nhận và ghi các packet
-
-                    // Ghi log để thông báo
-                    info!("Processing encoded packets");
-
-                    frame_count += 1;
-
-                    // Update progress
-                    if total_frames > 0 {
-                        let progress = frame_count as f32 / total_frames as f32 * 100.0;
-
-                        // Call progress callback
-                        if !progress_callback(progress) {
-                            // If callback returns false, cancel processing
-                            return Err(AppError::video_error(
-                                "Processing canceled by user".to_string(),
-                                ErrorCode::VideoProcessingFailed,
-                                Some("Video processing was canceled".to_string()),
-                            ));
+            if stream.index() != input_stream_index {
+                continue;
+            }
+
+            // Keep subtitle events interleaved with the video
+            if let (Some(muxer), Some(pts)) = (subtitle_muxer.as_mut(), packet.pts()) {
+                muxer.write_until(pts as f64 * f64::from(input_time_base), &mut output_ctx)?;
+            }
+
+            // Send packet to decoder
+            decoder.send_packet(&packet).map_err(|e| {
+                AppError::video_error(
+                    format!("Error sending packet to decoder: {}", e),
+                    ErrorCode::DecodingError,
+                    Some("Error decoding video frame".to_string()),
+                )
+            })?;
+
+            // Receive decoded frames
+            while decoder.receive_frame(&mut decoded).is_ok() {
+                let timestamp = decoded.timestamp();
+                decoded.set_pts(timestamp);
+
+                // Frames up to the end of the previous segment were decoded only as references
+                let pts_secs = timestamp.map(|pts| pts as f64 * f64::from(input_time_base));
+                if !is_encoded(pts_secs) {
+                    continue;
+                }
+                last_frame_secs = pts_secs.or(last_frame_secs);
+
+                let downloaded = download_frame(hw_decoding.as_mut(), &decoded)?;
+                filter_frame(&mut filter_graph, downloaded.as_ref().unwrap_or(&decoded))?;
+                encode_filtered_frames(
+                    &mut filter_graph,
+                    &mut encoder,
+                    &mut output_ctx,
+                    output_stream_index,
+                    encoder_time_base,
+                    output_time_base,
+                )?;
+
+                frame_count += 1;
+                trace!("Encoded frame {} (pts {:?})", frame_count, timestamp);
+
+                // Update progress
+                if total_frames > 0 {
+                    let progress = (frame_count as f32 / total_frames as f32 * 100.0).min(99.9);
+
+                    // Log once per milestone instead of per frame
+                    if progress >= next_log_progress {
+                        log_encoding_progress(Some(progress), frame_count, total_frames, encode_started);
+                        next_log_progress = ((progress / PROGRESS_LOG_STEP).floor() + 1.0) * PROGRESS_LOG_STEP;
+                    }
+
+                    // Call progress callback
+                    if !progress_callback(progress) {
+                        // A segment is finished where it stopped, anything else is canceled
+                        if segment.is_some() {
+                            stopped = true;
+                            break 'packets;
                         }
+                        return Err(AppError::video_error(
+                            "Processing canceled by user".to_string(),
+                            ErrorCode::VideoProcessingFailed,
+                            Some("Video processing was canceled".to_string()),
+                        ));
+                    }
+                } else {
+                    if frame_count % PROGRESS_LOG_FRAME_INTERVAL == 0 {
+                        log_encoding_progress(None, frame_count, total_frames, encode_started);
+                    }
+
+                    // Progress is unknown, but the caller must still be able to cancel
+                    let check_due = last_unknown_progress.elapsed() >= UNKNOWN_PROGRESS_INTERVAL;
+                    if check_due {
+                        last_unknown_progress = Instant::now();
+                    }
+                    if check_due && !progress_callback(0.0) {
+                        if segment.is_some() {
+                            stopped = true;
+                            break 'packets;
+                        }
+                        return Err(AppError::video_error(
+                            "Processing canceled by user".to_string(),
+                            ErrorCode::VideoProcessingFailed,
+                            Some("Video processing was canceled".to_string()),
+                        ));
                     }
                 }
             }
+        }
 
-            // Reset packet
-            // Không cần thiết vì packet sẽ được ghi đè trong vòng lặp tiếp theo
+        // Flush decoder; a stopped segment ends at the last frame already encoded
+        info!("Flushing decoder and encoder");
+        if !stopped {
+            let _ = decoder.send_eof();
+            while decoder.receive_frame(&mut decoded).is_ok() {
+                let timestamp = decoded.timestamp();
+                decoded.set_pts(timestamp);
+                if !is_encoded(timestamp.map(|pts| pts as f64 * f64::from(input_time_base))) {
+                    continue;
+                }
+                let downloaded = download_frame(hw_decoding.as_mut(), &decoded)?;
+                filter_frame(&mut filter_graph, downloaded.as_ref().unwrap_or(&decoded))?;
+            }
         }
 
-        // Flush encoder
-        // Trong một triển khai thực tế, chúng ta sẽ cần tái cấu trúc code để tránh vấn đề này
-        // Ví dụ: tạo một hàm riêng để xử lý việc flush encoder
+        // Flush filter graph
+        if let Some(mut source) = filter_graph.get("in") {
+            let _ = source.source().flush();
+        }
+        encode_filtered_frames(
+            &mut filter_graph,
+            &mut encoder,
+            &mut output_ctx,
+            output_stream_index,
+            encoder_time_base,
+            output_time_base,
+        )?;
 
-        // Ghi log để thông báo
-        info!("Flushing encoder");
+        // Flush encoder
+        let _ = encoder.send_eof();
+        write_encoded_packets(
+            &mut encoder,
+            &mut output_ctx,
+            output_stream_index,
+            encoder_time_base,
+            output_time_base,
+        )?;
+
+        // Flush audio
+        if let Some(transcoder) = audio_transcoder.as_mut() {
+            transcoder.finish(&mut output_ctx)?;
+        }
 
-        // Receive remaining packets
-        // Trong một triển khai thực tế, chúng ta sẽ cần tái cấu trúc code để tránh vấn đề này
-        // Ví dụ: tạo một hàm riêng để xử lý việc nhận và ghi các packet còn lại
+        // Write the remaining subtitles
+        if let Some(muxer) = subtitle_muxer.as_mut() {
+            muxer.finish(&mut output_ctx)?;
+        }
 
-        // Ghi log để thông báo
-        info!("Processing remaining packets");
+        let elapsed = encode_started.elapsed().as_secs_f64();
+        info!(
+            "Encoded {} frames in {:.1}s ({:.1} fps)",
+            frame_count,
+            elapsed,
+            if elapsed > 0.0 { frame_count as f64 / elapsed } else { 0.0 }
+        );
 
         // Write trailer
-        // Tái cấu trúc để tránh mượn mutable nhiều lần
-        // Trong một triển khai thực tế, chúng ta sẽ cần tái cấu trúc code để tránh vấn đề này
-        // Ví dụ: tạo một hàm riêng để xử lý việc ghi trailer
-
-        // Ghi log để thông báo
         info!("Writing trailer to output: {}", output_path);
+        output_ctx.write_trailer().map_err(|e| {
+            AppError::ffmpeg_error(
+                format!("Cannot write output trailer: {}", e),
+                ErrorCode::EncodingError,
+                Some(format!("Error finalizing output file: {}", output_path)),
+            )
+        })?;
 
-        // Final progress update
-        progress_callback(100.0);
-
-        Ok(())
+        Ok(if stopped { SegmentEnd::Stopped(last_frame_secs, (width, height)) } else { SegmentEnd::Finished })
     }
 
     /// Choose codec based on options
-    fn choose_codec(&self, options: &ProcessingOptions) -> codec::Id {
-        // First, determine the output format
-        let format = options.output_format.to_lowercase();
+    ///
+    /// A codec named in the options comes first, then the one set for the container with
+    /// `set_default_codecs`, then the container's built-in default.
+    pub(super) fn choose_codec(&self, options: &ProcessingOptions) -> codec::Id {
+        Self::named_codec(options)
+            .or_else(|| self.default_codec(&options.output_format.to_lowercase()))
+            .unwrap_or_else(|| Self::format_codec(&options.output_format))
+    }
+
+    /// Codec from the options alone, ignoring `set_default_codecs`
+    ///
+    /// The saved defaults always fit their container, so this is enough for checks that
+    /// only need to know whether the container can hold the codec.
+    pub(super) fn builtin_codec(options: &ProcessingOptions) -> codec::Id {
+        Self::named_codec(options).unwrap_or_else(|| Self::format_codec(&options.output_format))
+    }
 
-        // Default codecs based on format
-        let default_video_codec = match format.as_str() {
+    /// Built-in default codec of an output format
+    fn format_codec(format: &str) -> codec::Id {
+        match format.to_lowercase().as_str() {
             "mp4" => codec::Id::H264,
             "mkv" => codec::Id::H264,
             "webm" => codec::Id::VP9,
             "avi" => codec::Id::MPEG4,
             "mov" => codec::Id::H264,
             "flv" => codec::Id::H264,
+            "gif" => codec::Id::GIF,
             _ => codec::Id::H264, // Default to H.264
-        };
+        }
+    }
 
+    /// Codec of the GPU or CPU encoder named in the options, `None` if none is known
+    fn named_codec(options: &ProcessingOptions) -> Option<codec::Id> {
         if options.use_gpu {
             // Use GPU codec if specified and available
             if let Some(gpu_codec) = &options.gpu_codec {
-                // In a real implementation with full FFmpeg support, we would use the actual
-                // hardware-accelerated codec IDs. Since ffmpeg-next doesn't expose all of these,
-                // we map them to the software equivalents for demonstration purposes.
+                // Hardware encoders have no codec ID of their own, so they map to the codec
+                // they produce; `find_encoder` opens the hardware encoder itself by name.
                 match gpu_codec.as_str() {
                     // NVIDIA NVENC
-                    "h264_nvenc" => return codec::Id::H264,
-                    "hevc_nvenc" => return codec::Id::HEVC,
+                    "h264_nvenc" => return Some(codec::Id::H264),
+                    "hevc_nvenc" => return Some(codec::Id::HEVC),
+                    "av1_nvenc" => return Some(codec::Id::AV1),
 
                     // Intel QuickSync
-                    "h264_qsv" => return codec::Id::H264,
-                    "hevc_qsv" => return codec::Id::HEVC,
+                    "h264_qsv" => return Some(codec::Id::H264),
+                    "hevc_qsv" => return Some(codec::Id::HEVC),
+                    "av1_qsv" => return Some(codec::Id::AV1),
 
                     // AMD AMF
-                    "h264_amf" => return codec::Id::H264,
-                    "hevc_amf" => return codec::Id::HEVC,
+                    "h264_amf" => return Some(codec::Id::H264),
+                    "hevc_amf" => return Some(codec::Id::HEVC),
+                    "av1_amf" => return Some(codec::Id::AV1),
 
                     // Apple VideoToolbox
-                    "h264_videotoolbox" => return codec::Id::H264,
-                    "hevc_videotoolbox" => return codec::Id::HEVC,
+                    "h264_videotoolbox" => return Some(codec::Id::H264),
+                    "hevc_videotoolbox" => return Some(codec::Id::HEVC),
 
                     // If unknown GPU codec, fall back to default
                     _ => {}
@@ -555,47 +1186,88 @@ impl VideoProcessor {
         if let Some(cpu_codec) = &options.cpu_codec {
             match cpu_codec.as_str() {
                 // H.264 / AVC
-                "libx264" => return codec::Id::H264,
-                "libx264rgb" => return codec::Id::H264,
+                "libx264" => return Some(codec::Id::H264),
+                "libx264rgb" => return Some(codec::Id::H264),
 
                 // H.265 / HEVC
-                "libx265" => return codec::Id::HEVC,
+                "libx265" => return Some(codec::Id::HEVC),
 
                 // VP8/VP9
-                "libvpx" => return codec::Id::VP8,
-                "libvpx-vp9" => return codec::Id::VP9,
+                "libvpx" => return Some(codec::Id::VP8),
+                "libvpx-vp9" => return Some(codec::Id::VP9),
 
                 // AV1
-                "libaom-av1" => return codec::Id::AV1,
-                "libsvtav1" => return codec::Id::AV1,
+                "libaom-av1" => return Some(codec::Id::AV1),
+                "libsvtav1" => return Some(codec::Id::AV1),
 
                 // Other codecs
-                "libxvid" => return codec::Id::MPEG4,
-                "mpeg2video" => return codec::Id::MPEG2VIDEO,
-                "mjpeg" => return codec::Id::MJPEG,
-                "png" => return codec::Id::PNG,
-                "gif" => return codec::Id::GIF,
+                "libxvid" => return Some(codec::Id::MPEG4),
+                "mpeg2video" => return Some(codec::Id::MPEG2VIDEO),
+                "mjpeg" => return Some(codec::Id::MJPEG),
+                "png" => return Some(codec::Id::PNG),
+                "gif" => return Some(codec::Id::GIF),
 
                 // If unknown CPU codec, fall back to default
                 _ => {}
             }
         }
 
-        // Return the default codec for the format
-        default_video_codec
+        None
+    }
+
+    /// Encoder to open for the options
+    ///
+    /// With GPU encoding this is the GPU encoder by name if FFmpeg was built with it,
+    /// otherwise FFmpeg's default encoder for the codec from `choose_codec`.
+    pub(super) fn find_encoder(&self, options: &ProcessingOptions) -> Option<ffmpeg::Codec> {
+        if options.use_gpu {
+            if let Some(gpu_encoder) = options.gpu_codec.as_deref().and_then(encoder::find_by_name) {
+                return Some(gpu_encoder);
+            }
+        }
+
+        encoder::find(self.choose_codec(options))
+    }
+
+    /// Options that produce the same codec with a software encoder instead of the GPU
+    pub fn software_fallback(&self, options: &ProcessingOptions) -> ProcessingOptions {
+        let mut fallback = options.clone();
+        fallback.use_gpu = false;
+        fallback.cpu_codec = encoder::find(self.choose_codec(options)).map(|c| c.name().to_string());
+        fallback
     }
 
     /// Convert a map of options to ProcessingOptions
-    pub fn options_from_map(&self, map: &HashMap<String, String>) -> ProcessingOptions {
+    ///
+    /// Fails on a malformed `crop`, which would otherwise be dropped silently.
+    pub fn options_from_map(&self, map: &HashMap<String, String>) -> AppResult<ProcessingOptions> {
         let mut options = ProcessingOptions {
             output_format: map.get("output_format").cloned().unwrap_or_else(|| "mp4".to_string()),
             output_path: map.get("output_path").cloned().unwrap_or_default(),
             resolution: None,
             bitrate: None,
+            quality: None,
+            two_pass: map.get("two_pass").map_or(false, |v| v == "true"),
             framerate: None,
+            keyframe_interval: map.get("keyframe_interval").and_then(|v| v.parse::<u32>().ok()),
+            scene_cut: map.get("scene_cut").map(|v| v == "true"),
+            pixel_format: map.get("pixel_format").filter(|v| !v.is_empty()).cloned(),
             use_gpu: map.get("use_gpu").map_or(false, |v| v == "true"),
             gpu_codec: map.get("gpu_codec").cloned(),
             cpu_codec: map.get("cpu_codec").cloned(),
+            gpu_fallback: map.get("gpu_fallback").map_or(true, |v| v != "false"),
+            hardware_decode: map.get("hardware_decode").map_or(false, |v| v == "true"),
+            interpolate: map.get("interpolate").map_or(false, |v| v == "true"),
+            threads: map.get("threads").and_then(|v| v.parse::<usize>().ok()).filter(|&n| n > 0),
+            convert_if_needed: map.get("convert_if_needed").map_or(false, |v| v == "true"),
+            reencode: false,
+            verify_output: map.get("verify_output").map_or(false, |v| v == "true"),
+            compute_quality: map.get("compute_quality").map_or(false, |v| v == "true"),
+            gif_fps: map.get("gif_fps").and_then(|v| v.parse::<f32>().ok()),
+            gif_max_width: map.get("gif_max_width").and_then(|v| v.parse::<u32>().ok()),
+            target_size_mb: map.get("target_size_mb").and_then(|v| v.parse::<u64>().ok()),
+            video_stream_index: map.get("video_stream_index").and_then(|v| v.parse::<usize>().ok()),
+            audio_stream_index: map.get("audio_stream_index").and_then(|v| v.parse::<usize>().ok()),
 
             // Time options
             start_time: None,
@@ -604,19 +1276,26 @@ impl VideoProcessor {
             // Edit options
             crop: None,
             rotate: None,
+            auto_orient: map.get("auto_orient").map_or(true, |v| v != "false"),
             flip: None,
             flop: None,
 
             // Sanitize options
             remove_metadata: None,
             blur_regions: None,
+            normalize_audio: map.get("normalize_audio").map_or(false, |v| v == "true"),
             audio_volume: None,
             denoise: None,
+            denoise_strength: None,
+
+            // Branding options
+            watermark: WatermarkConfig::from_config(map),
+            subtitles: SubtitleConfig::from_config(map),
         };
 
         // Parse resolution if provided
         if let (Some(width), Some(height)) = (map.get("width"), map.get("height")) {
-            if let (Ok(w), Ok(h)) = (width.parse::<u32>(), height.parse::<u32>()) {
+            if let (Some(w), Some(h)) = (filters::parse_dimension(width), filters::parse_dimension(height)) {
                 options.resolution = Some((w, h));
             }
         }
@@ -628,6 +1307,13 @@ impl VideoProcessor {
             }
         }
 
+        // Parse quality (CRF) if provided
+        if let Some(quality) = map.get("quality") {
+            if let Ok(q) = quality.parse::<u8>() {
+                options.quality = Some(q);
+            }
+        }
+
         // Parse framerate if provided
         if let Some(framerate) = map.get("framerate") {
             if let Ok(f) = framerate.parse::<f32>() {
@@ -650,26 +1336,13 @@ impl VideoProcessor {
 
         // Parse edit options
         if let Some(crop) = map.get("crop") {
-            // Format: "x,y,width,height"
-            let parts: Vec<&str> = crop.split(',').collect();
-            if parts.len() == 4 {
-                if let (Ok(x), Ok(y), Ok(w), Ok(h)) = (
-                    parts[0].trim().parse::<u32>(),
-                    parts[1].trim().parse::<u32>(),
-                    parts[2].trim().parse::<u32>(),
-                    parts[3].trim().parse::<u32>(),
-                ) {
-                    options.crop = Some((x, y, w, h));
-                }
-            }
+            options.crop = Some(filters::parse_crop(crop)?);
         }
 
         if let Some(rotate) = map.get("rotate") {
+            // The angle is checked by ProcessingOptions::validate
             if let Ok(r) = rotate.parse::<i32>() {
-                // Only allow 90, 180, 270 degrees
-                if r == 90 || r == 180 || r == 270 {
-                    options.rotate = Some(r);
-                }
+                options.rotate = Some(r);
             }
         }
 
@@ -680,6 +1353,12 @@ impl VideoProcessor {
         options.remove_metadata = map.get("remove_metadata").map(|v| v == "true");
         options.denoise = map.get("denoise").map(|v| v == "true");
 
+        if let Some(strength) = map.get("denoise_strength") {
+            if let Ok(s) = strength.parse::<f32>() {
+                options.denoise_strength = Some(s);
+            }
+        }
+
         if let Some(volume) = map.get("audio_volume") {
             if let Ok(v) = volume.parse::<f32>() {
                 options.audio_volume = Some(v);
@@ -712,7 +1391,7 @@ impl VideoProcessor {
             }
         }
 
-        options
+        Ok(options)
     }
 
     /// Convert a video with the given options
@@ -760,27 +1439,14 @@ impl VideoProcessor {
 
         // Crop operation
         if let Some(crop) = edit_operations.get("crop") {
-            // Format: "x,y,width,height"
-            let parts: Vec<&str> = crop.split(',').collect();
-            if parts.len() == 4 {
-                if let (Ok(x), Ok(y), Ok(w), Ok(h)) = (
-                    parts[0].trim().parse::<u32>(),
-                    parts[1].trim().parse::<u32>(),
-                    parts[2].trim().parse::<u32>(),
-                    parts[3].trim().parse::<u32>(),
-                ) {
-                    options.crop = Some((x, y, w, h));
-                }
-            }
+            options.crop = Some(filters::parse_crop(crop)?);
         }
 
         // Rotate operation
         if let Some(rotate) = edit_operations.get("rotate") {
+            // The angle is checked by ProcessingOptions::validate
             if let Ok(r) = rotate.parse::<i32>() {
-                // Only allow 90, 180, 270 degrees
-                if r == 90 || r == 180 || r == 270 {
-                    options.rotate = Some(r);
-                }
+                options.rotate = Some(r);
             }
         }
 
@@ -809,6 +1475,17 @@ impl VideoProcessor {
         // Denoise
         options.denoise = sanitize_options.get("denoise").map(|v| v == "true");
 
+        if let Some(strength) = sanitize_options.get("denoise_strength") {
+            if let Ok(s) = strength.parse::<f32>() {
+                options.denoise_strength = Some(s);
+            }
+        }
+
+        // Loudness normalization, applied before the audio volume
+        if let Some(normalize) = sanitize_options.get("normalize_audio") {
+            options.normalize_audio = normalize == "true";
+        }
+
         // Audio volume
         if let Some(volume) = sanitize_options.get("audio_volume") {
             if let Ok(v) = volume.parse::<f32>() {
@@ -847,4 +1524,129 @@ impl VideoProcessor {
     }
 }
 
+/// Delete an output file left behind by a failed encode
+///
+/// Only files modified since `started_at` are removed, so an existing file that
+/// was never opened (e.g. the input could not be read) is kept.
+fn remove_incomplete_output(output_path: &str, started_at: SystemTime) {
+    let written_by_us = fs::metadata(output_path)
+        .and_then(|metadata| metadata.modified())
+        .map_or(false, |modified| modified >= started_at);
+    if !written_by_us {
+        return;
+    }
+
+    match fs::remove_file(output_path) {
+        Ok(()) => info!("Removed incomplete output {}", output_path),
+        Err(e) => warn!("Failed to remove incomplete output {}: {}", output_path, e),
+    }
+}
+
+/// Frame threading with at most `threads` threads
+fn thread_limit(threads: usize) -> threading::Config {
+    threading::Config {
+        kind: threading::Type::Frame,
+        count: threads,
+        ..Default::default()
+    }
+}
+
+/// Log encoding progress with the frame count, speed and elapsed time
+fn log_encoding_progress(progress: Option<f32>, frame_count: u64, total_frames: u64, started: Instant) {
+    let elapsed = started.elapsed().as_secs_f64();
+    let fps = if elapsed > 0.0 { frame_count as f64 / elapsed } else { 0.0 };
+
+    match progress {
+        Some(progress) => info!(
+            "Encoding {:.0}%: frame {}/{}, {:.1} fps, {:.1}s elapsed",
+            progress, frame_count, total_frames, fps, elapsed
+        ),
+        None => info!("Encoding: frame {}, {:.1} fps, {:.1}s elapsed", frame_count, fps, elapsed),
+    }
+}
+
+/// Software copy of a frame decoded on the GPU, `None` for software-decoded frames
+fn download_frame(hw_decoding: Option<&mut HardwareDecoding>, frame: &VideoFrame) -> AppResult<Option<VideoFrame>> {
+    match hw_decoding {
+        Some(hw) => hw.download(frame),
+        None => Ok(None),
+    }
+}
+
+/// Push a decoded frame into the filter graph
+fn filter_frame(filter_graph: &mut filter::Graph, frame: &VideoFrame) -> AppResult<()> {
+    let mut source = filter_graph.get("in").ok_or_else(|| {
+        AppError::video_error(
+            "Filter graph has no input".to_string(),
+            ErrorCode::VideoProcessingFailed,
+            Some("Error feeding frame to video filters".to_string()),
+        )
+    })?;
+
+    source.source().add(frame).map_err(|e| {
+        AppError::video_error(
+            format!("Error sending frame to filter graph: {}", e),
+            ErrorCode::VideoProcessingFailed,
+            Some("Error feeding frame to video filters".to_string()),
+        )
+    })
+}
+
+/// Drain filtered frames from the graph and send them to the encoder
+fn encode_filtered_frames(
+    filter_graph: &mut filter::Graph,
+    encoder: &mut encoder::Video,
+    output_ctx: &mut OutputContext,
+    stream_index: usize,
+    encoder_time_base: Rational,
+    output_time_base: Rational,
+) -> AppResult<()> {
+    let mut filtered = VideoFrame::empty();
+
+    loop {
+        let received = match filter_graph.get("out") {
+            Some(mut sink) => sink.sink().frame(&mut filtered).is_ok(),
+            None => false,
+        };
+
+        if !received {
+            return Ok(());
+        }
+
+        filtered.set_kind(picture::Type::None);
+        encoder.send_frame(&filtered).map_err(|e| {
+            AppError::video_error(
+                format!("Error sending frame to encoder: {}", e),
+                ErrorCode::EncodingError,
+                Some("Error encoding video frame".to_string()),
+            )
+        })?;
+
+        write_encoded_packets(encoder, output_ctx, stream_index, encoder_time_base, output_time_base)?;
+    }
+}
+
+/// Receive all available packets from the encoder and write them to the output
+fn write_encoded_packets(
+    encoder: &mut encoder::Video,
+    output_ctx: &mut OutputContext,
+    stream_index: usize,
+    encoder_time_base: Rational,
+    output_time_base: Rational,
+) -> AppResult<()> {
+    let mut encoded = Packet::empty();
+
+    while encoder.receive_packet(&mut encoded).is_ok() {
+        encoded.set_stream(stream_index);
+        encoded.rescale_ts(encoder_time_base, output_time_base);
+        encoded.write_interleaved(output_ctx).map_err(|e| {
+            AppError::ffmpeg_error(
+                format!("Error writing packet: {}", e),
+                ErrorCode::EncodingError,
+                Some("Error writing encoded data to output file".to_string()),
+            )
+        })?;
+    }
 
+    Ok(())
+}
@@ -0,0 +1,308 @@
+use std::fs;
+use std::path::Path;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use ffmpeg::codec::{self, decoder};
+use ffmpeg::filter;
+use ffmpeg::format::context::Input as InputContext;
+use ffmpeg::format::input;
+use ffmpeg::media::Type as MediaType;
+use ffmpeg::util::frame::video::Video as VideoFrame;
+use ffmpeg::util::rational::Rational;
+use ffmpeg::Packet;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::filters;
+use super::VideoProcessor;
+
+/// PSNR reported when every compared frame is identical (the real value is infinite)
+const MAX_PSNR: f64 = 100.0;
+
+/// Largest sample value of the 8-bit format the pictures are compared in
+const MAX_SAMPLE_VALUE: f64 = 255.0;
+
+/// Objective quality of an output compared with its source
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QualityScore {
+    pub psnr: Option<f64>,                  // dB from the mean squared error; above ~40 looks lossless
+    pub ssim: Option<f64>,                  // 0-1, 1 for identical pictures
+    #[serde(default)]
+    pub vmaf: Option<f64>,                  // 0-100, only measured in builds with the `vmaf` feature
+    pub frames: u64,                        // frame pairs compared
+}
+
+/// Sums of the per-frame values the metric filters attach to each frame
+#[derive(Default)]
+struct Totals {
+    mse: f64,
+    ssim: f64,
+    frames: u64,
+}
+
+impl Totals {
+    fn add(&mut self, frame: &VideoFrame) {
+        let metadata = frame.metadata();
+        let value = |key: &str| metadata.get(key).and_then(|v| v.trim().parse::<f64>().ok());
+
+        if let (Some(mse), Some(ssim)) = (value("lavfi.psnr.mse_avg"), value("lavfi.ssim.All")) {
+            self.mse += mse;
+            self.ssim += ssim;
+            self.frames += 1;
+        }
+    }
+}
+
+/// One input of the comparison, decoded a packet at a time
+struct VideoSource {
+    name: &'static str,
+    input_ctx: InputContext,
+    stream_index: usize,
+    decoder: decoder::Video,
+    time_base: Rational,
+    first_pts: Option<i64>,
+    position: f64,                          // seconds since the first frame sent to the graph
+    finished: bool,
+}
+
+impl VideoSource {
+    fn open(path: &str, name: &'static str) -> AppResult<Self> {
+        let input_ctx = input(path).map_err(|e| {
+            AppError::ffmpeg_error(
+                format!("Cannot open input file '{}': {}", path, e),
+                ErrorCode::FFmpegInitError,
+                Some(format!("Error opening input file: {}", path)),
+            )
+        })?;
+
+        let stream = input_ctx.streams().best(MediaType::Video).ok_or_else(|| {
+            AppError::video_error(
+                format!("No video stream found in file: {}", path),
+                ErrorCode::InvalidVideoFormat,
+                Some(format!("File does not contain a valid video stream: {}", path)),
+            )
+        })?;
+        let stream_index = stream.index();
+        let time_base = stream.time_base();
+
+        let decoder = codec::context::Context::from_parameters(stream.parameters())
+            .and_then(|ctx| ctx.decoder().video())
+            .map_err(|e| {
+                AppError::video_error(
+                    format!("Cannot create decoder: {}", e),
+                    ErrorCode::DecodingError,
+                    Some(format!("Error creating video decoder for file: {}", path)),
+                )
+            })?;
+
+        Ok(Self {
+            name,
+            input_ctx,
+            stream_index,
+            decoder,
+            time_base,
+            first_pts: None,
+            position: 0.0,
+            finished: false,
+        })
+    }
+
+    /// Decode packets until some frames reached the graph or the input ended
+    fn feed(&mut self, graph: &mut filter::Graph) -> Result<(), ffmpeg::Error> {
+        let mut packet = Packet::empty();
+        loop {
+            match packet.read(&mut self.input_ctx) {
+                Ok(()) => {
+                    if packet.stream() != self.stream_index || self.decoder.send_packet(&packet).is_err() {
+                        continue;
+                    }
+                    if self.send_frames(graph)? {
+                        return Ok(());
+                    }
+                }
+                Err(ffmpeg::Error::Eof) => {
+                    let _ = self.decoder.send_eof();
+                    self.send_frames(graph)?;
+                    if let Some(mut source) = graph.get(self.name) {
+                        source.source().flush()?;
+                    }
+                    self.finished = true;
+                    return Ok(());
+                }
+                Err(_) => continue,
+            }
+        }
+    }
+
+    /// Send the decoded frames to the graph, returning whether there were any
+    fn send_frames(&mut self, graph: &mut filter::Graph) -> Result<bool, ffmpeg::Error> {
+        let mut decoded = VideoFrame::empty();
+        let mut sent = false;
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            let timestamp = decoded.timestamp();
+            decoded.set_pts(timestamp);
+            if let Some(pts) = timestamp {
+                let first_pts = *self.first_pts.get_or_insert(pts);
+                self.position = (pts - first_pts) as f64 * f64::from(self.time_base);
+            }
+
+            let mut source = graph.get(self.name).ok_or(ffmpeg::Error::FilterNotFound)?;
+            source.source().add(&decoded)?;
+            sent = true;
+        }
+        Ok(sent)
+    }
+}
+
+/// `libvmaf` stage writing its pooled score to `log_path`, in builds with the `vmaf` feature
+#[cfg(feature = "vmaf")]
+fn vmaf_filter(log_path: &Path) -> Option<String> {
+    Some(format!(
+        "libvmaf=log_fmt=json:log_path={}",
+        filters::escape_filter_path(&log_path.to_string_lossy())
+    ))
+}
+
+#[cfg(not(feature = "vmaf"))]
+fn vmaf_filter(_log_path: &Path) -> Option<String> {
+    None
+}
+
+/// Mean VMAF from the JSON log `libvmaf` writes when the graph is freed
+fn read_vmaf_log(log_path: &Path) -> Option<f64> {
+    let log = fs::read_to_string(log_path).ok()?;
+    let _ = fs::remove_file(log_path);
+    let json: serde_json::Value = serde_json::from_str(&log).ok()?;
+    json["pooled_metrics"]["vmaf"]["mean"].as_f64()
+}
+
+impl VideoProcessor {
+    /// Score `output_path` against `reference_path` with PSNR and SSIM, plus VMAF in
+    /// builds with the `vmaf` feature (its models are large, so it is off by default)
+    ///
+    /// Both files are decoded side by side; frames are paired by their time from the
+    /// start of each file, and the reference is scaled to the output size. Outputs
+    /// with a different frame rate or a trimmed start therefore score lower than
+    /// their real quality. `progress_callback` gets the share of the output compared
+    /// so far and stops the comparison by returning false.
+    pub fn compare_quality(
+        &self,
+        reference_path: &str,
+        output_path: &str,
+        progress_callback: impl Fn(f32) -> bool,
+    ) -> AppResult<QualityScore> {
+        let mut main = VideoSource::open(output_path, "main")?;
+        let mut reference = VideoSource::open(reference_path, "ref")?;
+        let duration = main.input_ctx.duration() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
+
+        let vmaf_log = std::env::temp_dir().join(format!("vmaf_{}.json", uuid::Uuid::new_v4()));
+        let mut metrics = vec!["psnr".to_string(), "ssim".to_string()];
+        metrics.extend(vmaf_filter(&vmaf_log));
+
+        // Each metric passes the output frames through with its values added as metadata
+        let references: String = (0..metrics.len()).map(|i| format!("[r{}]", i)).collect();
+        let mut spec = format!(
+            "[main]setpts=PTS-STARTPTS,format=yuv420p[m0];\
+             [ref]setpts=PTS-STARTPTS,scale={}:{},format=yuv420p,split={}{}",
+            main.decoder.width(),
+            main.decoder.height(),
+            metrics.len(),
+            references
+        );
+        for (i, metric) in metrics.iter().enumerate() {
+            let output = if i + 1 == metrics.len() { "out".to_string() } else { format!("m{}", i + 1) };
+            spec.push_str(&format!(";[m{}][r{}]{}[{}]", i, i, metric, output));
+        }
+
+        let map_err = |e: ffmpeg::Error| {
+            AppError::video_error(
+                format!("Cannot compare the output with its source: {}", e),
+                ErrorCode::VideoProcessingFailed,
+                Some(format!("Error running the quality filters: {}", spec)),
+            )
+        };
+
+        let mut graph = filter::Graph::new();
+        let buffer = filter::find("buffer").ok_or_else(|| map_err(ffmpeg::Error::FilterNotFound))?;
+        let buffersink = filter::find("buffersink").ok_or_else(|| map_err(ffmpeg::Error::FilterNotFound))?;
+        graph
+            .add(&buffer, "main", &filters::buffer_args(&main.decoder, main.time_base))
+            .map_err(map_err)?;
+        graph
+            .add(&buffer, "ref", &filters::buffer_args(&reference.decoder, reference.time_base))
+            .map_err(map_err)?;
+        graph.add(&buffersink, "out", "").map_err(map_err)?;
+        graph
+            .output("main", 0)
+            .and_then(|parser| parser.output("ref", 0))
+            .and_then(|parser| parser.input("out", 0))
+            .and_then(|parser| parser.parse(&spec))
+            .map_err(map_err)?;
+        graph.validate().map_err(map_err)?;
+
+        let mut totals = Totals::default();
+        let mut filtered = VideoFrame::empty();
+        while !(main.finished && reference.finished) {
+            // Keep both inputs at about the same time so few frames wait in the graph
+            let source = if reference.finished || (!main.finished && main.position <= reference.position) {
+                &mut main
+            } else {
+                &mut reference
+            };
+            source.feed(&mut graph).map_err(map_err)?;
+
+            while let Some(mut sink) = graph.get("out") {
+                if sink.sink().frame(&mut filtered).is_err() {
+                    break;
+                }
+                totals.add(&filtered);
+            }
+
+            let progress = if duration > 0.0 {
+                (main.position / duration * 100.0).clamp(0.0, 100.0) as f32
+            } else {
+                0.0
+            };
+            if !progress_callback(progress) {
+                return Err(AppError::video_error(
+                    "Quality comparison canceled".to_string(),
+                    ErrorCode::VideoProcessingFailed,
+                    None,
+                ));
+            }
+        }
+
+        // libvmaf writes its log when it is freed
+        drop(graph);
+        let vmaf = read_vmaf_log(&vmaf_log);
+
+        if totals.frames == 0 {
+            warn!("No frames of {} could be compared with {}", output_path, reference_path);
+            return Ok(QualityScore { vmaf, ..QualityScore::default() });
+        }
+
+        let frames = totals.frames as f64;
+        let mse = totals.mse / frames;
+        let score = QualityScore {
+            psnr: Some(if mse > 0.0 {
+                (10.0 * (MAX_SAMPLE_VALUE * MAX_SAMPLE_VALUE / mse).log10()).min(MAX_PSNR)
+            } else {
+                MAX_PSNR
+            }),
+            ssim: Some(totals.ssim / frames),
+            vmaf,
+            frames: totals.frames,
+        };
+        info!(
+            "Quality of {} against {}: PSNR {:.2} dB, SSIM {:.4} over {} frames",
+            output_path,
+            reference_path,
+            score.psnr.unwrap_or_default(),
+            score.ssim.unwrap_or_default(),
+            score.frames
+        );
+
+        Ok(score)
+    }
+}
@@ -0,0 +1,260 @@
+use std::ffi::CString;
+use std::fs;
+use std::path::Path;
+use std::ptr;
+use log::{info, warn};
+
+use ffmpeg::codec::{self, encoder};
+use ffmpeg::format::{input, output};
+use ffmpeg::media::Type as MediaType;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::gif;
+use super::{ProcessingOptions, VideoProcessor};
+
+/// Whether the options leave every stream as it is, so a stream copy gives the same result.
+///
+/// Any option that changes the picture, the sound, the codec or the stream selection
+/// needs a full encode. The source must also already have the codec `choose_codec`
+/// picks for the output, which `can_remux` checks.
+pub fn is_remux_candidate(options: &ProcessingOptions) -> bool {
+    options.resolution.is_none()
+        && options.bitrate.is_none()
+        && options.quality.is_none()
+        && options.target_size_mb.is_none()
+        && !options.two_pass
+        && options.keyframe_interval.is_none()
+        && options.pixel_format.is_none()
+        && !options.use_gpu
+        && options.cpu_codec.is_none()
+        && !options.hardware_decode
+        && !options.reencode
+        && keeps_content(options)
+}
+
+/// Whether the options leave the picture, the sound, the time range and the stream
+/// selection alone; encoder settings and the output size are not looked at.
+pub fn keeps_content(options: &ProcessingOptions) -> bool {
+    options.framerate.is_none()
+        && options.video_stream_index.is_none()
+        && options.audio_stream_index.is_none()
+        && options.start_time.is_none()
+        && options.end_time.is_none()
+        && options.crop.is_none()
+        && options.rotate.is_none()
+        && options.flip != Some(true)
+        && options.flop != Some(true)
+        && options.remove_metadata != Some(true)
+        && options.blur_regions.as_ref().map_or(true, |regions| regions.is_empty())
+        && !options.normalize_audio
+        && options.audio_volume.is_none()
+        && options.denoise != Some(true)
+        && options.watermark.is_none()
+        && options.subtitles.is_none()
+        && !gif::is_gif_output(options)
+}
+
+/// Stream types carried over by a remux; data and attachment streams are dropped
+fn is_copied(media_type: MediaType) -> bool {
+    matches!(media_type, MediaType::Video | MediaType::Audio | MediaType::Subtitle)
+}
+
+/// Whether every stream of `input_path` can be copied into the container of `output_path`
+///
+/// With `video_codec` set, the best video stream must also already have that codec.
+/// Problems opening the input are logged and answered with `false`, so the caller
+/// falls back to a full encode that reports the error properly.
+pub fn can_remux(input_path: &str, output_path: &str, video_codec: Option<codec::Id>) -> bool {
+    let Ok(path) = CString::new(output_path) else {
+        return false;
+    };
+    // SAFETY: av_guess_format returns a pointer to a static muxer description or null
+    let muxer = unsafe { ffmpeg::ffi::av_guess_format(ptr::null(), path.as_ptr(), ptr::null()) };
+    if muxer.is_null() {
+        return false;
+    }
+
+    let input_ctx = match input(input_path) {
+        Ok(input_ctx) => input_ctx,
+        Err(e) => {
+            warn!("Cannot open {} to check for a remux: {}", input_path, e);
+            return false;
+        }
+    };
+
+    if let Some(video_codec) = video_codec {
+        let source_codec = input_ctx.streams().best(MediaType::Video).map(|stream| stream.parameters().id());
+        if source_codec != Some(video_codec) {
+            info!(
+                "Video of {} is {}, not {}, re-encoding",
+                input_path,
+                source_codec.map_or("missing", |id| id.name()),
+                video_codec.name()
+            );
+            return false;
+        }
+    }
+
+    let format = Path::new(output_path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let mut copied_streams = 0;
+    for stream in input_ctx.streams() {
+        let parameters = stream.parameters();
+        if !is_copied(parameters.medium()) {
+            continue;
+        }
+
+        let codec_id = parameters.id();
+        // SAFETY: muxer is a valid static description; the call only reads it
+        let supported = unsafe {
+            ffmpeg::ffi::avformat_query_codec(muxer, codec_id.into(), codec::Compliance::Normal.into())
+        };
+        let allowed = supported == 1
+            && (parameters.medium() != MediaType::Video
+                || VideoProcessor::validate_container_codec(&format, codec_id).is_ok());
+
+        if !allowed {
+            info!(
+                "Stream {} ({}) cannot be copied into {}, re-encoding",
+                stream.index(),
+                codec_id.name(),
+                output_path
+            );
+            return false;
+        }
+        copied_streams += 1;
+    }
+
+    copied_streams > 0
+}
+
+/// Copy the video, audio and subtitle streams of `input_path` into `output_path` without
+/// re-encoding. Progress is reported in percent; returning `false` from the callback
+/// cancels the copy.
+pub fn remux(input_path: &str, output_path: &str, progress_callback: &dyn Fn(f32) -> bool) -> AppResult<()> {
+    if !Path::new(input_path).exists() {
+        return Err(AppError::io_error(
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Input file not found"),
+            ErrorCode::FileNotFound,
+            Some(format!("Input video file not found: {}", input_path)),
+        ));
+    }
+
+    if let Some(parent) = Path::new(output_path).parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            AppError::io_error(
+                e,
+                ErrorCode::DirectoryError,
+                Some(format!("Failed to create output directory: {:?}", parent)),
+            )
+        })?;
+    }
+
+    let mut input_ctx = input(input_path).map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot open input file '{}': {}", input_path, e),
+            ErrorCode::FFmpegInitError,
+            Some(format!("Error opening input file: {}", input_path)),
+        )
+    })?;
+
+    let mut output_ctx = output(output_path).map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot create output context for '{}': {}", output_path, e),
+            ErrorCode::FFmpegInitError,
+            Some(format!("Error creating output file: {}", output_path)),
+        )
+    })?;
+
+    // Output stream index for each input stream, `None` for dropped streams
+    let mut stream_mapping: Vec<Option<usize>> = vec![None; input_ctx.nb_streams() as usize];
+    let mut input_time_bases = vec![ffmpeg::Rational::new(0, 1); input_ctx.nb_streams() as usize];
+
+    for stream in input_ctx.streams() {
+        let parameters = stream.parameters();
+        if !is_copied(parameters.medium()) {
+            info!("Dropping stream {} ({:?}) from the remux", stream.index(), parameters.medium());
+            continue;
+        }
+
+        let mut output_stream = output_ctx.add_stream(encoder::find(codec::Id::None)).map_err(|e| {
+            AppError::video_error(
+                format!("Cannot add output stream: {}", e),
+                ErrorCode::EncodingError,
+                Some("Error adding output stream to output context".to_string()),
+            )
+        })?;
+        output_stream.set_parameters(parameters);
+        output_stream.set_metadata(stream.metadata().to_owned());
+        // The source container's codec tag may not be valid in the new one
+        // SAFETY: the stream's codec parameters are owned by the output context
+        unsafe {
+            (*output_stream.parameters().as_mut_ptr()).codec_tag = 0;
+        }
+
+        stream_mapping[stream.index()] = Some(output_stream.index());
+        input_time_bases[stream.index()] = stream.time_base();
+    }
+
+    output_ctx.set_metadata(input_ctx.metadata().to_owned());
+    output_ctx.write_header().map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot write output header: {}", e),
+            ErrorCode::EncodingError,
+            Some(format!("Error writing header to output file: {}", output_path)),
+        )
+    })?;
+
+    let duration_secs = input_ctx.duration() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
+    info!("Remuxing {} into {}", input_path, output_path);
+
+    for (stream, mut packet) in input_ctx.packets() {
+        let Some(output_index) = stream_mapping[stream.index()] else {
+            continue;
+        };
+        let input_time_base = input_time_bases[stream.index()];
+        let position = packet.pts().map(|pts| pts as f64 * f64::from(input_time_base));
+
+        let output_time_base = output_ctx
+            .stream(output_index)
+            .map(|stream| stream.time_base())
+            .unwrap_or(input_time_base);
+        packet.rescale_ts(input_time_base, output_time_base);
+        packet.set_position(-1);
+        packet.set_stream(output_index);
+        packet.write_interleaved(&mut output_ctx).map_err(|e| {
+            AppError::ffmpeg_error(
+                format!("Error writing packet: {}", e),
+                ErrorCode::EncodingError,
+                Some("Error copying packets to output file".to_string()),
+            )
+        })?;
+
+        if let (Some(position), true) = (position, duration_secs > 0.0) {
+            let progress = (position / duration_secs * 100.0).clamp(0.0, 99.9) as f32;
+            if !progress_callback(progress) {
+                return Err(AppError::video_error(
+                    "Processing canceled by user".to_string(),
+                    ErrorCode::VideoProcessingFailed,
+                    Some("Remux was canceled".to_string()),
+                ));
+            }
+        }
+    }
+
+    output_ctx.write_trailer().map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot write output trailer: {}", e),
+            ErrorCode::EncodingError,
+            Some(format!("Error finalizing output file: {}", output_path)),
+        )
+    })?;
+
+    progress_callback(100.0);
+
+    Ok(())
+}
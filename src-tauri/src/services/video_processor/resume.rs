@@ -0,0 +1,270 @@
+use std::fs;
+use std::path::Path;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use ffmpeg::codec::{self, encoder};
+use ffmpeg::format::{input, output};
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::gif;
+use super::sequence;
+use super::subtitles::SubtitleMode;
+use super::{ProcessingOptions, VideoProcessor};
+
+/// Encoded part of an interrupted encode, enough to continue it later
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResumePoint {
+    pub segments: Vec<String>,              // finished parts in input order, each a playable file
+    pub encoded_secs: f64,                  // input time of the last frame in the last segment
+    #[serde(default)]
+    pub output_size: Option<(u32, u32)>,    // video size of the segments, later ones are encoded at it too
+}
+
+impl ResumePoint {
+    /// Delete the segment files, for an encode that will not be continued
+    pub fn discard(&self) {
+        for segment in &self.segments {
+            match fs::remove_file(segment) {
+                Ok(()) => info!("Deleted segment {}", segment),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => warn!("Failed to delete segment {}: {}", segment, e),
+            }
+        }
+    }
+}
+
+/// Start of the input range one segment covers
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct Segment {
+    pub after_secs: Option<f64>,            // last frame of the previous segment, `None` for the first
+    pub output_size: Option<(u32, u32)>,    // video size of the previous segments, `None` for the first
+}
+
+/// How `encode_pass` ended for a segment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum SegmentEnd {
+    Finished,
+    /// Stopped by the progress callback; the input time of the last frame written, if any,
+    /// and the size the video was encoded at
+    Stopped(Option<f64>, (u32, u32)),
+}
+
+/// Whether an encode with `options` can be split into segments and continued later
+///
+/// Two-pass encodes, image sequences, GIFs and muxed subtitles need the whole input in
+/// one go.
+pub fn is_resumable(input_path: &str, options: &ProcessingOptions) -> bool {
+    !options.two_pass
+        && !sequence::is_sequence_pattern(input_path)
+        && !gif::is_gif_output(options)
+        && options.subtitles.as_ref().map_or(true, |subtitles| subtitles.mode != SubtitleMode::Mux)
+}
+
+/// File segment `index` of `output_path` is written to: `name.seg<index>.ext`
+fn segment_path(output_path: &str, index: usize) -> String {
+    let output = Path::new(output_path);
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_name = match output.extension() {
+        Some(ext) => format!("{}.seg{}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}.seg{}", stem, index),
+    };
+    output.with_file_name(file_name).to_string_lossy().into_owned()
+}
+
+/// Join `segments`, encoded with the same settings, into `output_path` without re-encoding
+///
+/// Timestamps are moved forward where a segment starts before the previous one ended,
+/// which happens for audio at the joints and for encoders that reorder frames.
+fn join_segments(segments: &[String], output_path: &str) -> AppResult<()> {
+    let open_segment = |path: &str| {
+        input(path).map_err(|e| {
+            AppError::ffmpeg_error(
+                format!("Cannot open segment '{}': {}", path, e),
+                ErrorCode::FFmpegInitError,
+                Some(format!("Error opening encoded segment: {}", path)),
+            )
+        })
+    };
+
+    let mut output_ctx = output(output_path).map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot create output context for '{}': {}", output_path, e),
+            ErrorCode::FFmpegInitError,
+            Some(format!("Error creating output file: {}", output_path)),
+        )
+    })?;
+
+    // Every segment has the streams of the first one, in the same order
+    let stream_count = {
+        let first = open_segment(&segments[0])?;
+        for stream in first.streams() {
+            let mut output_stream = output_ctx.add_stream(encoder::find(codec::Id::None)).map_err(|e| {
+                AppError::video_error(
+                    format!("Cannot add output stream: {}", e),
+                    ErrorCode::EncodingError,
+                    Some("Error adding output stream to output context".to_string()),
+                )
+            })?;
+            output_stream.set_parameters(stream.parameters());
+            // SAFETY: the stream's codec parameters are owned by the output context
+            unsafe {
+                (*output_stream.parameters().as_mut_ptr()).codec_tag = 0;
+            }
+        }
+        first.nb_streams() as usize
+    };
+
+    output_ctx.write_header().map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot write output header: {}", e),
+            ErrorCode::EncodingError,
+            Some(format!("Error writing header to output file: {}", output_path)),
+        )
+    })?;
+
+    let mut last_dts: Vec<Option<i64>> = vec![None; stream_count];
+    for path in segments {
+        let mut input_ctx = open_segment(path)?;
+        let mut shifts: Vec<Option<i64>> = vec![None; stream_count];
+
+        for (stream, mut packet) in input_ctx.packets() {
+            let index = stream.index();
+            if index >= stream_count {
+                continue;
+            }
+
+            let output_time_base = output_ctx
+                .stream(index)
+                .map(|stream| stream.time_base())
+                .unwrap_or(stream.time_base());
+            packet.rescale_ts(stream.time_base(), output_time_base);
+
+            let dts = packet.dts().or(packet.pts());
+            let shift = *shifts[index].get_or_insert_with(|| match (dts, last_dts[index]) {
+                (Some(dts), Some(last)) if dts <= last => last + 1 - dts,
+                _ => 0,
+            });
+            if shift > 0 {
+                packet.set_pts(packet.pts().map(|pts| pts + shift));
+                packet.set_dts(packet.dts().map(|dts| dts + shift));
+            }
+            if let Some(dts) = packet.dts().or(packet.pts()) {
+                last_dts[index] = Some(dts);
+            }
+
+            packet.set_position(-1);
+            packet.set_stream(index);
+            packet.write_interleaved(&mut output_ctx).map_err(|e| {
+                AppError::ffmpeg_error(
+                    format!("Error writing packet: {}", e),
+                    ErrorCode::EncodingError,
+                    Some("Error joining encoded segments".to_string()),
+                )
+            })?;
+        }
+    }
+
+    output_ctx.write_trailer().map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot write output trailer: {}", e),
+            ErrorCode::EncodingError,
+            Some(format!("Error finalizing output file: {}", output_path)),
+        )
+    })
+}
+
+impl VideoProcessor {
+    /// Process a video in segments, so an interrupted encode can continue later
+    ///
+    /// Each call encodes one segment, starting after the frame `resume` stopped at.
+    /// When the progress callback returns false the segment is finished as a playable
+    /// file and the returned point lists every segment so far; pass it back to continue.
+    /// Once the input is fully encoded the segments are joined into `output_path`,
+    /// deleted, and `None` is returned. Video is cut at exact frames, audio at the
+    /// nearest packet. Options that are not [`is_resumable`] and stream copies, which are
+    /// quick enough to simply start over, are processed in one go. A failed encode
+    /// deletes all segments.
+    pub fn process_video_resumable(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        options: ProcessingOptions,
+        resume: Option<ResumePoint>,
+        progress_callback: impl Fn(f32) -> bool + Send + 'static,
+    ) -> AppResult<Option<ResumePoint>> {
+        if !is_resumable(input_path, &options) || self.copies_streams(input_path, output_path, &options) {
+            if let Some(resume) = resume {
+                resume.discard();
+            }
+            return self.process_video(input_path, output_path, options, progress_callback).map(|_| None);
+        }
+
+        let mut point = resume.unwrap_or_default();
+        let result = options.validate().and_then(|_| self.apply_target_size(input_path, options));
+        let options = match result {
+            Ok(options) => options,
+            Err(e) => {
+                point.discard();
+                return Err(e);
+            }
+        };
+
+        let resuming = !point.segments.is_empty();
+        let segment = Segment {
+            after_secs: resuming.then_some(point.encoded_secs),
+            // Segments can only be joined at one size, even if the upscale limit changed
+            output_size: point.output_size.filter(|_| resuming),
+        };
+        let segment_output = segment_path(output_path, point.segments.len());
+        if let Some(after_secs) = segment.after_secs {
+            info!("Resuming {} after {:.3}s into {}", input_path, after_secs, segment_output);
+        }
+
+        let end = self.encode_pass(input_path, &segment_output, &options, None, Some(segment), &progress_callback);
+        match end {
+            Ok(SegmentEnd::Stopped(last_frame_secs, output_size)) => {
+                // A segment without frames adds nothing to continue from
+                match last_frame_secs {
+                    Some(secs) => {
+                        point.segments.push(segment_output);
+                        point.encoded_secs = secs;
+                        point.output_size = Some(output_size);
+                    }
+                    None => ResumePoint { segments: vec![segment_output], ..ResumePoint::default() }.discard(),
+                }
+                info!("Stopped {} after {:.3}s in {} segments", input_path, point.encoded_secs, point.segments.len());
+                Ok(Some(point))
+            }
+            Ok(SegmentEnd::Finished) => {
+                point.segments.push(segment_output);
+                let result = if point.segments.len() == 1 {
+                    fs::rename(&point.segments[0], output_path).map_err(|e| {
+                        AppError::io_error(
+                            e,
+                            ErrorCode::FileWriteError,
+                            Some(format!("Failed to move {} to {}", point.segments[0], output_path)),
+                        )
+                    })
+                } else {
+                    info!("Joining {} segments into {}", point.segments.len(), output_path);
+                    join_segments(&point.segments, output_path)
+                };
+                point.discard();
+                result?;
+
+                progress_callback(100.0);
+                Ok(None)
+            }
+            Err(e) => {
+                point.segments.push(segment_output);
+                point.discard();
+                Err(e)
+            }
+        }
+    }
+}
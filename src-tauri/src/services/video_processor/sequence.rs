@@ -0,0 +1,201 @@
+use std::ffi::CString;
+use std::path::Path;
+use log::info;
+
+use ffmpeg::codec;
+use ffmpeg::format::{self, context::Input as InputContext};
+use ffmpeg::media::Type as MediaType;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::{ProcessingOptions, VideoProcessor};
+
+/// First frame numbers probed when a sequence does not start at 0, like FFmpeg's image2
+const START_NUMBER_RANGE: i64 = 5;
+
+/// Frame rate of a sequence opened without one
+pub const DEFAULT_SEQUENCE_FPS: f64 = 25.0;
+
+/// Byte range and zero-padding width of the `%d` / `%04d` placeholder in `pattern`
+fn placeholder(pattern: &str) -> Option<(usize, usize, usize)> {
+    let bytes = pattern.as_bytes();
+    let mut start = 0;
+
+    while let Some(offset) = pattern[start..].find('%') {
+        let percent = start + offset;
+        let digits_end = bytes[percent + 1..]
+            .iter()
+            .position(|b| !b.is_ascii_digit())
+            .map_or(bytes.len(), |n| percent + 1 + n);
+
+        if bytes.get(digits_end) == Some(&b'd') {
+            let width = pattern[percent + 1..digits_end].parse().unwrap_or(0);
+            return Some((percent, digits_end + 1, width));
+        }
+        start = percent + 1;
+    }
+
+    None
+}
+
+/// Whether `path` is a numbered image sequence pattern such as `frame_%04d.png`
+///
+/// A file that exists under that exact name, e.g. `100%5d.mp4`, is not a pattern.
+pub fn is_sequence_pattern(path: &str) -> bool {
+    placeholder(path).is_some() && !Path::new(path).exists()
+}
+
+/// Path of frame `number` in `pattern`
+fn frame_path(pattern: &str, number: i64) -> Option<String> {
+    let (start, end, width) = placeholder(pattern)?;
+    Some(format!("{}{:0width$}{}", &pattern[..start], number, &pattern[end..], width = width))
+}
+
+/// Width and height of the image at `path`
+fn image_dimensions(path: &str) -> AppResult<(u32, u32)> {
+    let input_ctx = format::input(path).map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot open frame '{}': {}", path, e),
+            ErrorCode::FFmpegInitError,
+            Some(format!("Error reading image: {}", path)),
+        )
+    })?;
+
+    input_ctx
+        .streams()
+        .best(MediaType::Video)
+        .and_then(|stream| codec::context::Context::from_parameters(stream.parameters()).ok())
+        .and_then(|ctx| ctx.decoder().video().ok())
+        .map(|decoder| (decoder.width(), decoder.height()))
+        .ok_or_else(|| {
+            AppError::video_error(
+                format!("Not an image: {}", path),
+                ErrorCode::InvalidVideoFormat,
+                Some("Every file in the sequence must be an image".to_string()),
+            )
+        })
+}
+
+/// Number of the first existing frame of `pattern`
+fn first_frame_number(pattern: &str) -> AppResult<i64> {
+    if !is_sequence_pattern(pattern) {
+        return Err(AppError::validation_error(
+            format!("Not an image sequence pattern: {}", pattern),
+            ErrorCode::InvalidArgument,
+            Some("Use a pattern with a frame number placeholder, e.g. frame_%04d.png".to_string()),
+        ));
+    }
+
+    (0..START_NUMBER_RANGE)
+        .find(|&number| frame_path(pattern, number).is_some_and(|path| Path::new(&path).exists()))
+        .ok_or_else(|| {
+            AppError::io_error(
+                std::io::Error::new(std::io::ErrorKind::NotFound, "First frame not found"),
+                ErrorCode::FileNotFound,
+                Some(format!(
+                    "No first frame found for {} (numbered 0 to {})",
+                    pattern,
+                    START_NUMBER_RANGE - 1
+                )),
+            )
+        })
+}
+
+/// The number of the first frame of `pattern` and how many consecutive frames follow.
+///
+/// Fails if no frame exists or if the frames do not all have the same dimensions.
+pub fn validate_sequence(pattern: &str) -> AppResult<(i64, usize)> {
+    let start_number = first_frame_number(pattern)?;
+
+    let mut dimensions = None;
+    let mut count = 0;
+    while let Some(path) = frame_path(pattern, start_number + count as i64) {
+        if !Path::new(&path).exists() {
+            break;
+        }
+
+        let frame_dimensions = image_dimensions(&path)?;
+        match dimensions {
+            None => dimensions = Some(frame_dimensions),
+            Some(expected) if expected != frame_dimensions => {
+                return Err(AppError::validation_error(
+                    format!(
+                        "Frame {} is {}x{} but the sequence is {}x{}",
+                        path, frame_dimensions.0, frame_dimensions.1, expected.0, expected.1
+                    ),
+                    ErrorCode::InvalidArgument,
+                    Some("All frames of a sequence must have the same dimensions".to_string()),
+                ));
+            }
+            Some(_) => {}
+        }
+        count += 1;
+    }
+
+    Ok((start_number, count))
+}
+
+/// Open `pattern` with FFmpeg's image2 demuxer, reading frames at `fps`
+pub fn open_sequence(pattern: &str, fps: f64) -> AppResult<InputContext> {
+    let start_number = first_frame_number(pattern)?;
+
+    let open_error = |e: ffmpeg::Error| {
+        AppError::ffmpeg_error(
+            format!("Cannot open image sequence '{}': {}", pattern, e),
+            ErrorCode::FFmpegInitError,
+            Some(format!("Error opening image sequence: {}", pattern)),
+        )
+    };
+
+    let name = CString::new("image2").expect("static demuxer name");
+    // SAFETY: returns a static demuxer description or null if image2 is not built in
+    let demuxer = unsafe { ffmpeg::ffi::av_find_input_format(name.as_ptr()) };
+    if demuxer.is_null() {
+        return Err(open_error(ffmpeg::Error::DemuxerNotFound));
+    }
+    let demuxer = ffmpeg::Format::Input(unsafe { format::Input::wrap(demuxer as *mut _) });
+
+    let mut demuxer_options = ffmpeg::Dictionary::new();
+    demuxer_options.set("framerate", &fps.to_string());
+    demuxer_options.set("start_number", &start_number.to_string());
+
+    match format::open_with(pattern, &demuxer, demuxer_options).map_err(open_error)? {
+        format::Context::Input(input_ctx) => Ok(input_ctx),
+        format::Context::Output(_) => Err(open_error(ffmpeg::Error::Bug)),
+    }
+}
+
+impl VideoProcessor {
+    /// Encode a numbered image sequence such as `frame_%04d.png` into a video at `fps`.
+    ///
+    /// The frames go through the same encoder path as `process_video`; `options` sets
+    /// the codec, quality and filters. Fails if the first frame is missing or if the
+    /// frames do not all share the same dimensions.
+    pub fn frames_to_video(
+        &self,
+        pattern: &str,
+        output_path: &str,
+        fps: f64,
+        mut options: ProcessingOptions,
+        progress_callback: impl Fn(f32) -> bool + Send + 'static,
+    ) -> AppResult<()> {
+        if !fps.is_finite() || fps <= 0.0 {
+            return Err(AppError::validation_error(
+                format!("Invalid frame rate: {}", fps),
+                ErrorCode::InvalidArgument,
+                Some("The frame rate must be greater than 0".to_string()),
+            ));
+        }
+
+        let (start_number, count) = validate_sequence(pattern)?;
+        info!(
+            "Encoding {} frames of {} starting at {} into {} at {} fps",
+            count, pattern, start_number, output_path, fps
+        );
+
+        // The encoder and the image2 demuxer both take the rate from the options
+        options.framerate = Some(fps as f32);
+        options.output_path = output_path.to_string();
+        self.process_video(pattern, output_path, options, progress_callback)
+    }
+}
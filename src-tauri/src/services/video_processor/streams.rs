@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+use ffmpeg::codec;
+use ffmpeg::format::context::Input as InputContext;
+use ffmpeg::media::Type as MediaType;
+use ffmpeg::Stream;
+use ffmpeg_next as ffmpeg;
+
+use super::{VideoError, VideoResult};
+
+/// One stream of an input file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamInfo {
+    pub index: usize,
+    pub media_type: String,                 // "video", "audio", "subtitle", "data", ...
+    pub codec: String,
+    pub language: Option<String>,           // ISO 639 code from the stream metadata
+}
+
+/// Audio track of an input file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioStreamInfo {
+    pub index: usize,
+    pub codec: String,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub language: Option<String>,
+    pub title: Option<String>,
+}
+
+/// Lowercase name of a media type as shown to the frontend
+fn media_type_name(media_type: MediaType) -> &'static str {
+    match media_type {
+        MediaType::Video => "video",
+        MediaType::Audio => "audio",
+        MediaType::Subtitle => "subtitle",
+        MediaType::Data => "data",
+        MediaType::Attachment => "attachment",
+        _ => "unknown",
+    }
+}
+
+fn metadata_value(stream: &Stream, key: &str) -> Option<String> {
+    stream.metadata().get(key).map(str::to_string)
+}
+
+/// Every stream of the input, with the audio streams described in more detail
+pub fn describe_streams(input_ctx: &InputContext) -> (Vec<StreamInfo>, Vec<AudioStreamInfo>) {
+    let mut streams = Vec::new();
+    let mut audio_streams = Vec::new();
+
+    for stream in input_ctx.streams() {
+        let parameters = stream.parameters();
+        let media_type = parameters.medium();
+        let codec_name = parameters.id().name().to_string();
+        let language = metadata_value(&stream, "language");
+
+        if media_type == MediaType::Audio {
+            // Channels and sample rate are only exposed through a decoder
+            let (channels, sample_rate) = codec::context::Context::from_parameters(parameters)
+                .and_then(|ctx| ctx.decoder().audio())
+                .map(|decoder| (decoder.channels(), decoder.rate()))
+                .unwrap_or((0, 0));
+
+            audio_streams.push(AudioStreamInfo {
+                index: stream.index(),
+                codec: codec_name.clone(),
+                channels,
+                sample_rate,
+                language: language.clone(),
+                title: metadata_value(&stream, "title"),
+            });
+        }
+
+        streams.push(StreamInfo {
+            index: stream.index(),
+            media_type: media_type_name(media_type).to_string(),
+            codec: codec_name,
+            language,
+        });
+    }
+
+    (streams, audio_streams)
+}
+
+/// The stream at `index` if one is requested, the best `media_type` stream otherwise
+///
+/// Fails with `VideoError::InvalidParam` if the requested stream does not exist or is
+/// not a `media_type` stream.
+pub fn select_stream(
+    input_ctx: &InputContext,
+    index: Option<usize>,
+    media_type: MediaType,
+) -> VideoResult<Option<Stream<'_>>> {
+    let Some(index) = index else {
+        return Ok(input_ctx.streams().best(media_type));
+    };
+
+    let stream = input_ctx.stream(index).ok_or_else(|| {
+        VideoError::invalid_param(format!(
+            "Stream {} does not exist, the input has {} streams",
+            index,
+            input_ctx.nb_streams()
+        ))
+    })?;
+
+    let actual = stream.parameters().medium();
+    if actual != media_type {
+        return Err(VideoError::invalid_param(format!(
+            "Stream {} is a {} stream, not {}",
+            index,
+            media_type_name(actual),
+            media_type_name(media_type)
+        )));
+    }
+
+    Ok(Some(stream))
+}
@@ -0,0 +1,357 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use ffmpeg::codec::{self, decoder, encoder};
+use ffmpeg::format::context::Output as OutputContext;
+use ffmpeg::media::Type as MediaType;
+use ffmpeg::util::rational::Rational;
+use ffmpeg::{Dictionary, Packet, Rescale, Subtitle};
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::filters::{self, FilterChain};
+
+/// Subtitle file formats that can be burned in or muxed
+const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "ass", "ssa", "vtt"];
+
+/// Size of the buffer a single subtitle event is encoded into
+const SUBTITLE_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Timestamps of subtitle events are handled in milliseconds
+const MILLISECONDS: Rational = Rational(1, 1000);
+
+/// How an external subtitle file is added to the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleMode {
+    /// Render the subtitles into the video frames
+    #[default]
+    Burn,
+    /// Add the subtitles as a separate (soft) track
+    Mux,
+}
+
+/// External subtitle file to add to the output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubtitleConfig {
+    pub path: String,
+    #[serde(default)]
+    pub mode: SubtitleMode,
+    pub language: Option<String>,           // ISO 639-2 code for muxed tracks, e.g. "eng"
+}
+
+impl SubtitleConfig {
+    /// Read the subtitles from the flat `subtitle_*` config keys
+    pub fn from_config(config: &HashMap<String, String>) -> Option<Self> {
+        let path = config.get("subtitle_path").filter(|p| !p.is_empty())?.clone();
+
+        let mode = match config.get("subtitle_mode").map(String::as_str) {
+            Some("mux") => SubtitleMode::Mux,
+            _ => SubtitleMode::Burn,
+        };
+
+        Some(Self {
+            path,
+            mode,
+            language: config.get("subtitle_language").filter(|l| !l.is_empty()).cloned(),
+        })
+    }
+
+    /// Lowercase extension of the subtitle file
+    fn extension(&self) -> String {
+        Path::new(&self.path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+
+    /// Check that the file exists and can be added to the given output format
+    pub fn validate(&self, output_format: &str) -> AppResult<()> {
+        if !Path::new(&self.path).is_file() {
+            return Err(AppError::validation_error(
+                format!("Subtitle file not found: {}", self.path),
+                ErrorCode::FileNotFound,
+                Some("Choose an existing subtitle file".to_string()),
+            ));
+        }
+
+        let extension = self.extension();
+        if !SUBTITLE_EXTENSIONS.contains(&extension.as_str()) {
+            return Err(AppError::validation_error(
+                format!("Unsupported subtitle format: {}", self.path),
+                ErrorCode::InvalidArgument,
+                Some(format!("Supported formats: {}", SUBTITLE_EXTENSIONS.join(", "))),
+            ));
+        }
+
+        if self.mode == SubtitleMode::Mux {
+            subtitle_codec_name(output_format, &extension)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Name of the subtitle encoder used to mux a file with `extension` into `output_format`
+fn subtitle_codec_name(output_format: &str, extension: &str) -> AppResult<&'static str> {
+    let codec = match (output_format.to_lowercase().as_str(), extension) {
+        ("mp4" | "mov" | "m4v", _) => Some("mov_text"),
+        ("mkv", "ass" | "ssa") => Some("ass"),
+        ("mkv", "vtt") => Some("webvtt"),
+        ("mkv", _) => Some("subrip"),
+        ("webm", "vtt") => Some("webvtt"),
+        _ => None,
+    };
+
+    codec.ok_or_else(|| {
+        AppError::validation_error(
+            format!(
+                "The {} container cannot carry {} subtitles as a separate track",
+                output_format, extension
+            ),
+            ErrorCode::FormatError,
+            Some("Burn the subtitles into the video, or use MKV/MP4 output (WebM only supports WebVTT)".to_string()),
+        )
+    })
+}
+
+/// Render the subtitle file into the frames.
+///
+/// Added before any scaling so the subtitles are laid out at the source resolution.
+pub fn add_burned_subtitles(chain: &mut FilterChain, subtitles: &SubtitleConfig) {
+    info!("Burning subtitles from {}", subtitles.path);
+    chain.push(format!("subtitles=filename={}", filters::escape_filter_path(&subtitles.path)));
+}
+
+/// One encoded subtitle event waiting to be written
+struct SubtitleEvent {
+    start_ms: i64,
+    duration_ms: i64,
+    data: Vec<u8>,
+}
+
+/// Encodes an external subtitle file into a soft subtitle track of the output
+pub struct SubtitleMuxer {
+    output_index: usize,
+    output_time_base: Rational,
+    events: VecDeque<SubtitleEvent>,
+}
+
+impl SubtitleMuxer {
+    /// Read and encode the subtitle file, adding a subtitle stream to `output_ctx`
+    pub fn new(
+        subtitles: &SubtitleConfig,
+        output_ctx: &mut OutputContext,
+        output_format: &str,
+    ) -> AppResult<Self> {
+        let codec_name = subtitle_codec_name(output_format, &subtitles.extension())?;
+
+        let map_err = |message: &str, e: ffmpeg::Error| {
+            AppError::video_error(
+                format!("{}: {}", message, e),
+                ErrorCode::EncodingError,
+                Some(format!("Error adding subtitles from {}", subtitles.path)),
+            )
+        };
+
+        let mut input_ctx = ffmpeg::format::input(&subtitles.path)
+            .map_err(|e| map_err("Cannot open subtitle file", e))?;
+
+        let (input_index, input_time_base, parameters) = {
+            let stream = input_ctx
+                .streams()
+                .best(MediaType::Subtitle)
+                .ok_or_else(|| map_err("No subtitle stream found", ffmpeg::Error::StreamNotFound))?;
+            (stream.index(), stream.time_base(), stream.parameters())
+        };
+
+        let mut decoder = codec::context::Context::from_parameters(parameters)
+            .and_then(|ctx| ctx.decoder().subtitle())
+            .map_err(|e| map_err("Cannot create subtitle decoder", e))?;
+
+        let subtitle_codec = encoder::find_by_name(codec_name)
+            .ok_or_else(|| map_err("Subtitle encoder not found", ffmpeg::Error::EncoderNotFound))?;
+
+        let output_index = output_ctx
+            .add_stream(subtitle_codec)
+            .map(|stream| stream.index())
+            .map_err(|e| map_err("Cannot add subtitle stream", e))?;
+
+        let mut encoder = codec::context::Context::new_with_codec(subtitle_codec)
+            .encoder()
+            .subtitle()
+            .map_err(|e| map_err("Cannot create subtitle encoder", e))?;
+
+        encoder.set_time_base(MILLISECONDS);
+        copy_subtitle_header(&decoder, &mut encoder);
+
+        let mut encoder = encoder
+            .open_as(subtitle_codec)
+            .map_err(|e| map_err("Cannot open subtitle encoder", e))?;
+
+        if let Some(mut output_stream) = output_ctx.stream_mut(output_index) {
+            output_stream.set_parameters(&encoder);
+            output_stream.set_time_base(MILLISECONDS);
+            if let Some(language) = &subtitles.language {
+                let mut metadata = Dictionary::new();
+                metadata.set("language", language);
+                output_stream.set_metadata(metadata);
+            }
+        }
+
+        // Subtitle files are small, so encode every event up front and interleave
+        // them with the video while writing
+        let mut events = VecDeque::new();
+        let mut buffer = vec![0u8; SUBTITLE_BUFFER_SIZE];
+        for (stream, packet) in input_ctx.packets() {
+            if stream.index() != input_index {
+                continue;
+            }
+
+            let mut subtitle = Subtitle::new();
+            let decoded = decoder
+                .decode(&packet, &mut subtitle)
+                .map_err(|e| map_err("Cannot decode subtitle", e))?;
+            if !decoded {
+                continue;
+            }
+
+            let Some(pts) = packet.pts() else {
+                continue;
+            };
+            let start_ms = pts.rescale(input_time_base, MILLISECONDS) + i64::from(subtitle.start());
+            let duration_ms = if subtitle.end() > subtitle.start() {
+                i64::from(subtitle.end() - subtitle.start())
+            } else {
+                packet.duration().rescale(input_time_base, MILLISECONDS)
+            };
+
+            subtitle.set_pts(Some(start_ms.rescale(MILLISECONDS, ffmpeg::rescale::TIME_BASE)));
+            subtitle.set_start(0);
+            subtitle.set_end(duration_ms.max(0) as u32);
+
+            let size = encode_subtitle(&mut encoder, &subtitle, &mut buffer)
+                .map_err(|e| map_err("Cannot encode subtitle", e))?;
+            if size > 0 {
+                events.push_back(SubtitleEvent {
+                    start_ms,
+                    duration_ms,
+                    data: buffer[..size].to_vec(),
+                });
+            }
+        }
+
+        info!(
+            "Muxing {} subtitle events from {} as {} (stream {})",
+            events.len(),
+            subtitles.path,
+            codec_name,
+            output_index
+        );
+
+        Ok(Self {
+            output_index,
+            output_time_base: MILLISECONDS,
+            events,
+        })
+    }
+
+    /// Refresh the output time base after the muxer has written its header
+    pub fn update_output_time_base(&mut self, output_ctx: &OutputContext) {
+        if let Some(stream) = output_ctx.stream(self.output_index) {
+            self.output_time_base = stream.time_base();
+        }
+    }
+
+    /// Write every subtitle event starting at or before `position` (in seconds)
+    pub fn write_until(&mut self, position: f64, output_ctx: &mut OutputContext) -> AppResult<()> {
+        let position_ms = (position * 1000.0) as i64;
+
+        while self.events.front().map_or(false, |event| event.start_ms <= position_ms) {
+            if let Some(event) = self.events.pop_front() {
+                self.write_event(event, output_ctx)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the remaining subtitle events
+    pub fn finish(&mut self, output_ctx: &mut OutputContext) -> AppResult<()> {
+        while let Some(event) = self.events.pop_front() {
+            self.write_event(event, output_ctx)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_event(&self, event: SubtitleEvent, output_ctx: &mut OutputContext) -> AppResult<()> {
+        let pts = event.start_ms.rescale(MILLISECONDS, self.output_time_base);
+
+        let mut packet = Packet::copy(&event.data);
+        packet.set_stream(self.output_index);
+        packet.set_pts(Some(pts));
+        packet.set_dts(Some(pts));
+        packet.set_duration(event.duration_ms.rescale(MILLISECONDS, self.output_time_base));
+
+        packet.write_interleaved(output_ctx).map_err(|e| {
+            AppError::ffmpeg_error(
+                format!("Error writing subtitle packet: {}", e),
+                ErrorCode::EncodingError,
+                Some("Error writing subtitles to output file".to_string()),
+            )
+        })
+    }
+}
+
+/// Give the encoder the decoder's ASS header, which text subtitle encoders require
+fn copy_subtitle_header(decoder: &decoder::Subtitle, encoder: &mut encoder::subtitle::Subtitle) {
+    // SAFETY: both pointers are valid codec contexts; the header is copied into a
+    // buffer owned by the encoder context, which frees it with the context.
+    unsafe {
+        let source = decoder.as_ptr();
+        let target = encoder.as_mut_ptr();
+
+        if (*source).subtitle_header.is_null() || (*source).subtitle_header_size <= 0 {
+            return;
+        }
+
+        let size = (*source).subtitle_header_size as usize;
+        let header = ffmpeg::ffi::av_mallocz(size + 1) as *mut u8;
+        if header.is_null() {
+            return;
+        }
+
+        std::ptr::copy_nonoverlapping((*source).subtitle_header, header, size);
+        (*target).subtitle_header = header;
+        (*target).subtitle_header_size = size as i32;
+    }
+}
+
+/// Encode one subtitle event, returning the number of bytes written to `buffer`.
+///
+/// `encoder::Subtitle::encode` discards the encoded size, so the FFmpeg call is made
+/// directly.
+fn encode_subtitle(
+    encoder: &mut encoder::Subtitle,
+    subtitle: &Subtitle,
+    buffer: &mut [u8],
+) -> Result<usize, ffmpeg::Error> {
+    // SAFETY: the encoder is open and the buffer length is passed along with it.
+    let size = unsafe {
+        ffmpeg::ffi::avcodec_encode_subtitle(
+            encoder.as_mut_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len() as i32,
+            subtitle.as_ptr(),
+        )
+    };
+
+    if size < 0 {
+        Err(ffmpeg::Error::from(size))
+    } else {
+        Ok(size as usize)
+    }
+}
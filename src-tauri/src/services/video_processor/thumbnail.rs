@@ -0,0 +1,284 @@
+use std::fs;
+use std::path::Path;
+use log::{info, warn};
+
+use ffmpeg::codec::{self, encoder};
+use ffmpeg::format::input;
+use ffmpeg::media::Type as MediaType;
+use ffmpeg::util::frame::video::Video as VideoFrame;
+use ffmpeg::util::rational::Rational;
+use ffmpeg::Packet;
+use ffmpeg_next as ffmpeg;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::filters;
+
+/// Image encoder for `codec_id` and the pixel format it takes
+pub(super) fn image_encoder(codec_id: codec::Id) -> AppResult<(ffmpeg::Codec, ffmpeg::format::Pixel)> {
+    let encoder_codec = encoder::find(codec_id).ok_or_else(|| {
+        AppError::video_error(
+            format!("Encoder codec not found: {:?}", codec_id),
+            ErrorCode::CodecNotSupported,
+            Some("The image encoder is not available".to_string()),
+        )
+    })?;
+    let pixel_format = encoder_codec
+        .video()
+        .ok()
+        .and_then(|video| video.formats())
+        .and_then(|mut formats| formats.next())
+        .unwrap_or(ffmpeg::format::Pixel::YUVJ420P);
+
+    Ok((encoder_codec, pixel_format))
+}
+
+/// Open an image encoder; every frame sent to it becomes one complete image
+pub(super) fn open_image_encoder(
+    encoder_codec: ffmpeg::Codec,
+    pixel_format: ffmpeg::format::Pixel,
+    width: u32,
+    height: u32,
+) -> AppResult<encoder::Video> {
+    let mut encoder = codec::context::Context::new_with_codec(encoder_codec)
+        .encoder()
+        .video()
+        .map_err(|e| {
+            AppError::video_error(
+                format!("Cannot create encoder: {}", e),
+                ErrorCode::EncodingError,
+                Some("Error creating image encoder".to_string()),
+            )
+        })?;
+
+    encoder.set_width(width);
+    encoder.set_height(height);
+    encoder.set_format(pixel_format);
+    encoder.set_time_base(Rational::new(1, 25));
+
+    encoder.open_as(encoder_codec).map_err(|e| {
+        AppError::video_error(
+            format!("Cannot open encoder: {}", e),
+            ErrorCode::EncodingError,
+            Some("Error opening image encoder".to_string()),
+        )
+    })
+}
+
+/// Pick the image encoder from the output file extension
+fn thumbnail_codec(output_path: &str) -> AppResult<codec::Id> {
+    let extension = Path::new(output_path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "jpg" | "jpeg" => Ok(codec::Id::MJPEG),
+        "png" => Ok(codec::Id::PNG),
+        _ => Err(AppError::validation_error(
+            format!("Unsupported thumbnail format: '{}'", extension),
+            ErrorCode::InvalidArgument,
+            Some("Thumbnails can be saved as .jpg, .jpeg or .png".to_string()),
+        )),
+    }
+}
+
+/// Decode the frame shown at `timestamp_secs` and save it as a JPEG or PNG image.
+///
+/// Timestamps past the end of the video return the last frame. A `width` of 0 keeps
+/// the source width; otherwise the height follows the source aspect ratio.
+pub fn extract_thumbnail(
+    input_path: &str,
+    timestamp_secs: f64,
+    output_path: &str,
+    width: u32,
+) -> AppResult<()> {
+    if !Path::new(input_path).exists() {
+        return Err(AppError::io_error(
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Input file not found"),
+            ErrorCode::FileNotFound,
+            Some(format!("Input video file not found: {}", input_path)),
+        ));
+    }
+
+    let codec_id = thumbnail_codec(output_path)?;
+
+    let mut input_ctx = input(input_path).map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("Cannot open input file '{}': {}", input_path, e),
+            ErrorCode::FFmpegInitError,
+            Some(format!("Error opening input file: {}", input_path)),
+        )
+    })?;
+
+    let input_stream = input_ctx.streams().best(MediaType::Video).ok_or_else(|| {
+        AppError::video_error(
+            format!("No video stream found in file: {}", input_path),
+            ErrorCode::InvalidVideoFormat,
+            Some("Cannot create a thumbnail for an audio-only file".to_string()),
+        )
+    })?;
+
+    let stream_index = input_stream.index();
+    let time_base = input_stream.time_base();
+    let start_time = if input_stream.start_time() == ffmpeg::ffi::AV_NOPTS_VALUE {
+        0
+    } else {
+        input_stream.start_time()
+    };
+
+    let mut decoder = codec::context::Context::from_parameters(input_stream.parameters())
+        .and_then(|ctx| ctx.decoder().video())
+        .map_err(|e| {
+            AppError::video_error(
+                format!("Cannot create decoder: {}", e),
+                ErrorCode::DecodingError,
+                Some(format!("Error creating video decoder for file: {}", input_path)),
+            )
+        })?;
+
+    // Clamp the timestamp into the video's duration
+    let duration_secs = if input_ctx.duration() > 0 {
+        input_ctx.duration() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE)
+    } else {
+        f64::MAX
+    };
+    let timestamp_secs = timestamp_secs.max(0.0);
+    if timestamp_secs > duration_secs {
+        warn!(
+            "Thumbnail timestamp {}s is past the end of the video ({}s), using the last frame",
+            timestamp_secs, duration_secs
+        );
+    }
+    let timestamp_secs = timestamp_secs.min(duration_secs);
+
+    let target_pts = start_time
+        + (timestamp_secs * f64::from(time_base.denominator()) / f64::from(time_base.numerator()))
+            as i64;
+
+    // Seek to the keyframe before the target
+    let seek_ts = (timestamp_secs * f64::from(ffmpeg::ffi::AV_TIME_BASE)) as i64;
+    if let Err(e) = input_ctx.seek(seek_ts, ..seek_ts) {
+        warn!("Seeking to {}s failed, decoding from the start: {}", timestamp_secs, e);
+    }
+
+    // Decode until we reach the target; keep the last frame in case we hit the end first
+    let mut decoded = VideoFrame::empty();
+    let mut thumbnail_frame: Option<VideoFrame> = None;
+    let mut reached_target = false;
+
+    for (stream, packet) in input_ctx.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+
+        if decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            let pts = decoded.timestamp().unwrap_or(target_pts);
+            if thumbnail_frame.is_none() || pts <= target_pts {
+                thumbnail_frame = Some(decoded.clone());
+            }
+            if pts >= target_pts {
+                reached_target = true;
+                break;
+            }
+        }
+
+        if reached_target {
+            break;
+        }
+    }
+
+    if !reached_target {
+        let _ = decoder.send_eof();
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            thumbnail_frame = Some(decoded.clone());
+        }
+    }
+
+    let mut frame = thumbnail_frame.ok_or_else(|| {
+        AppError::video_error(
+            format!("No frame could be decoded from: {}", input_path),
+            ErrorCode::DecodingError,
+            Some("Error decoding thumbnail frame".to_string()),
+        )
+    })?;
+
+    let (encoder_codec, pixel_format) = image_encoder(codec_id)?;
+
+    // Scale and convert the frame through a filter graph
+    let spec = if width > 0 {
+        format!("scale={}:-2", width)
+    } else {
+        "null".to_string()
+    };
+    let mut filter_graph = filters::create_video_filter_graph(&decoder, time_base, pixel_format, &spec)?;
+
+    let filter_error = |e: ffmpeg::Error| {
+        AppError::video_error(
+            format!("Error scaling thumbnail: {}", e),
+            ErrorCode::VideoProcessingFailed,
+            Some("Error filtering thumbnail frame".to_string()),
+        )
+    };
+
+    frame.set_pts(Some(0));
+    if let Some(mut source) = filter_graph.get("in") {
+        source.source().add(&frame).map_err(filter_error)?;
+        let _ = source.source().flush();
+    }
+
+    let mut scaled = VideoFrame::empty();
+    if let Some(mut sink) = filter_graph.get("out") {
+        sink.sink().frame(&mut scaled).map_err(filter_error)?;
+    }
+
+    // Encode a single image
+    let mut encoder = open_image_encoder(encoder_codec, pixel_format, scaled.width(), scaled.height())?;
+
+    let encode_error = |e: ffmpeg::Error| {
+        AppError::video_error(
+            format!("Error encoding thumbnail: {}", e),
+            ErrorCode::EncodingError,
+            Some("Error encoding thumbnail image".to_string()),
+        )
+    };
+
+    encoder.send_frame(&scaled).map_err(encode_error)?;
+    encoder.send_eof().map_err(encode_error)?;
+
+    let mut packet = Packet::empty();
+    encoder.receive_packet(&mut packet).map_err(encode_error)?;
+
+    let data = packet.data().ok_or_else(|| encode_error(ffmpeg::Error::InvalidData))?;
+
+    if let Some(parent) = Path::new(output_path).parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            AppError::io_error(
+                e,
+                ErrorCode::DirectoryError,
+                Some(format!("Failed to create output directory: {:?}", parent)),
+            )
+        })?;
+    }
+
+    fs::write(output_path, data).map_err(|e| {
+        AppError::io_error(
+            e,
+            ErrorCode::FileWriteError,
+            Some(format!("Failed to write thumbnail: {}", output_path)),
+        )
+    })?;
+
+    info!(
+        "Thumbnail {}x{} at {}s written to {}",
+        scaled.width(),
+        scaled.height(),
+        timestamp_secs,
+        output_path
+    );
+
+    Ok(())
+}
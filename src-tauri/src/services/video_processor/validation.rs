@@ -0,0 +1,233 @@
+use ffmpeg::format::Pixel;
+use ffmpeg_next as ffmpeg;
+
+use super::audio::{MAX_AUDIO_VOLUME, MIN_AUDIO_VOLUME};
+use super::encoding::MAX_QUALITY;
+use super::filters::{MAX_DENOISE_STRENGTH, MIN_DENOISE_STRENGTH};
+use super::gif::{self, MAX_GIF_FPS};
+use super::{ProcessingOptions, VideoError, VideoProcessor, VideoResult};
+
+/// Largest width/height accepted for resolutions and crop rectangles
+const MAX_DIMENSION: u32 = 16384;
+
+/// Highest accepted output frame rate
+const MAX_FRAMERATE: f32 = 240.0;
+
+/// Pixel format sizes are checked against when none is set, the one most encoders pick
+const ASSUMED_PIXEL_FORMAT: &str = "yuv420p";
+
+impl ProcessingOptions {
+    /// Check that every option is usable before any work is done.
+    ///
+    /// Returns `VideoError::InvalidParam` describing the first invalid value, or
+    /// `VideoError::Codec` if the codec cannot be stored in the output container.
+    pub fn validate(&self) -> VideoResult<()> {
+        let (align_w, align_h) = self.chroma_alignment();
+
+        if let Some((width, height)) = self.resolution {
+            if width == 0 && height == 0 {
+                return Err(VideoError::invalid_param(
+                    "Resolution needs at least a width or a height",
+                ));
+            }
+            // 0 means "follow the aspect ratio" and is rounded to fit the chroma later
+            for (name, value, align) in [("width", width, align_w), ("height", height, align_h)] {
+                if value != 0 {
+                    self.check_chroma_fit("Resolution", name, value, align)?;
+                }
+                if value > MAX_DIMENSION {
+                    return Err(VideoError::invalid_param(format!(
+                        "Resolution {} {} exceeds the maximum of {}",
+                        name, value, MAX_DIMENSION
+                    )));
+                }
+            }
+        }
+
+        if let Some((x, y, width, height)) = self.crop {
+            if width == 0 || height == 0 {
+                return Err(VideoError::invalid_param(format!("Crop {}x{} is empty", width, height)));
+            }
+            // Without a resolution the cropped size is the output size
+            if self.resolution.is_none() {
+                self.check_chroma_fit("Crop", "width", width, align_w)?;
+                self.check_chroma_fit("Crop", "height", height, align_h)?;
+            }
+            if x.saturating_add(width) > MAX_DIMENSION || y.saturating_add(height) > MAX_DIMENSION {
+                return Err(VideoError::invalid_param(format!(
+                    "Crop {}x{} at ({}, {}) is out of bounds",
+                    width, height, x, y
+                )));
+            }
+        }
+
+        if let Some(rotate) = self.rotate {
+            if ![90, 180, 270].contains(&rotate) {
+                return Err(VideoError::invalid_param(format!(
+                    "Rotation must be 90, 180 or 270 degrees, got {}",
+                    rotate
+                )));
+            }
+        }
+
+        if self.target_size_mb == Some(0) {
+            return Err(VideoError::invalid_param("Target size must be greater than 0 MB"));
+        }
+
+        if self.bitrate == Some(0) {
+            return Err(VideoError::invalid_param("Bitrate must be greater than 0"));
+        }
+
+        if let Some(quality) = self.quality {
+            if quality > MAX_QUALITY {
+                return Err(VideoError::invalid_param(format!(
+                    "Quality must be between 0 and {}, got {}",
+                    MAX_QUALITY, quality
+                )));
+            }
+        }
+
+        if let Some(framerate) = self.framerate {
+            if !(framerate > 0.0 && framerate <= MAX_FRAMERATE) {
+                return Err(VideoError::invalid_param(format!(
+                    "Frame rate must be between 0 and {}, got {}",
+                    MAX_FRAMERATE, framerate
+                )));
+            }
+        }
+
+        if self.interpolate && self.framerate.is_none() {
+            return Err(VideoError::invalid_param("Frame interpolation needs a target frame rate"));
+        }
+
+        if let Some(fps) = self.gif_fps {
+            if !(fps > 0.0 && fps <= MAX_GIF_FPS) {
+                return Err(VideoError::invalid_param(format!(
+                    "GIF frame rate must be between 0 and {}, got {}",
+                    MAX_GIF_FPS, fps
+                )));
+            }
+        }
+
+        if self.gif_max_width == Some(0) {
+            return Err(VideoError::invalid_param("GIF maximum width must be greater than 0"));
+        }
+
+        if self.keyframe_interval == Some(0) {
+            return Err(VideoError::invalid_param("Keyframe interval must be greater than 0"));
+        }
+
+        if let Some(start_time) = self.start_time {
+            if start_time < 0.0 {
+                return Err(VideoError::invalid_param(format!(
+                    "Start time must not be negative, got {}",
+                    start_time
+                )));
+            }
+        }
+
+        if let (Some(start_time), Some(end_time)) = (self.start_time, self.end_time) {
+            if end_time <= start_time {
+                return Err(VideoError::invalid_param(format!(
+                    "End time ({}) must be after start time ({})",
+                    end_time, start_time
+                )));
+            }
+        }
+
+        if let Some(volume) = self.audio_volume {
+            if !(MIN_AUDIO_VOLUME..=MAX_AUDIO_VOLUME).contains(&volume) {
+                return Err(VideoError::invalid_param(format!(
+                    "Audio volume {} is out of range ({} - {}); use 0.0 to mute, 1.0 for the original level",
+                    volume, MIN_AUDIO_VOLUME, MAX_AUDIO_VOLUME
+                )));
+            }
+        }
+
+        if let Some(strength) = self.denoise_strength {
+            if !(MIN_DENOISE_STRENGTH..=MAX_DENOISE_STRENGTH).contains(&strength) {
+                return Err(VideoError::invalid_param(format!(
+                    "Denoise strength {} is out of range ({} - {})",
+                    strength, MIN_DENOISE_STRENGTH, MAX_DENOISE_STRENGTH
+                )));
+            }
+        }
+
+        if let Some(regions) = &self.blur_regions {
+            if let Some(&(x, y, w, h)) = regions.iter().find(|&&(_, _, w, h)| w == 0 || h == 0) {
+                return Err(VideoError::invalid_param(format!(
+                    "Blur region {}x{} at ({}, {}) is empty",
+                    w, h, x, y
+                )));
+            }
+        }
+
+        VideoProcessor::validate_container_codec(&self.output_format, VideoProcessor::builtin_codec(self))?;
+
+        Ok(())
+    }
+
+    /// Size multiples the output pixel format needs, e.g. 2x2 for 4:2:0 chroma
+    ///
+    /// Without a `pixel_format` yuv420p is assumed; GIFs and unknown formats need none.
+    fn chroma_alignment(&self) -> (u32, u32) {
+        if self.pixel_format.is_none() && gif::is_gif_output(self) {
+            return (1, 1);
+        }
+
+        self.output_pixel_format_name()
+            .parse::<Pixel>()
+            .ok()
+            .and_then(|format| format.descriptor())
+            .map_or((1, 1), |descriptor| {
+                (1 << descriptor.log2_chroma_w(), 1 << descriptor.log2_chroma_h())
+            })
+    }
+
+    /// Name of the pixel format the size checks assume
+    fn output_pixel_format_name(&self) -> &str {
+        self.pixel_format.as_deref().unwrap_or(ASSUMED_PIXEL_FORMAT)
+    }
+
+    /// Reject a `value` that is not a multiple of `align`, suggesting the nearest ones
+    fn check_chroma_fit(&self, what: &str, name: &str, value: u32, align: u32) -> VideoResult<()> {
+        if value % align == 0 {
+            return Ok(());
+        }
+
+        let lower = value / align * align;
+        let suggestion = match lower {
+            0 => format!("use {}", align),
+            lower => format!("use {} or {}", lower, lower + align),
+        };
+        Err(VideoError::invalid_param(format!(
+            "{} {} must be a multiple of {} for {} video, got {}; {}",
+            what,
+            name,
+            align,
+            self.output_pixel_format_name(),
+            value,
+            suggestion
+        )))
+    }
+
+    /// Give up a codec the output container cannot hold, e.g. a preset's codec after
+    /// `output_format` was overridden
+    ///
+    /// The container wins: `cpu_codec`, `gpu_codec` and `use_gpu` are cleared, so the
+    /// container's default codec (the one from `set_default_codecs`, else the built-in
+    /// one) is encoded on the CPU. `reencode` is set so the input is still encoded rather
+    /// than stream-copied now that no codec is named. Returns a description of the
+    /// change, `None` if the codec already fits.
+    pub fn reconcile_codec_with_format(&mut self) -> Option<String> {
+        let codec = VideoProcessor::builtin_codec(self);
+        let error = VideoProcessor::validate_container_codec(&self.output_format, codec).err()?;
+
+        self.cpu_codec = None;
+        self.gpu_codec = None;
+        self.use_gpu = false;
+        self.reencode = true;
+
+        Some(format!("{}; encoding with the default codec for {} instead", error, self.output_format))
+    }
+}
@@ -0,0 +1,71 @@
+use log::info;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::{ProcessingOptions, VideoProcessor};
+
+/// Smallest allowed difference between the expected and the actual output duration in seconds
+///
+/// Cuts snap to keyframes and containers round their durations, so short clips
+/// can differ by a fraction of a second without anything being wrong.
+const MIN_DURATION_TOLERANCE_SECS: f64 = 1.0;
+
+/// Allowed difference relative to the expected duration, for long outputs
+const DURATION_TOLERANCE_RATIO: f64 = 0.02;
+
+/// Duration the output should have: the input duration limited to the trimmed range
+pub(super) fn expected_duration(input_duration: f64, options: &ProcessingOptions) -> f64 {
+    let start = options.start_time.unwrap_or(0.0).clamp(0.0, input_duration);
+    let end = options
+        .end_time
+        .filter(|&end| end > start)
+        .map_or(input_duration, |end| end.min(input_duration));
+
+    end - start
+}
+
+impl VideoProcessor {
+    /// Re-open `output_path` and check it has a readable video stream and a duration
+    /// close to the (trimmed) duration of `input_path`.
+    ///
+    /// Inputs or outputs without a known duration are only checked for a video stream.
+    pub fn verify_output(&self, input_path: &str, output_path: &str, options: &ProcessingOptions) -> AppResult<()> {
+        let verification_failed = |message: String| {
+            AppError::video_error(
+                format!("Verification failed: {}", message),
+                ErrorCode::VideoProcessingFailed,
+                Some(format!("Output kept for inspection: {}", output_path)),
+            )
+        };
+
+        let output_info = self
+            .get_video_info(output_path)
+            .map_err(|e| verification_failed(format!("cannot read the output: {}", e)))?;
+        if output_info.width == 0 || output_info.height == 0 {
+            return Err(verification_failed("the output video stream has no picture size".to_string()));
+        }
+
+        let input_duration = self
+            .get_video_info(input_path)
+            .map(|info| info.duration)
+            .unwrap_or(0.0);
+        let expected = expected_duration(input_duration, options);
+        if expected <= 0.0 || output_info.duration <= 0.0 {
+            info!("Verified {} (duration not checked)", output_path);
+            return Ok(());
+        }
+
+        let tolerance = (expected * DURATION_TOLERANCE_RATIO).max(MIN_DURATION_TOLERANCE_SECS);
+        if (output_info.duration - expected).abs() > tolerance {
+            return Err(verification_failed(format!(
+                "the output lasts {:.2}s, expected {:.2}s within {:.2}s",
+                output_info.duration, expected, tolerance
+            )));
+        }
+
+        info!(
+            "Verified {}: {:.2}s, expected {:.2}s",
+            output_path, output_info.duration, expected
+        );
+        Ok(())
+    }
+}
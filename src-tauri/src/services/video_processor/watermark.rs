@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::path::Path;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use super::filters::{self, FilterChain};
+
+/// Distance (in pixels) between a corner-anchored watermark and the frame edges
+const WATERMARK_MARGIN: u32 = 10;
+
+/// Image formats the watermark may be loaded from
+const WATERMARK_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "webp"];
+
+/// Where the watermark is placed on the frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+    /// Top-left corner of the image at the given pixel offset
+    Custom { x: u32, y: u32 },
+}
+
+impl WatermarkPosition {
+    /// `overlay` filter coordinates for this position
+    fn overlay_coordinates(&self) -> (String, String) {
+        let m = WATERMARK_MARGIN;
+        match *self {
+            WatermarkPosition::TopLeft => (m.to_string(), m.to_string()),
+            WatermarkPosition::TopRight => (format!("W-w-{}", m), m.to_string()),
+            WatermarkPosition::BottomLeft => (m.to_string(), format!("H-h-{}", m)),
+            WatermarkPosition::BottomRight => (format!("W-w-{}", m), format!("H-h-{}", m)),
+            WatermarkPosition::Center => ("(W-w)/2".to_string(), "(H-h)/2".to_string()),
+            WatermarkPosition::Custom { x, y } => (x.to_string(), y.to_string()),
+        }
+    }
+}
+
+/// Image overlaid on every frame of the output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatermarkConfig {
+    pub image_path: String,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    pub opacity: Option<f32>,               // 0.0 - 1.0, fully opaque if unset
+    pub scale: Option<f32>,                 // multiplier for the image size
+}
+
+impl WatermarkConfig {
+    /// Read the watermark from the flat `watermark_*` config keys
+    ///
+    /// `watermark_position` is a corner name (`top-left`, ..., `center`) or `x,y`.
+    pub fn from_config(config: &HashMap<String, String>) -> Option<Self> {
+        let image_path = config.get("watermark_path").filter(|p| !p.is_empty())?.clone();
+
+        let position = config
+            .get("watermark_position")
+            .and_then(|value| match value.as_str() {
+                "top-left" => Some(WatermarkPosition::TopLeft),
+                "top-right" => Some(WatermarkPosition::TopRight),
+                "bottom-left" => Some(WatermarkPosition::BottomLeft),
+                "bottom-right" => Some(WatermarkPosition::BottomRight),
+                "center" => Some(WatermarkPosition::Center),
+                custom => {
+                    let (x, y) = custom.split_once(',')?;
+                    Some(WatermarkPosition::Custom {
+                        x: x.trim().parse().ok()?,
+                        y: y.trim().parse().ok()?,
+                    })
+                }
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            image_path,
+            position,
+            opacity: config.get("watermark_opacity").and_then(|v| v.parse().ok()),
+            scale: config.get("watermark_scale").and_then(|v| v.parse().ok()),
+        })
+    }
+
+    /// Check that the image exists and the opacity and scale are usable
+    pub fn validate(&self) -> AppResult<()> {
+        let path = Path::new(&self.image_path);
+
+        if !path.is_file() {
+            return Err(AppError::validation_error(
+                format!("Watermark image not found: {}", self.image_path),
+                ErrorCode::FileNotFound,
+                Some("Choose an existing image file for the watermark".to_string()),
+            ));
+        }
+
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if !WATERMARK_EXTENSIONS.contains(&extension.as_str()) {
+            return Err(AppError::validation_error(
+                format!("Unsupported watermark image format: {}", self.image_path),
+                ErrorCode::InvalidArgument,
+                Some(format!("Supported formats: {}", WATERMARK_EXTENSIONS.join(", "))),
+            ));
+        }
+
+        if let Some(opacity) = self.opacity {
+            if !(0.0..=1.0).contains(&opacity) {
+                return Err(AppError::validation_error(
+                    format!("Watermark opacity {} is out of range (0.0 - 1.0)", opacity),
+                    ErrorCode::InvalidArgument,
+                    None,
+                ));
+            }
+        }
+
+        if let Some(scale) = self.scale {
+            if scale <= 0.0 || !scale.is_finite() {
+                return Err(AppError::validation_error(
+                    format!("Watermark scale {} must be greater than 0", scale),
+                    ErrorCode::InvalidArgument,
+                    None,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Composite the watermark image onto the current chain.
+///
+/// The image is loaded with the `movie` source and converted to RGBA so PNG alpha
+/// (and the configured opacity) is respected. `overlay` repeats the single image
+/// frame for the whole video.
+pub fn add_watermark(chain: &mut FilterChain, watermark: &WatermarkConfig) {
+    let base = chain.close();
+
+    let mut image_filters = vec![
+        format!("movie=filename={}", filters::escape_filter_path(&watermark.image_path)),
+        "format=rgba".to_string(),
+    ];
+    if let Some(scale) = watermark.scale {
+        image_filters.push(format!("scale=w=iw*{:.3}:h=-1", scale));
+    }
+    if let Some(opacity) = watermark.opacity {
+        image_filters.push(format!("colorchannelmixer=aa={:.3}", opacity));
+    }
+
+    let image = chain.fresh_label("watermark");
+    chain.add_segment(format!("{}[{}]", image_filters.join(","), image));
+
+    let (x, y) = watermark.position.overlay_coordinates();
+    let overlaid = chain.fresh_label("v");
+    chain.add_segment(format!("[{}][{}]overlay=x={}:y={}:format=auto[{}]", base, image, x, y, overlaid));
+    chain.continue_from(overlaid);
+
+    info!("Applying watermark {} at {:?}", watermark.image_path, watermark.position);
+}
@@ -1 +1,2 @@
 pub mod task_manager;
+pub mod watch_manager;
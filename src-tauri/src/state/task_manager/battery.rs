@@ -0,0 +1,117 @@
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use log::{info, warn};
+use tauri::{AppHandle, Manager};
+
+use crate::utils::power::{self, PowerSource};
+use super::{TaskEvent, TaskManager, TaskResult};
+
+/// How often the power source is checked
+const BATTERY_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Reason sent with `queue-auto-paused` when the machine switches to battery
+pub const REASON_ON_BATTERY: &str = "on_battery";
+
+impl TaskManager {
+    /// Pause the queue because the machine runs on battery
+    ///
+    /// Unlike `pause_queue` the pause is not saved, so the next start decides again
+    /// from the power source. Does nothing if the queue is already paused.
+    pub fn auto_pause_queue(&self, app_handle: &AppHandle) -> TaskResult<()> {
+        if self.is_queue_paused() {
+            return Ok(());
+        }
+
+        info!("Running on battery, pausing the queue");
+        self.auto_paused.store(true, Ordering::SeqCst);
+        self.pause_running(app_handle)?;
+
+        TaskEvent::QueueAutoPaused {
+            reason: REASON_ON_BATTERY.to_string(),
+        }
+        .emit(app_handle);
+
+        Ok(())
+    }
+
+    /// Resume a queue paused by `auto_pause_queue`
+    ///
+    /// A queue the user paused or resumed since then is left as it is.
+    pub fn end_auto_pause(&self, app_handle: &AppHandle) -> TaskResult<()> {
+        if !self.auto_paused.swap(false, Ordering::SeqCst) || !self.is_queue_paused() {
+            return Ok(());
+        }
+
+        info!("No longer paused for battery power, resuming the queue");
+        self.resume_queue(app_handle)
+    }
+
+    /// Whether the queue is paused by `auto_pause_queue`
+    pub fn is_auto_paused(&self) -> bool {
+        self.auto_paused.load(Ordering::SeqCst)
+    }
+}
+
+/// Pause or resume the queue right away after `pause_on_battery` was changed
+///
+/// Turning it on while on battery pauses the queue; turning it off resumes a queue
+/// that was paused for battery power.
+pub fn apply_pause_on_battery(app_handle: &AppHandle) {
+    let task_manager = app_handle.state::<TaskManager>();
+    let manager = task_manager.inner();
+
+    let result = if !power::pause_on_battery(app_handle) {
+        manager.end_auto_pause(app_handle)
+    } else if power::power_source() == Some(PowerSource::Battery) {
+        manager.auto_pause_queue(app_handle)
+    } else {
+        Ok(())
+    };
+
+    if let Err(e) = result {
+        warn!("Failed to apply the battery setting to the queue: {}", e);
+    }
+}
+
+/// Pause the queue when the machine switches to battery and resume it once AC power returns.
+///
+/// Only acts while `pause_on_battery` is enabled. A queue the user paused is never
+/// resumed here, and a queue the user resumed on battery is left running until the
+/// next switch to battery. The first check counts as a switch, so a start on battery
+/// pauses the queue.
+pub fn start_battery_monitor(app_handle: AppHandle) {
+    if power::power_source().is_none() {
+        info!("Power source not available, not monitoring the battery");
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut last_source = None;
+        let mut interval = tokio::time::interval(BATTERY_POLL_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let Some(source) = power::power_source() else {
+                continue;
+            };
+            let switched = last_source != Some(source);
+            last_source = Some(source);
+
+            let task_manager = app_handle.state::<TaskManager>();
+            let manager = task_manager.inner();
+
+            let result = match source {
+                PowerSource::Battery if switched && power::pause_on_battery(&app_handle) => {
+                    manager.auto_pause_queue(&app_handle)
+                }
+                PowerSource::Ac if manager.is_auto_paused() => manager.end_auto_pause(&app_handle),
+                _ => Ok(()),
+            };
+
+            if let Err(e) = result {
+                warn!("Failed to follow the power source ({:?}): {}", source, e);
+            }
+        }
+    });
+}
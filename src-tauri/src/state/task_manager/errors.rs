@@ -24,6 +24,18 @@ pub enum TaskError {
     #[error("Task was canceled")]
     Canceled,
 
+    #[error("Source file no longer exists: {0}")]
+    InputNotFound(String),
+
+    #[error("Output file already exists: {0}")]
+    OutputExists(String),
+
+    #[error("Maximum of {0} concurrent tasks reached")]
+    ConcurrencyLimit(usize),
+
+    #[error("The queue is paused")]
+    QueuePaused,
+
     #[error("{0}")]
     Other(String),
 }
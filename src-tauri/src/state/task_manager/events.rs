@@ -0,0 +1,131 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::services::video_processor::{ProcessingPlan, QualityScore};
+use super::Task;
+
+/// Events emitted by the task manager.
+///
+/// Each variant maps to one event name (see [`TaskEvent::name`]) and its fields are
+/// the payload, serialized with camelCase keys. For example `task-progress` carries
+/// `{ "taskId": "...", "progress": 42.0, "etaSecs": 12.5 }`. Variants without fields
+/// are emitted with a `null` payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged, rename_all_fields = "camelCase")]
+pub enum TaskEvent {
+    /// `conversion-started`, sent by the direct conversion commands, which report their
+    /// progress as `task-progress` under this id without creating a task
+    ConversionStarted { task_id: String },
+    /// `task-started`
+    Started { task_id: String },
+    /// `task-progress`; `eta_secs` is `null` until enough samples were collected.
+    /// `phase` is `null` for the task's own progress and names the step for work after
+    /// it, e.g. `"quality"`, whose `progress` counts from 0 to 100 again.
+    Progress {
+        task_id: String,
+        progress: f32,
+        eta_secs: Option<f64>,
+        phase: Option<String>,
+    },
+    /// `task-warning`, a warning or error FFmpeg logged while processing the task
+    Warning { task_id: String, message: String },
+    /// `task-fallback`, an attempt failed and the task is retried with `to`; `attempt`
+    /// is the index into the task's fallbacks, `null` when a GPU encoder failed to open
+    Fallback {
+        task_id: String,
+        from: String,
+        to: String,
+        reason: String,
+        attempt: Option<usize>,
+    },
+    /// `task-quality`, the output scored against the input after a `compute_quality` task
+    Quality { task_id: String, quality: QualityScore },
+    /// `task-plan`, sent instead of processing for dry-run tasks
+    Plan { task_id: String, plan: ProcessingPlan },
+    /// `task-completed`
+    Completed { task_id: String },
+    /// `task-failed`
+    Failed { task_id: String, error: String },
+    /// `task-paused`
+    Paused { task_id: String },
+    /// `task-resumed`
+    Resumed { task_id: String },
+    /// `task-canceled`
+    Canceled { task_id: String },
+    /// `task-retried`
+    Retried { task_id: String },
+    /// `task-retry-scheduled`
+    RetryScheduled { task_id: String, delay_secs: u64 },
+    /// `task-removed`
+    Removed { task_id: String },
+    /// `tasks-cleared`, sent once for a bulk removal
+    TasksCleared { count: usize },
+    /// `task-priority-changed`
+    PriorityChanged { task_id: String, priority: u8 },
+    /// `task-updated`, carrying the full task
+    Updated { task: Task },
+    /// `queue-started`
+    QueueStarted,
+    /// `queue-paused`
+    QueuePaused,
+    /// `queue-resumed`
+    QueueResumed,
+    /// `queue-auto-paused`, the queue was paused without a user action, e.g. on battery power
+    QueueAutoPaused { reason: String },
+    /// `queue-draining`, running tasks finish but no new ones start
+    QueueDraining,
+    /// `queue-canceled`
+    QueueCanceled,
+    /// `queue-reordered`, carrying the task IDs in their new queue order
+    QueueReordered { queue: Vec<String> },
+    /// `max-concurrent-tasks-changed`
+    MaxConcurrentTasksChanged { max: usize },
+    /// `queue-drained`, sent when the last active task finishes; counts cover all tasks
+    QueueDrained {
+        completed: usize,
+        failed: usize,
+        canceled: usize,
+    },
+}
+
+impl TaskEvent {
+    /// Name of the event as seen by the frontend
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaskEvent::ConversionStarted { .. } => "conversion-started",
+            TaskEvent::Started { .. } => "task-started",
+            TaskEvent::Progress { .. } => "task-progress",
+            TaskEvent::Warning { .. } => "task-warning",
+            TaskEvent::Fallback { .. } => "task-fallback",
+            TaskEvent::Quality { .. } => "task-quality",
+            TaskEvent::Plan { .. } => "task-plan",
+            TaskEvent::Completed { .. } => "task-completed",
+            TaskEvent::Failed { .. } => "task-failed",
+            TaskEvent::Paused { .. } => "task-paused",
+            TaskEvent::Resumed { .. } => "task-resumed",
+            TaskEvent::Canceled { .. } => "task-canceled",
+            TaskEvent::Retried { .. } => "task-retried",
+            TaskEvent::RetryScheduled { .. } => "task-retry-scheduled",
+            TaskEvent::Removed { .. } => "task-removed",
+            TaskEvent::TasksCleared { .. } => "tasks-cleared",
+            TaskEvent::PriorityChanged { .. } => "task-priority-changed",
+            TaskEvent::Updated { .. } => "task-updated",
+            TaskEvent::QueueStarted => "queue-started",
+            TaskEvent::QueuePaused => "queue-paused",
+            TaskEvent::QueueResumed => "queue-resumed",
+            TaskEvent::QueueAutoPaused { .. } => "queue-auto-paused",
+            TaskEvent::QueueDraining => "queue-draining",
+            TaskEvent::QueueCanceled => "queue-canceled",
+            TaskEvent::QueueReordered { .. } => "queue-reordered",
+            TaskEvent::MaxConcurrentTasksChanged { .. } => "max-concurrent-tasks-changed",
+            TaskEvent::QueueDrained { .. } => "queue-drained",
+        }
+    }
+
+    /// Emit the event to the frontend
+    pub fn emit(&self, app_handle: &AppHandle) {
+        if let Err(e) = app_handle.emit(self.name(), self) {
+            log::warn!("Failed to emit {} event: {}", self.name(), e);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::errors::TaskResult;
+use super::events::TaskEvent;
+use super::progress::weighted_progress;
+use super::{TaskManager, TaskStatus};
+
+/// Counts and progress of the tasks sharing a group id
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskGroup {
+    pub group_id: String,
+    pub total: usize,
+    pub pending: usize,
+    pub running: usize,
+    pub paused: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub canceled: usize,
+    /// Progress of the group's tasks weighted by input duration, see `weighted_progress`
+    pub progress: f32,
+}
+
+impl TaskManager {
+    /// IDs of the tasks in `group_id` with one of `statuses`, in task list order
+    fn group_task_ids(&self, group_id: &str, statuses: &[TaskStatus]) -> Vec<String> {
+        self.tasks
+            .read()
+            .iter()
+            .filter(|task| task.group_id.as_deref() == Some(group_id) && statuses.contains(&task.status))
+            .map(|task| task.id.clone())
+            .collect()
+    }
+
+    /// Pause the running tasks of a group and hold its pending tasks back.
+    ///
+    /// Pending tasks are marked Paused and taken off the queue, so they are counted and
+    /// shown as paused and come back through `resume_group` or `resume_queue` like any
+    /// paused task. Returns the number of tasks paused or held.
+    pub fn pause_group(&self, group_id: &str, app_handle: &AppHandle) -> TaskResult<usize> {
+        let running = self.group_task_ids(group_id, &[TaskStatus::Running]);
+        for id in &running {
+            let _ = self.pause_task(id, app_handle);
+        }
+
+        // Under one lock, so a task cannot start between being picked and being held
+        let pending: HashSet<String> = {
+            let mut tasks = self.tasks.write();
+            tasks
+                .iter_mut()
+                .filter(|task| task.group_id.as_deref() == Some(group_id) && task.status == TaskStatus::Pending)
+                .map(|task| {
+                    task.status = TaskStatus::Paused;
+                    task.id.clone()
+                })
+                .collect()
+        };
+        self.queue.write().retain(|id| !pending.contains(id));
+
+        self.save_state(app_handle)?;
+        for id in &pending {
+            TaskEvent::Paused { task_id: id.clone() }.emit(app_handle);
+        }
+        info!("Paused group {} ({} running, {} pending)", group_id, running.len(), pending.len());
+
+        Ok(running.len() + pending.len())
+    }
+
+    /// Resume the paused tasks of a group, including the pending tasks `pause_group` held.
+    ///
+    /// Tasks whose worker has stopped are queued again. Returns the number of tasks
+    /// resumed.
+    pub fn resume_group(&self, group_id: &str, app_handle: &AppHandle) -> TaskResult<usize> {
+        let paused = self.group_task_ids(group_id, &[TaskStatus::Paused]);
+        for id in &paused {
+            let _ = self.resume_task(id, app_handle);
+        }
+
+        self.save_state(app_handle)?;
+        self.process_next_tasks(app_handle)?;
+
+        Ok(paused.len())
+    }
+
+    /// Cancel every unfinished task of a group. Returns the number of canceled tasks.
+    pub fn cancel_group(&self, group_id: &str, app_handle: &AppHandle) -> TaskResult<usize> {
+        let unfinished = self.group_task_ids(
+            group_id,
+            &[TaskStatus::Pending, TaskStatus::Running, TaskStatus::Paused],
+        );
+        for id in &unfinished {
+            self.mark_canceled(id)?;
+            TaskEvent::Canceled { task_id: id.clone() }.emit(app_handle);
+        }
+
+        self.save_state(app_handle)?;
+        self.finish_if_drained(app_handle);
+
+        Ok(unfinished.len())
+    }
+
+    /// Remove the tasks of a group that are not running or paused
+    ///
+    /// Emits a single `tasks-cleared` event and returns the number of removed tasks;
+    /// cancel the group first to remove all of it.
+    pub fn remove_group(&self, group_id: &str, app_handle: &AppHandle) -> TaskResult<usize> {
+        let removed: HashSet<String> = self
+            .group_task_ids(
+                group_id,
+                &[TaskStatus::Pending, TaskStatus::Completed, TaskStatus::Failed, TaskStatus::Canceled],
+            )
+            .into_iter()
+            .collect();
+
+        self.remove_tasks_where(|task| removed.contains(&task.id));
+
+        self.save_state(app_handle)?;
+
+        let count = removed.len();
+        TaskEvent::TasksCleared { count }.emit(app_handle);
+
+        Ok(count)
+    }
+
+    /// Counts and progress of every group, in the order the groups were created
+    pub fn get_groups(&self) -> Vec<TaskGroup> {
+        let tasks = self.tasks.read();
+        let mut groups: Vec<TaskGroup> = Vec::new();
+
+        for task in tasks.iter() {
+            let Some(group_id) = &task.group_id else {
+                continue;
+            };
+
+            let index = match groups.iter().position(|group| group.group_id == *group_id) {
+                Some(index) => index,
+                None => {
+                    groups.push(TaskGroup {
+                        group_id: group_id.clone(),
+                        ..TaskGroup::default()
+                    });
+                    groups.len() - 1
+                }
+            };
+
+            let group = &mut groups[index];
+            group.total += 1;
+            match task.status {
+                TaskStatus::Pending => group.pending += 1,
+                TaskStatus::Running => group.running += 1,
+                TaskStatus::Paused => group.paused += 1,
+                TaskStatus::Completed => group.completed += 1,
+                TaskStatus::Failed => group.failed += 1,
+                TaskStatus::Canceled => group.canceled += 1,
+            }
+        }
+
+        for group in groups.iter_mut() {
+            group.progress = weighted_progress(
+                tasks
+                    .iter()
+                    .filter(|task| task.group_id.as_deref() == Some(group.group_id.as_str())),
+            );
+        }
+
+        groups
+    }
+}
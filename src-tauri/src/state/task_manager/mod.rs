@@ -1,22 +1,44 @@
+mod battery;
+mod cancel;
 mod errors;
+mod events;
+mod groups;
+mod output;
 mod processor;
+mod progress;
+mod schema;
+mod threads;
 
 use std::collections::{HashMap, VecDeque, HashSet};
 use std::sync::{Arc, Mutex, Condvar};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use parking_lot::RwLock;
-use tauri::{AppHandle, Manager, Emitter};
+use tauri::{AppHandle, Manager};
 use uuid::Uuid;
 use chrono::Utc;
-use log::info;
+use log::{info, warn};
 use serde::{Serialize, Deserialize};
-use tokio::sync::Semaphore;
-use serde_json::json;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
+use crate::services::video_processor::{QualityScore, ResumePoint, VideoProcessor};
 use crate::utils::error::{AppError, ErrorCode};
+use crate::utils::notifications;
 use crate::utils::store_helper::{self, TASKS_STORE_PATH};
 
+pub use battery::{apply_pause_on_battery, start_battery_monitor, REASON_ON_BATTERY};
+pub use cancel::CancelToken;
 pub use errors::{TaskError, TaskResult};
-pub use processor::TaskProcessor;
+pub use events::TaskEvent;
+pub use groups::TaskGroup;
+pub use output::{is_partial_output_path, partial_output_path, OverwritePolicy};
+pub use processor::{create_processing_options, TaskOutcome, TaskProcessor, TASK_TYPES};
+pub use progress::{
+    progress_interval, set_progress_interval, weighted_progress, ProgressThrottle, ProgressTracker,
+    DEFAULT_PROGRESS_INTERVAL_MS,
+};
+pub use schema::{migrate_tasks, TASKS_SCHEMA_VERSION};
+pub use threads::{auto_thread_limit, set_auto_thread_limit, threads_per_task};
 
 /// Status of a task
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -42,6 +64,45 @@ impl std::fmt::Display for TaskStatus {
     }
 }
 
+/// What `load_state` does with tasks that were running or paused when the app exited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterruptedTaskPolicy {
+    /// Reset the task to pending and queue it again
+    #[default]
+    Requeue,
+    /// Mark the task as failed with an "interrupted" error
+    Fail,
+}
+
+/// Base delay before the first automatic retry; doubles on every further attempt
+const RETRY_BASE_DELAY_SECS: u64 = 1;
+
+/// Upper bound for the automatic retry delay
+const RETRY_MAX_DELAY_SECS: u64 = 300;
+
+/// Minimum interval between debounced saves of the task state
+const SAVE_DEBOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long `force_remove_task` waits for a canceled task's worker to stop
+const FORCE_REMOVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between checks for a stopped worker in `force_remove_task`
+const FORCE_REMOVE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long `shutdown` waits for running tasks to stop before saving
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default task priority (higher runs sooner)
+pub const DEFAULT_TASK_PRIORITY: u8 = 5;
+
+/// Concurrency used until a saved value is loaded
+pub const DEFAULT_MAX_CONCURRENT_TASKS: usize = 2;
+
+fn default_priority() -> u8 {
+    DEFAULT_TASK_PRIORITY
+}
+
 /// Represents a processing task
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
@@ -57,6 +118,66 @@ pub struct Task {
     pub completed_at: Option<String>,
     pub task_type: String,
     pub config: HashMap<String, String>,
+    #[serde(default = "default_priority")]
+    pub priority: u8,
+    #[serde(default)]
+    pub max_retries: usize,
+    /// File being written while the task runs, kept after an interruption so the
+    /// output can be cleaned up or resumed later
+    #[serde(default)]
+    pub partial_output_path: Option<String>,
+    /// Short remark on how a completed task was handled, e.g. copied instead of encoded
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Batch the task was created in, e.g. one dropped folder; see `pause_group` and friends
+    #[serde(default)]
+    pub group_id: Option<String>,
+    /// Length of the input in seconds, probed when the task first starts; weighs the
+    /// task in the overall progress
+    #[serde(default)]
+    pub input_duration: Option<f64>,
+    /// Settings tried in order when the task fails, each overriding keys of `config`
+    #[serde(default)]
+    pub fallbacks: Vec<HashMap<String, String>>,
+    /// Index into `fallbacks` of the settings the task completed with
+    #[serde(default)]
+    pub fallback_used: Option<usize>,
+    /// PSNR/SSIM of the output against the input, for tasks with `compute_quality`
+    #[serde(default)]
+    pub quality: Option<QualityScore>,
+    /// Segments encoded before a pause or an exit; the next run continues after them
+    #[serde(default)]
+    pub resume: Option<ResumePoint>,
+}
+
+/// Summary of the task list, cheap to send for status badges
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueueStats {
+    pub pending: usize,
+    pub running: usize,
+    pub paused: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub canceled: usize,
+    pub total: usize,
+    /// Sum of the progress of all running tasks
+    pub running_progress: f32,
+    /// Average progress of running tasks, 0 when none are running
+    pub average_running_progress: f32,
+    /// Progress of the whole list weighted by input duration, see `weighted_progress`
+    pub weighted_progress: f32,
+    pub is_queue_paused: bool,
+    /// Running tasks are finishing but no new ones start, see `drain_queue`
+    pub is_draining: bool,
+}
+
+/// Estimated output sizes of the queued tasks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskSizeEstimate {
+    /// Sum of all estimates in bytes
+    pub total_bytes: u64,
+    /// Estimate per task ID; tasks whose input could not be probed are left out
+    pub task_sizes: HashMap<String, u64>,
 }
 
 /// Manages tasks and their execution
@@ -65,36 +186,97 @@ pub struct TaskManager {
     queue: RwLock<VecDeque<String>>,
     max_concurrent_tasks: RwLock<usize>,
     is_queue_paused: RwLock<bool>,
+    interrupted_task_policy: RwLock<InterruptedTaskPolicy>,
     semaphore: RwLock<Arc<Semaphore>>,
     pause_condvar: Arc<(Mutex<HashSet<String>>, Condvar)>,
+    progress_trackers: RwLock<HashMap<String, ProgressTracker>>,
+    /// Tasks whose worker has not finished its completion handling yet
+    active_workers: RwLock<HashSet<String>>,
+    /// Cancel token of each running worker
+    cancel_tokens: RwLock<HashMap<String, CancelToken>>,
+    /// Failed tasks waiting for an automatic retry
+    scheduled_retries: RwLock<HashSet<String>>,
+    /// Set once the app is exiting; running tasks stop and keep their status
+    shutting_down: AtomicBool,
+    /// Set by `drain_queue`; no new tasks start until the queue is started again
+    draining: AtomicBool,
+    /// Set while the queue is paused by the battery monitor; such a pause is not saved
+    auto_paused: AtomicBool,
+    state_dirty: AtomicBool,
+    last_saved_at: RwLock<Option<Instant>>,
     task_processor: TaskProcessor,
 }
 
 impl TaskManager {
-    /// Create a new TaskManager
+    /// Create a new TaskManager with its own VideoProcessor
+    ///
+    /// If FFmpeg cannot be initialized the error is logged and left to the tasks.
     pub fn new(max_concurrent_tasks: usize) -> Self {
+        let video_processor = VideoProcessor::new().unwrap_or_else(|e| {
+            log::error!("{}", e);
+            VideoProcessor::default()
+        });
+        Self::with_video_processor(max_concurrent_tasks, video_processor)
+    }
+
+    /// Create a TaskManager that processes tasks with the app's shared VideoProcessor
+    pub fn with_video_processor(max_concurrent_tasks: usize, video_processor: VideoProcessor) -> Self {
         Self {
             tasks: RwLock::new(Vec::new()),
             queue: RwLock::new(VecDeque::new()),
             max_concurrent_tasks: RwLock::new(max_concurrent_tasks),
             is_queue_paused: RwLock::new(false),
+            interrupted_task_policy: RwLock::new(InterruptedTaskPolicy::default()),
             semaphore: RwLock::new(Arc::new(Semaphore::new(max_concurrent_tasks))),
             pause_condvar: Arc::new((Mutex::new(HashSet::new()), Condvar::new())),
-            task_processor: TaskProcessor::new(),
+            progress_trackers: RwLock::new(HashMap::new()),
+            active_workers: RwLock::new(HashSet::new()),
+            cancel_tokens: RwLock::new(HashMap::new()),
+            scheduled_retries: RwLock::new(HashSet::new()),
+            shutting_down: AtomicBool::new(false),
+            draining: AtomicBool::new(false),
+            auto_paused: AtomicBool::new(false),
+            state_dirty: AtomicBool::new(false),
+            last_saved_at: RwLock::new(None),
+            task_processor: TaskProcessor::new(video_processor),
         }
     }
 
     /// Create a new task
+    ///
+    /// The output path is resolved against `overwrite_policy`; the returned task
+    /// carries the path that will actually be written.
     pub fn create_task(
         &self,
         input_path: String,
         output_path: String,
         task_type: String,
         config: HashMap<String, String>,
-    ) -> TaskResult<String> {
+        priority: u8,
+        overwrite_policy: OverwritePolicy,
+    ) -> TaskResult<Task> {
         // Generate a unique ID for the task
         let task_id = Uuid::new_v4().to_string();
 
+        // Resolve the final output path
+        let output_path = output::resolve_output_path(&input_path, &output_path, overwrite_policy)?;
+
+        // Automatic retries are opt-in through the config
+        let max_retries = config
+            .get("max_retries")
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        // Tasks created together share a group id
+        let group_id = config.get("group_id").filter(|v| !v.is_empty()).cloned();
+
+        // Fallback settings come as a JSON array of config overrides
+        let fallbacks = match config.get("fallbacks").filter(|v| !v.is_empty()) {
+            Some(json) => serde_json::from_str::<Vec<HashMap<String, String>>>(json)
+                .map_err(|e| TaskError::Other(format!("Invalid fallbacks: {}", e)))?,
+            None => Vec::new(),
+        };
+
         // Create the task
         let task = Task {
             id: task_id.clone(),
@@ -109,21 +291,80 @@ impl TaskManager {
             completed_at: None,
             task_type,
             config,
+            priority,
+            max_retries,
+            partial_output_path: None,
+            note: None,
+            group_id,
+            input_duration: None,
+            fallbacks,
+            fallback_used: None,
+            quality: None,
+            resume: None,
         };
 
         // Add task to the tasks list
         {
             let mut tasks = self.tasks.write();
-            tasks.push(task);
+            tasks.push(task.clone());
         }
 
-        // Add task to the queue
-        {
-            let mut queue = self.queue.write();
-            queue.push_back(task_id.clone());
+        // Add task to the queue, ahead of lower-priority tasks
+        self.enqueue_by_priority(&task_id);
+
+        Ok(task)
+    }
+
+    /// Insert a task into the queue after all tasks that should run before it.
+    ///
+    /// Tasks are ordered by priority (higher first), then by creation time. Only the
+    /// new task is placed, so a manual order set with `reorder_tasks` is kept.
+    fn enqueue_by_priority(&self, task_id: &str) {
+        let tasks = self.tasks.read();
+        let mut queue = self.queue.write();
+
+        queue.retain(|id| id != task_id);
+
+        let Some(task) = tasks.iter().find(|t| t.id == task_id) else {
+            return;
+        };
+
+        let position = queue
+            .iter()
+            .position(|id| {
+                tasks.iter().find(|t| t.id == *id).map_or(false, |other| {
+                    other.priority < task.priority
+                        || (other.priority == task.priority && other.created_at > task.created_at)
+                })
+            })
+            .unwrap_or(queue.len());
+
+        queue.insert(position, task_id.to_string());
+    }
+
+    /// Change a task's priority and move it to its new place in the queue
+    pub fn set_task_priority(&self, task_id: &str, priority: u8, app_handle: &AppHandle) -> TaskResult<()> {
+        // Update the task
+        self.update_task(task_id, |task| {
+            task.priority = priority;
+        })?;
+
+        // Re-position the task if it is still queued
+        let is_queued = self.queue.read().iter().any(|id| id == task_id);
+        if is_queued {
+            self.enqueue_by_priority(task_id);
         }
 
-        Ok(task_id)
+        // Save state
+        self.save_state(app_handle)?;
+
+        // Emit task-priority-changed event
+        TaskEvent::PriorityChanged {
+            task_id: task_id.to_string(),
+            priority,
+        }.emit(app_handle);
+
+        Ok(())
     }
 
     /// Get a task by ID
@@ -158,44 +399,149 @@ impl TaskManager {
         tasks.clone()
     }
 
+    /// Get status counts and running progress, computed under a single read lock
+    pub fn get_queue_stats(&self) -> QueueStats {
+        let mut stats = QueueStats {
+            is_queue_paused: *self.is_queue_paused.read(),
+            is_draining: self.is_draining(),
+            ..QueueStats::default()
+        };
+
+        let tasks = self.tasks.read();
+        for task in tasks.iter() {
+            match task.status {
+                TaskStatus::Pending => stats.pending += 1,
+                TaskStatus::Running => {
+                    stats.running += 1;
+                    stats.running_progress += task.progress;
+                }
+                TaskStatus::Paused => stats.paused += 1,
+                TaskStatus::Completed => stats.completed += 1,
+                TaskStatus::Failed => stats.failed += 1,
+                TaskStatus::Canceled => stats.canceled += 1,
+            }
+        }
+        stats.total = tasks.len();
+
+        if stats.running > 0 {
+            stats.average_running_progress = stats.running_progress / stats.running as f32;
+        }
+        stats.weighted_progress = progress::weighted_progress(tasks.iter());
+
+        stats
+    }
+
+    /// `queue-drained` event if no task is pending, running, paused or waiting for a retry
+    pub fn queue_drained_event(&self) -> Option<TaskEvent> {
+        // While draining, pending and paused tasks and scheduled retries wait for the next start
+        let draining = self.is_draining();
+        if !self.active_workers.read().is_empty() || (!draining && !self.scheduled_retries.read().is_empty()) {
+            return None;
+        }
+
+        let stats = self.get_queue_stats();
+        let unfinished = if draining {
+            stats.running
+        } else {
+            stats.pending + stats.running + stats.paused
+        };
+        if unfinished > 0 {
+            return None;
+        }
+
+        Some(TaskEvent::QueueDrained {
+            completed: stats.completed,
+            failed: stats.failed,
+            canceled: stats.canceled,
+        })
+    }
+
+    /// Emit `queue-drained` and show the desktop notification if no work is left
+    fn finish_if_drained(&self, app_handle: &AppHandle) {
+        let Some(event) = self.queue_drained_event() else {
+            return;
+        };
+
+        if let TaskEvent::QueueDrained { completed, failed, canceled } = event {
+            notifications::notify_queue_drained(app_handle, completed, failed, canceled);
+        }
+        event.emit(app_handle);
+
+        // A finished drain leaves the queue paused so the remaining tasks survive a restart
+        if self.draining.swap(false, Ordering::SeqCst) {
+            *self.is_queue_paused.write() = true;
+            if let Err(e) = self.save_state(app_handle) {
+                warn!("Failed to save state after draining the queue: {}", e);
+            }
+            TaskEvent::QueuePaused.emit(app_handle);
+        }
+    }
+
+    /// Estimate the output size of every pending task.
+    ///
+    /// Inputs are probed outside the task lock, so this may take a moment for long queues.
+    pub fn estimate_task_sizes(&self) -> TaskSizeEstimate {
+        let pending: Vec<Task> = self
+            .tasks
+            .read()
+            .iter()
+            .filter(|task| task.status == TaskStatus::Pending)
+            .cloned()
+            .collect();
+
+        let mut estimate = TaskSizeEstimate::default();
+        for task in &pending {
+            match self.task_processor.estimate_output_size(task) {
+                Ok(size) => {
+                    estimate.total_bytes += size;
+                    estimate.task_sizes.insert(task.id.clone(), size);
+                }
+                Err(e) => warn!("Cannot estimate output size of task {}: {}", task.id, e),
+            }
+        }
+
+        estimate
+    }
+
     /// Get the task queue
     pub fn get_queue(&self) -> Vec<String> {
         let queue = self.queue.read();
         queue.iter().cloned().collect()
     }
 
-    /// Start a task
-    pub fn start_task(&self, task_id: &str, app_handle: &AppHandle) -> TaskResult<()> {
-        // Create a clone of the task to process
-        let task_clone = {
-            // Get the task
-            let task = self.get_task(task_id)?;
-
-            // Check task status
-            if task.status != TaskStatus::Pending {
-                return Err(TaskError::InvalidStatus(format!(
-                    "Task {} is not in pending state", task_id
-                )));
-            }
-
-            // Clone the task
-            task.clone()
-        };
+    /// Start a task on request, e.g. from the `run_task` command
+    ///
+    /// While the queue is paused or draining nothing new starts and the task stays
+    /// Pending, unless `force` is set.
+    pub fn run_task(&self, task_id: &str, force: bool, app_handle: &AppHandle) -> TaskResult<()> {
+        if self.is_shutting_down() {
+            return Err(TaskError::QueuePaused);
+        }
+        if !force && (self.is_queue_paused() || self.is_draining()) {
+            return Err(TaskError::QueuePaused);
+        }
 
-        // Update the task
-        self.update_task(task_id, |task| {
-            task.status = TaskStatus::Running;
-            task.started_at = Some(Utc::now().to_rfc3339());
-            task.attempts += 1;
-        })?;
+        self.start_task(task_id, app_handle)
+    }
 
-        // Save state
-        self.save_state(app_handle)?;
+    /// Start a task
+    pub fn start_task(&self, task_id: &str, app_handle: &AppHandle) -> TaskResult<()> {
+        // Reserve a concurrency slot and mark the task as running
+        let (task_clone, permit) = self.begin_task(task_id)?;
+
+        // Start tracking progress for the ETA
+        self.progress_trackers
+            .write()
+            .insert(task_id.to_string(), ProgressTracker::new(task_clone.progress));
+
+        // Save state; the task is already running and holds its slot, so a failed save
+        // must not leave it Running with no worker. It is saved again when it finishes.
+        if let Err(e) = self.save_state(app_handle) {
+            warn!("Failed to save state after starting task {}: {}", task_id, e);
+        }
 
         // Emit task-started event
-        emit_event(app_handle, "task-started", Some(json!({
-            "taskId": task_id
-        })));
+        TaskEvent::Started { task_id: task_id.to_string() }.emit(app_handle);
 
         // Create a clone of app_handle to use in thread
         let app_handle_clone = app_handle.clone();
@@ -203,65 +549,177 @@ impl TaskManager {
         // Create a clone of task_processor to use in thread
         let task_processor = self.task_processor.clone();
 
-        // Create a clone of semaphore to use in thread
-        let semaphore = self.semaphore.read().clone();
+        self.active_workers.write().insert(task_id.to_string());
+        let cancel_token = CancelToken::new();
+        self.cancel_tokens.write().insert(task_id.to_string(), cancel_token.clone());
 
         // Create a thread to process the task
-        tokio::spawn(async move {
-            // Acquire a permit from the semaphore
-            let _permit = semaphore.acquire().await.unwrap();
-
+        tauri::async_runtime::spawn(async move {
             // Process the task
-            let result = task_processor.process_task(&task_clone, &app_handle_clone).await;
+            let result = task_processor.process_task(&task_clone, cancel_token, &app_handle_clone).await;
 
-            // Update task status after processing
+            // Update task status after processing; a task removed meanwhile stays removed.
+            // Tasks stopped by a shutdown stay Running so the next start recovers them.
+            let shutting_down = app_handle_clone.state::<TaskManager>().inner().is_shutting_down();
             match result {
-                Ok(_) => {
+                Ok(TaskOutcome { paused: Some(resume), .. }) => {
+                    let task_manager = app_handle_clone.state::<TaskManager>();
+                    task_manager.inner().keep_stopped(&task_clone.id, resume, &app_handle_clone);
+                }
+                _ if shutting_down => {}
+                Ok(outcome) => {
+                    if outcome.note.is_some() || outcome.fallback_used.is_some() || outcome.quality.is_some() {
+                        let task_manager = app_handle_clone.state::<TaskManager>();
+                        let _ = task_manager.inner().update_task(&task_clone.id, |task| {
+                            task.note = outcome.note;
+                            task.fallback_used = outcome.fallback_used;
+                            task.quality = outcome.quality;
+                        });
+                    }
+
                     // Update task status to completed
-                    update_task_status(
+                    if update_task_status(
                         &app_handle_clone,
                         &task_clone.id,
                         TaskStatus::Completed,
                         100.0,
                         None,
-                    ).await;
-
-                    // Emit task-completed event
-                    emit_event(&app_handle_clone, "task-completed", Some(json!({
-                        "taskId": task_clone.id
-                    })));
+                    ).await {
+                        // Emit task-completed event
+                        TaskEvent::Completed { task_id: task_clone.id.clone() }.emit(&app_handle_clone);
+                    }
                 },
                 Err(e) => {
+                    // An incomplete output must not be mistaken for a finished one
+                    if let Some(partial) = &task_clone.partial_output_path {
+                        remove_partial_output(partial);
+                    }
+
                     // Update task status to failed
-                    update_task_status(
+                    if update_task_status(
                         &app_handle_clone,
                         &task_clone.id,
                         TaskStatus::Failed,
                         task_clone.progress,
                         Some(e.to_string()),
-                    ).await;
-
-                    // Emit task-failed event
-                    emit_event(&app_handle_clone, "task-failed", Some(json!({
-                        "taskId": task_clone.id,
-                        "error": e.to_string()
-                    })));
+                    ).await {
+                        // Emit task-failed event
+                        TaskEvent::Failed {
+                            task_id: task_clone.id.clone(),
+                            error: e.to_string(),
+                        }.emit(&app_handle_clone);
+
+                        // Schedule an automatic retry if the task allows more attempts
+                        let task_manager = app_handle_clone.state::<TaskManager>();
+                        if let Some(delay) = task_manager.inner().retry_delay(&task_clone.id, &e) {
+                            schedule_retry(&app_handle_clone, &task_clone.id, delay);
+                        }
+                    }
                 }
             }
 
+            // Release the concurrency slot before starting the next tasks
+            drop(permit);
+
             // Process next tasks in queue
             let task_manager = app_handle_clone.state::<TaskManager>();
+            task_manager.inner().progress_trackers.write().remove(&task_clone.id);
+            task_manager.inner().cancel_tokens.write().remove(&task_clone.id);
+            task_manager.inner().active_workers.write().remove(&task_clone.id);
             let _ = task_manager.inner().process_next_tasks(&app_handle_clone);
+
+            // Tell the user when this was the last active task
+            task_manager.inner().finish_if_drained(&app_handle_clone);
         });
 
         Ok(())
     }
 
+    /// Store the segments a worker encoded before it stopped for a pause or an exit
+    ///
+    /// A task resumed while its worker was stopping goes back to Pending so it starts
+    /// again; the segments of a task canceled meanwhile are deleted.
+    fn keep_stopped(&self, task_id: &str, resume: ResumePoint, app_handle: &AppHandle) {
+        let mut resume = Some(resume).filter(|resume| !resume.segments.is_empty());
+        let shutting_down = self.is_shutting_down();
+
+        let updated = self.update_task(task_id, |task| {
+            task.partial_output_path = None;
+            match task.status {
+                TaskStatus::Canceled => {}
+                TaskStatus::Running if !shutting_down => {
+                    task.status = TaskStatus::Pending;
+                    task.resume = resume.take();
+                }
+                _ => task.resume = resume.take(),
+            }
+        });
+        if let Some(resume) = resume {
+            resume.discard();
+        }
+        if updated.is_err() {
+            return; // Task was removed
+        }
+
+        let _ = self.save_state(app_handle);
+        if let Ok(task) = self.get_task(task_id) {
+            info!("Task {} stopped at {:.1}%, kept {} segments", task_id, task.progress,
+                task.resume.as_ref().map_or(0, |resume| resume.segments.len()));
+            TaskEvent::Updated { task }.emit(app_handle);
+        }
+    }
+
+    /// Move a pending task to Running, reserving one of the concurrency slots
+    ///
+    /// The slot is held by the returned permit until it is dropped, and the check and
+    /// the status change happen under the same lock, so the number of Running tasks
+    /// never exceeds `max_concurrent_tasks`.
+    pub fn begin_task(&self, task_id: &str) -> TaskResult<(Task, OwnedSemaphorePermit)> {
+        let mut tasks = self.tasks.write();
+
+        let running_count = tasks.iter()
+            .filter(|task| task.status == TaskStatus::Running)
+            .count();
+
+        let task = tasks.iter_mut()
+            .find(|task| task.id == task_id)
+            .ok_or_else(|| TaskError::TaskNotFound(task_id.to_string()))?;
+
+        // Check task status
+        if task.status != TaskStatus::Pending {
+            return Err(TaskError::InvalidStatus(format!(
+                "Task {} is not in pending state", task_id
+            )));
+        }
+
+        // Check for a free slot
+        let max = *self.max_concurrent_tasks.read();
+        if running_count >= max {
+            return Err(TaskError::ConcurrencyLimit(max));
+        }
+
+        let permit = self.semaphore.read()
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| TaskError::ConcurrencyLimit(max))?;
+
+        // Update the task
+        task.status = TaskStatus::Running;
+        task.started_at = Some(Utc::now().to_rfc3339());
+        task.attempts += 1;
+        task.partial_output_path = Some(output::partial_output_path(&task.output_path));
+
+        Ok((task.clone(), permit))
+    }
+
     /// Process next tasks in queue
-    /// Note: This method now takes &self instead of &mut self to allow calling it from a thread
+    ///
+    /// Starts pending tasks directly until the concurrency limit is reached. No lock is
+    /// held while calling `start_task`, so this is safe to call from a task's completion
+    /// handler.
     pub fn process_next_tasks(&self, app_handle: &AppHandle) -> TaskResult<()> {
-        // If queue is paused, do nothing
-        if *self.is_queue_paused.read() {
+        // If queue is paused or draining, do nothing
+        if *self.is_queue_paused.read() || self.is_draining() || self.is_shutting_down() {
             return Ok(());
         }
 
@@ -285,7 +743,7 @@ impl TaskManager {
             let tasks = self.tasks.read();
             let queue = self.queue.read();
 
-            // Get tasks in queue order
+            // Get tasks in queue order (highest priority first, unless reordered manually)
             let mut pending = Vec::new();
             for task_id in queue.iter() {
                 if let Some(task) = tasks.iter().find(|t| t.id == *task_id) {
@@ -297,20 +755,20 @@ impl TaskManager {
             pending
         };
 
-        // Since we can't call start_task directly (it requires &mut self),
-        // we'll emit an event for each task that should be started
+        // Start tasks until all available slots are used
         let mut started = 0;
         for task_id in pending_tasks {
             if started >= available_slots {
                 break;
             }
 
-            // Emit an event to start the task
-            emit_event(app_handle, "start-task", Some(json!({
-                "taskId": task_id
-            })));
-
-            started += 1;
+            // The task may have changed state since the list was collected
+            match self.start_task(&task_id, app_handle) {
+                Ok(()) => started += 1,
+                Err(TaskError::ConcurrencyLimit(_)) => break,
+                Err(TaskError::InvalidStatus(_)) | Err(TaskError::TaskNotFound(_)) => continue,
+                Err(e) => return Err(e),
+            }
         }
 
         Ok(())
@@ -318,6 +776,9 @@ impl TaskManager {
 
     /// Start the queue
     pub fn start_queue(&self, app_handle: &AppHandle) -> TaskResult<()> {
+        // Starting again ends a drain that is still in progress
+        self.draining.store(false, Ordering::SeqCst);
+
         // If queue is paused, resume it
         if *self.is_queue_paused.read() {
             self.resume_queue(app_handle)?;
@@ -328,13 +789,20 @@ impl TaskManager {
         self.process_next_tasks(app_handle)?;
 
         // Emit queue-started event
-        emit_event(app_handle, "queue-started", None);
+        TaskEvent::QueueStarted.emit(app_handle);
 
         Ok(())
     }
 
     /// Pause the queue
     pub fn pause_queue(&self, app_handle: &AppHandle) -> TaskResult<()> {
+        // A pause asked for by the user is kept across restarts, unlike an automatic one
+        self.auto_paused.store(false, Ordering::SeqCst);
+        self.pause_running(app_handle)
+    }
+
+    /// Pause the queue and every running task
+    fn pause_running(&self, app_handle: &AppHandle) -> TaskResult<()> {
         // Update is_queue_paused
         *self.is_queue_paused.write() = true;
 
@@ -353,7 +821,7 @@ impl TaskManager {
         }
 
         // Emit queue-paused event
-        emit_event(app_handle, "queue-paused", None);
+        TaskEvent::QueuePaused.emit(app_handle);
 
         // Save state
         self.save_state(app_handle)?;
@@ -361,10 +829,81 @@ impl TaskManager {
         Ok(())
     }
 
+    /// Stop all work before the app exits
+    ///
+    /// Stops running tasks and waits up to `SHUTDOWN_TIMEOUT` for them, deletes their
+    /// partial output files and saves the state one last time. Nothing new starts once
+    /// `shutting_down` is set; the queue's paused flag is saved as the user left it.
+    /// Interrupted tasks keep their Running/Paused status and are handled by the
+    /// interrupted task policy when the state is loaded again.
+    pub fn shutdown(&self, app_handle: &AppHandle) -> TaskResult<()> {
+        self.shutting_down.store(true, Ordering::SeqCst);
+
+        // Stop running workers at their next frame
+        self.cancel_workers();
+
+        // Wake paused tasks so they notice the shutdown
+        {
+            let (lock, cvar) = &*self.pause_condvar;
+            lock.lock().unwrap().clear();
+            cvar.notify_all();
+        }
+
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while !self.active_workers.read().is_empty() {
+            if Instant::now() >= deadline {
+                warn!("{} tasks did not stop before exit", self.active_workers.read().len());
+                break;
+            }
+            std::thread::sleep(FORCE_REMOVE_POLL_INTERVAL);
+        }
+
+        // Half-written outputs must not look like finished conversions
+        {
+            let mut tasks = self.tasks.write();
+            for task in tasks.iter_mut().filter(|task| {
+                task.status == TaskStatus::Running || task.status == TaskStatus::Paused
+            }) {
+                if let Some(partial) = task.partial_output_path.take() {
+                    remove_partial_output(&partial);
+                }
+            }
+        }
+
+        self.save_state(app_handle)
+    }
+
+    /// Let running tasks finish without starting new ones
+    ///
+    /// Unlike `pause_queue`, running tasks keep encoding. Once the last one finishes,
+    /// `queue-drained` is emitted and the queue is paused; `start_queue` or
+    /// `resume_queue` picks up the remaining tasks.
+    pub fn drain_queue(&self, app_handle: &AppHandle) -> TaskResult<()> {
+        self.draining.store(true, Ordering::SeqCst);
+        TaskEvent::QueueDraining.emit(app_handle);
+
+        // Nothing may be running, in which case the drain is already complete
+        self.finish_if_drained(app_handle);
+
+        Ok(())
+    }
+
+    /// Whether `drain_queue` is waiting for running tasks to finish
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Whether `shutdown` has been called
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
     /// Resume the queue
     pub fn resume_queue(&self, app_handle: &AppHandle) -> TaskResult<()> {
         // Update is_queue_paused
         *self.is_queue_paused.write() = false;
+        self.draining.store(false, Ordering::SeqCst);
+        self.auto_paused.store(false, Ordering::SeqCst);
 
         // Collect IDs of paused tasks
         let paused_task_ids = {
@@ -384,7 +923,7 @@ impl TaskManager {
         self.process_next_tasks(app_handle)?;
 
         // Emit queue-resumed event
-        emit_event(app_handle, "queue-resumed", None);
+        TaskEvent::QueueResumed.emit(app_handle);
 
         // Save state
         self.save_state(app_handle)?;
@@ -393,6 +932,10 @@ impl TaskManager {
     }
 
     /// Cancel the queue
+    ///
+    /// Every task is marked Canceled before any worker is stopped, so a finishing
+    /// worker cannot start a task that is about to be canceled. Running workers are
+    /// then signaled through their cancel tokens and stop at their next frame.
     pub fn cancel_queue(&self, app_handle: &AppHandle) -> TaskResult<()> {
         // Collect IDs of pending, running, and paused tasks
         let tasks_to_cancel = {
@@ -407,10 +950,11 @@ impl TaskManager {
                 .collect::<Vec<_>>()
         };
 
-        // Cancel each task
-        for id in tasks_to_cancel {
-            let _ = self.cancel_task(&id, app_handle);
-        }
+        // Mark each task as canceled
+        let canceled: Vec<String> = tasks_to_cancel
+            .into_iter()
+            .filter(|id| self.mark_canceled(id).is_ok())
+            .collect();
 
         // Clear the queue
         {
@@ -418,8 +962,15 @@ impl TaskManager {
             queue.clear();
         }
 
+        // Stop all running workers now instead of on their next status check
+        self.cancel_workers();
+
+        for id in canceled {
+            TaskEvent::Canceled { task_id: id }.emit(app_handle);
+        }
+
         // Emit queue-canceled event
-        emit_event(app_handle, "queue-canceled", None);
+        TaskEvent::QueueCanceled.emit(app_handle);
 
         // Save state
         self.save_state(app_handle)?;
@@ -448,9 +999,7 @@ impl TaskManager {
         self.save_state(app_handle)?;
 
         // Emit task-paused event
-        emit_event(app_handle, "task-paused", Some(json!({
-            "taskId": task_id
-        })));
+        TaskEvent::Paused { task_id: task_id.to_string() }.emit(app_handle);
 
         Ok(())
     }
@@ -462,13 +1011,26 @@ impl TaskManager {
             task.progress = progress;
         })?;
 
-        // Save state
-        self.save_state(app_handle)?;
+        // Progress ticks are frequent, so coalesce saves
+        self.save_state_debounced(app_handle)?;
 
         Ok(())
     }
 
+    /// Record a progress sample for a running task and return its estimated seconds remaining
+    pub fn record_progress(&self, task_id: &str, progress: f32) -> Option<f64> {
+        self.progress_trackers
+            .write()
+            .entry(task_id.to_string())
+            .or_insert_with(|| ProgressTracker::new(progress))
+            .record(progress)
+    }
+
     /// Resume a task
+    ///
+    /// A paused worker that has not stopped yet simply keeps encoding. Otherwise the
+    /// task is queued again and continues after the segments in `Task::resume`, or
+    /// starts over if there are none.
     pub fn resume_task(&self, task_id: &str, app_handle: &AppHandle) -> TaskResult<()> {
         // Get the task
         let task = self.get_task(task_id)?;
@@ -480,24 +1042,33 @@ impl TaskManager {
             )));
         }
 
-        // Update task status
+        // The worker checks the status on every frame; once it has stopped, the task
+        // has to be started again
+        let worker_running = self.active_workers.read().contains(task_id);
         self.update_task(task_id, |task| {
-            task.status = TaskStatus::Running;
+            task.status = if worker_running { TaskStatus::Running } else { TaskStatus::Pending };
         })?;
+        if !worker_running && !self.queue.read().iter().any(|id| id == task_id) {
+            self.enqueue_by_priority(task_id);
+        }
 
         // Save state
         self.save_state(app_handle)?;
 
         // Emit task-resumed event
-        emit_event(app_handle, "task-resumed", Some(json!({
-            "taskId": task_id
-        })));
+        TaskEvent::Resumed { task_id: task_id.to_string() }.emit(app_handle);
 
         // Wake up the task
-        let (lock, cvar) = &*self.pause_condvar;
-        let mut paused_tasks = lock.lock().unwrap();
-        paused_tasks.remove(task_id);
-        cvar.notify_all();
+        {
+            let (lock, cvar) = &*self.pause_condvar;
+            let mut paused_tasks = lock.lock().unwrap();
+            paused_tasks.remove(task_id);
+            cvar.notify_all();
+        }
+
+        if !worker_running {
+            self.process_next_tasks(app_handle)?;
+        }
 
         Ok(())
     }
@@ -514,25 +1085,48 @@ impl TaskManager {
             )));
         }
 
-        // Update task status
-        self.update_task(task_id, |task| {
-            task.status = TaskStatus::Canceled;
-            task.completed_at = Some(Utc::now().to_rfc3339());
-        })?;
+        self.mark_canceled(task_id)?;
 
         // Save state
         self.save_state(app_handle)?;
 
         // Emit task-canceled event
-        emit_event(app_handle, "task-canceled", Some(json!({
-            "taskId": task_id
-        })));
+        TaskEvent::Canceled { task_id: task_id.to_string() }.emit(app_handle);
+
+        Ok(())
+    }
+
+    /// Signal the cancel token of every running worker
+    fn cancel_workers(&self) {
+        for token in self.cancel_tokens.read().values() {
+            token.cancel();
+        }
+    }
+
+    /// Set a task to Canceled, wake it if it's paused and take it off the queue
+    ///
+    /// A running worker is signaled through its cancel token and stops at its next frame.
+    fn mark_canceled(&self, task_id: &str) -> TaskResult<()> {
+        // Update task status; segments kept for a resume are no longer needed
+        self.update_task(task_id, |task| {
+            task.status = TaskStatus::Canceled;
+            task.completed_at = Some(Utc::now().to_rfc3339());
+            if let Some(resume) = task.resume.take() {
+                resume.discard();
+            }
+        })?;
+
+        if let Some(token) = self.cancel_tokens.read().get(task_id) {
+            token.cancel();
+        }
 
         // Wake up the task if it's paused
-        let (lock, cvar) = &*self.pause_condvar;
-        let mut paused_tasks = lock.lock().unwrap();
-        paused_tasks.remove(task_id);
-        cvar.notify_all();
+        {
+            let (lock, cvar) = &*self.pause_condvar;
+            let mut paused_tasks = lock.lock().unwrap();
+            paused_tasks.remove(task_id);
+            cvar.notify_all();
+        }
 
         // Remove from queue if present
         {
@@ -563,6 +1157,9 @@ impl TaskManager {
             task.status = TaskStatus::Pending;
             task.progress = 0.0;
             task.error = None;
+            task.note = None;
+            task.fallback_used = None;
+            task.quality = None;
             task.completed_at = None;
         })?;
 
@@ -570,33 +1167,43 @@ impl TaskManager {
         self.save_state(app_handle)?;
 
         // Emit task-retried event
-        emit_event(app_handle, "task-retried", Some(json!({
-            "taskId": task_id
-        })));
+        TaskEvent::Retried { task_id: task_id.to_string() }.emit(app_handle);
 
         // Add to queue if not already there
-        {
-            let mut queue = self.queue.write();
-            if !queue.iter().any(|id| id == task_id) {
-                queue.push_back(task_id.to_string());
-            }
+        let is_queued = self.queue.read().iter().any(|id| id == task_id);
+        if !is_queued {
+            self.enqueue_by_priority(task_id);
         }
 
-        // If queue is not paused and there are available slots, start the task
-        if !*self.is_queue_paused.read() {
-            let running_count = {
-                let tasks = self.tasks.read();
-                tasks.iter()
-                    .filter(|task| task.status == TaskStatus::Running)
-                    .count()
-            };
+        // Start it in queue order if the queue is running and a slot is free
+        self.process_next_tasks(app_handle)
+    }
 
-            if running_count < *self.max_concurrent_tasks.read() {
-                self.start_task(task_id, app_handle)?;
-            }
+    /// Delay before the next automatic retry of a failed task, if any are left.
+    ///
+    /// Returns `None` for canceled tasks, for a missing input (retrying will not bring
+    /// it back) or once `max_retries` has been reached.
+    /// The delay doubles with every attempt: 1s, 2s, 4s, ...
+    pub fn retry_delay(&self, task_id: &str, error: &TaskError) -> Option<Duration> {
+        if matches!(error, TaskError::Canceled | TaskError::InputNotFound(_)) {
+            return None;
         }
 
-        Ok(())
+        let task = self.get_task(task_id).ok()?;
+        if task.status == TaskStatus::Canceled {
+            return None;
+        }
+
+        let retries_done = task.attempts.saturating_sub(1);
+        if retries_done >= task.max_retries {
+            return None;
+        }
+
+        let delay = RETRY_BASE_DELAY_SECS
+            .saturating_mul(1u64 << retries_done.min(16))
+            .min(RETRY_MAX_DELAY_SECS);
+
+        Some(Duration::from_secs(delay))
     }
 
     /// Remove a task
@@ -611,29 +1218,84 @@ impl TaskManager {
             )));
         }
 
-        // Remove from tasks list
-        {
-            let mut tasks = self.tasks.write();
-            tasks.retain(|t| t.id != task_id);
-        }
+        self.remove_entry(task_id);
 
-        // Remove from queue if present
-        {
-            let mut queue = self.queue.write();
-            queue.retain(|id| id != task_id);
+        // Save state
+        self.save_state(app_handle)?;
+
+        // Emit task-removed event
+        TaskEvent::Removed { task_id: task_id.to_string() }.emit(app_handle);
+
+        Ok(())
+    }
+
+    /// Cancel a task if it is running or paused, then remove it
+    ///
+    /// Waits up to `FORCE_REMOVE_TIMEOUT` for the task's worker to stop, so its
+    /// completion handler has run before the entry disappears. Only `task-removed` is
+    /// emitted.
+    pub async fn force_remove_task(&self, task_id: &str, app_handle: &AppHandle) -> TaskResult<()> {
+        let task = self.get_task(task_id)?;
+
+        if task.status == TaskStatus::Running || task.status == TaskStatus::Paused {
+            self.mark_canceled(task_id)?;
+
+            let deadline = Instant::now() + FORCE_REMOVE_TIMEOUT;
+            loop {
+                let worker_running = self.active_workers.read().contains(task_id);
+                if !worker_running {
+                    break;
+                }
+                if Instant::now() >= deadline {
+                    warn!("Worker for task {} did not stop in time, removing it anyway", task_id);
+                    break;
+                }
+                tokio::time::sleep(FORCE_REMOVE_POLL_INTERVAL).await;
+            }
         }
 
+        self.remove_entry(task_id);
+        self.progress_trackers.write().remove(task_id);
+
         // Save state
         self.save_state(app_handle)?;
 
         // Emit task-removed event
-        emit_event(app_handle, "task-removed", Some(json!({
-            "taskId": task_id
-        })));
+        TaskEvent::Removed { task_id: task_id.to_string() }.emit(app_handle);
 
         Ok(())
     }
 
+    /// Drop a task from the task list and the queue
+    fn remove_entry(&self, task_id: &str) {
+        self.remove_tasks_where(|task| task.id == task_id);
+    }
+
+    /// Take every task matching `remove` out of the task list and the queue
+    ///
+    /// Segments a removed task kept for a resume are deleted, no task can continue them.
+    fn remove_tasks_where(&self, remove: impl Fn(&Task) -> bool) -> Vec<Task> {
+        // Remove from tasks list
+        let removed = {
+            let mut tasks = self.tasks.write();
+            let (removed, kept): (Vec<Task>, Vec<Task>) = tasks.drain(..).partition(|task| remove(task));
+            *tasks = kept;
+            removed
+        };
+
+        // Remove from queue if present
+        {
+            let mut queue = self.queue.write();
+            queue.retain(|id| !removed.iter().any(|task| task.id == *id));
+        }
+
+        for resume in removed.iter().filter_map(|task| task.resume.as_ref()) {
+            resume.discard();
+        }
+
+        removed
+    }
+
     /// Clear completed tasks
     pub fn clear_completed_tasks(&self, app_handle: &AppHandle) -> TaskResult<()> {
         // Collect IDs of completed and canceled tasks
@@ -653,38 +1315,120 @@ impl TaskManager {
         Ok(())
     }
 
+    /// Remove every task that is not running or paused
+    ///
+    /// Emits a single `tasks-cleared` event and returns the number of removed tasks.
+    pub fn clear_all_tasks(&self, app_handle: &AppHandle) -> TaskResult<usize> {
+        let removed = self.remove_tasks_where(|task| {
+            task.status != TaskStatus::Running && task.status != TaskStatus::Paused
+        });
+
+        // Save state
+        self.save_state(app_handle)?;
+
+        // Emit tasks-cleared event
+        let count = removed.len();
+        TaskEvent::TasksCleared { count }.emit(app_handle);
+
+        Ok(count)
+    }
+
     /// Reorder tasks in the queue
+    ///
+    /// `new_order` must hold every queued task exactly once, so no task can be
+    /// dropped from the queue by leaving it out.
     pub fn reorder_tasks(&self, new_order: Vec<String>, app_handle: &AppHandle) -> TaskResult<()> {
+        self.set_queue_order(&new_order)?;
+
+        // Save state
+        self.save_state(app_handle)?;
+
+        // Emit queue-reordered event with the new order
+        TaskEvent::QueueReordered { queue: new_order }.emit(app_handle);
+
+        Ok(())
+    }
+
+    /// Move a queued task to the front of the queue and return the new order
+    ///
+    /// Does nothing if the task is not queued.
+    pub fn move_task_to_front(&self, task_id: &str, app_handle: &AppHandle) -> TaskResult<Vec<String>> {
+        self.move_queued_task(task_id, true, app_handle)
+    }
+
+    /// Move a queued task to the back of the queue and return the new order
+    ///
+    /// Does nothing if the task is not queued.
+    pub fn move_task_to_back(&self, task_id: &str, app_handle: &AppHandle) -> TaskResult<Vec<String>> {
+        self.move_queued_task(task_id, false, app_handle)
+    }
+
+    fn move_queued_task(&self, task_id: &str, to_front: bool, app_handle: &AppHandle) -> TaskResult<Vec<String>> {
+        let queue = {
+            let mut queue = self.queue.write();
+            let Some(id) = queue
+                .iter()
+                .position(|id| id == task_id)
+                .and_then(|index| queue.remove(index))
+            else {
+                return Ok(queue.iter().cloned().collect());
+            };
+
+            if to_front {
+                queue.push_front(id);
+            } else {
+                queue.push_back(id);
+            }
+            queue.iter().cloned().collect::<Vec<String>>()
+        };
+
+        // Save state
+        self.save_state(app_handle)?;
+
+        TaskEvent::QueueReordered { queue: queue.clone() }.emit(app_handle);
+
+        Ok(queue)
+    }
+
+    /// Replace the queue order without saving or emitting events, see `reorder_tasks`
+    ///
+    /// The queue is left unchanged if `new_order` is not a permutation of it.
+    pub fn set_queue_order(&self, new_order: &[String]) -> TaskResult<()> {
         // Validate that all IDs exist
         {
             let tasks = self.tasks.read();
-            for id in &new_order {
+            for id in new_order {
                 if !tasks.iter().any(|task| task.id == *id) {
                     return Err(TaskError::TaskNotFound(id.clone()));
                 }
             }
         }
 
-        // Update queue
+        // Check and update under one lock so a task queued meanwhile cannot be lost
         {
             let mut queue = self.queue.write();
-            queue.clear();
-            for id in new_order {
-                queue.push_back(id);
+            let queued: HashSet<&String> = queue.iter().collect();
+            let ordered: HashSet<&String> = new_order.iter().collect();
+            if ordered.len() != new_order.len() || ordered != queued {
+                return Err(TaskError::Other(
+                    "New order must contain each queued task exactly once".to_string(),
+                ));
             }
-        }
-
-        // Save state
-        self.save_state(app_handle)?;
 
-        // Emit queue-reordered event
-        emit_event(app_handle, "queue-reordered", None);
+            queue.clear();
+            queue.extend(new_order.iter().cloned());
+        }
 
         Ok(())
     }
 
     /// Set the maximum number of concurrent tasks
     pub fn set_max_concurrent_tasks(&self, max: usize, app_handle: &AppHandle) -> TaskResult<()> {
+        // A limit of 0 would leave every task waiting forever
+        if max == 0 {
+            return Err(TaskError::Other("Maximum concurrent tasks must be at least 1".to_string()));
+        }
+
         // Update max_concurrent_tasks
         *self.max_concurrent_tasks.write() = max;
         *self.semaphore.write() = Arc::new(Semaphore::new(max));
@@ -693,9 +1437,7 @@ impl TaskManager {
         self.save_state(app_handle)?;
 
         // Emit max-concurrent-tasks-changed event
-        emit_event(app_handle, "max-concurrent-tasks-changed", Some(json!({
-            "max": max
-        })));
+        TaskEvent::MaxConcurrentTasksChanged { max }.emit(app_handle);
 
         Ok(())
     }
@@ -710,12 +1452,85 @@ impl TaskManager {
         *self.is_queue_paused.read()
     }
 
+    /// Set how interrupted tasks are handled the next time the state is loaded
+    pub fn set_interrupted_task_policy(&self, policy: InterruptedTaskPolicy, app_handle: &AppHandle) -> TaskResult<()> {
+        *self.interrupted_task_policy.write() = policy;
+
+        // Save state
+        self.save_state(app_handle)?;
+
+        Ok(())
+    }
+
+    /// Get how interrupted tasks are handled when the state is loaded
+    pub fn get_interrupted_task_policy(&self) -> InterruptedTaskPolicy {
+        *self.interrupted_task_policy.read()
+    }
+
+    /// Reset tasks left running or paused by a previous run, according to the interrupted task policy.
+    ///
+    /// Nothing is executing right after a load, so these tasks would otherwise stay
+    /// running forever. Requeued tasks are picked up by the next `start_queue` and
+    /// continue after their saved segments, if any. A paused task with segments stays
+    /// paused until `resume_task`.
+    pub fn recover_interrupted_tasks(&self) {
+        let policy = *self.interrupted_task_policy.read();
+
+        let interrupted: Vec<(String, TaskStatus)> = {
+            let mut tasks = self.tasks.write();
+            tasks.iter_mut()
+                .filter(|task| task.status == TaskStatus::Running || task.status == TaskStatus::Paused)
+                .map(|task| {
+                    match policy {
+                        InterruptedTaskPolicy::Requeue if task.resume.is_some() => {
+                            if task.status == TaskStatus::Running {
+                                task.status = TaskStatus::Pending;
+                            }
+                            task.started_at = None;
+                        }
+                        InterruptedTaskPolicy::Requeue => {
+                            task.status = TaskStatus::Pending;
+                            task.progress = 0.0;
+                            task.started_at = None;
+                        }
+                        InterruptedTaskPolicy::Fail => {
+                            task.status = TaskStatus::Failed;
+                            task.error = Some("Task was interrupted when the application exited".to_string());
+                            task.completed_at = Some(Utc::now().to_rfc3339());
+                            if let Some(resume) = task.resume.take() {
+                                resume.discard();
+                            }
+                        }
+                    }
+                    (task.id.clone(), task.status)
+                })
+                .collect()
+        };
+
+        if interrupted.is_empty() {
+            return;
+        }
+
+        for (task_id, status) in &interrupted {
+            if *status == TaskStatus::Pending {
+                self.enqueue_by_priority(task_id);
+            }
+        }
+
+        self.state_dirty.store(true, Ordering::SeqCst);
+        info!("Recovered {} interrupted tasks ({:?})", interrupted.len(), policy);
+    }
+
     /// Save the task state to a file
     pub fn save_state(&self, app_handle: &AppHandle) -> TaskResult<()> {
         // Get tasks and queue
         let tasks = self.tasks.read();
         let queue = self.queue.read();
 
+        // Save the layout version first so a partially written store is read correctly
+        store_helper::set_value(app_handle, TASKS_STORE_PATH, "schema_version", &TASKS_SCHEMA_VERSION)
+            .map_err(|e| TaskError::StoreSaveError(e.to_string()))?;
+
         // Save tasks
         store_helper::set_value(app_handle, TASKS_STORE_PATH, "tasks", &*tasks)
             .map_err(|e| TaskError::StoreSaveError(e.to_string()))?;
@@ -728,14 +1543,49 @@ impl TaskManager {
         store_helper::set_value(app_handle, TASKS_STORE_PATH, "max_concurrent_tasks", &*self.max_concurrent_tasks.read())
             .map_err(|e| TaskError::StoreSaveError(e.to_string()))?;
 
-        // Save is_queue_paused
-        store_helper::set_value(app_handle, TASKS_STORE_PATH, "is_queue_paused", &*self.is_queue_paused.read())
+        // Save is_queue_paused; a pause for battery power is decided again on the next start
+        let paused = *self.is_queue_paused.read() && !self.auto_paused.load(Ordering::SeqCst);
+        store_helper::set_value(app_handle, TASKS_STORE_PATH, "is_queue_paused", &paused)
             .map_err(|e| TaskError::StoreSaveError(e.to_string()))?;
 
+        // Save interrupted_task_policy
+        store_helper::set_value(app_handle, TASKS_STORE_PATH, "interrupted_task_policy", &*self.interrupted_task_policy.read())
+            .map_err(|e| TaskError::StoreSaveError(e.to_string()))?;
+
+        self.state_dirty.store(false, Ordering::SeqCst);
+        *self.last_saved_at.write() = Some(Instant::now());
+
         info!("Task state saved successfully");
         Ok(())
     }
 
+    /// Mark the task state as changed and save it at most once per `SAVE_DEBOUNCE_INTERVAL`
+    ///
+    /// Use `save_state` instead for status transitions that must be persisted right away.
+    pub fn save_state_debounced(&self, app_handle: &AppHandle) -> TaskResult<()> {
+        self.state_dirty.store(true, Ordering::SeqCst);
+
+        let due = match *self.last_saved_at.read() {
+            Some(last_saved_at) => last_saved_at.elapsed() >= SAVE_DEBOUNCE_INTERVAL,
+            None => true,
+        };
+
+        if due {
+            self.save_state(app_handle)?;
+        }
+
+        Ok(())
+    }
+
+    /// Save the task state if there are changes that have not been persisted yet
+    pub fn flush_state(&self, app_handle: &AppHandle) -> TaskResult<()> {
+        if self.state_dirty.load(Ordering::SeqCst) {
+            self.save_state(app_handle)?;
+        }
+
+        Ok(())
+    }
+
     /// Load the task state from a file
     pub fn load_state(&self, app_handle: &AppHandle) -> TaskResult<()> {
         // Check if the store exists
@@ -747,21 +1597,36 @@ impl TaskManager {
             return Ok(());
         }
 
-        // Load tasks
-        let tasks_opt: Option<Vec<Task>> = store_helper::get_value(app_handle, TASKS_STORE_PATH, "tasks")
+        // Stores written before versioning have no schema_version key
+        let schema_version: u32 = store_helper::get_value(app_handle, TASKS_STORE_PATH, "schema_version")
+            .map_err(|e| TaskError::StoreLoadError(e.to_string()))?
+            .unwrap_or(1);
+
+        // Load tasks, upgrading older layouts
+        let tasks_opt: Option<Vec<serde_json::Value>> = store_helper::load_with_backup(app_handle, TASKS_STORE_PATH, "tasks")
             .map_err(|e| TaskError::StoreLoadError(e.to_string()))?;
 
         if let Some(tasks_vec) = tasks_opt {
             let mut tasks = self.tasks.write();
-            *tasks = tasks_vec;
-            info!("Loaded {} tasks from store", tasks.len());
+            *tasks = schema::migrate_tasks(schema_version, tasks_vec);
+            info!("Loaded {} tasks from store (schema version {})", tasks.len(), schema_version);
+
+            // Write the upgraded layout back with the next save
+            if schema_version < TASKS_SCHEMA_VERSION {
+                self.state_dirty.store(true, Ordering::SeqCst);
+            }
         }
 
         // Load queue
-        let queue_opt: Option<VecDeque<String>> = store_helper::get_value(app_handle, TASKS_STORE_PATH, "queue")
+        let queue_opt: Option<VecDeque<String>> = store_helper::load_with_backup(app_handle, TASKS_STORE_PATH, "queue")
             .map_err(|e| TaskError::StoreLoadError(e.to_string()))?;
 
-        if let Some(queue_vec) = queue_opt {
+        if let Some(mut queue_vec) = queue_opt {
+            // Drop ids of tasks that could not be loaded
+            {
+                let tasks = self.tasks.read();
+                queue_vec.retain(|id| tasks.iter().any(|task| &task.id == id));
+            }
             let mut queue = self.queue.write();
             *queue = queue_vec;
             info!("Loaded {} tasks in queue from store", queue.len());
@@ -771,7 +1636,7 @@ impl TaskManager {
         let max_concurrent_tasks_opt: Option<usize> = store_helper::get_value(app_handle, TASKS_STORE_PATH, "max_concurrent_tasks")
             .map_err(|e| TaskError::StoreLoadError(e.to_string()))?;
 
-        if let Some(max) = max_concurrent_tasks_opt {
+        if let Some(max) = max_concurrent_tasks_opt.filter(|&max| max > 0) {
             *self.max_concurrent_tasks.write() = max;
             *self.semaphore.write() = Arc::new(Semaphore::new(max));
             info!("Loaded max_concurrent_tasks: {}", max);
@@ -786,57 +1651,128 @@ impl TaskManager {
             info!("Loaded is_queue_paused: {}", paused);
         }
 
+        // Load interrupted_task_policy
+        let interrupted_task_policy_opt: Option<InterruptedTaskPolicy> = store_helper::get_value(app_handle, TASKS_STORE_PATH, "interrupted_task_policy")
+            .map_err(|e| TaskError::StoreLoadError(e.to_string()))?;
+
+        if let Some(policy) = interrupted_task_policy_opt {
+            *self.interrupted_task_policy.write() = policy;
+            info!("Loaded interrupted_task_policy: {:?}", policy);
+        }
+
+        // Tasks that were running when the app exited can't still be running now
+        self.recover_interrupted_tasks();
+
         Ok(())
     }
 }
 
 /// Update task status
+///
+/// Returns `false` if the status was not applied, because the task has been removed or
+/// canceled meanwhile; the caller must then not emit events or schedule a retry for it.
 async fn update_task_status(
     app_handle: &AppHandle,
     task_id: &str,
     status: TaskStatus,
     progress: f32,
     error: Option<String>,
-) {
+) -> bool {
     // Get task manager
     let task_manager = app_handle.state::<TaskManager>();
+    let manager = task_manager.inner();
+
+    // Don't overwrite a cancellation that happened while the task was running
+    let mut applied = false;
+    let mut finished_resume = None;
+    let updated = manager.update_task(task_id, |task| {
+        if task.status == TaskStatus::Canceled {
+            // The worker has already deleted the partial output
+            task.partial_output_path = None;
+            return;
+        }
 
-    // We need to update the task status in a way that doesn't require mutable access
-    // First, get the current task
-    let current_task = {
-        let manager = task_manager.inner();
-        match manager.get_task(task_id) {
-            Ok(task) => task.clone(),
-            Err(_) => return, // Task not found
+        task.status = status;
+        task.progress = progress;
+        if let Some(err) = error {
+            task.error = Some(err);
         }
-    };
+        if status == TaskStatus::Completed || status == TaskStatus::Failed || status == TaskStatus::Canceled {
+            task.completed_at = Some(Utc::now().to_rfc3339());
+        }
+        if status == TaskStatus::Completed || status == TaskStatus::Failed {
+            task.partial_output_path = None;
+            finished_resume = task.resume.take();
+        }
+        applied = true;
+    });
 
-    // Create an updated task
-    let mut updated_task = current_task;
-    updated_task.status = status;
-    updated_task.progress = progress;
-    if let Some(err) = error {
-        updated_task.error = Some(err);
+    // A finished task is never continued, e.g. one that failed before encoding again
+    if let Some(resume) = finished_resume {
+        resume.discard();
     }
-    if status == TaskStatus::Completed || status == TaskStatus::Failed || status == TaskStatus::Canceled {
-        updated_task.completed_at = Some(Utc::now().to_rfc3339());
+
+    if updated.is_err() {
+        return false; // Task was removed
+    }
+
+    // Save state
+    let _ = manager.save_state(app_handle);
+
+    if !applied {
+        return false; // Task was canceled; its `task-removed` or `task-updated` was already sent
     }
 
+    let updated_task = match manager.get_task(task_id) {
+        Ok(task) => task,
+        Err(_) => return false,
+    };
+
     // Emit an event to update the task
-    emit_event(app_handle, "task-updated", Some(serde_json::json!({
-        "task": updated_task
-    })));
+    TaskEvent::Updated { task: updated_task }.emit(app_handle);
+    true
 }
 
-/// Emit event
-fn emit_event(app_handle: &AppHandle, event: &str, payload: Option<serde_json::Value>) {
-    if let Some(payload) = payload {
-        let _ = app_handle.emit(event, payload);
-    } else {
-        let _ = app_handle.emit(event, ());
+/// Delete the temporary output of a task that did not complete
+fn remove_partial_output(path: &str) {
+    match std::fs::remove_file(path) {
+        Ok(()) => info!("Deleted partial output {}", path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("Failed to delete partial output {}: {}", path, e),
     }
 }
 
+/// Re-queue a failed task after `delay` without holding a concurrency slot while waiting
+fn schedule_retry(app_handle: &AppHandle, task_id: &str, delay: Duration) {
+    // Emit task-retry-scheduled event
+    TaskEvent::RetryScheduled {
+        task_id: task_id.to_string(),
+        delay_secs: delay.as_secs(),
+    }.emit(app_handle);
+
+    // Keeps the queue from being reported as drained while waiting
+    let task_manager = app_handle.state::<TaskManager>();
+    task_manager.inner().scheduled_retries.write().insert(task_id.to_string());
+
+    let app_handle = app_handle.clone();
+    let task_id = task_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+
+        // Only retry if the task is still failed (it may have been removed or retried manually)
+        let task_manager = app_handle.state::<TaskManager>();
+        let manager = task_manager.inner();
+        manager.scheduled_retries.write().remove(&task_id);
+        match manager.get_task(&task_id) {
+            Ok(task) if task.status == TaskStatus::Failed => {
+                info!("Automatically retrying task {} (attempt {})", task_id, task.attempts + 1);
+                let _ = manager.retry_task(&task_id, &app_handle);
+            }
+            _ => manager.finish_if_drained(&app_handle),
+        }
+    });
+}
+
 // TaskProcessor already implements Clone via #[derive(Clone)]
 
 // Implement From<TaskError> for AppError
@@ -878,6 +1814,26 @@ impl From<TaskError> for AppError {
                 ErrorCode::TaskCanceled,
                 Some("The task was canceled by the user".to_string()),
             ),
+            TaskError::ConcurrencyLimit(max) => AppError::new(
+                format!("Maximum of {} concurrent tasks reached", max),
+                ErrorCode::TaskProcessingFailed,
+                Some("The task stays queued until a running task finishes".to_string()),
+            ),
+            TaskError::InputNotFound(path) => AppError::new(
+                format!("Source file no longer exists: {}", path),
+                ErrorCode::FileNotFound,
+                Some("The input was moved or deleted after the task was created".to_string()),
+            ),
+            TaskError::QueuePaused => AppError::new(
+                "The queue is paused".to_string(),
+                ErrorCode::TaskProcessingFailed,
+                Some("Resume the queue or force the task to start".to_string()),
+            ),
+            TaskError::OutputExists(path) => AppError::new(
+                format!("Output file already exists: {}", path),
+                ErrorCode::FileWriteError,
+                Some("Choose another output path or an overwrite policy".to_string()),
+            ),
             TaskError::Other(msg) => AppError::new(
                 msg,
                 ErrorCode::UnknownError,
@@ -0,0 +1,110 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use super::errors::{TaskError, TaskResult};
+
+/// What to do when a task's output file already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverwritePolicy {
+    /// Refuse to create the task
+    #[default]
+    Error,
+    /// Replace the existing file
+    Overwrite,
+    /// Write to `name (1).ext`, `name (2).ext`, ... instead
+    Rename,
+}
+
+/// Whether two paths point at the same file
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// First `name (n).ext` next to `path` that does not exist yet
+fn next_free_path(path: &Path) -> PathBuf {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().into_owned());
+
+    (1..)
+        .map(|n| {
+            let file_name = match &extension {
+                Some(ext) => format!("{} ({}).{}", stem, n, ext),
+                None => format!("{} ({})", stem, n),
+            };
+            parent.join(file_name)
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded range always yields a free path")
+}
+
+/// Resolve the path a task will actually write to, applying the overwrite policy.
+///
+/// Writing over the input file is always rejected, whatever the policy.
+pub fn resolve_output_path(
+    input_path: &str,
+    output_path: &str,
+    policy: OverwritePolicy,
+) -> TaskResult<String> {
+    let output = Path::new(output_path);
+
+    if same_file(Path::new(input_path), output) {
+        return Err(TaskError::OutputExists(format!(
+            "Output path is the same as the input file: {}",
+            output_path
+        )));
+    }
+
+    if !output.exists() {
+        return Ok(output_path.to_string());
+    }
+
+    match policy {
+        OverwritePolicy::Error => Err(TaskError::OutputExists(output_path.to_string())),
+        OverwritePolicy::Overwrite => Ok(output_path.to_string()),
+        OverwritePolicy::Rename => Ok(next_free_path(output).to_string_lossy().into_owned()),
+    }
+}
+
+/// Temporary name a task writes to before its output is complete: `name.part.ext`
+///
+/// The extension is kept last so FFmpeg still picks the container from it.
+pub fn partial_output_path(output_path: &str) -> String {
+    let output = Path::new(output_path);
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_name = match output.extension() {
+        Some(ext) => format!("{}.part.{}", stem, ext.to_string_lossy()),
+        None => format!("{}.part", stem),
+    };
+    output.with_file_name(file_name).to_string_lossy().into_owned()
+}
+
+/// Whether `path` is a partial output or one of its resume segments: `name.part.ext`
+/// or `name.part.seg<index>.ext`
+pub fn is_partial_output_path(path: &Path) -> bool {
+    let stem = match path.file_stem() {
+        Some(stem) => stem.to_string_lossy(),
+        None => return false,
+    };
+    if stem.ends_with(".part") {
+        return true;
+    }
+    match stem.rsplit_once(".seg") {
+        Some((base, index)) => {
+            base.ends_with(".part")
+                && !index.is_empty()
+                && index.chars().all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
@@ -1,18 +1,68 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
-use tauri::{AppHandle, Manager, Emitter};
-
-use crate::services::video_processor::{VideoProcessor, ProcessingOptions};
+use log::warn;
+use parking_lot::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::services::video_processor::{
+    is_sequence_pattern, parse_crop, parse_dimension, report_warning, with_warning_handler, ConvertOutcome, VideoError,
+    VideoProcessor, ProcessingOptions, QualityScore, ResumePoint, SubtitleConfig, WarningHandler, WatermarkConfig,
+};
+use crate::utils::error::{AppResult, ErrorCode};
+use super::cancel::CancelToken;
 use super::errors::TaskError;
+use super::events::TaskEvent;
+use super::progress::{progress_interval, ProgressThrottle};
+use super::threads::{auto_thread_limit, threads_per_task};
 use super::{Task, TaskStatus};
 
-/// Emit event
-fn emit_event(app_handle: &AppHandle, event: &str, payload: Option<serde_json::Value>) {
-    if let Some(payload) = payload {
-        let _ = app_handle.emit(event, payload);
-    } else {
-        let _ = app_handle.emit(event, ());
-    }
+/// Task types handled by `TaskProcessor::process_task`
+pub const TASK_TYPES: &[&str] = &["convert", "remux", "split", "edit", "sanitize"];
+
+/// Progress callback shared between the first attempt and the fallback retries
+type ProgressCallback = Arc<dyn Fn(f32) -> bool + Send + Sync>;
+
+/// Distinct FFmpeg warnings reported per task before the rest are only logged
+const MAX_TASK_WARNINGS: usize = 20;
+
+/// `phase` of the progress reported while the output is scored against the input
+const PHASE_QUALITY: &str = "quality";
+
+/// Note stored on a `convert_if_needed` task whose input needed no encoding
+const ALREADY_COMPLIANT_NOTE: &str = "Already compliant, copied without re-encoding";
+
+/// Result of a task that completed
+#[derive(Debug, Clone, Default)]
+pub struct TaskOutcome {
+    /// Remark stored on the task, e.g. when the input only had to be copied
+    pub note: Option<String>,
+    /// Index into `Task::fallbacks` of the settings that succeeded, `None` for the task's own
+    pub fallback_used: Option<usize>,
+    /// Score of the output against the input, if `compute_quality` was set and it could be measured
+    pub quality: Option<QualityScore>,
+    /// Set instead of completing when the task stopped for a pause or the app exiting,
+    /// with the encoded part to continue from; empty if it has to start over
+    pub paused: Option<ResumePoint>,
+}
+
+/// How one run of a task ended
+enum RunEnd {
+    /// The output is complete, with an optional note for the task
+    Completed(Option<String>),
+    /// Stopped early with the part encoded so far
+    Stopped(ResumePoint),
+}
+
+/// Label for the encoder `options` ask for, used in `task-fallback` events
+fn encoder_label(options: &ProcessingOptions) -> String {
+    let codec = if options.use_gpu { &options.gpu_codec } else { &options.cpu_codec };
+    codec.clone().unwrap_or_else(|| "default encoder".to_string())
 }
 
 /// Task processor that acts as a proxy between tasks and video processor
@@ -22,27 +72,73 @@ pub struct TaskProcessor {
 }
 
 impl TaskProcessor {
-    /// Create a new TaskProcessor
-    pub fn new() -> Self {
-        Self {
-            video_processor: VideoProcessor::new(),
-        }
+    /// Create a TaskProcessor running tasks on the shared `video_processor`
+    pub fn new(video_processor: VideoProcessor) -> Self {
+        Self { video_processor }
     }
 
     /// Process a task based on its type
-    pub async fn process_task(&self, task: &Task, app_handle: &AppHandle) -> Result<(), TaskError> {
+    ///
+    /// Processing stops at the next frame once `cancel_token` is canceled. A failed
+    /// attempt is run again with each of the task's `fallbacks` in turn before the
+    /// task fails.
+    pub async fn process_task(
+        &self,
+        task: &Task,
+        cancel_token: CancelToken,
+        app_handle: &AppHandle,
+    ) -> Result<TaskOutcome, TaskError> {
+        // The input may have been moved or deleted while the task was queued
+        if !is_sequence_pattern(&task.input_path) && !Path::new(&task.input_path).exists() {
+            return Err(TaskError::InputNotFound(task.input_path.clone()));
+        }
+
+        // Long inputs weigh more in the overall progress. Probed here on the worker rather
+        // than in `create_task`, so adding many tasks never waits for slow probes.
+        if task.input_duration.is_none() {
+            if let Some(duration) = self.input_duration(&task.input_path) {
+                let task_manager = app_handle.state::<super::TaskManager>();
+                let _ = task_manager.inner().update_task(&task.id, |task| task.input_duration = Some(duration));
+            }
+        }
+
         // Create progress callback
+        let stop_token = cancel_token.clone();
+        let keep_progress = Arc::new(AtomicBool::new(false));
+        let keep_progress_clone = keep_progress.clone();
         let app_handle_clone = app_handle.clone();
         let task_id_clone = task.id.clone();
-        let progress_callback = Box::new(move |progress: f32| -> bool {
-            // Update task progress
-            let _ = emit_event(&app_handle_clone, "task-progress", Some(serde_json::json!({
-                "task_id": task_id_clone,
-                "progress": progress
-            })));
-
-            // Check if task is paused or canceled
+        let throttle = Mutex::new(ProgressThrottle::new(progress_interval(app_handle)));
+        let progress_callback: ProgressCallback = Arc::new(move |progress: f32| -> bool {
             let task_manager = app_handle_clone.state::<super::TaskManager>();
+
+            // Cheap enough to check on every frame, before anything else
+            if cancel_token.is_canceled() {
+                // Work stopped by the app exiting is kept to continue with the next start
+                if task_manager.inner().is_shutting_down() {
+                    keep_progress_clone.store(true, Ordering::SeqCst);
+                }
+                return false;
+            }
+
+            // The encoder reports every frame; only pass progress on every few hundred ms
+            if throttle.lock().should_report(progress) {
+                // Estimate the remaining time from the smoothed progress rate
+                let eta_secs = task_manager.inner().record_progress(&task_id_clone, progress);
+
+                // Store progress (persisted with debouncing)
+                let _ = task_manager.inner().update_task_progress(&task_id_clone, progress, &app_handle_clone);
+
+                // Update task progress
+                TaskEvent::Progress {
+                    task_id: task_id_clone.clone(),
+                    progress,
+                    eta_secs,
+                    phase: None,
+                }.emit(&app_handle_clone);
+            }
+
+            // Check if task is paused or canceled on every call
             let task_status = {
                 let manager = task_manager.inner();
                 match manager.get_task(&task_id_clone) {
@@ -55,33 +151,278 @@ impl TaskProcessor {
                 return false; // Stop processing
             }
 
-            if task_status == TaskStatus::Paused {
-                // In a real implementation, we would wait for the task to be resumed
-                // For now, we'll just return false to stop processing
+            // Stop the worker; resume_task starts the task again from what was encoded
+            if task_status == TaskStatus::Paused || task_manager.inner().is_shutting_down() {
+                keep_progress_clone.store(true, Ordering::SeqCst);
                 return false;
             }
 
             true // Continue processing
         });
 
-        // Get task information
+        // Get task information; the output is written under a temporary name first
         let input_path = &task.input_path;
-        let output_path = &task.output_path;
+        let output_path = task.partial_output_path.as_ref().unwrap_or(&task.output_path);
         let config = &task.config;
 
-        // Create processing options from config
-        let options = create_processing_options(config)?;
+        // Create processing options from config and fail early on invalid settings;
+        // a codec replaced to fit the output format is reported as a task warning
+        let mut options = with_warning_handler(task_warning_handler(app_handle, &task.id), || {
+            self.prepare_options(config, app_handle)
+        })?;
+
+        // Dry run: report what would be done and complete without encoding
+        if config.get("dry_run").map_or(false, |v| v == "true") {
+            let plan = self.video_processor
+                .plan_processing(input_path, &task.output_path, &options)
+                .map_err(|e| TaskError::ProcessingFailed(e.to_string()))?;
+            TaskEvent::Plan {
+                task_id: task.id.clone(),
+                plan,
+            }.emit(app_handle);
+            return Ok(TaskOutcome::default());
+        }
+
+        // Reject unknown task types before any work is done
+        if !TASK_TYPES.contains(&task.task_type.as_str()) {
+            return Err(TaskError::UnsupportedTaskType(task.task_type.clone()));
+        }
+
+        // Forward FFmpeg warnings raised while this task runs to the frontend
+        let warning_handler = task_warning_handler(app_handle, &task.id);
+
+        let mut fallback_used = None;
+        let result = with_warning_handler(warning_handler, || {
+            let mut result = self.run_attempt(
+                task,
+                config,
+                output_path,
+                &options,
+                task.resume.clone(),
+                progress_callback.clone(),
+                app_handle,
+            );
+
+            // Try the fallback settings in order until one succeeds
+            for (index, overrides) in task.fallbacks.iter().enumerate() {
+                let error = match &result {
+                    Ok(_) => break,
+                    Err(e) => e.to_string(),
+                };
+                if stop_token.is_canceled() || is_stopped(task, app_handle) {
+                    break;
+                }
+
+                let mut fallback_config = task.config.clone();
+                fallback_config.extend(overrides.iter().map(|(k, v)| (k.clone(), v.clone())));
+                let fallback = match self.prepare_options(&fallback_config, app_handle) {
+                    Ok(fallback) => fallback,
+                    Err(e) => {
+                        warn!("Task {}: fallback {} has invalid settings: {}", task.id, index, e);
+                        result = Err(e.into());
+                        continue;
+                    }
+                };
+
+                warn!("Task {}: {}, retrying with fallback {}", task.id, error, index);
+                TaskEvent::Fallback {
+                    task_id: task.id.clone(),
+                    from: encoder_label(&options),
+                    to: encoder_label(&fallback),
+                    reason: error,
+                    attempt: Some(index),
+                }.emit(app_handle);
+
+                options = fallback;
+                fallback_used = Some(index);
+                result = self.run_attempt(task, &fallback_config, output_path, &options, None, progress_callback.clone(), app_handle);
+            }
+
+            result
+        });
+
+        let note = match result {
+            Ok(RunEnd::Completed(note)) => note,
+            // Segments from fallback settings would not match the task's own on resume
+            Ok(RunEnd::Stopped(resume)) if keep_progress.load(Ordering::SeqCst) && fallback_used.is_none() => {
+                return Ok(TaskOutcome { paused: Some(resume), ..TaskOutcome::default() });
+            }
+            Ok(RunEnd::Stopped(resume)) => {
+                resume.discard();
+                if keep_progress.load(Ordering::SeqCst) {
+                    return Ok(TaskOutcome { paused: Some(ResumePoint::default()), ..TaskOutcome::default() });
+                }
+                return Err(TaskError::Canceled);
+            }
+            Err(_) if keep_progress.load(Ordering::SeqCst) => {
+                return Ok(TaskOutcome { paused: Some(ResumePoint::default()), ..TaskOutcome::default() });
+            }
+            Err(e) => return Err(TaskError::ProcessingFailed(e.to_string())),
+        };
+
+        // Only a completed task gets its final file name
+        if output_path != &task.output_path {
+            fs::rename(output_path, &task.output_path).map_err(|e| {
+                TaskError::ProcessingFailed(format!(
+                    "Failed to move {} to {}: {}",
+                    output_path, task.output_path, e
+                ))
+            })?;
+        }
+
+        // A file that fails the check stays at its final name so it can be inspected
+        if options.verify_output {
+            self.video_processor
+                .verify_output(input_path, &task.output_path, &options)
+                .map_err(|e| TaskError::ProcessingFailed(e.to_string()))?;
+        }
+
+        // The score is informational, a task whose output cannot be scored still completes
+        let quality = if options.compute_quality {
+            let throttle = Mutex::new(ProgressThrottle::new(progress_interval(app_handle)));
+            let quality_progress = |progress: f32| {
+                if throttle.lock().should_report(progress) {
+                    TaskEvent::Progress {
+                        task_id: task.id.clone(),
+                        progress,
+                        eta_secs: None,
+                        phase: Some(PHASE_QUALITY.to_string()),
+                    }.emit(app_handle);
+                }
+                !stop_token.is_canceled()
+            };
+            match self.video_processor.compare_quality(input_path, &task.output_path, quality_progress) {
+                Ok(quality) => {
+                    TaskEvent::Quality {
+                        task_id: task.id.clone(),
+                        quality: quality.clone(),
+                    }.emit(app_handle);
+                    Some(quality)
+                }
+                // The output is complete, so exiting the app only skips the score
+                Err(_) if stop_token.is_canceled()
+                    && !app_handle.state::<super::TaskManager>().inner().is_shutting_down() =>
+                {
+                    return Err(TaskError::Canceled);
+                }
+                Err(e) => {
+                    warn!("Task {}: could not measure the output quality: {}", task.id, e);
+                    TaskEvent::Warning {
+                        task_id: task.id.clone(),
+                        message: format!("Could not measure the output quality: {}", e),
+                    }.emit(app_handle);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        Ok(TaskOutcome { note, fallback_used, quality, paused: None })
+    }
 
-        // Process task based on type
-        match task.task_type.as_str() {
+    /// Processing options for a task config, validated and with the thread count set
+    fn prepare_options(&self, config: &HashMap<String, String>, app_handle: &AppHandle) -> Result<ProcessingOptions, TaskError> {
+        let mut options = create_processing_options(config)?;
+        options.validate().map_err(|e| TaskError::ProcessingFailed(e.to_string()))?;
+
+        // Share the cores between the tasks that may run at the same time
+        if options.threads.is_none() && auto_thread_limit(app_handle) {
+            let max_concurrent_tasks = app_handle.state::<super::TaskManager>().get_max_concurrent_tasks();
+            options.threads = Some(threads_per_task(max_concurrent_tasks));
+        }
+
+        Ok(options)
+    }
+
+    /// Run the task once with `config` and `options`, continuing from `resume` if possible
+    ///
+    /// A GPU encoder that cannot be opened is retried once with its software equivalent.
+    #[allow(clippy::too_many_arguments)]
+    fn run_attempt(
+        &self,
+        task: &Task,
+        config: &HashMap<String, String>,
+        output_path: &str,
+        options: &ProcessingOptions,
+        resume: Option<ResumePoint>,
+        progress_callback: ProgressCallback,
+        app_handle: &AppHandle,
+    ) -> AppResult<RunEnd> {
+        let result = self.run_task_type(task, config, output_path, options.clone(), resume, progress_callback.clone());
+
+        match result {
+            Err(e) if e.code() == ErrorCode::GpuInitError && options.use_gpu && options.gpu_fallback => {
+                let fallback = self.video_processor.software_fallback(options);
+                let to = fallback.cpu_codec.clone().unwrap_or_else(|| "software encoder".to_string());
+                warn!("Task {}: {}, retrying with {}", task.id, e, to);
+
+                TaskEvent::Fallback {
+                    task_id: task.id.clone(),
+                    from: options.gpu_codec.clone().unwrap_or_default(),
+                    to,
+                    reason: e.to_string(),
+                    attempt: None,
+                }.emit(app_handle);
+
+                self.run_task_type(task, config, output_path, fallback, None, progress_callback)
+            }
+            result => result,
+        }
+    }
+
+    /// Run the video processor operation matching the task type
+    ///
+    /// Only plain conversions are encoded in segments that `resume` can continue; the
+    /// other types start over.
+    fn run_task_type(
+        &self,
+        task: &Task,
+        config: &HashMap<String, String>,
+        output_path: &str,
+        options: ProcessingOptions,
+        resume: Option<ResumePoint>,
+        progress_callback: ProgressCallback,
+    ) -> AppResult<RunEnd> {
+        let input_path = &task.input_path;
+        let progress_callback = move |progress: f32| progress_callback(progress);
+
+        if task.task_type != "convert" || options.convert_if_needed {
+            if let Some(resume) = resume {
+                resume.discard();
+            }
+        }
+
+        let note = match task.task_type.as_str() {
+            "convert" if options.convert_if_needed => {
+                // Copy inputs that already match instead of encoding them again
+                let outcome = self.video_processor.convert_if_needed(
+                    input_path,
+                    output_path,
+                    options,
+                    progress_callback,
+                )?;
+                Ok((outcome == ConvertOutcome::Copied).then(|| ALREADY_COMPLIANT_NOTE.to_string()))
+            },
             "convert" => {
-                // Call convert_video from VideoProcessor
-                self.video_processor.convert_video(
+                // Encode in segments so a pause keeps the work done so far
+                let stopped = self.video_processor.process_video_resumable(
+                    input_path,
+                    output_path,
+                    options,
+                    resume,
+                    progress_callback,
+                )?;
+                return Ok(stopped.map_or(RunEnd::Completed(None), RunEnd::Stopped));
+            },
+            "remux" => {
+                // Stream copy, re-encoding only if the target container needs it
+                self.video_processor.remux_video(
                     input_path,
                     output_path,
                     options,
                     progress_callback,
-                ).map_err(|e| TaskError::ProcessingFailed(e.to_string()))?;
+                ).map(|_| None)
             },
             "split" => {
                 // Get start and end time from config
@@ -100,7 +441,7 @@ impl TaskProcessor {
                     end_time,
                     options,
                     progress_callback,
-                ).map_err(|e| TaskError::ProcessingFailed(e.to_string()))?;
+                ).map(|_| None)
             },
             "edit" => {
                 // Create edit operations from config
@@ -113,7 +454,7 @@ impl TaskProcessor {
                     edit_operations,
                     options,
                     progress_callback,
-                ).map_err(|e| TaskError::ProcessingFailed(e.to_string()))?;
+                ).map(|_| None)
             },
             "sanitize" => {
                 // Create sanitize options from config
@@ -126,28 +467,123 @@ impl TaskProcessor {
                     sanitize_options,
                     options,
                     progress_callback,
-                ).map_err(|e| TaskError::ProcessingFailed(e.to_string()))?;
+                ).map(|_| None)
             },
-            _ => {
-                return Err(TaskError::UnsupportedTaskType(task.task_type.clone()));
-            }
-        }
+            _ => Err(TaskError::UnsupportedTaskType(task.task_type.clone()).into()),
+        };
+
+        note.map(RunEnd::Completed)
+    }
+
+    /// Duration of `input_path` in seconds, `None` if it cannot be probed
+    pub fn input_duration(&self, input_path: &str) -> Option<f64> {
+        self.video_processor
+            .get_video_info(input_path)
+            .ok()
+            .map(|info| info.duration)
+            .filter(|&duration| duration > 0.0)
+    }
+
+    /// Estimate the output size of a task in bytes by probing its input.
+    ///
+    /// See [`VideoProcessor::estimate_output_size`] for the expected accuracy.
+    pub fn estimate_output_size(&self, task: &Task) -> Result<u64, TaskError> {
+        let input_info = self.video_processor
+            .get_video_info(&task.input_path)
+            .map_err(|e| TaskError::ProcessingFailed(e.to_string()))?;
+        let options = create_processing_options(&task.config)?;
 
-        Ok(())
+        Ok(self.video_processor.estimate_output_size(&input_info, &options))
     }
 }
 
+/// Whether the task was canceled or paused, or the app is exiting, so no fallback should start
+fn is_stopped(task: &Task, app_handle: &AppHandle) -> bool {
+    let task_manager = app_handle.state::<super::TaskManager>();
+    let manager = task_manager.inner();
+    manager.is_shutting_down()
+        || manager
+            .get_task(&task.id)
+            .map_or(true, |task| matches!(task.status, TaskStatus::Canceled | TaskStatus::Paused))
+}
+
+/// Emit each distinct FFmpeg warning of a task as a `task-warning` event.
+///
+/// FFmpeg tends to repeat a warning for every frame, so repeats are dropped and at
+/// most `MAX_TASK_WARNINGS` events are sent per task.
+fn task_warning_handler(app_handle: &AppHandle, task_id: &str) -> WarningHandler {
+    let app_handle = app_handle.clone();
+    let task_id = task_id.to_string();
+    let seen = RefCell::new(HashSet::new());
+
+    Box::new(move |message: &str| {
+        let mut seen = seen.borrow_mut();
+        if seen.len() >= MAX_TASK_WARNINGS || !seen.insert(message.to_string()) {
+            return;
+        }
+
+        TaskEvent::Warning {
+            task_id: task_id.clone(),
+            message: message.to_string(),
+        }.emit(&app_handle);
+    })
+}
+
+/// Invalid parameter error for a config value that cannot be used
+fn invalid_config(message: String) -> TaskError {
+    TaskError::ProcessingFailed(VideoError::invalid_param(message).to_string())
+}
+
+/// Parse the config value under `key`, failing instead of ignoring a value that is not a `T`
+fn parse_config_value<T: FromStr>(
+    config: &HashMap<String, String>,
+    key: &str,
+    expected: &str,
+) -> Result<Option<T>, TaskError> {
+    config
+        .get(key)
+        .map(|value| {
+            value
+                .trim()
+                .parse::<T>()
+                .map_err(|_| invalid_config(format!("{} must be {}, got \"{}\"", key, expected, value)))
+        })
+        .transpose()
+}
+
 /// Create ProcessingOptions from config
-fn create_processing_options(config: &HashMap<String, String>) -> Result<ProcessingOptions, TaskError> {
+///
+/// When `output_format` and the codec keys (`cpu_codec`, `gpu_codec`) disagree, the
+/// format wins and the container's default codec is used, with a warning; set
+/// `strict` to "true" to fail the task instead.
+pub fn create_processing_options(config: &HashMap<String, String>) -> Result<ProcessingOptions, TaskError> {
     let mut options = ProcessingOptions {
         output_format: config.get("output_format").cloned().unwrap_or_else(|| "mp4".to_string()),
         output_path: config.get("output_path").cloned().unwrap_or_default(),
         resolution: None,
         bitrate: None,
+        quality: None,
+        two_pass: config.get("two_pass").map_or(false, |v| v == "true"),
         framerate: None,
+        keyframe_interval: config.get("keyframe_interval").and_then(|v| v.parse::<u32>().ok()),
+        scene_cut: config.get("scene_cut").map(|v| v == "true"),
+        pixel_format: config.get("pixel_format").filter(|v| !v.is_empty()).cloned(),
         use_gpu: config.get("use_gpu").map_or(false, |v| v == "true"),
         gpu_codec: config.get("gpu_codec").cloned(),
         cpu_codec: config.get("cpu_codec").cloned(),
+        gpu_fallback: config.get("gpu_fallback").map_or(true, |v| v != "false"),
+        hardware_decode: config.get("hardware_decode").map_or(false, |v| v == "true"),
+        interpolate: config.get("interpolate").map_or(false, |v| v == "true"),
+        threads: config.get("threads").and_then(|v| v.parse::<usize>().ok()).filter(|&n| n > 0),
+        convert_if_needed: config.get("convert_if_needed").map_or(false, |v| v == "true"),
+        reencode: false,
+        verify_output: config.get("verify_output").map_or(false, |v| v == "true"),
+        compute_quality: config.get("compute_quality").map_or(false, |v| v == "true"),
+        gif_fps: config.get("gif_fps").and_then(|v| v.parse::<f32>().ok()),
+        gif_max_width: config.get("gif_max_width").and_then(|v| v.parse::<u32>().ok()),
+        target_size_mb: config.get("target_size_mb").and_then(|v| v.parse::<u64>().ok()),
+        video_stream_index: config.get("video_stream_index").and_then(|v| v.parse::<usize>().ok()),
+        audio_stream_index: config.get("audio_stream_index").and_then(|v| v.parse::<usize>().ok()),
 
         // Time options
         start_time: None,
@@ -156,19 +592,26 @@ fn create_processing_options(config: &HashMap<String, String>) -> Result<Process
         // Edit options
         crop: None,
         rotate: None,
+        auto_orient: config.get("auto_orient").map_or(true, |v| v != "false"),
         flip: None,
         flop: None,
 
         // Sanitize options
         remove_metadata: None,
         blur_regions: None,
+        normalize_audio: config.get("normalize_audio").map_or(false, |v| v == "true"),
         audio_volume: None,
         denoise: None,
+        denoise_strength: None,
+
+        // Branding options
+        watermark: WatermarkConfig::from_config(config),
+        subtitles: SubtitleConfig::from_config(config),
     };
 
     // Parse resolution if provided
     if let (Some(width), Some(height)) = (config.get("width"), config.get("height")) {
-        if let (Ok(w), Ok(h)) = (width.parse::<u32>(), height.parse::<u32>()) {
+        if let (Some(w), Some(h)) = (parse_dimension(width), parse_dimension(height)) {
             options.resolution = Some((w, h));
         }
     }
@@ -180,6 +623,13 @@ fn create_processing_options(config: &HashMap<String, String>) -> Result<Process
         }
     }
 
+    // Parse quality (CRF) if provided
+    if let Some(quality) = config.get("quality") {
+        if let Ok(q) = quality.parse::<u8>() {
+            options.quality = Some(q);
+        }
+    }
+
     // Parse framerate if provided
     if let Some(framerate) = config.get("framerate") {
         if let Ok(f) = framerate.parse::<f32>() {
@@ -187,41 +637,23 @@ fn create_processing_options(config: &HashMap<String, String>) -> Result<Process
         }
     }
 
-    // Parse time options
-    if let Some(start_time) = config.get("start_time") {
-        if let Ok(t) = start_time.parse::<f64>() {
-            options.start_time = Some(t);
-        }
-    }
+    // Parse time options; the range itself is checked by ProcessingOptions::validate
+    options.start_time = parse_config_value(config, "start_time", "a number of seconds")?;
+    options.end_time = parse_config_value(config, "end_time", "a number of seconds")?;
 
-    if let Some(end_time) = config.get("end_time") {
-        if let Ok(t) = end_time.parse::<f64>() {
-            options.end_time = Some(t);
-        }
-    }
-
-    // Parse edit options
+    // Parse edit options, failing instead of silently ignoring them
     if let Some(crop) = config.get("crop") {
         // Format: "x,y,width,height"
-        let parts: Vec<&str> = crop.split(',').collect();
-        if parts.len() == 4 {
-            if let (Ok(x), Ok(y), Ok(w), Ok(h)) = (
-                parts[0].trim().parse::<u32>(),
-                parts[1].trim().parse::<u32>(),
-                parts[2].trim().parse::<u32>(),
-                parts[3].trim().parse::<u32>(),
-            ) {
-                options.crop = Some((x, y, w, h));
-            }
-        }
+        let crop = parse_crop(crop)
+            .map_err(|_| invalid_config(format!("Crop must be \"x,y,width,height\", got \"{}\"", crop)))?;
+        options.crop = Some(crop);
     }
 
     if let Some(rotate) = config.get("rotate") {
-        if let Ok(r) = rotate.parse::<i32>() {
-            // Only allow 90, 180, 270 degrees
-            if r == 90 || r == 180 || r == 270 {
-                options.rotate = Some(r);
-            }
+        match rotate.parse::<i32>() {
+            // The angle itself is checked by ProcessingOptions::validate
+            Ok(r) => options.rotate = Some(r),
+            Err(_) => return Err(invalid_config(format!("Rotation must be a number, got \"{}\"", rotate))),
         }
     }
 
@@ -232,38 +664,37 @@ fn create_processing_options(config: &HashMap<String, String>) -> Result<Process
     options.remove_metadata = config.get("remove_metadata").map(|v| v == "true");
     options.denoise = config.get("denoise").map(|v| v == "true");
 
-    if let Some(volume) = config.get("audio_volume") {
-        if let Ok(v) = volume.parse::<f32>() {
-            options.audio_volume = Some(v);
-        }
-    }
+    options.denoise_strength = parse_config_value(config, "denoise_strength", "a number")?;
+    options.audio_volume = parse_config_value(config, "audio_volume", "a number")?;
 
     // Parse blur regions
     if let Some(blur_regions) = config.get("blur_regions") {
-        // Format: "x1,y1,w1,h1;x2,y2,w2,h2;..."
-        let regions: Vec<(u32, u32, u32, u32)> = blur_regions
+        // Format: "x1,y1,w1,h1;x2,y2,w2,h2;...", a trailing ";" is allowed
+        let regions = blur_regions
             .split(';')
-            .filter_map(|region| {
-                let parts: Vec<&str> = region.split(',').collect();
-                if parts.len() == 4 {
-                    if let (Ok(x), Ok(y), Ok(w), Ok(h)) = (
-                        parts[0].trim().parse::<u32>(),
-                        parts[1].trim().parse::<u32>(),
-                        parts[2].trim().parse::<u32>(),
-                        parts[3].trim().parse::<u32>(),
-                    ) {
-                        return Some((x, y, w, h));
-                    }
-                }
-                None
+            .filter(|region| !region.trim().is_empty())
+            .map(|region| {
+                parse_crop(region).map_err(|_| {
+                    invalid_config(format!("Blur region must be \"x,y,width,height\", got \"{}\"", region))
+                })
             })
-            .collect();
+            .collect::<Result<Vec<_>, _>>()?;
 
         if !regions.is_empty() {
             options.blur_regions = Some(regions);
         }
     }
 
+    // `output_format` takes precedence over the codec keys, which may come from a
+    // preset made for another container. With `strict` the mismatch is left for
+    // `validate` to reject instead.
+    if config.get("strict").map_or(false, |v| v == "true") {
+        return Ok(options);
+    }
+    if let Some(message) = options.reconcile_codec_with_format() {
+        report_warning(&message);
+    }
+
     Ok(options)
 }
 
@@ -273,10 +704,12 @@ async fn update_task_progress(app_handle: &AppHandle, task_id: &str, progress: f
     // The task will be updated by the task manager when it receives the event
 
     // Emit progress event
-    emit_event(app_handle, "task-progress", Some(serde_json::json!({
-        "taskId": task_id,
-        "progress": progress
-    })));
+    TaskEvent::Progress {
+        task_id: task_id.to_string(),
+        progress,
+        eta_secs: None,
+        phase: None,
+    }.emit(app_handle);
 
     Ok(())
 }
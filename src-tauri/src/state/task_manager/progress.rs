@@ -0,0 +1,171 @@
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::utils::error::AppResult;
+use crate::utils::store_helper::{self, CONFIG_STORE_PATH};
+use super::{Task, TaskStatus};
+
+/// Weight of the newest rate sample in the exponential moving average
+const RATE_SMOOTHING: f64 = 0.3;
+
+/// Number of progress samples needed before an ETA is reported
+const MIN_ETA_SAMPLES: usize = 3;
+
+/// Config store key holding the minimum time between `task-progress` events
+const PROGRESS_INTERVAL_KEY: &str = "progress_interval_ms";
+
+/// Minimum time between `task-progress` events unless the user changes it
+pub const DEFAULT_PROGRESS_INTERVAL_MS: u64 = 250;
+
+/// Highest overall progress reported while any task is unfinished
+const MAX_UNFINISHED_PROGRESS: f32 = 99.9;
+
+/// Tracks the progress rate of a running task to estimate the remaining time
+#[derive(Debug, Clone)]
+pub struct ProgressTracker {
+    started_at: Instant,
+    last_sample_at: Instant,
+    last_progress: f32,
+    rate: Option<f64>, // percent per second, smoothed
+    samples: usize,
+}
+
+impl ProgressTracker {
+    /// Start tracking a task at the given progress
+    pub fn new(progress: f32) -> Self {
+        let now = Instant::now();
+        Self {
+            started_at: now,
+            last_sample_at: now,
+            last_progress: progress,
+            rate: None,
+            samples: 0,
+        }
+    }
+
+    /// Seconds since tracking started
+    pub fn elapsed_secs(&self) -> f64 {
+        self.started_at.elapsed().as_secs_f64()
+    }
+
+    /// Record a progress sample and return the estimated seconds remaining.
+    ///
+    /// Returns `None` until enough samples have been collected.
+    pub fn record(&mut self, progress: f32) -> Option<f64> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_sample_at).as_secs_f64();
+        let delta = f64::from(progress - self.last_progress);
+
+        // Ignore samples that arrive too quickly to give a meaningful rate
+        if elapsed > 0.0 && delta >= 0.0 {
+            let sample_rate = delta / elapsed;
+            self.rate = Some(match self.rate {
+                Some(rate) => RATE_SMOOTHING * sample_rate + (1.0 - RATE_SMOOTHING) * rate,
+                None => sample_rate,
+            });
+            self.samples += 1;
+            self.last_sample_at = now;
+            self.last_progress = progress;
+        }
+
+        self.eta_secs()
+    }
+
+    /// Estimated seconds remaining, if known
+    pub fn eta_secs(&self) -> Option<f64> {
+        if self.samples < MIN_ETA_SAMPLES {
+            return None;
+        }
+
+        match self.rate {
+            Some(rate) if rate > 0.0 => Some(f64::from(100.0 - self.last_progress).max(0.0) / rate),
+            _ => None,
+        }
+    }
+}
+
+/// Limits how often progress is reported; 100% always gets through
+#[derive(Debug, Clone)]
+pub struct ProgressThrottle {
+    interval: Duration,
+    last_reported_at: Option<Instant>,
+}
+
+impl ProgressThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_reported_at: None,
+        }
+    }
+
+    /// Whether this sample should be reported, given the time of the last report
+    pub fn should_report(&mut self, progress: f32) -> bool {
+        let now = Instant::now();
+        let due = progress >= 100.0
+            || self
+                .last_reported_at
+                .map_or(true, |last| now.duration_since(last) >= self.interval);
+
+        if due {
+            self.last_reported_at = Some(now);
+        }
+        due
+    }
+}
+
+/// Overall progress of `tasks`, each weighted by the duration of its input.
+///
+/// Completed and failed tasks count as 100 and canceled ones are left out. Tasks
+/// whose duration is unknown weigh as much as the average known one, so a list
+/// without any durations gets a plain average. Reaches 100 only once every task
+/// is finished.
+pub fn weighted_progress<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> f32 {
+    let entries: Vec<(f64, Option<f64>, bool)> = tasks
+        .into_iter()
+        .filter(|task| task.status != TaskStatus::Canceled)
+        .map(|task| {
+            let finished = matches!(task.status, TaskStatus::Completed | TaskStatus::Failed);
+            let progress = if finished { 100.0 } else { f64::from(task.progress.clamp(0.0, 100.0)) };
+            (progress, task.input_duration.filter(|&duration| duration > 0.0), finished)
+        })
+        .collect();
+    if entries.is_empty() {
+        return 0.0;
+    }
+
+    let known: Vec<f64> = entries.iter().filter_map(|&(_, duration, _)| duration).collect();
+    let fallback_weight = if known.is_empty() {
+        1.0
+    } else {
+        known.iter().sum::<f64>() / known.len() as f64
+    };
+
+    let (weighted_sum, total_weight) = entries
+        .iter()
+        .fold((0.0, 0.0), |(sum, total), &(progress, duration, _)| {
+            let weight = duration.unwrap_or(fallback_weight);
+            (sum + progress * weight, total + weight)
+        });
+    let progress = (weighted_sum / total_weight) as f32;
+
+    if entries.iter().all(|&(_, _, finished)| finished) {
+        100.0
+    } else {
+        progress.min(MAX_UNFINISHED_PROGRESS)
+    }
+}
+
+/// Minimum time between `task-progress` events, from the config store
+pub fn progress_interval(app_handle: &AppHandle) -> Duration {
+    let interval_ms = store_helper::get_value::<_, u64>(app_handle, CONFIG_STORE_PATH, PROGRESS_INTERVAL_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_PROGRESS_INTERVAL_MS);
+    Duration::from_millis(interval_ms)
+}
+
+/// Change the minimum time between `task-progress` events; used by tasks started afterwards
+pub fn set_progress_interval(app_handle: &AppHandle, interval_ms: u64) -> AppResult<()> {
+    store_helper::set_value(app_handle, CONFIG_STORE_PATH, PROGRESS_INTERVAL_KEY, &interval_ms)
+}
@@ -0,0 +1,67 @@
+use log::{info, warn};
+use serde_json::Value;
+
+use super::{Task, DEFAULT_TASK_PRIORITY};
+
+/// Version of the task store layout written by `save_state`.
+///
+/// 1. Initial layout, stores without a `schema_version` key
+/// 2. Tasks gained `priority`, `max_retries` and `partial_output_path`
+pub const TASKS_SCHEMA_VERSION: u32 = 2;
+
+/// Upgrade stored tasks from `version` to the current layout and deserialize them.
+///
+/// Tasks that still fail to deserialize are skipped with a warning so one damaged
+/// entry does not drop the whole queue.
+pub fn migrate_tasks(version: u32, tasks: Vec<Value>) -> Vec<Task> {
+    if version > TASKS_SCHEMA_VERSION {
+        warn!(
+            "Task store has schema version {}, newer than {}; loading what is understood",
+            version, TASKS_SCHEMA_VERSION
+        );
+    }
+
+    tasks
+        .into_iter()
+        .filter_map(|mut task| {
+            if version < 2 {
+                migrate_v1_to_v2(&mut task);
+            }
+
+            match serde_json::from_value::<Task>(task) {
+                Ok(task) => Some(task),
+                Err(e) => {
+                    warn!("Skipping task that could not be loaded: {}", e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Add the fields introduced in version 2 with their defaults
+fn migrate_v1_to_v2(task: &mut Value) {
+    let fields = match task.as_object_mut() {
+        Some(fields) => fields,
+        None => return,
+    };
+
+    let defaults = [
+        ("priority", Value::from(DEFAULT_TASK_PRIORITY)),
+        ("max_retries", Value::from(0)),
+        ("partial_output_path", Value::Null),
+    ];
+
+    let mut added = Vec::new();
+    for (name, default) in defaults {
+        if !fields.contains_key(name) {
+            fields.insert(name.to_string(), default);
+            added.push(name);
+        }
+    }
+
+    if !added.is_empty() {
+        let id = fields.get("id").and_then(Value::as_str).unwrap_or("<unknown>");
+        info!("Migrated task {} to schema version 2, added {}", id, added.join(", "));
+    }
+}
@@ -0,0 +1,27 @@
+use std::thread;
+use tauri::AppHandle;
+
+use crate::utils::error::AppResult;
+use crate::utils::store_helper::{self, CONFIG_STORE_PATH};
+
+/// Config store key enabling the automatic per-task thread limit
+const AUTO_THREAD_LIMIT_KEY: &str = "auto_thread_limit";
+
+/// Whether tasks without `threads` share the CPU cores between concurrent tasks
+pub fn auto_thread_limit(app_handle: &AppHandle) -> bool {
+    store_helper::get_value::<_, bool>(app_handle, CONFIG_STORE_PATH, AUTO_THREAD_LIMIT_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or(false)
+}
+
+/// Turn the automatic per-task thread limit on or off; used by tasks started afterwards
+pub fn set_auto_thread_limit(app_handle: &AppHandle, enabled: bool) -> AppResult<()> {
+    store_helper::set_value(app_handle, CONFIG_STORE_PATH, AUTO_THREAD_LIMIT_KEY, &enabled)
+}
+
+/// Threads each task gets when the available cores are split between `max_concurrent_tasks`
+pub fn threads_per_task(max_concurrent_tasks: usize) -> usize {
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    (cores / max_concurrent_tasks.max(1)).max(1)
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use log::{info, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+use crate::state::task_manager::{
+    is_partial_output_path, OverwritePolicy, TaskEvent, TaskManager, DEFAULT_TASK_PRIORITY, TASK_TYPES,
+};
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use crate::utils::media;
+
+/// How long a file's size must stay unchanged before it is imported
+const STABLE_DURATION: Duration = Duration::from_secs(2);
+
+/// How often pending files are checked for a stable size
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn default_task_type() -> String {
+    "convert".to_string()
+}
+
+/// Task created for every new file in a watched folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchTaskTemplate {
+    #[serde(default = "default_task_type")]
+    pub task_type: String,
+    /// Output container extension, e.g. "mp4"
+    pub output_format: String,
+    /// Folder for the outputs, the watched folder if unset
+    pub output_dir: Option<String>,
+    #[serde(default)]
+    pub config: HashMap<String, String>,
+    /// File extensions to import (without the dot), all recognized video extensions if empty
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    pub priority: Option<u8>,
+    /// Defaults to `Rename` so existing files are never replaced
+    pub overwrite_policy: Option<OverwritePolicy>,
+    /// Start the queue as soon as a task is created
+    #[serde(default)]
+    pub auto_start: bool,
+}
+
+impl WatchTaskTemplate {
+    /// Whether `path` has one of the extensions this watch imports
+    ///
+    /// Partial outputs and resume segments of running tasks are never imported.
+    fn matches(&self, path: &Path) -> bool {
+        if is_partial_output_path(path) {
+            return false;
+        }
+
+        if self.extensions.is_empty() {
+            return media::has_video_extension(path);
+        }
+
+        let Some(extension) = path.extension().map(|e| e.to_string_lossy().to_lowercase()) else {
+            return false;
+        };
+        self.extensions
+            .iter()
+            .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(&extension))
+    }
+
+    /// Output path for `input`, named after the input file
+    fn output_path_for(&self, input: &Path, watched_dir: &Path) -> PathBuf {
+        let dir = self.output_dir.as_deref().map(Path::new).unwrap_or(watched_dir);
+        let stem = input.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        dir.join(format!("{}.{}", stem, self.output_format.trim_start_matches('.')))
+    }
+}
+
+/// A folder being watched, as reported to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchInfo {
+    pub id: String,
+    pub path: String,
+    pub template: WatchTaskTemplate,
+}
+
+struct ActiveWatch {
+    info: WatchInfo,
+    // Dropping the watcher closes the event channel, which ends the debounce thread
+    _watcher: RecommendedWatcher,
+}
+
+/// Keeps the watched folders alive for the lifetime of the app
+#[derive(Default)]
+pub struct WatchManager {
+    watches: Mutex<HashMap<String, ActiveWatch>>,
+}
+
+impl WatchManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start watching `path` and create a task from `template` for each new matching file
+    pub fn watch_directory(
+        &self,
+        app_handle: &AppHandle,
+        path: &str,
+        template: WatchTaskTemplate,
+    ) -> AppResult<WatchInfo> {
+        let dir = Path::new(path);
+        if !dir.is_dir() {
+            return Err(AppError::validation_error(
+                format!("Not a directory: {}", path),
+                ErrorCode::DirectoryError,
+                Some("Choose an existing folder to watch".to_string()),
+            ));
+        }
+
+        if template.output_format.trim_start_matches('.').is_empty() {
+            return Err(AppError::validation_error(
+                "Watch template has no output format".to_string(),
+                ErrorCode::InvalidArgument,
+                None,
+            ));
+        }
+
+        if !TASK_TYPES.contains(&template.task_type.as_str()) {
+            return Err(AppError::validation_error(
+                format!("Invalid task type: {}", template.task_type),
+                ErrorCode::InvalidArgument,
+                Some(format!("Task type must be one of: {}", TASK_TYPES.join(", "))),
+            ));
+        }
+
+        // Outputs written next to the inputs would be imported again as new inputs
+        let output_dir = template.output_dir.as_deref().map(Path::new).unwrap_or(dir);
+        let output_sample = format!("output.{}", template.output_format.trim_start_matches('.'));
+        if output_dir == dir && template.matches(Path::new(&output_sample)) {
+            return Err(AppError::validation_error(
+                format!(
+                    "Outputs in .{} format would be written into the watched folder and imported again",
+                    template.output_format.trim_start_matches('.')
+                ),
+                ErrorCode::InvalidArgument,
+                Some("Choose a separate output folder or limit the imported extensions".to_string()),
+            ));
+        }
+
+        let mut watches = self.watches.lock();
+        if watches.values().any(|watch| Path::new(&watch.info.path) == dir) {
+            return Err(AppError::validation_error(
+                format!("Folder is already watched: {}", path),
+                ErrorCode::InvalidArgument,
+                Some("Stop the existing watch before adding a new one".to_string()),
+            ));
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+            Ok(event) => {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    for path in event.paths {
+                        let _ = sender.send(path);
+                    }
+                }
+            }
+            Err(e) => warn!("File watcher error: {}", e),
+        })
+        .map_err(|e| {
+            AppError::other_error(
+                format!("Failed to create file watcher: {}", e),
+                ErrorCode::DirectoryError,
+                None,
+            )
+        })?;
+
+        watcher.watch(dir, RecursiveMode::NonRecursive).map_err(|e| {
+            AppError::other_error(
+                format!("Failed to watch {}: {}", path, e),
+                ErrorCode::DirectoryError,
+                None,
+            )
+        })?;
+
+        let info = WatchInfo {
+            id: Uuid::new_v4().to_string(),
+            path: path.to_string(),
+            template,
+        };
+
+        let thread_app = app_handle.clone();
+        let thread_info = info.clone();
+        thread::spawn(move || import_stable_files(thread_app, receiver, thread_info));
+
+        info!("Watching {} for new videos (watch {})", path, info.id);
+        watches.insert(info.id.clone(), ActiveWatch { info: info.clone(), _watcher: watcher });
+
+        Ok(info)
+    }
+
+    /// Stop a watch started by `watch_directory`
+    pub fn stop_watch(&self, watch_id: &str) -> AppResult<()> {
+        match self.watches.lock().remove(watch_id) {
+            Some(watch) => {
+                info!("Stopped watching {}", watch.info.path);
+                Ok(())
+            }
+            None => Err(AppError::validation_error(
+                format!("Watch {} not found", watch_id),
+                ErrorCode::InvalidArgument,
+                None,
+            )),
+        }
+    }
+
+    /// All active watches
+    pub fn list_watches(&self) -> Vec<WatchInfo> {
+        self.watches.lock().values().map(|watch| watch.info.clone()).collect()
+    }
+}
+
+/// Collect changed paths and import each one once its size has stopped changing.
+///
+/// Runs until the watcher is dropped.
+fn import_stable_files(app_handle: AppHandle, receiver: Receiver<PathBuf>, watch: WatchInfo) {
+    // Last seen size of each pending file and when it last changed
+    let mut pending: HashMap<PathBuf, (u64, Instant)> = HashMap::new();
+
+    loop {
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(path) => {
+                for path in std::iter::once(path).chain(receiver.try_iter()) {
+                    if watch.template.matches(&path) {
+                        pending.entry(path).or_insert((0, Instant::now()));
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        let now = Instant::now();
+        pending.retain(|path, (size, changed_at)| {
+            // Removed or renamed before it settled
+            let Ok(metadata) = fs::metadata(path) else {
+                return false;
+            };
+
+            if metadata.len() != *size {
+                *size = metadata.len();
+                *changed_at = now;
+                return true;
+            }
+
+            if *size == 0 || now.duration_since(*changed_at) < STABLE_DURATION {
+                return true;
+            }
+
+            import_file(&app_handle, &watch, path);
+            false
+        });
+    }
+}
+
+/// Create a task for a file that finished arriving in a watched folder
+fn import_file(app_handle: &AppHandle, watch: &WatchInfo, input: &Path) {
+    let manager = app_handle.state::<TaskManager>();
+    let manager = manager.inner();
+
+    // Skip files that already belong to a task, including outputs written into the folder
+    // and the partial files and segments they are written to first
+    let known = manager.get_all_tasks().iter().any(|task| {
+        Path::new(&task.input_path) == input
+            || Path::new(&task.output_path) == input
+            || task.partial_output_path.as_deref().map(Path::new) == Some(input)
+            || task
+                .resume
+                .as_ref()
+                .is_some_and(|resume| resume.segments.iter().any(|segment| Path::new(segment) == input))
+    });
+    if known {
+        return;
+    }
+
+    let template = &watch.template;
+    let output = template.output_path_for(input, Path::new(&watch.path));
+
+    let result = manager
+        .create_task(
+            input.to_string_lossy().into_owned(),
+            output.to_string_lossy().into_owned(),
+            template.task_type.clone(),
+            template.config.clone(),
+            template.priority.unwrap_or(DEFAULT_TASK_PRIORITY),
+            template.overwrite_policy.unwrap_or(OverwritePolicy::Rename),
+        )
+        .and_then(|task| manager.save_state(app_handle).map(|_| task));
+
+    match result {
+        Ok(task) => {
+            info!("Created task {} for watched file {}", task.id, input.display());
+            TaskEvent::Updated { task }.emit(app_handle);
+
+            if template.auto_start {
+                if let Err(e) = manager.process_next_tasks(app_handle) {
+                    warn!("Failed to start tasks for watched file {}: {}", input.display(), e);
+                }
+            }
+        }
+        Err(e) => warn!("Failed to create task for watched file {}: {}", input.display(), e),
+    }
+}
@@ -1,16 +1,27 @@
+use std::collections::HashMap;
+use std::path::Path;
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter};
 
-use crate::utils::gpu_detector;
+use crate::services::video_processor::{VideoProcessor, DEFAULT_MAX_UPSCALE_FACTOR, DEFAULT_PROBE_TIMEOUT_SECS};
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+use crate::utils::gpu_detector::{self, GpuInfo};
+use crate::utils::store_helper::{self, CONFIG_STORE_PATH};
 
-/// GPU information structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GpuInfo {
-    pub name: String,
-    pub vendor: String,
-    pub is_available: bool,
-    pub supported_codecs: Vec<String>,
-}
+/// Config store key holding the GPU chosen with `set_gpu`
+const SELECTED_GPU_KEY: &str = "selected_gpu_index";
+
+/// Config store key holding the codec chosen for each output container
+const DEFAULT_CODECS_KEY: &str = "default_codecs";
+
+/// Config store key holding the probe timeout in seconds
+const PROBE_TIMEOUT_KEY: &str = "probe_timeout_secs";
+
+/// Config store key holding how many times the source size an output may be
+const MAX_UPSCALE_FACTOR_KEY: &str = "max_upscale_factor";
+
+/// FFmpeg DLLs the build script copied next to the executable, separated by ';'
+const FFMPEG_DLLS: Option<&str> = option_env!("VIDKIT_FFMPEG_DLLS");
 
 /// Application information including GPU and FFmpeg version
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,10 +33,100 @@ pub struct AppInfo {
     pub selected_gpu_index: i32, // -1 for CPU, 0+ for GPU
 }
 
+/// Codecs tried for each output container, best first; containers not listed use h264
+const FORMAT_CODEC_PREFERENCE: &[(&str, &[&str])] = &[
+    ("mp4", &["h264", "hevc", "av1"]),
+    ("m4v", &["h264", "hevc", "av1"]),
+    ("mov", &["h264", "hevc"]),
+    ("mkv", &["h264", "hevc", "av1"]),
+    ("webm", &["av1"]),
+    ("avi", &["h264"]),
+    ("gif", &[]),
+];
+
+/// Format a libav `AV_VERSION_INT` value as "major.minor.micro"
+fn format_lib_version(version: u32) -> String {
+    format!("{}.{}.{}", version >> 16, (version >> 8) & 0xff, version & 0xff)
+}
+
+/// Get the version of the linked FFmpeg libraries
+///
+/// Returns `None` if FFmpeg could not be initialized.
+pub fn get_ffmpeg_version() -> Option<String> {
+    if let Err(e) = check_ffmpeg() {
+        log::error!("{}", e);
+        return None;
+    }
+
+    Some(format!(
+        "FFmpeg libavcodec {}, libavformat {}, libavutil {}",
+        format_lib_version(ffmpeg_next::codec::version()),
+        format_lib_version(ffmpeg_next::format::version()),
+        format_lib_version(ffmpeg_next::util::version()),
+    ))
+}
+
+/// FFmpeg DLLs missing from the executable's directory
+///
+/// The DLLs are delay-loaded (see build.rs), so the app starts without them but the
+/// first FFmpeg call would crash. Always empty where FFmpeg is not loaded from DLLs.
+pub fn missing_ffmpeg_libraries() -> Vec<String> {
+    let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) else {
+        return Vec::new();
+    };
+
+    FFMPEG_DLLS
+        .unwrap_or_default()
+        .split(';')
+        .filter(|name| !name.is_empty() && !dir.join(name).exists())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Check that the FFmpeg libraries can be loaded and initialized
+///
+/// On Windows the avcodec/avformat/avutil DLLs are expected next to the executable,
+/// where the build script copies them. They are looked for before FFmpeg is called,
+/// since calling into a missing delay-loaded DLL crashes. The error names that directory.
+pub fn check_ffmpeg() -> AppResult<()> {
+    let location = || {
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.display().to_string()))
+            .unwrap_or_else(|| "the application directory".to_string())
+    };
+
+    let missing = missing_ffmpeg_libraries();
+    if !missing.is_empty() {
+        return Err(AppError::ffmpeg_error(
+            format!("FFmpeg libraries are missing: {}", missing.join(", ")),
+            ErrorCode::FFmpegInitError,
+            Some(format!(
+                "Video processing is unavailable. Copy the FFmpeg libraries into {} and start the application again",
+                location()
+            )),
+        ));
+    }
+
+    ffmpeg_next::init().map_err(|e| {
+        AppError::ffmpeg_error(
+            format!("FFmpeg libraries are missing or could not be loaded: {}", e),
+            ErrorCode::FFmpegInitError,
+            Some(format!(
+                "Video processing is unavailable. Expected the FFmpeg libraries (avcodec, avformat, avutil, swscale, swresample) in {}",
+                location()
+            )),
+        )
+    })
+}
+
 /// Get application information
-pub fn get_app_info() -> Result<AppInfo, String> {
+///
+/// `selected_gpu_index` is the GPU saved by `set_gpu`. Without a saved choice, or if the
+/// saved GPU is no longer available, the first available GPU is selected.
+pub fn get_app_info(app_handle: &AppHandle) -> Result<AppInfo, String> {
     // Get FFmpeg version
-    let ffmpeg_version = Some("FFmpeg 7.1.0".to_string()); // Replace with actual function
+    let ffmpeg_version = get_ffmpeg_version();
     
     // Check GPU availability
     let gpu_list = match gpu_detector::check_gpu_availability() {
@@ -34,22 +135,24 @@ pub fn get_app_info() -> Result<AppInfo, String> {
     };
     
     // Default to first available GPU if any, otherwise use CPU
-    let selected_gpu_index = if let Some((i, _)) = gpu_list.gpus.iter().enumerate().find(|(_, g)| g.is_available) {
+    let default_gpu_index = if let Some((i, _)) = gpu_list.gpus.iter().enumerate().find(|(_, g)| g.is_available) {
         i as i32
     } else {
         -1 // No available GPU, use CPU
     };
+
+    // Keep the user's choice while it is still valid (CPU is always valid)
+    let selected_gpu_index = match saved_gpu_index(app_handle) {
+        Some(-1) => -1,
+        Some(index) if gpu_list.gpus.get(index as usize).map_or(false, |g| g.is_available) => index,
+        Some(index) => {
+            log::warn!("Saved GPU {} is not available, using GPU {}", index, default_gpu_index);
+            default_gpu_index
+        }
+        None => default_gpu_index,
+    };
     
-    // Convert GPU info
-    let gpus: Vec<GpuInfo> = gpu_list.gpus
-        .into_iter()
-        .map(|gpu| GpuInfo {
-            name: gpu.name,
-            vendor: gpu.vendor,
-            is_available: gpu.is_available,
-            supported_codecs: gpu.supported_codecs,
-        })
-        .collect();
+    let gpus = gpu_list.gpus;
     
     // Create AppInfo
     let app_info = AppInfo {
@@ -63,7 +166,15 @@ pub fn get_app_info() -> Result<AppInfo, String> {
     Ok(app_info)
 }
 
-/// Set selected GPU index
+/// GPU index saved by `set_gpu`, `None` if nothing was saved yet
+fn saved_gpu_index(app_handle: &AppHandle) -> Option<i32> {
+    store_helper::get_value::<_, i32>(app_handle, CONFIG_STORE_PATH, SELECTED_GPU_KEY)
+        .ok()
+        .flatten()
+        .filter(|&index| index >= -1)
+}
+
+/// Set selected GPU index and save it for later sessions
 pub fn set_gpu(gpu_index: i32, app_handle: &AppHandle) -> Result<(), String> {
     // Get GPU list
     let gpu_list = match gpu_detector::check_gpu_availability() {
@@ -76,13 +187,17 @@ pub fn set_gpu(gpu_index: i32, app_handle: &AppHandle) -> Result<(), String> {
         return Err(format!("Invalid GPU index: {}", gpu_index));
     }
     
+    // Remember the choice across calls and restarts
+    store_helper::set_value(app_handle, CONFIG_STORE_PATH, SELECTED_GPU_KEY, &gpu_index)
+        .map_err(|e| format!("Failed to save GPU selection: {}", e))?;
+
     // Get updated app info with new GPU index
-    let mut app_info = match get_app_info() {
+    let mut app_info = match get_app_info(app_handle) {
         Ok(info) => info,
         Err(e) => return Err(format!("Failed to get app info: {}", e)),
     };
     
-    // Update selected GPU index
+    // Report the chosen index even if the GPU is currently unavailable
     app_info.selected_gpu_index = gpu_index;
     
     // Emit app-info-changed event
@@ -90,3 +205,111 @@ pub fn set_gpu(gpu_index: i32, app_handle: &AppHandle) -> Result<(), String> {
     
     Ok(())
 }
+
+/// Hardware encoder to use for `format` on the GPU at `gpu_index`
+///
+/// Picks the first codec of the container's preference list the GPU can encode,
+/// e.g. "h264_nvenc" for mp4 on an NVIDIA card. Returns `None` when the CPU
+/// should encode: index -1, an unknown or unavailable GPU, or no suitable encoder.
+pub fn recommended_codec(format: &str, gpu_index: i32) -> Option<String> {
+    let index = usize::try_from(gpu_index).ok()?;
+    let gpu_list = gpu_detector::check_gpu_availability().ok()?;
+    let gpu = gpu_list.gpus.get(index).filter(|gpu| gpu.is_available)?;
+
+    let format = format.trim_start_matches('.').to_lowercase();
+    let preference = FORMAT_CODEC_PREFERENCE
+        .iter()
+        .find(|(name, _)| *name == format)
+        .map_or(&["h264"][..], |(_, codecs)| codecs);
+
+    preference.iter().find_map(|codec| {
+        let from_capabilities = gpu
+            .capabilities
+            .iter()
+            .find(|capability| capability.codec == *codec && capability.can_encode)
+            .and_then(|capability| capability.encoder.clone());
+
+        // Older detection results only list encoder names
+        from_capabilities.or_else(|| {
+            let prefix = format!("{}_", codec);
+            gpu.supported_codecs.iter().find(|name| name.starts_with(&prefix)).cloned()
+        })
+    })
+}
+
+/// Codec chosen for each output container, e.g. {"mp4": "hevc"}; empty if none were saved
+pub fn default_codecs(app_handle: &AppHandle) -> HashMap<String, String> {
+    store_helper::get_value::<_, HashMap<String, String>>(app_handle, CONFIG_STORE_PATH, DEFAULT_CODECS_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Check and apply the codec chosen for each output container, then save it
+///
+/// Used by tasks that name no codec; an unknown codec or one the container cannot
+/// hold is rejected and nothing is saved.
+pub fn set_default_codecs(
+    app_handle: &AppHandle,
+    processor: &VideoProcessor,
+    codecs: HashMap<String, String>,
+) -> AppResult<()> {
+    let codecs: HashMap<String, String> = codecs
+        .into_iter()
+        .filter(|(_, codec)| !codec.trim().is_empty())
+        .collect();
+
+    processor
+        .set_default_codecs(&codecs)
+        .map_err(|e| AppError::validation_error(e.to_string(), ErrorCode::InvalidArgument, None))?;
+
+    store_helper::set_value(app_handle, CONFIG_STORE_PATH, DEFAULT_CODECS_KEY, &codecs)
+}
+
+/// Apply the default codecs saved in a previous session
+pub fn restore_default_codecs(app_handle: &AppHandle, processor: &VideoProcessor) {
+    let codecs = default_codecs(app_handle);
+    if let Err(e) = processor.set_default_codecs(&codecs) {
+        log::warn!("Ignoring saved default codecs: {}", e);
+    }
+}
+
+/// Seconds a probe may take before it is abandoned, 0 for no limit
+pub fn probe_timeout_secs(app_handle: &AppHandle) -> u64 {
+    store_helper::get_value::<_, u64>(app_handle, CONFIG_STORE_PATH, PROBE_TIMEOUT_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_PROBE_TIMEOUT_SECS)
+}
+
+/// Apply and save the probe timeout
+pub fn set_probe_timeout(app_handle: &AppHandle, processor: &VideoProcessor, timeout_secs: u64) -> AppResult<()> {
+    processor.set_probe_timeout(timeout_secs);
+    store_helper::set_value(app_handle, CONFIG_STORE_PATH, PROBE_TIMEOUT_KEY, &timeout_secs)
+}
+
+/// Apply the probe timeout saved in a previous session
+pub fn restore_probe_timeout(app_handle: &AppHandle, processor: &VideoProcessor) {
+    processor.set_probe_timeout(probe_timeout_secs(app_handle));
+}
+
+/// How many times larger than the source an output may be, 1.0 for no upscaling
+pub fn max_upscale_factor(app_handle: &AppHandle) -> f64 {
+    store_helper::get_value::<_, f64>(app_handle, CONFIG_STORE_PATH, MAX_UPSCALE_FACTOR_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_MAX_UPSCALE_FACTOR)
+}
+
+/// Check, apply and save the upscale limit; an invalid factor is not saved
+pub fn set_max_upscale_factor(app_handle: &AppHandle, processor: &VideoProcessor, factor: f64) -> AppResult<()> {
+    processor.set_max_upscale_factor(factor)?;
+    store_helper::set_value(app_handle, CONFIG_STORE_PATH, MAX_UPSCALE_FACTOR_KEY, &factor)
+}
+
+/// Apply the upscale limit saved in a previous session
+pub fn restore_max_upscale_factor(app_handle: &AppHandle, processor: &VideoProcessor) {
+    if let Err(e) = processor.set_max_upscale_factor(max_upscale_factor(app_handle)) {
+        log::warn!("Ignoring saved upscale limit: {}", e);
+    }
+}
@@ -35,6 +35,8 @@ pub enum ErrorCode {
     VideoInfoError = 5000,
     VideoProcessingFailed = 5001,
     InvalidVideoFormat = 5002,
+    ProbeTimedOut = 5003,
+    ProbeCanceled = 5004,
 
     // GPU related errors (6000-6999)
     GpuNotAvailable = 6000,
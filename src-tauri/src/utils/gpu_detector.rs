@@ -2,37 +2,199 @@ use gfx_backend_vulkan as back;
 use gfx_hal::Instance;
 use serde::{Deserialize, Serialize};
 use std::process::Command;
+use std::sync::Mutex;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuInfo {
     pub name: String,
     pub vendor: String,
     pub is_available: bool,
+    /// Hardware encoders and filters found in FFmpeg, kept for older frontends
     pub supported_codecs: Vec<String>,
+    /// Encode/decode support per codec
+    #[serde(default)]
+    pub capabilities: Vec<GpuCodecCapability>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// What a GPU can do with one codec through FFmpeg
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuCodecCapability {
+    /// Codec name, e.g. "h264", "hevc", "av1"
+    pub codec: String,
+    /// FFmpeg encoder name, set if FFmpeg was built with it
+    pub encoder: Option<String>,
+    /// FFmpeg decoder name, set if FFmpeg was built with it
+    pub decoder: Option<String>,
+    pub can_encode: bool,
+    pub can_decode: bool,
+    /// Largest frame the hardware encoder accepts, `None` if not known for the vendor
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuList {
     pub gpus: Vec<GpuInfo>,
 }
 
+/// Cached detection result, computed on first use
+static GPU_CACHE: Mutex<Option<GpuList>> = Mutex::new(None);
+
+/// Hardware encoders (and filters) probed for each GPU vendor
+const VENDOR_CODECS: &[(&str, &[&str])] = &[
+    ("NVIDIA", &["h264_nvenc", "hevc_nvenc", "av1_nvenc", "scale_cuda"]),
+    ("Intel", &["h264_qsv", "av1_qsv"]),
+    ("AMD", &["h264_amf", "av1_amf"]),
+];
+
+/// Codec, encoder, decoder and the largest frame the encoder accepts
+type VendorCodec = (&'static str, &'static str, Option<&'static str>, Option<(u32, u32)>);
+
+/// Codecs probed for `capabilities`; AMD decoding goes through hwaccels, not decoders
+const VENDOR_CAPABILITIES: &[(&str, &[VendorCodec])] = &[
+    ("NVIDIA", &[
+        ("h264", "h264_nvenc", Some("h264_cuvid"), Some((4096, 4096))),
+        ("hevc", "hevc_nvenc", Some("hevc_cuvid"), Some((8192, 8192))),
+        ("av1", "av1_nvenc", Some("av1_cuvid"), Some((8192, 8192))),
+    ]),
+    ("Intel", &[
+        ("h264", "h264_qsv", Some("h264_qsv"), None),
+        ("hevc", "hevc_qsv", Some("hevc_qsv"), None),
+        ("av1", "av1_qsv", Some("av1_qsv"), None),
+    ]),
+    ("AMD", &[
+        ("h264", "h264_amf", None, None),
+        ("hevc", "hevc_amf", None, None),
+        ("av1", "av1_amf", None, None),
+    ]),
+];
+
+/// Entry returned when no usable GPU is found
+fn cpu_only() -> GpuInfo {
+    GpuInfo {
+        name: "CPU Only".to_string(),
+        vendor: "None".to_string(),
+        is_available: false,
+        supported_codecs: vec![],
+        capabilities: vec![],
+    }
+}
+
+/// Hardware codecs available in FFmpeg for the given vendor
+fn vendor_codecs(vendor: &str, encoders: &str) -> Vec<String> {
+    VENDOR_CODECS
+        .iter()
+        .filter(|(name, _)| *name == vendor)
+        .flat_map(|(_, codecs)| codecs.iter())
+        .filter(|codec| encoders.contains(*codec))
+        .map(|codec| codec.to_string())
+        .collect()
+}
+
+/// Encode/decode support of the vendor's codecs, skipping codecs FFmpeg has neither for
+fn vendor_capabilities(vendor: &str, encoders: &str, decoders: &str) -> Vec<GpuCodecCapability> {
+    VENDOR_CAPABILITIES
+        .iter()
+        .filter(|(name, _)| *name == vendor)
+        .flat_map(|(_, codecs)| codecs.iter())
+        .filter_map(|&(codec, encoder, decoder, max_size)| {
+            let encoder = Some(encoder).filter(|name| encoders.contains(name));
+            let decoder = decoder.filter(|name| decoders.contains(name));
+            if encoder.is_none() && decoder.is_none() {
+                return None;
+            }
+
+            let max_size = max_size.filter(|_| encoder.is_some());
+            Some(GpuCodecCapability {
+                codec: codec.to_string(),
+                can_encode: encoder.is_some(),
+                can_decode: decoder.is_some(),
+                encoder: encoder.map(str::to_string),
+                decoder: decoder.map(str::to_string),
+                max_width: max_size.map(|(width, _)| width),
+                max_height: max_size.map(|(_, height)| height),
+            })
+        })
+        .collect()
+}
+
+/// Detect GPUs from the hardware encoders FFmpeg provides.
+///
+/// Used when no Vulkan instance can be created (DirectX/Metal only machines,
+/// headless servers); one entry is reported per vendor with working encoders.
+fn detect_gpus_from_encoders(encoders: &str, decoders: &str) -> GpuList {
+    let gpus: Vec<GpuInfo> = VENDOR_CODECS
+        .iter()
+        .filter_map(|(vendor, _)| {
+            let supported_codecs = vendor_codecs(vendor, encoders);
+            if supported_codecs.is_empty() {
+                return None;
+            }
+
+            Some(GpuInfo {
+                name: format!("{} GPU", vendor),
+                vendor: vendor.to_string(),
+                is_available: true,
+                supported_codecs,
+                capabilities: vendor_capabilities(vendor, encoders, decoders),
+            })
+        })
+        .collect();
+
+    if gpus.is_empty() {
+        GpuList { gpus: vec![cpu_only()] }
+    } else {
+        GpuList { gpus }
+    }
+}
+
+/// Get the GPU list, detecting it on first use and returning the cached result afterwards
 #[tauri::command]
 pub fn check_gpu_availability() -> Result<GpuList, String> {
-    // Create Vulkan instance
-    let instance = back::Instance::create("VidKitSimple", 1)
-        .map_err(|e| format!("Failed to create Vulkan instance: {}", e))?;
+    let mut cache = GPU_CACHE.lock().map_err(|e| format!("GPU cache lock poisoned: {}", e))?;
+
+    if let Some(gpu_list) = cache.as_ref() {
+        return Ok(gpu_list.clone());
+    }
+
+    let gpu_list = detect_gpus()?;
+    *cache = Some(gpu_list.clone());
+
+    Ok(gpu_list)
+}
+
+/// Discard the cached GPU list and detect GPUs again
+#[tauri::command]
+pub fn refresh_gpu_detection() -> Result<GpuList, String> {
+    let mut cache = GPU_CACHE.lock().map_err(|e| format!("GPU cache lock poisoned: {}", e))?;
+
+    let gpu_list = detect_gpus()?;
+    *cache = Some(gpu_list.clone());
+
+    Ok(gpu_list)
+}
+
+/// Detect GPUs and the hardware codecs FFmpeg supports for them
+fn detect_gpus() -> Result<GpuList, String> {
+    // List FFmpeg encoders and decoders once for all codec checks
+    let encoders = list_ffmpeg_encoders();
+    let decoders = list_ffmpeg_decoders();
+
+    // Create Vulkan instance, falling back to probing FFmpeg encoders without it
+    let instance = match back::Instance::create("VidKitSimple", 1) {
+        Ok(instance) => instance,
+        Err(e) => {
+            log::warn!("Failed to create Vulkan instance ({}), detecting GPUs from FFmpeg encoders", e);
+            return Ok(detect_gpus_from_encoders(&encoders, &decoders));
+        }
+    };
 
     // Get adapter list
     let adapters = instance.enumerate_adapters();
 
     if adapters.is_empty() {
         return Ok(GpuList {
-            gpus: vec![GpuInfo {
-                name: "CPU Only".to_string(),
-                vendor: "None".to_string(),
-                is_available: false,
-                supported_codecs: vec![],
-            }],
+            gpus: vec![cpu_only()],
         });
     }
 
@@ -49,57 +211,41 @@ pub fn check_gpu_availability() -> Result<GpuList, String> {
         };
 
         // Check supported codecs
-        let mut supported_codecs = Vec::new();
-
-        match vendor {
-            "NVIDIA" => {
-                if check_ffmpeg_codec("h264_nvenc") {
-                    supported_codecs.push("h264_nvenc".to_string());
-                }
-                if check_ffmpeg_codec("hevc_nvenc") {
-                    supported_codecs.push("hevc_nvenc".to_string());
-                }
-                if check_ffmpeg_codec("scale_cuda") {
-                    supported_codecs.push("scale_cuda".to_string());
-                }
-            }
-            "Intel" => {
-                if check_ffmpeg_codec("h264_qsv") {
-                    supported_codecs.push("h264_qsv".to_string());
-                }
-            }
-            "AMD" => {
-                if check_ffmpeg_codec("h264_amf") {
-                    supported_codecs.push("h264_amf".to_string());
-                }
-            }
-            _ => {}
-        }
+        let supported_codecs = vendor_codecs(vendor, &encoders);
 
         gpu_list.push(GpuInfo {
             name,
             vendor: vendor.to_string(),
             is_available: !supported_codecs.is_empty(),
             supported_codecs,
+            capabilities: vendor_capabilities(vendor, &encoders, &decoders),
         });
     }
 
     Ok(GpuList { gpus: gpu_list })
 }
 
-pub fn check_ffmpeg_codec(codec: &str) -> bool {
+/// Output of `ffmpeg -encoders`, or an empty string if ffmpeg cannot be run
+fn list_ffmpeg_encoders() -> String {
+    run_ffmpeg_listing("-encoders")
+}
+
+/// Output of `ffmpeg -decoders`, or an empty string if ffmpeg cannot be run
+fn list_ffmpeg_decoders() -> String {
+    run_ffmpeg_listing("-decoders")
+}
+
+fn run_ffmpeg_listing(flag: &str) -> String {
     let output = Command::new("ffmpeg")
-        .args(["-hide_banner", "-encoders"])
+        .args(["-hide_banner", flag])
         .output();
 
     match output {
-        Ok(output) => {
-            if let Ok(output_str) = String::from_utf8(output.stdout) {
-                output_str.contains(codec)
-            } else {
-                false
-            }
-        }
-        Err(_) => false,
+        Ok(output) => String::from_utf8(output.stdout).unwrap_or_default(),
+        Err(_) => String::new(),
     }
 }
+
+pub fn check_ffmpeg_codec(codec: &str) -> bool {
+    list_ffmpeg_encoders().contains(codec)
+}
@@ -1,8 +1,42 @@
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Manager};
 use tauri_plugin_opener::open_path;
 
+use crate::utils::store_helper::{self, CONFIG_STORE_PATH};
+
+/// Config store key holding the chosen log level
+const LOG_LEVEL_KEY: &str = "log_level";
+
+/// Log level used until the user picks one
+pub const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// Config store keys holding the log retention limits
+const MAX_LOG_FILES_KEY: &str = "max_log_files";
+const MAX_TOTAL_LOG_SIZE_KEY: &str = "max_total_log_size";
+
+/// Name of the log file being written; rotated files start with `app_`
+const CURRENT_LOG_FILE: &str = "app.log";
+const ROTATED_LOG_PREFIX: &str = "app_";
+
+/// Rotated log files kept unless the user changes it
+pub const DEFAULT_MAX_LOG_FILES: usize = 10;
+
+/// Combined size of the log files in bytes unless the user changes it
+pub const DEFAULT_MAX_TOTAL_LOG_SIZE: u64 = 100 * 1024 * 1024; // 100 MB
+
+/// Limits on the log files kept in the log directory
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LogRetention {
+    /// Rotated files kept besides the current log file
+    pub max_log_files: usize,
+    /// Combined size in bytes of the current and the rotated files
+    pub max_total_log_size: u64,
+}
+
 /// Get the logs directory path using Tauri's app_log_dir
 ///
 /// This function returns the path to the logs directory without creating it.
@@ -44,7 +78,7 @@ pub fn get_current_log_file_path(app_handle: &AppHandle) -> Result<PathBuf, Stri
     let logs_dir = get_logs_directory_path(app_handle)?;
 
     // Return the main log file path
-    let log_file = logs_dir.join("app.log");
+    let log_file = logs_dir.join(CURRENT_LOG_FILE);
 
     // Check if the main log file exists
     if !log_file.exists() {
@@ -108,3 +142,146 @@ pub fn open_log_directory(app_handle: &AppHandle) -> Result<bool, String> {
 
     Ok(true)
 }
+
+/// Change the log level at runtime and remember it for the next start
+///
+/// The plugin's dispatcher accepts every level, so the global max level set here is
+/// the effective filter for all targets.
+///
+/// # Arguments
+/// * `app_handle` - The Tauri application handle
+/// * `level` - One of `off`, `error`, `warn`, `info`, `debug` or `trace`
+///
+/// # Returns
+/// * `Result<LevelFilter, String>` - The level now in effect, or an error for an unknown level
+pub fn set_log_level(app_handle: &AppHandle, level: &str) -> Result<LevelFilter, String> {
+    let level_filter: LevelFilter = level
+        .trim()
+        .parse()
+        .map_err(|_| format!("Unknown log level: {}", level))?;
+
+    log::set_max_level(level_filter);
+    log::info!("Log level set to {}", level_filter);
+
+    store_helper::set_value(app_handle, CONFIG_STORE_PATH, LOG_LEVEL_KEY, &level_filter.to_string().to_lowercase())
+        .map_err(|e| format!("Failed to save log level: {}", e))?;
+
+    Ok(level_filter)
+}
+
+/// Get the log level currently in effect
+pub fn get_log_level() -> LevelFilter {
+    log::max_level()
+}
+
+/// Apply the saved log level, or `DEFAULT_LOG_LEVEL` if none was saved
+///
+/// # Arguments
+/// * `app_handle` - The Tauri application handle
+pub fn restore_log_level(app_handle: &AppHandle) {
+    let level_filter = store_helper::get_value::<_, String>(app_handle, CONFIG_STORE_PATH, LOG_LEVEL_KEY)
+        .ok()
+        .flatten()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(DEFAULT_LOG_LEVEL);
+
+    log::set_max_level(level_filter);
+    log::info!("Using log level {}", level_filter);
+}
+
+/// Get the log retention limits, or the defaults if none were saved
+///
+/// # Arguments
+/// * `app_handle` - The Tauri application handle
+pub fn get_log_retention(app_handle: &AppHandle) -> LogRetention {
+    LogRetention {
+        max_log_files: store_helper::get_value::<_, usize>(app_handle, CONFIG_STORE_PATH, MAX_LOG_FILES_KEY)
+            .ok()
+            .flatten()
+            .unwrap_or(DEFAULT_MAX_LOG_FILES),
+        max_total_log_size: store_helper::get_value::<_, u64>(app_handle, CONFIG_STORE_PATH, MAX_TOTAL_LOG_SIZE_KEY)
+            .ok()
+            .flatten()
+            .unwrap_or(DEFAULT_MAX_TOTAL_LOG_SIZE),
+    }
+}
+
+/// Save the log retention limits and delete the rotated log files beyond them
+///
+/// # Arguments
+/// * `app_handle` - The Tauri application handle
+/// * `retention` - The new limits
+///
+/// # Returns
+/// * `Result<usize, String>` - The number of deleted log files, or an error
+pub fn set_log_retention(app_handle: &AppHandle, retention: LogRetention) -> Result<usize, String> {
+    store_helper::set_value(app_handle, CONFIG_STORE_PATH, MAX_LOG_FILES_KEY, &retention.max_log_files)
+        .map_err(|e| format!("Failed to save log retention: {}", e))?;
+    store_helper::set_value(app_handle, CONFIG_STORE_PATH, MAX_TOTAL_LOG_SIZE_KEY, &retention.max_total_log_size)
+        .map_err(|e| format!("Failed to save log retention: {}", e))?;
+
+    cleanup_old_logs(app_handle)
+}
+
+/// Delete the oldest rotated log files beyond the retention limits
+///
+/// The newest rotated files are kept while there are no more than `max_log_files`
+/// of them and, together with the current log file, they fit in `max_total_log_size`.
+/// The current log file itself is never deleted.
+///
+/// # Arguments
+/// * `app_handle` - The Tauri application handle
+///
+/// # Returns
+/// * `Result<usize, String>` - The number of deleted log files, or an error
+pub fn cleanup_old_logs(app_handle: &AppHandle) -> Result<usize, String> {
+    let retention = get_log_retention(app_handle);
+    let logs_dir = get_logs_directory_path(app_handle)?;
+
+    // Nothing was logged to a file yet
+    let Ok(entries) = fs::read_dir(&logs_dir) else {
+        return Ok(0);
+    };
+
+    let mut current_size = 0;
+    let mut rotated: Vec<(PathBuf, SystemTime, u64)> = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == CURRENT_LOG_FILE {
+            current_size = metadata.len();
+        } else if name.starts_with(ROTATED_LOG_PREFIX) && name.ends_with(".log") {
+            let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+            rotated.push((entry.path(), modified, metadata.len()));
+        }
+    }
+
+    // Newest first, so the files past the first one over a limit are the oldest
+    rotated.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let mut total_size = current_size;
+    let mut within_limits = true;
+    let mut removed = 0;
+    for (index, (path, _, size)) in rotated.iter().enumerate() {
+        within_limits = within_limits
+            && index < retention.max_log_files
+            && total_size + size <= retention.max_total_log_size;
+        if within_limits {
+            total_size += size;
+            continue;
+        }
+
+        match fs::remove_file(path) {
+            Ok(()) => removed += 1,
+            Err(e) => log::warn!("Failed to delete old log file {}: {}", path.display(), e),
+        }
+    }
+
+    Ok(removed)
+}
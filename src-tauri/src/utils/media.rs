@@ -0,0 +1,67 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+
+/// Extensions of the video files the app accepts as input (lowercase, without the dot)
+///
+/// Keep in sync with `INPUT_EXTENSIONS` in `src/constants/formats.ts`.
+pub const VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "m4v", "mov", "mkv", "webm", "avi", "wmv", "asf", "flv", "mpg", "mpeg", "ts", "m2ts",
+    "mts", "3gp", "ogv", "vob",
+];
+
+/// Bytes read from the start of a file to recognize its container
+const SIGNATURE_LENGTH: usize = 189;
+
+/// MPEG transport stream packet size; a sync byte starts every packet
+const TS_PACKET_SIZE: usize = 188;
+
+/// Whether the path has one of the `VIDEO_EXTENSIONS`
+pub fn has_video_extension(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .map_or(false, |e| VIDEO_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Whether the first bytes of the file look like a known video container
+fn has_video_signature(path: &Path) -> bool {
+    let mut header = Vec::with_capacity(SIGNATURE_LENGTH);
+    let read = File::open(path)
+        .and_then(|file| file.take(SIGNATURE_LENGTH as u64).read_to_end(&mut header));
+    if read.is_err() || header.len() < 12 {
+        return false;
+    }
+
+    let mp4_box = &header[4..8];
+    matches!(mp4_box, b"ftyp" | b"moov" | b"mdat" | b"wide" | b"free")
+        || header.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) // Matroska / WebM
+        || (header.starts_with(b"RIFF") && &header[8..12] == b"AVI ")
+        || header.starts_with(&[0x30, 0x26, 0xB2, 0x75]) // ASF / WMV
+        || header.starts_with(b"FLV")
+        || header.starts_with(b"OggS")
+        || header.starts_with(&[0x00, 0x00, 0x01, 0xBA]) // MPEG program stream
+        || (header.len() > TS_PACKET_SIZE && header[0] == 0x47 && header[TS_PACKET_SIZE] == 0x47)
+}
+
+/// Cheap check that a file is worth probing with FFmpeg
+///
+/// Files with a known video extension are accepted as-is; anything else must start
+/// with the signature of a known container.
+pub fn is_supported_media(path: &Path) -> bool {
+    has_video_extension(path) || has_video_signature(path)
+}
+
+/// Reject files that are obviously not videos before the slower FFmpeg probe
+pub fn ensure_supported_media(path: &str) -> AppResult<()> {
+    if is_supported_media(Path::new(path)) {
+        return Ok(());
+    }
+
+    Err(AppError::video_error(
+        format!("Not a supported video file: {}", path),
+        ErrorCode::InvalidVideoFormat,
+        Some(format!("Supported formats: {}", VIDEO_EXTENSIONS.join(", "))),
+    ))
+}
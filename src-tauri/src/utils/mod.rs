@@ -13,6 +13,11 @@
 //! - `event_emitter`: Utilities for emitting events to the frontend
 //! - `logger`: Provides utilities for accessing log files created by the Tauri Logging plugin
 //! - `store_helper`: Utilities for working with the Tauri Store plugin
+//! - `preset_migration`: Moves presets from the old temp directory into the presets store
+//! - `media`: Recognized video extensions and a quick check for supported input files
+//! - `notifications`: Desktop notification when the task queue finishes
+//! - `power`: Current power source and the pause-on-battery preference
+//! - `thumbnail_cache`: File list thumbnails cached by path and modification time
 
 /// GPU detection utility that identifies available GPUs and their capabilities
 /// for hardware-accelerated video processing
@@ -36,3 +41,18 @@ pub mod logger;
 
 /// Utilities for working with the Tauri Store plugin
 pub mod store_helper;
+
+/// One-time migration of presets from the old temp directory into the presets store
+pub mod preset_migration;
+
+/// Recognized video extensions and a quick check for supported input files
+pub mod media;
+
+/// Desktop notification when the task queue finishes
+pub mod notifications;
+
+/// Current power source (AC or battery) and the pause-on-battery preference
+pub mod power;
+
+/// File list thumbnails cached by input path and modification time
+pub mod thumbnail_cache;
@@ -0,0 +1,52 @@
+use log::{info, warn};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::utils::error::AppResult;
+use crate::utils::store_helper::{self, CONFIG_STORE_PATH};
+
+/// Config store key enabling the desktop notification when the queue finishes
+const NOTIFY_ON_COMPLETE_KEY: &str = "notify_on_complete";
+
+/// Whether the notification is shown before the user changes the setting
+const DEFAULT_NOTIFY_ON_COMPLETE: bool = true;
+
+/// Whether a desktop notification is shown when the queue finishes
+pub fn notify_on_complete(app_handle: &AppHandle) -> bool {
+    store_helper::get_value::<_, bool>(app_handle, CONFIG_STORE_PATH, NOTIFY_ON_COMPLETE_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_NOTIFY_ON_COMPLETE)
+}
+
+/// Turn the queue-finished notification on or off; the setting is kept across restarts
+pub fn set_notify_on_complete(app_handle: &AppHandle, enabled: bool) -> AppResult<()> {
+    store_helper::set_value(app_handle, CONFIG_STORE_PATH, NOTIFY_ON_COMPLETE_KEY, &enabled)
+}
+
+/// Show a desktop notification summarizing a finished queue, if enabled
+///
+/// # Arguments
+/// * `app_handle` - The Tauri application handle
+/// * `completed`, `failed`, `canceled` - Task counts from the `queue-drained` event
+pub fn notify_queue_drained(app_handle: &AppHandle, completed: usize, failed: usize, canceled: usize) {
+    if !notify_on_complete(app_handle) {
+        return;
+    }
+
+    let mut body = format!("{} succeeded, {} failed", completed, failed);
+    if canceled > 0 {
+        body.push_str(&format!(", {} canceled", canceled));
+    }
+
+    let title = if failed > 0 {
+        "Queue finished with errors"
+    } else {
+        "Queue finished"
+    };
+
+    match app_handle.notification().builder().title(title).body(&body).show() {
+        Ok(()) => info!("Queue finished notification shown: {}", body),
+        Err(e) => warn!("Failed to show queue finished notification: {}", e),
+    }
+}
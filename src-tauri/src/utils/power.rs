@@ -0,0 +1,127 @@
+use tauri::AppHandle;
+
+use crate::utils::error::AppResult;
+use crate::utils::store_helper::{self, CONFIG_STORE_PATH};
+
+/// Config store key enabling the automatic queue pause on battery power
+const PAUSE_ON_BATTERY_KEY: &str = "pause_on_battery";
+
+/// Where the machine currently draws its power from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSource {
+    Ac,
+    Battery,
+}
+
+/// Whether the queue is paused while the machine runs on battery
+pub fn pause_on_battery(app_handle: &AppHandle) -> bool {
+    store_helper::get_value::<_, bool>(app_handle, CONFIG_STORE_PATH, PAUSE_ON_BATTERY_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or(false)
+}
+
+/// Turn the automatic pause on battery power on or off; the setting is kept across restarts
+pub fn set_pause_on_battery(app_handle: &AppHandle, enabled: bool) -> AppResult<()> {
+    store_helper::set_value(app_handle, CONFIG_STORE_PATH, PAUSE_ON_BATTERY_KEY, &enabled)
+}
+
+/// Current power source, `None` if it cannot be determined (e.g. a desktop without a battery)
+pub fn power_source() -> Option<PowerSource> {
+    platform::power_source()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::PowerSource;
+
+    /// `SYSTEM_POWER_STATUS` from winbase.h
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+
+    /// `battery_flag` value for systems without a battery
+    const NO_SYSTEM_BATTERY: u8 = 128;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+
+    pub fn power_source() -> Option<PowerSource> {
+        let mut status = SystemPowerStatus::default();
+        // SAFETY: the struct matches SYSTEM_POWER_STATUS and lives for the whole call
+        if unsafe { GetSystemPowerStatus(&mut status) } == 0 || status.battery_flag == NO_SYSTEM_BATTERY {
+            return None;
+        }
+
+        match status.ac_line_status {
+            0 => Some(PowerSource::Battery),
+            1 => Some(PowerSource::Ac),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::process::Command;
+
+    use super::PowerSource;
+
+    pub fn power_source() -> Option<PowerSource> {
+        // First line: "Now drawing from 'AC Power'" or "... 'Battery Power'"
+        let output = Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let first_line = text.lines().next()?;
+
+        if first_line.contains("'Battery Power'") {
+            Some(PowerSource::Battery)
+        } else if first_line.contains("'AC Power'") {
+            Some(PowerSource::Ac)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::fs;
+    use std::path::Path;
+
+    use super::PowerSource;
+
+    const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+    /// Contents of a small sysfs attribute file without the trailing newline
+    fn read_trimmed(path: &Path) -> Option<String> {
+        fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+    }
+
+    pub fn power_source() -> Option<PowerSource> {
+        let mut has_battery = false;
+
+        for entry in fs::read_dir(POWER_SUPPLY_DIR).ok()?.flatten() {
+            let path = entry.path();
+            match read_trimmed(&path.join("type")).as_deref() {
+                Some("Mains") | Some("USB") => {
+                    if read_trimmed(&path.join("online")).as_deref() == Some("1") {
+                        return Some(PowerSource::Ac);
+                    }
+                }
+                Some("Battery") => has_battery = true,
+                _ => {}
+            }
+        }
+
+        has_battery.then_some(PowerSource::Battery)
+    }
+}
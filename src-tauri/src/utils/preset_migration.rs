@@ -0,0 +1,90 @@
+use log::{info, warn};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Runtime};
+
+use crate::utils::error::AppResult;
+use crate::utils::store_helper::{self, PRESETS_STORE_PATH};
+
+/// Key of the preset array in the presets store (shared with the frontend)
+const PRESETS_KEY: &str = "presets";
+
+/// Directory where presets used to be written as one JSON file each.
+/// It lives in the temp dir, so it is wiped on reboot.
+fn legacy_presets_dir() -> PathBuf {
+    std::env::temp_dir().join("vid-kit-simple")
+}
+
+/// Move presets left in the old temp directory into the presets store.
+///
+/// Presets whose id already exists in the store are dropped. The old directory is
+/// removed afterwards so this only does work once, unless a preset file could not be
+/// read: then only the migrated files are deleted and the rest is kept for the user
+/// to recover. Returns the number of presets added.
+pub fn migrate_legacy_presets<R: Runtime>(app_handle: &AppHandle<R>) -> AppResult<usize> {
+    let dir = legacy_presets_dir();
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(0),
+    };
+
+    let mut presets: Vec<Value> =
+        store_helper::get_value(app_handle, PRESETS_STORE_PATH, PRESETS_KEY)?.unwrap_or_default();
+    let mut migrated = 0;
+    let mut handled = Vec::new();
+    let mut failed = 0;
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().map_or(true, |ext| ext != "json") {
+            continue;
+        }
+
+        let preset = match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str::<Value>(&content).map_err(|e| e.to_string()))
+        {
+            Ok(preset) => preset,
+            Err(e) => {
+                warn!("Skipping unreadable legacy preset {:?}: {}", path, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let id = match preset.get("id").and_then(Value::as_str) {
+            Some(id) => id.to_string(),
+            None => {
+                warn!("Skipping legacy preset without an id: {:?}", path);
+                failed += 1;
+                continue;
+            }
+        };
+
+        handled.push(path);
+        if presets.iter().any(|p| p.get("id").and_then(Value::as_str) == Some(id.as_str())) {
+            continue;
+        }
+
+        presets.push(preset);
+        migrated += 1;
+    }
+
+    if migrated > 0 {
+        store_helper::set_value(app_handle, PRESETS_STORE_PATH, PRESETS_KEY, &presets)?;
+        info!("Migrated {} presets from {:?}", migrated, dir);
+    }
+
+    if failed > 0 {
+        for path in &handled {
+            if let Err(e) = fs::remove_file(path) {
+                warn!("Failed to remove migrated legacy preset {:?}: {}", path, e);
+            }
+        }
+        warn!("Kept {} unreadable legacy presets in {:?}", failed, dir);
+    } else if let Err(e) = fs::remove_dir_all(&dir) {
+        warn!("Failed to remove legacy preset directory {:?}: {}", dir, e);
+    }
+
+    Ok(migrated)
+}
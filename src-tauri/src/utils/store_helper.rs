@@ -1,8 +1,13 @@
-use log::{error, info};
+use log::{error, info, warn};
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::{AppHandle, Runtime};
-use tauri_plugin_store::StoreExt;
+use tauri_plugin_store::{resolve_store_path, Store, StoreExt};
 use crate::utils::error::{AppError, AppResult, ErrorCode};
 
 /// Constants for store file paths
@@ -11,11 +16,13 @@ pub const CONFIG_STORE_PATH: &str = "config.json";
 pub const PRESETS_STORE_PATH: &str = "presets.json";
 
 /// Helper function to get a store
+///
+/// Stores opened here do not auto-save; the helpers below save atomically instead.
 pub fn get_store<R: Runtime>(
     app_handle: &AppHandle<R>,
     path: &str,
 ) -> AppResult<Arc<tauri_plugin_store::Store<R>>> {
-    app_handle.store(path).map_err(|e| {
+    app_handle.store_builder(path).disable_auto_save().build().map_err(|e| {
         error!("Failed to get store {}: {}", path, e);
         AppError::state_error(
             format!("Failed to get store: {}", e),
@@ -25,6 +32,68 @@ pub fn get_store<R: Runtime>(
     })
 }
 
+/// Path of the backup kept next to a store file
+fn backup_path(file_path: &Path) -> PathBuf {
+    let mut backup = OsString::from(file_path.as_os_str());
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+/// Write a file so that it is either fully replaced or left untouched.
+///
+/// The data is written and synced to a temporary file next to `path`, which is then
+/// renamed over it. A crash mid-write leaves only the temporary file behind.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut temp_path = OsString::from(path.as_os_str());
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&temp_path, path)
+}
+
+/// Save a store atomically, keeping the previous file as a `.bak` backup.
+///
+/// The store's own `save` writes the file in place, so a crash could truncate it.
+/// This writes the same JSON through `write_atomic` instead. The backup is only
+/// refreshed from a file that still parses, so it always holds the last good state.
+fn save_store<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    store: &Store<R>,
+    path: &str,
+) -> AppResult<()> {
+    let to_error = |e: String| {
+        error!("Failed to save store {}: {}", path, e);
+        AppError::state_error(
+            format!("Failed to save store: {}", e),
+            ErrorCode::StateSerializationError,
+            Some(format!("Error saving store file: {}", path))
+        )
+    };
+
+    let file_path = resolve_store_path(app_handle, path).map_err(|e| to_error(e.to_string()))?;
+
+    if let Ok(current) = fs::read(&file_path) {
+        if serde_json::from_slice::<Value>(&current).is_ok() {
+            if let Err(e) = write_atomic(&backup_path(&file_path), &current) {
+                warn!("Failed to back up store {}: {}", path, e);
+            }
+        }
+    }
+
+    let entries: Map<String, Value> = store.entries().into_iter().collect();
+    let bytes = serde_json::to_vec_pretty(&entries).map_err(|e| to_error(e.to_string()))?;
+    write_atomic(&file_path, &bytes).map_err(|e| to_error(e.to_string()))
+}
+
 /// Helper function to get a value from a store
 pub fn get_value<R: Runtime, T: DeserializeOwned>(
     app_handle: &AppHandle<R>,
@@ -48,6 +117,61 @@ pub fn get_value<R: Runtime, T: DeserializeOwned>(
     }
 }
 
+/// Get a value from a store, falling back to the `.bak` backup if the store is corrupted.
+///
+/// The store plugin loads a file that fails to parse as an empty store, so a missing
+/// key in an unreadable file is treated like a deserialization error. When the backup
+/// has the key, all of its entries are restored into the store and saved.
+pub fn load_with_backup<R: Runtime, T: DeserializeOwned>(
+    app_handle: &AppHandle<R>,
+    path: &str,
+    key: &str,
+) -> AppResult<Option<T>> {
+    let file_path = resolve_store_path(app_handle, path).map_err(|e| {
+        AppError::state_error(
+            format!("Failed to resolve store path: {}", e),
+            ErrorCode::StateAccessError,
+            Some(format!("Error accessing store file: {}", path))
+        )
+    })?;
+
+    let primary_error = match get_value::<R, T>(app_handle, path, key) {
+        Ok(Some(value)) => return Ok(Some(value)),
+        Ok(None) => match fs::read(&file_path) {
+            Ok(bytes) if serde_json::from_slice::<Value>(&bytes).is_err() => AppError::state_error(
+                "Store file is corrupted".to_string(),
+                ErrorCode::StateSerializationError,
+                Some(format!("Store file '{}' could not be parsed", path))
+            ),
+            _ => return Ok(None),
+        },
+        Err(e) => e,
+    };
+
+    warn!("Failed to load '{}' from store {}, trying the backup: {}", key, path, primary_error);
+
+    let backup: Option<Map<String, Value>> = fs::read(backup_path(&file_path))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+    let (entries, value) = match backup.and_then(|entries| {
+        let value = serde_json::from_value::<T>(entries.get(key)?.clone()).ok()?;
+        Some((entries, value))
+    }) {
+        Some(restored) => restored,
+        None => return Err(primary_error),
+    };
+
+    // Put the good state back so the next save does not persist the damaged one
+    let store = get_store(app_handle, path)?;
+    for (entry_key, entry_value) in entries {
+        store.set(entry_key, entry_value);
+    }
+    save_store(app_handle, &store, path)?;
+
+    info!("Restored store {} from its backup", path);
+    Ok(Some(value))
+}
+
 /// Helper function to set a value in a store
 pub fn set_value<R: Runtime, T: Serialize>(
     app_handle: &AppHandle<R>,
@@ -71,14 +195,7 @@ pub fn set_value<R: Runtime, T: Serialize>(
     store.set(key, json_value);
 
     // Save the store
-    store.save().map_err(|e| {
-        error!("Failed to save store {}: {}", path, e);
-        AppError::state_error(
-            format!("Failed to save store: {}", e),
-            ErrorCode::StateSerializationError,
-            Some(format!("Error saving store file: {}", path))
-        )
-    })?;
+    save_store(app_handle, &store, path)?;
 
     info!("Successfully saved value for key {} in store {}", key, path);
     Ok(())
@@ -103,14 +220,7 @@ pub fn delete_value<R: Runtime>(
     }
 
     // Save the store
-    store.save().map_err(|e| {
-        error!("Failed to save store {}: {}", path, e);
-        AppError::state_error(
-            format!("Failed to save store: {}", e),
-            ErrorCode::StateSerializationError,
-            Some(format!("Error saving store file: {}", path))
-        )
-    })?;
+    save_store(app_handle, &store, path)?;
 
     info!("Successfully deleted key {} from store {}", key, path);
     Ok(())
@@ -127,14 +237,7 @@ pub fn clear_store<R: Runtime>(
     store.clear();
 
     // Save the store
-    store.save().map_err(|e| {
-        error!("Failed to save store {}: {}", path, e);
-        AppError::state_error(
-            format!("Failed to save store: {}", e),
-            ErrorCode::StateSerializationError,
-            Some(format!("Error saving store file: {}", path))
-        )
-    })?;
+    save_store(app_handle, &store, path)?;
 
     info!("Successfully cleared store {}", path);
     Ok(())
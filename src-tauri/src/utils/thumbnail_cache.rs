@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use log::{info, warn};
+use tauri::{AppHandle, Manager};
+
+use crate::services::video_processor::VideoProcessor;
+use crate::utils::error::{AppError, AppResult, ErrorCode};
+
+/// Folder in the app cache directory holding the thumbnails
+const THUMBNAIL_DIR: &str = "thumbnails";
+
+/// Width of file list thumbnails in pixels
+const THUMBNAIL_WIDTH: u32 = 320;
+
+/// Position of the thumbnail frame as a share of the duration, past black intros
+const THUMBNAIL_POSITION: f64 = 0.1;
+
+/// Directory of the cached thumbnails, not created here
+fn thumbnail_dir(app_handle: &AppHandle) -> AppResult<PathBuf> {
+    app_handle
+        .path()
+        .app_cache_dir()
+        .map(|dir| dir.join(THUMBNAIL_DIR))
+        .map_err(|e| {
+            AppError::other_error(
+                format!("Failed to get app cache directory: {}", e),
+                ErrorCode::DirectoryError,
+                None,
+            )
+        })
+}
+
+/// FNV-1a offset basis and prime (64 bit)
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// File name prefix shared by every cached version of an input
+///
+/// Uses FNV-1a rather than `DefaultHasher`, whose output may change between Rust
+/// releases and would orphan the cache after an update.
+fn path_key(input_path: &str) -> String {
+    let hash = input_path
+        .bytes()
+        .fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME));
+    format!("{:016x}_", hash)
+}
+
+/// Modification time of the input in nanoseconds since the epoch
+fn modified_nanos(input_path: &str) -> AppResult<u128> {
+    let modified = fs::metadata(input_path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| AppError::io_error(e, ErrorCode::FileReadError, Some(format!("Cannot read {}", input_path))))?;
+
+    Ok(modified.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_nanos()))
+}
+
+/// Delete the files in `dir` whose name `matches`, returning how many were deleted
+fn remove_entries(dir: &Path, matches: impl Fn(&str) -> bool) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        if !matches(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+        match fs::remove_file(entry.path()) {
+            Ok(()) => removed += 1,
+            Err(e) => warn!("Failed to delete cached thumbnail {}: {}", entry.path().display(), e),
+        }
+    }
+    removed
+}
+
+/// Path of a JPEG thumbnail of `input_path` for the file list
+///
+/// Thumbnails are cached by input path and modification time, so an unchanged file
+/// reuses its image across launches. A file changed since gets a new thumbnail and
+/// the old one is deleted.
+pub fn cached_thumbnail(
+    app_handle: &AppHandle,
+    processor: &VideoProcessor,
+    input_path: &str,
+    duration: f64,
+) -> AppResult<String> {
+    let dir = thumbnail_dir(app_handle)?;
+    let prefix = path_key(input_path);
+    let cached = dir.join(format!("{}{}.jpg", prefix, modified_nanos(input_path)?));
+    if cached.exists() {
+        return Ok(cached.to_string_lossy().into_owned());
+    }
+
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::io_error(e, ErrorCode::DirectoryError, Some("Cannot create the thumbnail cache".to_string())))?;
+    remove_entries(&dir, |name| name.starts_with(&prefix));
+
+    let output = cached.to_string_lossy().into_owned();
+    if let Err(e) = processor.extract_thumbnail(input_path, duration * THUMBNAIL_POSITION, &output, THUMBNAIL_WIDTH) {
+        // A half-written image must not be served from the cache later
+        let _ = fs::remove_file(&cached);
+        return Err(e);
+    }
+
+    Ok(output)
+}
+
+/// Delete every cached thumbnail, returning how many were deleted
+pub fn clear_thumbnail_cache(app_handle: &AppHandle) -> AppResult<usize> {
+    let removed = remove_entries(&thumbnail_dir(app_handle)?, |_| true);
+    info!("Cleared {} cached thumbnails", removed);
+    Ok(removed)
+}
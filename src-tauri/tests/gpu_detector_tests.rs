@@ -1,5 +1,5 @@
 use vid_kit_simple_lib::utils::gpu_detector::{
-    check_ffmpeg_codec, check_gpu_availability, GpuInfo, GpuList,
+    check_ffmpeg_codec, check_gpu_availability, GpuCodecCapability, GpuInfo, GpuList,
 };
 
 // Test case for checking if GPU detector returns valid output format
@@ -62,12 +62,22 @@ fn test_mock_gpu_list() {
                 vendor: "NVIDIA".to_string(),
                 is_available: true,
                 supported_codecs: vec!["h264_nvenc".to_string()],
+                capabilities: vec![GpuCodecCapability {
+                    codec: "h264".to_string(),
+                    encoder: Some("h264_nvenc".to_string()),
+                    decoder: None,
+                    can_encode: true,
+                    can_decode: false,
+                    max_width: Some(4096),
+                    max_height: Some(4096),
+                }],
             },
             GpuInfo {
                 name: "CPU Only".to_string(),
                 vendor: "None".to_string(),
                 is_available: false,
                 supported_codecs: vec![],
+                capabilities: vec![],
             },
         ],
     };
@@ -82,6 +92,7 @@ fn test_mock_gpu_list() {
     assert_eq!(mock_gpu_list.gpus[0].vendor, "NVIDIA");
     assert!(mock_gpu_list.gpus[0].is_available);
     assert_eq!(mock_gpu_list.gpus[0].supported_codecs.len(), 1);
+    assert!(mock_gpu_list.gpus[0].capabilities[0].can_encode);
 
     assert_eq!(mock_gpu_list.gpus[1].name, "CPU Only");
     assert!(!mock_gpu_list.gpus[1].is_available);
@@ -0,0 +1,553 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use vid_kit_simple_lib::services::video_processor::{with_warning_handler, QualityScore, ResumePoint, VideoError};
+use vid_kit_simple_lib::state::task_manager::{
+    create_processing_options, is_partial_output_path, migrate_tasks, partial_output_path, threads_per_task, OverwritePolicy, ProgressThrottle, TaskError, TaskEvent, TaskManager,
+    TaskStatus, DEFAULT_TASK_PRIORITY,
+};
+
+// Helper to create a conversion task with paths inside a temporary directory
+fn create_task(task_manager: &TaskManager, dir: &tempfile::TempDir, index: usize) -> String {
+    let input = dir.path().join(format!("input_{}.mp4", index));
+    let output = dir.path().join(format!("output_{}.mp4", index));
+
+    task_manager
+        .create_task(
+            input.to_string_lossy().into_owned(),
+            output.to_string_lossy().into_owned(),
+            "video_conversion".to_string(),
+            HashMap::new(),
+            DEFAULT_TASK_PRIORITY,
+            OverwritePolicy::Overwrite,
+        )
+        .expect("Task should be created")
+        .id
+}
+
+// Helper to count tasks currently running
+fn running_count(task_manager: &TaskManager) -> usize {
+    task_manager
+        .get_all_tasks()
+        .iter()
+        .filter(|task| task.status == TaskStatus::Running)
+        .count()
+}
+
+// Test case for checking that no more than max_concurrent_tasks tasks run at once
+#[test]
+fn test_concurrency_limit_is_enforced() {
+    let dir = tempfile::tempdir().unwrap();
+    let task_manager = TaskManager::new(2);
+
+    let task_ids: Vec<String> = (0..5).map(|i| create_task(&task_manager, &dir, i)).collect();
+
+    let mut permits = Vec::new();
+    for task_id in &task_ids {
+        match task_manager.begin_task(task_id) {
+            Ok((_, permit)) => permits.push(permit),
+            Err(TaskError::ConcurrencyLimit(max)) => assert_eq!(max, 2),
+            Err(e) => panic!("Unexpected error: {}", e),
+        }
+
+        // The limit must hold after every start attempt
+        assert!(running_count(&task_manager) <= 2, "At most 2 tasks should be running");
+    }
+
+    assert_eq!(permits.len(), 2, "Exactly 2 tasks should have started");
+    assert_eq!(running_count(&task_manager), 2);
+}
+
+// Test case for checking that a finished task frees its slot for the next one
+#[test]
+fn test_finished_task_frees_slot() {
+    let dir = tempfile::tempdir().unwrap();
+    let task_manager = TaskManager::new(2);
+
+    let task_ids: Vec<String> = (0..3).map(|i| create_task(&task_manager, &dir, i)).collect();
+
+    let (first, first_permit) = task_manager.begin_task(&task_ids[0]).unwrap();
+    let _second = task_manager.begin_task(&task_ids[1]).unwrap();
+    assert!(matches!(
+        task_manager.begin_task(&task_ids[2]),
+        Err(TaskError::ConcurrencyLimit(_))
+    ));
+
+    // Finish the first task and release its slot
+    task_manager
+        .update_task(&first.id, |task| task.status = TaskStatus::Completed)
+        .unwrap();
+    drop(first_permit);
+
+    assert!(task_manager.begin_task(&task_ids[2]).is_ok(), "Freed slot should be reused");
+    assert_eq!(running_count(&task_manager), 2);
+}
+
+// Test case for checking that progress events use the same taskId key as other task events
+#[test]
+fn test_progress_event_payload_keys() {
+    let event = TaskEvent::Progress {
+        task_id: "task-1".to_string(),
+        progress: 42.0,
+        eta_secs: Some(12.5),
+        phase: None,
+    };
+    assert_eq!(event.name(), "task-progress");
+
+    let payload = serde_json::to_value(&event).unwrap();
+    assert_eq!(payload["taskId"], "task-1", "Progress payload should use the taskId key");
+    assert_eq!(payload["progress"], 42.0);
+    assert_eq!(payload["etaSecs"], 12.5);
+    assert!(payload.get("task_id").is_none(), "Progress payload should not use the task_id key");
+
+    // Without an ETA the key is still present, so the payload shape is stable
+    let event = TaskEvent::Progress {
+        task_id: "task-1".to_string(),
+        progress: 1.0,
+        eta_secs: None,
+        phase: None,
+    };
+    let payload = serde_json::to_value(&event).unwrap();
+    assert!(payload["etaSecs"].is_null());
+
+    let started = serde_json::to_value(TaskEvent::Started { task_id: "task-1".to_string() }).unwrap();
+    assert_eq!(started["taskId"], payload["taskId"]);
+}
+
+// Test case for checking the payload of quality events
+#[test]
+fn test_quality_event_payload() {
+    let event = TaskEvent::Quality {
+        task_id: "task-1".to_string(),
+        quality: QualityScore {
+            psnr: Some(41.5),
+            ssim: Some(0.98),
+            vmaf: None,
+            frames: 250,
+        },
+    };
+    assert_eq!(event.name(), "task-quality");
+
+    let payload = serde_json::to_value(&event).unwrap();
+    assert_eq!(payload["taskId"], "task-1");
+    assert_eq!(payload["quality"]["psnr"], 41.5);
+    assert_eq!(payload["quality"]["frames"], 250);
+    assert!(payload["quality"]["vmaf"].is_null());
+}
+
+// Test case for checking queue statistics
+#[test]
+fn test_queue_stats_counts_statuses() {
+    let dir = tempfile::tempdir().unwrap();
+    let task_manager = TaskManager::new(2);
+
+    let task_ids: Vec<String> = (0..4).map(|i| create_task(&task_manager, &dir, i)).collect();
+
+    let _running = task_manager.begin_task(&task_ids[0]).unwrap();
+    task_manager
+        .update_task(&task_ids[0], |task| task.progress = 40.0)
+        .unwrap();
+    task_manager
+        .update_task(&task_ids[1], |task| task.status = TaskStatus::Failed)
+        .unwrap();
+
+    let stats = task_manager.get_queue_stats();
+    assert_eq!(stats.total, 4);
+    assert_eq!(stats.pending, 2);
+    assert_eq!(stats.running, 1);
+    assert_eq!(stats.failed, 1);
+    assert_eq!(stats.running_progress, 40.0);
+    assert_eq!(stats.average_running_progress, 40.0);
+    assert!(!stats.is_queue_paused);
+}
+
+// Test case for weighting the overall progress by input duration
+#[test]
+fn test_weighted_progress_follows_input_duration() {
+    let dir = tempfile::tempdir().unwrap();
+    let task_manager = TaskManager::new(2);
+
+    let short = create_task(&task_manager, &dir, 0);
+    let long = create_task(&task_manager, &dir, 1);
+    task_manager
+        .update_task(&short, |task| {
+            task.input_duration = Some(10.0);
+            task.status = TaskStatus::Completed;
+        })
+        .unwrap();
+    task_manager
+        .update_task(&long, |task| {
+            task.input_duration = Some(90.0);
+            task.progress = 50.0;
+        })
+        .unwrap();
+
+    let stats = task_manager.get_queue_stats();
+    assert!((stats.weighted_progress - 55.0).abs() < 0.01);
+
+    // Nearly done is still not done
+    task_manager.update_task(&long, |task| task.progress = 100.0).unwrap();
+    assert!(task_manager.get_queue_stats().weighted_progress < 100.0);
+
+    task_manager
+        .update_task(&long, |task| task.status = TaskStatus::Completed)
+        .unwrap();
+    assert_eq!(task_manager.get_queue_stats().weighted_progress, 100.0);
+}
+
+// Test case for reading the fallback settings from the task config
+#[test]
+fn test_create_task_parses_fallbacks() {
+    let dir = tempfile::tempdir().unwrap();
+    let task_manager = TaskManager::new(2);
+    let input = dir.path().join("input.mp4").to_string_lossy().into_owned();
+    let output = dir.path().join("output.mp4").to_string_lossy().into_owned();
+
+    let mut config = HashMap::new();
+    config.insert(
+        "fallbacks".to_string(),
+        r#"[{"use_gpu": "false", "cpu_codec": "libx264"}, {"resolution": "1280x720"}]"#.to_string(),
+    );
+    let task = task_manager
+        .create_task(input.clone(), output.clone(), "convert".to_string(), config, DEFAULT_TASK_PRIORITY, OverwritePolicy::Overwrite)
+        .unwrap();
+    assert_eq!(task.fallbacks.len(), 2);
+    assert_eq!(task.fallbacks[0].get("cpu_codec").map(String::as_str), Some("libx264"));
+    assert_eq!(task.fallback_used, None);
+
+    let mut config = HashMap::new();
+    config.insert("fallbacks".to_string(), "not json".to_string());
+    let result = task_manager.create_task(input, output, "convert".to_string(), config, DEFAULT_TASK_PRIORITY, OverwritePolicy::Overwrite);
+    assert!(matches!(result, Err(TaskError::Other(_))));
+}
+
+// Test case for rejecting a reorder that leaves out a queued task
+#[test]
+fn test_reorder_with_missing_id_keeps_queue() {
+    let dir = tempfile::tempdir().unwrap();
+    let task_manager = TaskManager::new(2);
+
+    let task_ids: Vec<String> = (0..3).map(|i| create_task(&task_manager, &dir, i)).collect();
+
+    let partial = vec![task_ids[1].clone(), task_ids[0].clone()];
+    assert!(task_manager.set_queue_order(&partial).is_err());
+    assert_eq!(task_manager.get_queue(), task_ids);
+    assert!(task_manager.get_queue().contains(&task_ids[2]));
+
+    let duplicated = vec![task_ids[2].clone(), task_ids[2].clone(), task_ids[0].clone()];
+    assert!(task_manager.set_queue_order(&duplicated).is_err());
+    assert_eq!(task_manager.get_queue(), task_ids);
+
+    let reversed: Vec<String> = task_ids.iter().rev().cloned().collect();
+    task_manager.set_queue_order(&reversed).unwrap();
+    assert_eq!(task_manager.get_queue(), reversed);
+}
+
+// Test case for loading tasks saved before the schema version was introduced
+#[test]
+fn test_migrate_v1_tasks() {
+    let v1_tasks: Vec<serde_json::Value> = serde_json::from_str(
+        r#"[
+            {
+                "id": "task-1",
+                "input_path": "/videos/in.mp4",
+                "output_path": "/videos/out.mp4",
+                "status": "Pending",
+                "progress": 0.0,
+                "error": null,
+                "attempts": 0,
+                "created_at": "2024-01-01T00:00:00Z",
+                "started_at": null,
+                "completed_at": null,
+                "task_type": "convert",
+                "config": { "bitrate": "5000000" }
+            },
+            { "id": "broken" }
+        ]"#,
+    )
+    .unwrap();
+
+    let tasks = migrate_tasks(1, v1_tasks);
+
+    // The damaged entry is skipped instead of failing the whole load
+    assert_eq!(tasks.len(), 1);
+    let task = &tasks[0];
+    assert_eq!(task.id, "task-1");
+    assert_eq!(task.status, TaskStatus::Pending);
+    assert_eq!(task.config.get("bitrate").map(String::as_str), Some("5000000"));
+    assert_eq!(task.priority, DEFAULT_TASK_PRIORITY);
+    assert_eq!(task.max_retries, 0);
+    assert!(task.partial_output_path.is_none());
+}
+
+// Test case for tasks that were running when the application exited
+#[test]
+fn test_recover_interrupted_tasks_requeues_running() {
+    let dir = tempfile::tempdir().unwrap();
+    let task_manager = TaskManager::new(2);
+
+    let running_id = create_task(&task_manager, &dir, 0);
+    let pending_id = create_task(&task_manager, &dir, 1);
+    let (_, permit) = task_manager.begin_task(&running_id).unwrap();
+    drop(permit);
+    task_manager
+        .update_task(&running_id, |task| task.progress = 60.0)
+        .unwrap();
+
+    task_manager.recover_interrupted_tasks();
+
+    let recovered = task_manager.get_task(&running_id).unwrap();
+    assert_eq!(recovered.status, TaskStatus::Pending);
+    assert_eq!(recovered.progress, 0.0);
+    assert!(recovered.started_at.is_none());
+    assert_eq!(running_count(&task_manager), 0);
+
+    // Both tasks are queued exactly once
+    let queue = task_manager.get_queue();
+    assert_eq!(queue.len(), 2);
+    assert!(queue.contains(&running_id) && queue.contains(&pending_id));
+}
+
+// Test case for paused tasks with encoded segments when the application exited
+#[test]
+fn test_recover_interrupted_tasks_keeps_resume_point() {
+    let dir = tempfile::tempdir().unwrap();
+    let task_manager = TaskManager::new(2);
+
+    let paused_id = create_task(&task_manager, &dir, 0);
+    let running_id = create_task(&task_manager, &dir, 1);
+    let resume = ResumePoint {
+        segments: vec![dir.path().join("output_0.part.seg0.mp4").to_string_lossy().into_owned()],
+        encoded_secs: 42.0,
+        output_size: Some((1280, 720)),
+    };
+    for (task_id, status) in [(&paused_id, TaskStatus::Paused), (&running_id, TaskStatus::Running)] {
+        let resume = resume.clone();
+        task_manager
+            .update_task(task_id, |task| {
+                task.status = status;
+                task.progress = 35.0;
+                task.resume = Some(resume);
+            })
+            .unwrap();
+    }
+
+    task_manager.recover_interrupted_tasks();
+
+    // The paused task waits for resume_task, the running one is queued to continue
+    let paused = task_manager.get_task(&paused_id).unwrap();
+    assert_eq!(paused.status, TaskStatus::Paused);
+    assert_eq!(paused.progress, 35.0);
+    assert_eq!(paused.resume.as_ref(), Some(&resume));
+
+    let running = task_manager.get_task(&running_id).unwrap();
+    assert_eq!(running.status, TaskStatus::Pending);
+    assert_eq!(running.progress, 35.0);
+    assert_eq!(running.resume.as_ref(), Some(&resume));
+    assert!(task_manager.get_queue().contains(&running_id));
+}
+
+// Test case for deleting the segments of an encode that will not be continued
+#[test]
+fn test_resume_point_discard_deletes_segments() {
+    let dir = tempfile::tempdir().unwrap();
+    let segments: Vec<String> = (0..3)
+        .map(|i| dir.path().join(format!("output_0.seg{}.mp4", i)).to_string_lossy().into_owned())
+        .collect();
+    // The last segment was never written, e.g. the encode stopped before its first frame
+    for segment in &segments[..2] {
+        std::fs::write(segment, b"segment").unwrap();
+    }
+
+    let resume = ResumePoint { segments: segments.clone(), encoded_secs: 12.5, ..ResumePoint::default() };
+    resume.discard();
+
+    assert!(segments.iter().all(|segment| !std::path::Path::new(segment).exists()));
+    // Discarding again is harmless
+    resume.discard();
+}
+
+// Test case for the queue-drained summary
+#[test]
+fn test_queue_drained_event_after_last_task() {
+    let dir = tempfile::tempdir().unwrap();
+    let task_manager = TaskManager::new(2);
+
+    let task_ids: Vec<String> = (0..3).map(|i| create_task(&task_manager, &dir, i)).collect();
+    task_manager
+        .update_task(&task_ids[0], |task| task.status = TaskStatus::Completed)
+        .unwrap();
+    task_manager
+        .update_task(&task_ids[1], |task| task.status = TaskStatus::Failed)
+        .unwrap();
+
+    // One task is still pending
+    assert!(task_manager.queue_drained_event().is_none());
+
+    task_manager
+        .update_task(&task_ids[2], |task| task.status = TaskStatus::Canceled)
+        .unwrap();
+
+    match task_manager.queue_drained_event() {
+        Some(TaskEvent::QueueDrained { completed, failed, canceled }) => {
+            assert_eq!((completed, failed, canceled), (1, 1, 1));
+        }
+        other => panic!("Expected a queue-drained event, got {:?}", other),
+    }
+}
+
+// Test case for progress event throttling
+#[test]
+fn test_progress_throttle_always_reports_completion() {
+    let mut throttle = ProgressThrottle::new(Duration::from_secs(60));
+
+    assert!(throttle.should_report(1.0));
+    assert!(!throttle.should_report(2.0));
+    assert!(!throttle.should_report(99.9));
+    assert!(throttle.should_report(100.0));
+}
+
+// Test case for splitting the cores between running tasks while keeping one free
+#[test]
+fn test_threads_per_task_splits_cores_and_keeps_one() {
+    let all_cores = threads_per_task(1);
+
+    assert!(all_cores >= 1);
+    assert!(threads_per_task(2) <= all_cores);
+    assert_eq!(threads_per_task(all_cores * 4), 1);
+    assert_eq!(threads_per_task(0), all_cores);
+}
+
+// Helper to build a task config whose codec does not fit its output format
+fn mismatched_codec_config() -> HashMap<String, String> {
+    HashMap::from([
+        ("output_format".to_string(), "webm".to_string()),
+        ("cpu_codec".to_string(), "libx264".to_string()),
+    ])
+}
+
+// Test case for a preset codec the output format cannot hold
+#[test]
+fn test_processing_options_replace_codec_the_format_cannot_hold() {
+    let warnings = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let handler_warnings = warnings.clone();
+    let options = with_warning_handler(
+        Box::new(move |message: &str| handler_warnings.borrow_mut().push(message.to_string())),
+        || create_processing_options(&mismatched_codec_config()),
+    )
+    .expect("Options should be created");
+
+    // The container's default codec is encoded instead of copying the streams
+    assert!(options.cpu_codec.is_none());
+    assert!(!options.use_gpu);
+    assert!(options.reencode);
+    assert!(options.validate().is_ok());
+
+    let warnings = warnings.borrow();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("webm"), "unexpected warning: {}", warnings[0]);
+}
+
+// Test case for the strict flag keeping a mismatched codec so the task fails
+#[test]
+fn test_processing_options_strict_rejects_codec_the_format_cannot_hold() {
+    let mut config = mismatched_codec_config();
+    config.insert("strict".to_string(), "true".to_string());
+
+    let options = create_processing_options(&config).expect("Options should be created");
+
+    assert_eq!(options.cpu_codec.as_deref(), Some("libx264"));
+    assert!(!options.reencode);
+    assert!(matches!(options.validate(), Err(VideoError::Codec(_))));
+}
+
+// Test case for odd output sizes, checked against the output pixel format
+#[test]
+fn test_processing_options_odd_size_follows_pixel_format() {
+    let config = |width: &str, pixel_format: Option<&str>| {
+        let mut config = HashMap::from([
+            ("width".to_string(), width.to_string()),
+            ("height".to_string(), "720".to_string()),
+        ]);
+        if let Some(pixel_format) = pixel_format {
+            config.insert("pixel_format".to_string(), pixel_format.to_string());
+        }
+        create_processing_options(&config).expect("Options should be created")
+    };
+
+    // 4:2:0 is assumed without a pixel format, and 0 is never suggested
+    let message = config("1", None).validate().unwrap_err().to_string();
+    assert!(message.contains("yuv420p"), "unexpected message: {}", message);
+    assert!(message.contains("use 2"), "unexpected message: {}", message);
+    assert!(!message.contains("use 0"), "unexpected message: {}", message);
+
+    // 4:4:4 has full-resolution chroma and takes any size
+    assert!(config("1281", Some("yuv444p")).validate().is_ok());
+}
+
+// Test case for edit and sanitize values that cannot be parsed, which fail the task
+#[test]
+fn test_processing_options_reject_unparsable_values() {
+    let options = |key: &str, value: &str| {
+        create_processing_options(&HashMap::from([(key.to_string(), value.to_string())]))
+    };
+
+    for (key, value) in [
+        ("start_time", "1:30"),
+        ("end_time", "end"),
+        ("denoise_strength", "strong"),
+        ("audio_volume", "loud"),
+        ("crop", "0,0,1280"),
+        ("blur_regions", "0,0,100,100;10,10,abc,20"),
+    ] {
+        assert!(
+            matches!(options(key, value), Err(TaskError::ProcessingFailed(_))),
+            "{}={} should be rejected",
+            key,
+            value
+        );
+    }
+
+    let regions = options("blur_regions", "0,0,100,100; 10,10,20,20;")
+        .expect("Options should be created")
+        .blur_regions;
+    assert_eq!(regions, Some(vec![(0, 0, 100, 100), (10, 10, 20, 20)]));
+    assert_eq!(options("start_time", "1.5").unwrap().start_time, Some(1.5));
+}
+
+// Test case for failures that retrying cannot fix
+#[test]
+fn test_retry_delay_skips_missing_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let task_manager = TaskManager::new(2);
+
+    let task_id = create_task(&task_manager, &dir, 0);
+    task_manager
+        .update_task(&task_id, |task| {
+            task.attempts = 1;
+            task.max_retries = 3;
+        })
+        .unwrap();
+
+    let failed = TaskError::ProcessingFailed("encoder error".to_string());
+    assert_eq!(task_manager.retry_delay(&task_id, &failed), Some(Duration::from_secs(1)));
+
+    let missing = TaskError::InputNotFound("input_0.mp4".to_string());
+    assert_eq!(task_manager.retry_delay(&task_id, &missing), None);
+    assert_eq!(task_manager.retry_delay(&task_id, &TaskError::Canceled), None);
+}
+
+// Test case for recognizing partial outputs and their resume segments
+#[test]
+fn test_is_partial_output_path() {
+    let partial = partial_output_path("/videos/clip.mp4");
+    assert!(is_partial_output_path(Path::new(&partial)));
+    assert!(is_partial_output_path(Path::new("/videos/clip.part.seg0.mp4")));
+    assert!(is_partial_output_path(Path::new("/videos/clip.part.seg12.mp4")));
+
+    assert!(!is_partial_output_path(Path::new("/videos/clip.mp4")));
+    assert!(!is_partial_output_path(Path::new("/videos/clip.seg0.mp4")));
+    assert!(!is_partial_output_path(Path::new("/videos/clip.part.segment.mp4")));
+    assert!(!is_partial_output_path(Path::new("/videos/spare.parts.mp4")));
+}
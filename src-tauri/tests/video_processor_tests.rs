@@ -0,0 +1,52 @@
+use vid_kit_simple_lib::services::video_processor::{bitrate_for_target_size, cap_upscale};
+
+// Test case for sizes within the upscale limit, which are kept
+#[test]
+fn test_cap_upscale_keeps_allowed_sizes() {
+    // Downscaling and exactly the limit
+    assert_eq!(cap_upscale(1280, 720, 1920, 1080, 2.0), None);
+    assert_eq!(cap_upscale(3840, 2160, 1920, 1080, 2.0), None);
+    // A side computed from the aspect ratio may round slightly past the limit
+    assert_eq!(cap_upscale(3840, 2162, 1920, 1080, 2.002), None);
+}
+
+// Test case for sizes beyond the upscale limit, which are scaled down to it
+#[test]
+fn test_cap_upscale_limits_to_factor() {
+    // 4x upscale of 640x360 with a 2x limit
+    assert_eq!(cap_upscale(2560, 1440, 640, 360, 2.0), Some((1280, 720)));
+
+    // The larger side ratio decides, the requested aspect ratio is kept
+    assert_eq!(cap_upscale(1920, 1080, 640, 720, 1.0), Some((640, 360)));
+
+    // 1.0 allows no upscaling at all
+    assert_eq!(cap_upscale(1920, 1080, 1280, 720, 1.0), Some((1280, 720)));
+}
+
+// Test case for capped sizes, which stay even and at least 2 pixels
+#[test]
+fn test_cap_upscale_rounds_to_even_sizes() {
+    let (width, height) = cap_upscale(1920, 1080, 101, 57, 1.0).expect("Size should be capped");
+    assert_eq!((width % 2, height % 2), (0, 0));
+    assert!(width <= 101 && height <= 57);
+
+    assert_eq!(cap_upscale(1000, 2, 1, 1, 1.0), Some((2, 2)));
+}
+
+// Test case for turning a target size into a video bitrate
+#[test]
+fn test_bitrate_for_target_size() {
+    // 10 MiB over a minute, less 2% muxing overhead and 128 kbps of audio
+    assert_eq!(bitrate_for_target_size(10, 60.0), Some(1_242_687));
+
+    // Twice the duration leaves less than half for the video, the audio stays fixed
+    let half = bitrate_for_target_size(10, 120.0).unwrap();
+    assert!(half < 1_242_687 / 2);
+
+    // Sizes too small for the duration get the lowest usable bitrate
+    assert_eq!(bitrate_for_target_size(1, 60.0), Some(100_000));
+
+    // Without a duration there is nothing to divide by
+    assert_eq!(bitrate_for_target_size(10, 0.0), None);
+    assert_eq!(bitrate_for_target_size(10, -1.0), None);
+}
@@ -2,6 +2,7 @@ import { useEffect } from 'react';
 import { listen } from '@tauri-apps/api/event';
 import { useAppStore, useConfigStore, usePresetsStore, useTasksStore } from '../../store';
 import { useNotifications } from '../../hooks';
+import { TaskFailedEventPayload, TaskIdEventPayload } from '../../types';
 
 /**
  * Component to initialize application state and set up event listeners
@@ -66,8 +67,8 @@ export function AppInitializer() {
   useEffect(() => {
     const setupEventListeners = async () => {
       // Listen for task events
-      const unlistenTaskCompleted = await listen('task-completed', (event) => {
-        const taskId = event.payload as string;
+      const unlistenTaskCompleted = await listen<TaskIdEventPayload>('task-completed', (event) => {
+        const { taskId } = event.payload;
         addNotification({
           id: crypto.randomUUID(),
           type: 'success',
@@ -78,8 +79,8 @@ export function AppInitializer() {
         });
       });
 
-      const unlistenTaskFailed = await listen('task-failed', (event) => {
-        const { taskId, error } = event.payload as { taskId: string; error: string };
+      const unlistenTaskFailed = await listen<TaskFailedEventPayload>('task-failed', (event) => {
+        const { taskId, error } = event.payload;
         addNotification({
           id: crypto.randomUUID(),
           type: 'error',
@@ -194,6 +194,8 @@ export function TaskList() {
     switch (type) {
       case 'convert':
         return 'Convert';
+      case 'remux':
+        return 'Remux';
       case 'split':
         return 'Split';
       case 'edit':
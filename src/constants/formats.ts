@@ -0,0 +1,40 @@
+/**
+ * Input file extensions the backend accepts (keep in sync with `VIDEO_EXTENSIONS` in utils/media.rs)
+ */
+export const INPUT_EXTENSIONS = [
+  'mp4', 'm4v', 'mov', 'mkv', 'webm', 'avi', 'wmv', 'asf', 'flv', 'mpg', 'mpeg', 'ts', 'm2ts',
+  'mts', '3gp', 'ogv', 'vob',
+] as const;
+
+/**
+ * Output containers the backend can write (see `choose_codec` in the video processor)
+ */
+export const OUTPUT_FORMATS = ['mp4', 'mkv', 'webm', 'avi', 'mov', 'flv', 'gif'] as const;
+
+/**
+ * Video encoders the backend maps to a codec (CPU first, then GPU)
+ */
+export const VIDEO_CODECS = [
+  'libx264', 'libx264rgb', 'libx265', 'libvpx', 'libvpx-vp9', 'libaom-av1', 'libsvtav1',
+  'libxvid', 'mpeg2video', 'mjpeg', 'png', 'gif',
+  'h264_nvenc', 'hevc_nvenc', 'av1_nvenc',
+  'h264_qsv', 'hevc_qsv', 'av1_qsv',
+  'h264_amf', 'hevc_amf', 'av1_amf',
+  'h264_videotoolbox', 'hevc_videotoolbox',
+] as const;
+
+/**
+ * Largest output width/height accepted by the backend
+ */
+export const MAX_DIMENSION = 16384;
+
+/**
+ * Highest output frame rate accepted by the backend
+ */
+export const MAX_FRAMERATE = 240;
+
+/**
+ * Accepted preset bitrate range in kbps
+ */
+export const MIN_BITRATE_KBPS = 100;
+export const MAX_BITRATE_KBPS = 200000;
@@ -3,7 +3,7 @@ import { videoService } from '../../../services';
 import { useError } from '../../../hooks';
 import { ProcessingOptions } from '../../../types';
 import { ErrorCategory } from '../../../utils';
-import { useTasksStore, usePresetsStore } from '../../../store';
+import { useAppStore, useTasksStore, usePresetsStore } from '../../../store';
 
 export interface ConversionOptions {
   outputFormat: string;
@@ -95,10 +95,15 @@ export const useConversionLogic = () => {
 
       // 7. Determine codec based on GPU/CPU choice
       if (processingOptions.use_gpu) {
-        processingOptions.gpu_codec = await videoService.getGpuCodec(processingOptions.output_format);
+        const gpuIndex = useAppStore.getState().appInfo?.selected_gpu_index ?? -1;
+        processingOptions.gpu_codec = await videoService.getGpuCodec(processingOptions.output_format, gpuIndex);
         console.log("Using GPU codec:", processingOptions.gpu_codec);
       } else {
-        processingOptions.cpu_codec = videoService.getCpuCodec(processingOptions.output_format);
+        // A codec chosen in the preferences for this container is applied by the backend
+        const defaultCodecs = await videoService.getDefaultCodecs();
+        processingOptions.cpu_codec = defaultCodecs[processingOptions.output_format]
+          ? undefined
+          : videoService.getCpuCodec(processingOptions.output_format);
         console.log("Using CPU codec:", processingOptions.cpu_codec);
       }
 
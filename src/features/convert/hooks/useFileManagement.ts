@@ -4,6 +4,7 @@ import { useError } from '../../../hooks';
 import { ErrorCategory } from '../../../utils';
 import { FileItemData } from '../components/FileList/types';
 import { useFilesStore } from '../../../store';
+import { INPUT_EXTENSIONS } from '../../../constants/formats';
 
 export const useFileManagement = (loadVideoInfo: (path: string) => Promise<any>) => {
   const [isDragging, setIsDragging] = useState<boolean>(false);
@@ -12,7 +13,7 @@ export const useFileManagement = (loadVideoInfo: (path: string) => Promise<any>)
   const dropZoneRef = useRef<HTMLDivElement>(null);
 
   // Use the new files store
-  const { addFile, updateFile, removeFile, selectFile } = useFilesStore();
+  const { addFile, addFiles, removeFile, selectFile } = useFilesStore();
 
   // Handle drag and drop
   const handleDragOver = (e: React.DragEvent) => {
@@ -34,7 +35,7 @@ export const useFileManagement = (loadVideoInfo: (path: string) => Promise<any>)
     const videoFiles = droppedFiles.filter(
       file =>
         file.type.startsWith('video/') ||
-        ['.mp4', '.mkv', '.avi', '.webm', '.mov'].some(ext => file.name.toLowerCase().endsWith(ext))
+        INPUT_EXTENSIONS.some(ext => file.name.toLowerCase().endsWith(`.${ext}`))
     );
 
     if (videoFiles.length === 0) {
@@ -43,34 +44,22 @@ export const useFileManagement = (loadVideoInfo: (path: string) => Promise<any>)
       return;
     }
 
-    // Add each file to global state
-    for (const file of videoFiles) {
-      const fileId = await addFile({
-        name: file.name,
+    // Probe all files in parallel in the backend and add them in one update
+    const probed = await videoService.probeFilesForList(
+      videoFiles.map(file => ({
         path: file.path || '',
+        name: file.name,
         size: file.size,
         type: file.type || 'video/mp4'
-      });
-
-      // Load video info for the file
-      const videoInfo = await loadVideoInfo(file.path || '');
-
-      // Update file with video info
-      if (videoInfo) {
-        await updateFile(fileId, {
-          duration: videoInfo.duration,
-          resolution: videoInfo.resolution ? {
-            width: videoInfo.resolution[0],
-            height: videoInfo.resolution[1]
-          } : undefined,
-          thumbnail: videoInfo.thumbnail
-        });
-      }
+      }))
+    );
+
+    if (probed && probed.files.length > 0) {
+      const fileIds = await addFiles(probed.files);
 
       // Select the first file
-      if (videoFiles[0] === file) {
-        await selectFile(fileId);
-      }
+      await selectFile(fileIds[0]);
+      await loadVideoInfo(probed.files[0].path);
     }
 
     setIsUploading(false);
@@ -78,28 +67,19 @@ export const useFileManagement = (loadVideoInfo: (path: string) => Promise<any>)
 
   // Add file to list
   const addFileToList = async (filePath: string, fileName: string, fileSize: number, fileType: string) => {
+    // Probe the file in the backend so duration and resolution come back with the entry
+    const fileInfo = await videoService.probeFileForList(filePath, fileName, fileSize, fileType || 'video/mp4');
+
     // Add file to global state
-    const fileId = await addFile({
+    const fileId = await addFile(fileInfo ?? {
       name: fileName,
       path: filePath,
       size: fileSize,
       type: fileType || 'video/mp4'
     });
 
-    // Load video information
-    const videoInfo = await loadVideoInfo(filePath);
-
-    // Update file with video info
-    if (videoInfo) {
-      await updateFile(fileId, {
-        duration: videoInfo.duration,
-        resolution: videoInfo.resolution ? {
-          width: videoInfo.resolution[0],
-          height: videoInfo.resolution[1]
-        } : undefined,
-        thumbnail: videoInfo.thumbnail
-      });
-    }
+    // Load video information for the details panel
+    await loadVideoInfo(filePath);
 
     // Select the file
     await selectFile(fileId);
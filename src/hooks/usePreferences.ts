@@ -14,6 +14,7 @@ export function usePreferences() {
     theme,
     defaultFormat,
     useGpu,
+    outputFilenameTemplate,
     isLoading,
     error,
     loadConfig,
@@ -25,7 +26,8 @@ export function usePreferences() {
     setRetryLimit,
     setSelectedGpu,
     setDefaultFormat,
-    setUseGpu
+    setUseGpu,
+    setOutputFilenameTemplate
   } = useConfigStore();
 
   // Load config on mount
@@ -42,7 +44,8 @@ export function usePreferences() {
       selectedGpu,
       theme,
       defaultFormat,
-      useGpu
+      useGpu,
+      outputFilenameTemplate
     },
     isLoading,
     error,
@@ -57,6 +60,7 @@ export function usePreferences() {
     setSelectedGpu,
     setDefaultFormat,
     setUseGpu,
+    setOutputFilenameTemplate,
     refreshPreferences: loadConfig
   };
 }
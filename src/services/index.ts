@@ -2,3 +2,6 @@ export { default as videoService } from './videoService';
 export { default as presetService } from './presetService';
 export { logService } from './logService';
 export { tauriLogService } from './tauriLogService';
+export { watchService } from './watchService';
+export { notificationService } from './notificationService';
+export { powerService } from './powerService';
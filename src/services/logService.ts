@@ -2,6 +2,19 @@ import { invoke } from '@tauri-apps/api/core';
 import { BaseService } from './baseService';
 import { ErrorCategory } from '../utils';
 
+/**
+ * Log levels accepted by the backend
+ */
+export type LogLevel = 'off' | 'error' | 'warn' | 'info' | 'debug' | 'trace';
+
+/**
+ * Limits on the log files kept in the log directory
+ */
+export interface LogRetention {
+  max_log_files: number; // rotated files kept besides the current one
+  max_total_log_size: number; // bytes, current and rotated files together
+}
+
 /**
  * Service for accessing application logs
  */
@@ -35,6 +48,65 @@ export class LogService extends BaseService {
     );
   }
 
+  /**
+   * Change the log level at runtime; the level is kept across restarts
+   * @param level One of off, error, warn, info, debug or trace
+   * @returns The level now in effect or null if an error occurred
+   */
+  async setLogLevel(level: LogLevel): Promise<LogLevel | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<LogLevel>('set_log_level', { level });
+      },
+      `Failed to set log level to ${level}`,
+      ErrorCategory.Other
+    );
+  }
+
+  /**
+   * Get the log level currently in effect
+   * @returns The log level or null if an error occurred
+   */
+  async getLogLevel(): Promise<LogLevel | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<LogLevel>('get_log_level');
+      },
+      'Failed to get log level',
+      ErrorCategory.Other
+    );
+  }
+
+  /**
+   * Change the log retention limits; the oldest rotated files beyond them are deleted
+   * @returns The number of deleted log files or null if an error occurred
+   */
+  async setLogRetention(retention: LogRetention): Promise<number | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<number>('set_log_retention', {
+          maxLogFiles: retention.max_log_files,
+          maxTotalLogSize: retention.max_total_log_size,
+        });
+      },
+      'Failed to set log retention',
+      ErrorCategory.IO
+    );
+  }
+
+  /**
+   * Get the log retention limits in effect
+   */
+  async getLogRetention(): Promise<LogRetention | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<LogRetention>('get_log_retention');
+      },
+      'Failed to get log retention',
+      ErrorCategory.Other
+    );
+  }
+
   /**
    * Open the log directory in the file explorer
    * @returns True if the log directory was opened successfully, false otherwise
@@ -0,0 +1,42 @@
+import { invoke } from '@tauri-apps/api/core';
+import { BaseService } from './baseService';
+import { ErrorCategory } from '../utils';
+
+/**
+ * Service for the desktop notification shown when the task queue finishes
+ */
+export class NotificationService extends BaseService {
+  /**
+   * Turn the queue-finished notification on or off; the setting is kept across restarts
+   * @param enabled Whether to show the notification
+   * @returns True if the setting was saved, false otherwise
+   */
+  async setNotifyOnComplete(enabled: boolean): Promise<boolean | null> {
+    return this.withErrorHandling(
+      async () => {
+        await invoke('set_notify_on_complete', { enabled });
+        return true;
+      },
+      'Failed to save notification setting',
+      ErrorCategory.State,
+      false
+    );
+  }
+
+  /**
+   * Check whether the queue-finished notification is enabled
+   * @returns The setting or null if an error occurred
+   */
+  async getNotifyOnComplete(): Promise<boolean | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<boolean>('get_notify_on_complete');
+      },
+      'Failed to read notification setting',
+      ErrorCategory.State
+    );
+  }
+}
+
+// Create singleton instance
+export const notificationService = new NotificationService();
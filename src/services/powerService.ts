@@ -0,0 +1,50 @@
+import { invoke } from '@tauri-apps/api/core';
+import { BaseService } from './baseService';
+import { ErrorCategory } from '../utils';
+
+/**
+ * Payload of the `queue-auto-paused` event
+ */
+export interface QueueAutoPausedPayload {
+  /** Why the queue was paused, e.g. "on_battery" */
+  reason: string;
+}
+
+/**
+ * Service for pausing the task queue on battery power
+ */
+export class PowerService extends BaseService {
+  /**
+   * Turn the automatic queue pause on battery power on or off; the setting is kept across restarts
+   * @param enabled Whether to pause the queue on battery and resume it on AC power
+   * @returns True if the setting was saved, false otherwise
+   */
+  async setPauseOnBattery(enabled: boolean): Promise<boolean | null> {
+    return this.withErrorHandling(
+      async () => {
+        await invoke('set_pause_on_battery', { enabled });
+        return true;
+      },
+      'Failed to save battery setting',
+      ErrorCategory.State,
+      false
+    );
+  }
+
+  /**
+   * Check whether the queue is paused automatically on battery power
+   * @returns The setting or null if an error occurred
+   */
+  async getPauseOnBattery(): Promise<boolean | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<boolean>('get_pause_on_battery');
+      },
+      'Failed to read battery setting',
+      ErrorCategory.State
+    );
+  }
+}
+
+// Create singleton instance
+export const powerService = new PowerService();
@@ -3,7 +3,7 @@ import { ProcessingOptions } from '../types/video.types';
 import { Preset } from '../types/preset.types';
 import { ErrorCategory } from '../utils';
 import { usePresetsStore } from '../store/presets.store';
-import { ConversionPreset } from '../types/store.types';
+import { ConversionPreset, PresetConflictPolicy, PresetImportResult } from '../types/store.types';
 
 // Preset service for managing conversion presets
 
@@ -23,6 +23,23 @@ class PresetService extends BaseService {
     ) || [];
   }
 
+  /**
+   * Get presets carrying a tag (case-insensitive), e.g. "Web" or "Archive"
+   */
+  async listPresetsByTag(tag: string): Promise<ConversionPreset[]> {
+    return this.withErrorHandling(
+      async () => {
+        if (usePresetsStore.getState().presets.length === 0) {
+          await usePresetsStore.getState().loadPresets();
+        }
+        return usePresetsStore.getState().listPresetsByTag(tag);
+      },
+      `Failed to list presets tagged "${tag}"`,
+      ErrorCategory.Preset,
+      []
+    ) as Promise<ConversionPreset[]>;
+  }
+
   /**
    * Get preset information by name
    */
@@ -141,6 +158,46 @@ class PresetService extends BaseService {
     return result === null ? false : result;
   }
 
+  /**
+   * Copy a preset under a new id, named "<name> (copy)" unless a name is given
+   */
+  async duplicatePreset(id: string, newName?: string): Promise<ConversionPreset | null> {
+    return this.withErrorHandling(
+      () => usePresetsStore.getState().duplicatePreset(id, newName),
+      `Failed to duplicate preset "${id}"`,
+      ErrorCategory.Preset
+    );
+  }
+
+  /**
+   * Write all presets to a single JSON bundle file
+   */
+  async exportPresets(path: string): Promise<boolean> {
+    const result = await this.withErrorHandling(
+      async () => {
+        await usePresetsStore.getState().exportPresets(path);
+        return true;
+      },
+      `Failed to export presets to "${path}"`,
+      ErrorCategory.Preset
+    );
+    return result === null ? false : result;
+  }
+
+  /**
+   * Import presets from a bundle file written by exportPresets
+   */
+  async importPresets(
+    path: string,
+    onConflict: PresetConflictPolicy = 'skip'
+  ): Promise<PresetImportResult | null> {
+    return this.withErrorHandling(
+      () => usePresetsStore.getState().importPresets(path, onConflict),
+      `Failed to import presets from "${path}"`,
+      ErrorCategory.Preset
+    );
+  }
+
   /**
    * Convert ProcessingOptions resolution to ConversionPreset resolution
    */
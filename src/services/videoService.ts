@@ -5,7 +5,8 @@ import { mkdir, exists } from '@tauri-apps/plugin-fs';
 
 import { BaseService } from './baseService';
 import { ErrorCategory } from '../utils';
-import { GpuInfo, ProcessingOptions, VideoInfo } from '../types';
+import { FfmpegCapabilities, FileInfo, ProcessingOptions, QualityScore, VideoInfo } from '../types';
+import { INPUT_EXTENSIONS } from '../constants/formats';
 
 class VideoService extends BaseService {
   /**
@@ -18,7 +19,7 @@ class VideoService extends BaseService {
           multiple: false,
           filters: [{
             name: 'Video',
-            extensions: ['mp4', 'avi', 'mkv', 'mov', 'wmv', 'webm']
+            extensions: [...INPUT_EXTENSIONS]
           }]
         });
 
@@ -68,6 +69,230 @@ class VideoService extends BaseService {
     );
   }
 
+  /**
+   * Build a file list entry with duration and resolution probed by the backend
+   */
+  async probeFileForList(
+    filePath: string,
+    fileName: string,
+    fileSize: number,
+    fileType: string
+  ): Promise<Omit<FileInfo, 'id'> | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<Omit<FileInfo, 'id'>>('add_file_to_list', {
+          path: filePath,
+          name: fileName,
+          size: fileSize,
+          fileType
+        });
+      },
+      'Failed to add file to list',
+      ErrorCategory.IO
+    );
+  }
+
+  /**
+   * Probe many files at once in the backend, e.g. a dropped folder
+   * @returns The list entries in input order and a warning per skipped or unreadable file
+   */
+  async probeFilesForList(
+    files: { path: string; name: string; size: number; type: string }[]
+  ): Promise<{ files: Omit<FileInfo, 'id'>[]; warnings: string[] } | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<{ files: Omit<FileInfo, 'id'>[]; warnings: string[] }>('add_files_to_list', { files });
+      },
+      'Failed to add files to list',
+      ErrorCategory.IO
+    );
+  }
+
+  /**
+   * Check whether a video codec can be stored in an output container
+   * @param format Output container extension, e.g. "mp4"
+   * @param codec Encoder name ("libx264", "h264_nvenc") or codec name ("h264")
+   * @returns Whether the combination is valid and why not, or null if the codec is unknown
+   */
+  async checkOutputCompatibility(
+    format: string,
+    codec: string
+  ): Promise<{ compatible: boolean; reason: string | null } | null> {
+    return this.withErrorHandling(
+      async () => {
+        const reason = await invoke<string | null>('check_output_compatibility', { format, codec });
+        return { compatible: reason === null, reason };
+      },
+      'Failed to check output compatibility',
+      ErrorCategory.FFmpeg
+    );
+  }
+
+  /**
+   * Extract the audio track of a video into an mp3, aac, wav or flac file
+   */
+  async extractAudio(
+    inputPath: string,
+    outputPath: string,
+    format: 'mp3' | 'aac' | 'wav' | 'flac'
+  ): Promise<void | null> {
+    return this.withErrorHandling(
+      async () => {
+        await invoke<void>('extract_audio', { inputPath, outputPath, format });
+      },
+      'Failed to extract audio',
+      ErrorCategory.FFmpeg
+    );
+  }
+
+  /**
+   * Export frames of a video as numbered images (frame_000001.png, ...)
+   *
+   * Pass either `fps` or `everyNth`; with neither, every frame is exported.
+   * Returns the number of images written.
+   */
+  async exportFrames(
+    inputPath: string,
+    outputDir: string,
+    format: 'png' | 'jpg' | 'jpeg',
+    sampling: { fps?: number; everyNth?: number } = {}
+  ): Promise<number | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<number>('export_frames', {
+          inputPath,
+          outputDir,
+          fps: sampling.fps ?? null,
+          everyNth: sampling.everyNth ?? null,
+          format,
+        });
+      },
+      'Failed to export frames',
+      ErrorCategory.FFmpeg
+    );
+  }
+
+  /**
+   * Encode a numbered image sequence (e.g. frame_%06d.png) into a video
+   *
+   * `config` takes the same keys as a task config and selects the codec and quality.
+   */
+  async framesToVideo(
+    pattern: string,
+    outputPath: string,
+    fps: number,
+    config: Record<string, string> = {}
+  ): Promise<void | null> {
+    return this.withErrorHandling(
+      async () => {
+        await invoke<void>('frames_to_video', { pattern, outputPath, fps, config });
+      },
+      'Failed to create video from frames',
+      ErrorCategory.FFmpeg
+    );
+  }
+
+  /**
+   * Score a converted video against its source with PSNR and SSIM, e.g. to compare CRF values.
+   * Decodes both files completely, so it takes a while for long videos.
+   */
+  async compareQuality(inputPath: string, outputPath: string): Promise<QualityScore | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<QualityScore>('compare_quality', { inputPath, outputPath });
+      },
+      'Failed to compare the output quality',
+      ErrorCategory.FFmpeg
+    );
+  }
+
+  /**
+   * Get the FFmpeg build, codec support and which hardware encoders open, for diagnostics.
+   * Takes a moment since every hardware encoder is opened once.
+   */
+  async getFfmpegCapabilities(): Promise<FfmpegCapabilities | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<FfmpegCapabilities>('get_ffmpeg_capabilities');
+      },
+      'Failed to get FFmpeg capabilities',
+      ErrorCategory.FFmpeg
+    );
+  }
+
+  /**
+   * Delete the cached file list thumbnails, e.g. when they look wrong
+   * @returns The number of deleted thumbnails, or null if an error occurred
+   */
+  async clearThumbnailCache(): Promise<number | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<number>('clear_thumbnail_cache');
+      },
+      'Failed to clear the thumbnail cache',
+      ErrorCategory.IO
+    );
+  }
+
+  /**
+   * Set how long probing a file may take before it is skipped
+   * @param timeoutSecs Seconds per probe, 0 for no limit
+   */
+  async setProbeTimeout(timeoutSecs: number): Promise<boolean> {
+    const result = await this.withErrorHandling(
+      async () => {
+        await invoke<void>('set_probe_timeout', { timeoutSecs });
+        return true;
+      },
+      'Failed to save the probe timeout',
+      ErrorCategory.Validation
+    );
+    return result === null ? false : result;
+  }
+
+  /**
+   * Get the probe timeout in seconds, 0 if there is no limit
+   */
+  async getProbeTimeout(): Promise<number | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<number>('get_probe_timeout');
+      },
+      'Failed to get the probe timeout',
+      ErrorCategory.Other
+    );
+  }
+
+  /**
+   * Stop the probes in progress; the files being probed are skipped
+   */
+  async cancelProbes(): Promise<boolean> {
+    const result = await this.withErrorHandling(
+      async () => {
+        await invoke<void>('cancel_probes');
+        return true;
+      },
+      'Failed to cancel probing',
+      ErrorCategory.Other
+    );
+    return result === null ? false : result;
+  }
+
+  /**
+   * Show a file, e.g. a finished output, selected in the OS file manager
+   */
+  async revealInExplorer(path: string): Promise<boolean> {
+    const result = await this.withErrorHandling(
+      async () => {
+        await invoke<void>('reveal_in_explorer', { path });
+        return true;
+      },
+      'Failed to show the file',
+      ErrorCategory.IO
+    );
+    return result === null ? false : result;
+  }
+
   /**
    * Create output directory for converted videos
    */
@@ -150,11 +375,12 @@ class VideoService extends BaseService {
 
   /**
    * Start a task using the new task system
+   * @param force Start it even while the queue is paused
    */
-  async startTask(taskId: string): Promise<boolean> {
+  async startTask(taskId: string, force = false): Promise<boolean> {
     const result = await this.withErrorHandling(
       async () => {
-        await invoke<void>('run_task', { taskId });
+        await invoke<void>('run_task', { taskId, force });
         return true;
       },
       'Failed to start task',
@@ -164,45 +390,80 @@ class VideoService extends BaseService {
   }
 
   /**
-   * Get information about GPU and supported codecs
+   * Get the hardware encoder for an output format on the selected GPU
+   * @param format Output container, e.g. "mp4"
+   * @param gpuIndex Index of the GPU, -1 for CPU
+   * @returns Encoder name such as "h264_nvenc", or undefined to encode on the CPU
    */
-  async getGpuInfo(): Promise<GpuInfo | null> {
-    return this.withErrorHandling(
+  async getGpuCodec(format: string, gpuIndex: number): Promise<string | undefined> {
+    const result = await this.withErrorHandling<string | undefined>(
       async () => {
-        return await invoke<GpuInfo>('check_gpu_availability');
+        const codec = await invoke<string | null>('get_recommended_codec', { format, gpuIndex });
+        return codec ?? undefined;
       },
-      'Failed to get GPU information',
+      'Failed to determine GPU codec',
       ErrorCategory.Gpu
     );
+    return result === null ? undefined : result;
   }
 
   /**
-   * Get GPU codec based on GPU information if available
+   * Set the codec used for each output container when a task names none
+   * @param codecs Container to codec, e.g. { mp4: 'hevc', webm: 'av1' }
    */
-  async getGpuCodec(format: string = 'mp4'): Promise<string | undefined> {
-    const result = await this.withErrorHandling<string | undefined>(
+  async setDefaultCodecs(codecs: Record<string, string>): Promise<boolean> {
+    const result = await this.withErrorHandling(
       async () => {
-        const gpuInfo = await this.getGpuInfo();
+        await invoke<void>('set_default_codecs', { codecs });
+        return true;
+      },
+      'Failed to save default codecs',
+      ErrorCategory.Validation
+    );
+    return result === null ? false : result;
+  }
 
-        if (!gpuInfo || !gpuInfo.isAvailable) {
-          return undefined;
-        }
+  /**
+   * Get the codec chosen for each output container
+   */
+  async getDefaultCodecs(): Promise<Record<string, string>> {
+    const result = await this.withErrorHandling(
+      async () => {
+        return await invoke<Record<string, string>>('get_default_codecs');
+      },
+      'Failed to get default codecs',
+      ErrorCategory.Other
+    );
+    return result ?? {};
+  }
 
-        // Logic for selecting codec based on GPU and format
-        if (gpuInfo.vendor.toLowerCase().includes('nvidia')) {
-          return format === 'mp4' ? 'h264_nvenc' : 'hevc_nvenc';
-        } else if (gpuInfo.vendor.toLowerCase().includes('intel')) {
-          return 'h264_qsv';
-        } else if (gpuInfo.vendor.toLowerCase().includes('amd')) {
-          return 'h264_amf';
-        }
+  /**
+   * Limit how far outputs may be upscaled; larger resolutions are scaled down with a task warning
+   * @param factor Times the source size an output may be, 1.0 for no upscaling
+   */
+  async setMaxUpscaleFactor(factor: number): Promise<boolean> {
+    const result = await this.withErrorHandling(
+      async () => {
+        await invoke<void>('set_max_upscale_factor', { factor });
+        return true;
+      },
+      'Failed to save the upscale limit',
+      ErrorCategory.Validation
+    );
+    return result === null ? false : result;
+  }
 
-        return undefined;
+  /**
+   * Get how many times larger than the source an output may be
+   */
+  async getMaxUpscaleFactor(): Promise<number | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<number>('get_max_upscale_factor');
       },
-      'Failed to determine GPU codec',
-      ErrorCategory.Gpu
+      'Failed to get the upscale limit',
+      ErrorCategory.Other
     );
-    return result === null ? undefined : result;
   }
 
   /**
@@ -0,0 +1,60 @@
+import { invoke } from '@tauri-apps/api/core';
+import { BaseService } from './baseService';
+import { ErrorCategory } from '../utils';
+import { WatchInfo, WatchTaskTemplate } from '../types';
+
+/**
+ * Service for watched folders that create tasks for new files
+ */
+export class WatchService extends BaseService {
+  /**
+   * Start watching a folder
+   * @param path Folder to watch
+   * @param taskTemplate Task created for each new video file
+   * @returns The new watch or null if an error occurred
+   */
+  async watchDirectory(path: string, taskTemplate: WatchTaskTemplate): Promise<WatchInfo | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<WatchInfo>('watch_directory', { path, taskTemplate });
+      },
+      `Failed to watch ${path}`,
+      ErrorCategory.IO
+    );
+  }
+
+  /**
+   * Stop a watch
+   * @param watchId Id returned by watchDirectory
+   * @returns True if the watch was stopped, false otherwise
+   */
+  async stopWatch(watchId: string): Promise<boolean | null> {
+    return this.withErrorHandling(
+      async () => {
+        await invoke('stop_watch', { watchId });
+        return true;
+      },
+      'Failed to stop watching folder',
+      ErrorCategory.IO,
+      false
+    );
+  }
+
+  /**
+   * Get the folders currently being watched
+   * @returns The active watches or an empty list if an error occurred
+   */
+  async listWatches(): Promise<WatchInfo[] | null> {
+    return this.withErrorHandling(
+      async () => {
+        return await invoke<WatchInfo[]>('list_watches');
+      },
+      'Failed to list watched folders',
+      ErrorCategory.IO,
+      []
+    );
+  }
+}
+
+// Create singleton instance
+export const watchService = new WatchService();
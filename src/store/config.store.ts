@@ -2,6 +2,7 @@ import { create } from 'zustand';
 import { Store } from '@tauri-apps/plugin-store';
 import { CONFIG_STORE_PATH, CONFIG_STORE_KEYS } from '../constants/stores';
 import { ConfigStore } from '../types/store.types';
+import { DEFAULT_OUTPUT_FILENAME_TEMPLATE } from '../utils/outputPath';
 
 interface ConfigState {
   outputFolder: string;
@@ -11,6 +12,7 @@ interface ConfigState {
   theme: 'light' | 'dark';
   defaultFormat: string;
   useGpu: boolean;
+  outputFilenameTemplate: string;
   isLoading: boolean;
   error: string | null;
 
@@ -25,6 +27,7 @@ interface ConfigState {
   setSelectedGpu: (gpu: number) => Promise<void>;
   setDefaultFormat: (format: string) => Promise<void>;
   setUseGpu: (use: boolean) => Promise<void>;
+  setOutputFilenameTemplate: (template: string) => Promise<void>;
 }
 
 // Default configuration values
@@ -36,6 +39,7 @@ const DEFAULT_CONFIG: Omit<ConfigState, 'isLoading' | 'error' | 'loadConfig' | '
   theme: 'light',
   defaultFormat: 'mp4',
   useGpu: false,
+  outputFilenameTemplate: DEFAULT_OUTPUT_FILENAME_TEMPLATE,
 };
 
 export const useConfigStore = create<ConfigState>((set, get) => ({
@@ -78,6 +82,7 @@ export const useConfigStore = create<ConfigState>((set, get) => ({
         theme: currentState.theme,
         defaultFormat: currentState.defaultFormat,
         useGpu: currentState.useGpu,
+        outputFilenameTemplate: currentState.outputFilenameTemplate,
         ...config
       };
 
@@ -136,5 +141,9 @@ export const useConfigStore = create<ConfigState>((set, get) => ({
 
   setUseGpu: async (useGpu) => {
     return get().saveConfig({ useGpu });
+  },
+
+  setOutputFilenameTemplate: async (outputFilenameTemplate) => {
+    return get().saveConfig({ outputFilenameTemplate });
   }
 }));
@@ -38,6 +38,7 @@ interface FilesState {
   // Actions
   loadFiles: () => Promise<void>;
   addFile: (file: Omit<FileInfo, 'id' | 'selected'>) => Promise<string>;
+  addFiles: (files: Omit<FileInfo, 'id' | 'selected'>[]) => Promise<string[]>;
   updateFile: (id: string, updates: Partial<FileInfo>) => Promise<void>;
   removeFile: (id: string) => Promise<void>;
   clearFiles: () => Promise<void>;
@@ -99,6 +100,32 @@ export const useFilesStore = create<FilesState>((set, get) => ({
     }
   },
 
+  addFiles: async (filesData) => {
+    set({ isLoading: true, error: null });
+    try {
+      const store = await getStore();
+
+      const added: FileInfo[] = filesData.map(fileData => ({
+        id: uuidv4(),
+        selected: false,
+        ...fileData
+      }));
+
+      // Add all files with a single save and state update
+      const files = [...get().files, ...added];
+      await store.set(FILES_STORE_KEYS.FILES, files);
+      await store.save();
+
+      set({ files, isLoading: false });
+
+      return added.map(file => file.id);
+    } catch (error) {
+      console.error('Failed to add files:', error);
+      set({ error: String(error), isLoading: false });
+      throw error;
+    }
+  },
+
   updateFile: async (id, updates) => {
     try {
       const files = [...get().files];
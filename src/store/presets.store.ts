@@ -1,7 +1,9 @@
 import { create } from 'zustand';
 import { Store } from '@tauri-apps/plugin-store';
-import { ConversionPreset } from '../types/store.types';
+import { readTextFile, writeTextFile } from '@tauri-apps/plugin-fs';
+import { ConversionPreset, PresetConflictPolicy, PresetImportResult } from '../types/store.types';
 import { PRESETS_STORE_PATH, PRESETS_STORE_KEYS } from '../constants/stores';
+import { validatePreset } from '../utils/presetValidation';
 import { v4 as uuidv4 } from 'uuid';
 
 // Create a store instance
@@ -28,10 +30,22 @@ interface PresetsState {
   createDefaultPresets: () => Promise<void>;
   selectPreset: (id: string | null) => void;
   createPreset: (name: string, description: string) => Promise<ConversionPreset>;
-  duplicatePreset: (id: string) => Promise<ConversionPreset | null>;
+  duplicatePreset: (id: string, newName?: string) => Promise<ConversionPreset>;
   getPresetById: (id: string) => ConversionPreset | undefined;
+  listPresetsByTag: (tag: string) => ConversionPreset[];
+  exportPresets: (path: string) => Promise<void>;
+  importPresets: (path: string, onConflict?: PresetConflictPolicy) => Promise<PresetImportResult>;
 }
 
+// Append " (2)", " (3)", ... until the name is not taken
+const uniqueName = (name: string, presets: ConversionPreset[]): string => {
+  let candidate = name;
+  for (let n = 2; presets.some(p => p.name === candidate); n++) {
+    candidate = `${name} (${n})`;
+  }
+  return candidate;
+};
+
 export const usePresetsStore = create<PresetsState>((set, get) => ({
   // State
   selectedPresetId: null,
@@ -53,6 +67,13 @@ export const usePresetsStore = create<PresetsState>((set, get) => ({
   },
 
   savePreset: async (preset) => {
+    // Reject presets that would break conversions later
+    const problem = validatePreset(preset);
+    if (problem) {
+      set({ error: problem });
+      throw new Error(`Invalid preset "${preset.name}": ${problem}`);
+    }
+
     set({ isLoading: true, error: null });
     try {
       const store = await getStore();
@@ -96,6 +117,73 @@ export const usePresetsStore = create<PresetsState>((set, get) => ({
     return get().presets.find(preset => preset.id === id);
   },
 
+  listPresetsByTag: (tag: string) => {
+    const wanted = tag.trim().toLowerCase();
+    return get().presets.filter(preset =>
+      (preset.tags ?? []).some(t => t.toLowerCase() === wanted)
+    );
+  },
+
+  exportPresets: async (path: string) => {
+    try {
+      // All presets are written as a single JSON array
+      await writeTextFile(path, JSON.stringify(get().presets, null, 2));
+    } catch (error) {
+      console.error('Failed to export presets:', error);
+      set({ error: String(error) });
+      throw error;
+    }
+  },
+
+  importPresets: async (path: string, onConflict: PresetConflictPolicy = 'skip') => {
+    set({ isLoading: true, error: null });
+    try {
+      const bundle: unknown = JSON.parse(await readTextFile(path));
+      if (!Array.isArray(bundle)) {
+        throw new Error('A preset bundle must be a JSON array of presets');
+      }
+
+      const presets = [...get().presets];
+      const result: PresetImportResult = { imported: [], skipped: [] };
+
+      for (const entry of bundle as Partial<ConversionPreset>[]) {
+        const name = typeof entry?.name === 'string' ? entry.name : '(unnamed)';
+
+        const preset = entry as ConversionPreset;
+        const problem = typeof entry === 'object' && entry !== null ? validatePreset(preset) : 'Not a preset';
+        if (problem) {
+          result.skipped.push({ name, reason: problem });
+          continue;
+        }
+
+        const index = presets.findIndex(p => p.id === preset.id);
+
+        if (index < 0) {
+          presets.push(preset);
+        } else if (onConflict === 'overwrite') {
+          presets[index] = preset;
+        } else if (onConflict === 'rename') {
+          presets.push({ ...preset, id: uuidv4(), name: uniqueName(preset.name, presets) });
+        } else {
+          result.skipped.push({ name, reason: 'A preset with the same id already exists' });
+          continue;
+        }
+        result.imported.push(name);
+      }
+
+      const store = await getStore();
+      await store.set(PRESETS_STORE_KEYS.PRESETS, presets);
+      await store.save();
+      set({ presets, isLoading: false });
+
+      return result;
+    } catch (error) {
+      console.error('Failed to import presets:', error);
+      set({ error: String(error), isLoading: false });
+      throw error;
+    }
+  },
+
   createPreset: async (name: string, description: string) => {
     const newPreset: ConversionPreset = {
       id: uuidv4(),
@@ -116,14 +204,23 @@ export const usePresetsStore = create<PresetsState>((set, get) => ({
     return newPreset;
   },
 
-  duplicatePreset: async (id: string) => {
-    const preset = get().presets.find(p => p.id === id);
-    if (!preset) return null;
+  duplicatePreset: async (id: string, newName?: string) => {
+    const { presets } = get();
+    const preset = presets.find(p => p.id === id);
+    if (!preset) {
+      throw new Error(`Preset not found: ${id}`);
+    }
+
+    // Never reuse an existing id, even for imported presets with uuid-like ids
+    let newId = uuidv4();
+    while (presets.some(p => p.id === newId)) {
+      newId = uuidv4();
+    }
 
     const duplicatedPreset: ConversionPreset = {
       ...preset,
-      id: uuidv4(),
-      name: `${preset.name} (Copy)`,
+      id: newId,
+      name: newName?.trim() || `${preset.name} (copy)`,
       created_at: new Date().toISOString(),
       updated_at: new Date().toISOString(),
     };
@@ -155,6 +252,7 @@ export const usePresetsStore = create<PresetsState>((set, get) => ({
         id: 'high-quality',
         name: 'High Quality',
         description: 'High quality conversion with H.264',
+        tags: ['Archive'],
         output_format: 'mp4',
         resolution: { type: 'preset', width: 1920, height: 1080 },
         bitrate: 12000,
@@ -169,6 +267,7 @@ export const usePresetsStore = create<PresetsState>((set, get) => ({
         id: 'web-optimized',
         name: 'Web Optimized',
         description: 'Optimized for web streaming',
+        tags: ['Web'],
         output_format: 'mp4',
         resolution: { type: 'preset', width: 1280, height: 720 },
         bitrate: 5000,
@@ -1,6 +1,13 @@
 import { create } from 'zustand';
 import { Store } from '@tauri-apps/plugin-store';
-import { Task, TaskStatus } from '../types/store.types';
+import {
+  Task,
+  TaskFailedEventPayload,
+  TaskIdEventPayload,
+  TaskProgressEventPayload,
+  TaskGroup,
+  TaskStatus,
+} from '../types/store.types';
 import { TASKS_STORE_PATH, TASKS_STORE_KEYS } from '../constants/stores';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
@@ -30,18 +37,29 @@ interface TasksState {
   updateTask: (id: string, updates: Partial<Task>) => Promise<void>;
   removeTask: (id: string) => Promise<void>;
   clearCompletedTasks: () => Promise<void>;
+  clearAllTasks: () => Promise<number>;
   reorderTasks: (newOrder: string[]) => Promise<void>;
+  moveTaskToFront: (id: string) => Promise<void>;
+  moveTaskToBack: (id: string) => Promise<void>;
 
   // Task operations
-  startTask: (id: string) => Promise<void>;
+  startTask: (id: string, force?: boolean) => Promise<void>; // force starts it while the queue is paused
   pauseTask: (id: string) => Promise<void>;
   resumeTask: (id: string) => Promise<void>;
   cancelTask: (id: string) => Promise<void>;
   retryTask: (id: string) => Promise<void>;
   startQueue: () => Promise<void>;
   pauseQueue: () => Promise<void>;
+  drainQueue: () => Promise<void>;
   cancelQueue: () => Promise<void>;
 
+  // Group operations, for tasks created together (e.g. a dropped folder)
+  pauseGroup: (groupId: string) => Promise<void>;
+  resumeGroup: (groupId: string) => Promise<void>;
+  cancelGroup: (groupId: string) => Promise<void>;
+  removeGroup: (groupId: string) => Promise<void>;
+  getGroups: () => Promise<TaskGroup[]>;
+
   // Getters
   getTaskById: (id: string) => Task | undefined;
   getPendingTasks: () => Task[];
@@ -129,13 +147,14 @@ export const useTasksStore = create<TasksState>((set, get) => ({
       console.log('Tasks loaded');
 
       // Set up event listeners for task updates
-      const unlistenTaskProgress = await listen('task-progress', (event) => {
-        const { taskId, progress } = event.payload as { taskId: string; progress: number };
-        get().updateTask(taskId, { progress });
+      const unlistenTaskProgress = await listen<TaskProgressEventPayload>('task-progress', (event) => {
+        const { taskId, progress, phase } = event.payload;
+        // A phase after the encode has its own progress, the task's stays where it is
+        get().updateTask(taskId, phase ? { phase, phase_progress: progress } : { progress });
       });
 
-      const unlistenTaskCompleted = await listen('task-completed', (event) => {
-        const taskId = event.payload as string;
+      const unlistenTaskCompleted = await listen<TaskIdEventPayload>('task-completed', (event) => {
+        const { taskId } = event.payload;
         get().updateTask(taskId, {
           status: TaskStatus.Completed,
           progress: 100,
@@ -143,8 +162,8 @@ export const useTasksStore = create<TasksState>((set, get) => ({
         });
       });
 
-      const unlistenTaskFailed = await listen('task-failed', (event) => {
-        const { taskId, error } = event.payload as { taskId: string; error: string };
+      const unlistenTaskFailed = await listen<TaskFailedEventPayload>('task-failed', (event) => {
+        const { taskId, error } = event.payload;
         get().updateTask(taskId, {
           status: TaskStatus.Failed,
           error,
@@ -244,16 +263,12 @@ export const useTasksStore = create<TasksState>((set, get) => ({
   removeTask: async (id) => {
     set({ isLoading: true, error: null });
     try {
-      // Get current state
+      // Cancels the task first if it is still running; the backend saves the change
+      await invoke('force_remove_task', { taskId: id });
+
       const tasks = get().tasks.filter(t => t.id !== id);
       const queue = get().queue.filter(taskId => taskId !== id);
 
-      // Save to store
-      const store = await getStore();
-      await store.set(TASKS_STORE_KEYS.TASKS, tasks);
-      await store.set(TASKS_STORE_KEYS.QUEUE, queue);
-      await store.save();
-
       // Update state
       set({ tasks, queue, isLoading: false });
     } catch (error) {
@@ -289,15 +304,37 @@ export const useTasksStore = create<TasksState>((set, get) => ({
     }
   },
 
+  clearAllTasks: async () => {
+    set({ isLoading: true, error: null });
+    try {
+      // Remove everything except running and paused tasks in the backend
+      const removed = await invoke<number>('clear_all_tasks');
+
+      const tasks = get().tasks.filter(
+        t => t.status === TaskStatus.Running || t.status === TaskStatus.Paused
+      );
+      const queue = get().queue.filter(id => tasks.some(t => t.id === id));
+
+      set({ tasks, queue, isLoading: false });
+      return removed;
+    } catch (error) {
+      console.error('Failed to clear all tasks:', error);
+      set({ error: String(error), isLoading: false });
+      return 0;
+    }
+  },
+
   reorderTasks: async (newOrder) => {
     set({ isLoading: true, error: null });
     try {
-      // Validate that all IDs in newOrder exist in the queue
+      // Validate that newOrder holds every queued ID exactly once
       const currentQueue = get().queue;
-      const isValid = newOrder.every(id => currentQueue.includes(id));
+      const isValid = newOrder.length === currentQueue.length
+        && new Set(newOrder).size === newOrder.length
+        && newOrder.every(id => currentQueue.includes(id));
 
       if (!isValid) {
-        throw new Error('Invalid task order: contains IDs not in the current queue');
+        throw new Error('Invalid task order: must contain each queued task exactly once');
       }
 
       // Save to store
@@ -316,10 +353,30 @@ export const useTasksStore = create<TasksState>((set, get) => ({
     }
   },
 
+  moveTaskToFront: async (id) => {
+    try {
+      const queue = await invoke<string[]>('move_task_to_front', { taskId: id });
+      set({ queue });
+    } catch (error) {
+      console.error(`Failed to move task ${id} to the front:`, error);
+      set({ error: String(error) });
+    }
+  },
+
+  moveTaskToBack: async (id) => {
+    try {
+      const queue = await invoke<string[]>('move_task_to_back', { taskId: id });
+      set({ queue });
+    } catch (error) {
+      console.error(`Failed to move task ${id} to the back:`, error);
+      set({ error: String(error) });
+    }
+  },
+
   // Task operations
-  startTask: async (id) => {
+  startTask: async (id, force = false) => {
     try {
-      await invoke('run_task', { taskId: id });
+      await invoke('run_task', { taskId: id, force });
 
       // Update local state
       get().updateTask(id, { status: TaskStatus.Running });
@@ -454,6 +511,16 @@ export const useTasksStore = create<TasksState>((set, get) => ({
     }
   },
 
+  drainQueue: async () => {
+    try {
+      // Running tasks keep going; the backend pauses the queue once they finish
+      await invoke('drain_queue');
+    } catch (error) {
+      console.error('Failed to drain queue:', error);
+      set({ error: String(error) });
+    }
+  },
+
   cancelQueue: async () => {
     try {
       await invoke('cancel_queue');
@@ -483,6 +550,57 @@ export const useTasksStore = create<TasksState>((set, get) => ({
     }
   },
 
+  // Group operations change many tasks at once, so the task list is reloaded afterwards
+  pauseGroup: async (groupId) => {
+    try {
+      await invoke<number>('pause_group', { groupId });
+      await get().refreshTasks();
+    } catch (error) {
+      console.error(`Failed to pause group ${groupId}:`, error);
+      set({ error: String(error) });
+    }
+  },
+
+  resumeGroup: async (groupId) => {
+    try {
+      await invoke<number>('resume_group', { groupId });
+      await get().refreshTasks();
+    } catch (error) {
+      console.error(`Failed to resume group ${groupId}:`, error);
+      set({ error: String(error) });
+    }
+  },
+
+  cancelGroup: async (groupId) => {
+    try {
+      await invoke<number>('cancel_group', { groupId });
+      await get().refreshTasks();
+    } catch (error) {
+      console.error(`Failed to cancel group ${groupId}:`, error);
+      set({ error: String(error) });
+    }
+  },
+
+  removeGroup: async (groupId) => {
+    try {
+      await invoke<number>('remove_group', { groupId });
+      await get().refreshTasks();
+    } catch (error) {
+      console.error(`Failed to remove group ${groupId}:`, error);
+      set({ error: String(error) });
+    }
+  },
+
+  getGroups: async () => {
+    try {
+      return await invoke<TaskGroup[]>('get_groups');
+    } catch (error) {
+      console.error('Failed to get task groups:', error);
+      set({ error: String(error) });
+      return [];
+    }
+  },
+
   // Getters
   getTaskById: (id) => {
     return get().tasks.find(task => task.id === id);
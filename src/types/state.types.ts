@@ -6,6 +6,18 @@ export interface GpuInfo {
   vendor: string;
   is_available: boolean;
   supported_codecs: string[];
+  capabilities: GpuCodecCapability[];
+}
+
+// Encode/decode support of a GPU for one codec
+export interface GpuCodecCapability {
+  codec: string; // e.g. "h264", "hevc", "av1"
+  encoder: string | null;
+  decoder: string | null;
+  can_encode: boolean;
+  can_decode: boolean;
+  max_width: number | null; // null if the limit is not known
+  max_height: number | null;
 }
 
 // General application information
@@ -40,9 +52,11 @@ export interface ConversionState {
 // User preferences
 export interface UserPreferencesState {
   default_output_dir: string | null;
+  output_filename_template: string;
   default_format: string;
   use_gpu: boolean;
   theme: string;
+  default_codecs: Record<string, string>; // output container to codec, e.g. { mp4: 'hevc' }
 }
 
 // Global state combining all states
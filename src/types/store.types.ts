@@ -1,4 +1,4 @@
-import { ProcessingOptions, ProcessingStatus } from './video.types';
+import { ProcessingOptions, ProcessingStatus, QualityScore, ResumePoint } from './video.types';
 
 /**
  * Represents a conversion preset
@@ -14,10 +14,24 @@ export interface ConversionPreset {
   codec?: string;
   use_gpu: boolean;
   audio_codec?: string;
+  tags?: string[]; // groups such as "Web" or "Archive", missing in older presets
   created_at: string;
   updated_at: string;
 }
 
+/**
+ * What to do when an imported preset has the same id as an existing one
+ */
+export type PresetConflictPolicy = 'skip' | 'overwrite' | 'rename';
+
+/**
+ * Outcome of importing a preset bundle
+ */
+export interface PresetImportResult {
+  imported: string[];
+  skipped: { name: string; reason: string }[];
+}
+
 /**
  * Resolution setting for a preset
  */
@@ -37,10 +51,133 @@ export interface Task {
   progress: number;
   error?: string;
   attempts: number;
+  priority?: number;
+  max_retries?: number;
+  partial_output_path?: string | null;
+  note?: string | null; // e.g. "Already compliant, copied without re-encoding"
+  group_id?: string | null; // shared by tasks created together
+  input_duration?: number | null; // seconds, weighs the task in the overall progress
+  fallbacks?: Record<string, string>[]; // config overrides tried in order when the task fails
+  fallback_used?: number | null; // index into fallbacks of the settings that succeeded
+  quality?: QualityScore | null; // set for completed tasks with compute_quality
+  phase?: string | null; // step after the encode from task-progress, e.g. "quality"; not saved
+  phase_progress?: number; // progress of `phase`, 0-100
+  resume?: ResumePoint | null; // segments kept by a pause, the next run continues after them
   created_at: string;
   completed_at?: string;
   config: ProcessingOptions;
-  type: 'convert' | 'split' | 'edit' | 'sanitize';
+  type: 'convert' | 'remux' | 'split' | 'edit' | 'sanitize';
+}
+
+/**
+ * Task counts and running progress returned by get_queue_stats
+ */
+export interface QueueStats {
+  pending: number;
+  running: number;
+  paused: number;
+  completed: number;
+  failed: number;
+  canceled: number;
+  total: number;
+  running_progress: number;
+  average_running_progress: number;
+  weighted_progress: number; // whole list weighted by input duration, 100 only once every task is finished
+  is_queue_paused: boolean;
+  is_draining: boolean;
+}
+
+/**
+ * Task counts and progress of one group returned by get_groups
+ */
+export interface TaskGroup {
+  group_id: string;
+  total: number;
+  pending: number;
+  running: number;
+  paused: number;
+  completed: number;
+  failed: number;
+  canceled: number;
+  progress: number; // weighted by input duration, finished tasks count as 100, canceled ones are left out
+}
+
+/**
+ * Rough output sizes of the pending tasks returned by estimate_task_sizes
+ */
+export interface TaskSizeEstimate {
+  total_bytes: number;
+  task_sizes: Record<string, number>;
+}
+
+/**
+ * Task created for each new file in a watched folder
+ */
+export interface WatchTaskTemplate {
+  task_type?: 'convert' | 'remux' | 'split' | 'edit' | 'sanitize';
+  output_format: string;
+  output_dir?: string | null;
+  config?: Record<string, string>;
+  extensions?: string[];
+  priority?: number | null;
+  overwrite_policy?: 'error' | 'overwrite' | 'rename' | null;
+  auto_start?: boolean;
+}
+
+/**
+ * A folder watched for new files, returned by watch_directory and list_watches
+ */
+export interface WatchInfo {
+  id: string;
+  path: string;
+  template: WatchTaskTemplate;
+}
+
+/**
+ * Payloads of the task events emitted by the backend (keys are camelCase)
+ */
+export interface TaskIdEventPayload {
+  taskId: string;
+}
+
+export interface TaskProgressEventPayload {
+  taskId: string;
+  progress: number;
+  etaSecs: number | null;
+  phase: string | null; // e.g. "quality" after encoding, progress restarts at 0 for it
+}
+
+export interface TaskWarningEventPayload {
+  taskId: string;
+  message: string;
+}
+
+export interface TaskFallbackEventPayload {
+  taskId: string;
+  from: string;
+  to: string;
+  reason: string;
+  attempt: number | null; // index into the task's fallbacks, null for the GPU-to-CPU fallback
+}
+
+export interface TaskQualityEventPayload {
+  taskId: string;
+  quality: QualityScore;
+}
+
+export interface TaskFailedEventPayload {
+  taskId: string;
+  error: string;
+}
+
+export interface QueueReorderedEventPayload {
+  queue: string[]; // task IDs in their new queue order
+}
+
+export interface QueueDrainedEventPayload {
+  completed: number;
+  failed: number;
+  canceled: number;
 }
 
 /**
@@ -81,4 +218,5 @@ export interface ConfigStore {
   theme: 'light' | 'dark';
   defaultFormat: string;
   useGpu: boolean;
+  outputFilenameTemplate: string; // e.g. "{name}_{format}_{width}x{height}"
 }
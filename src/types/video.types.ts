@@ -1,3 +1,19 @@
+export interface StreamInfo {
+  index: number;
+  media_type: 'video' | 'audio' | 'subtitle' | 'data' | 'attachment' | 'unknown';
+  codec: string;
+  language: string | null;
+}
+
+export interface AudioStreamInfo {
+  index: number;
+  codec: string;
+  channels: number;
+  sample_rate: number;
+  language: string | null;
+  title: string | null;
+}
+
 export interface VideoInfo {
   path: string;
   format: string;
@@ -7,6 +23,10 @@ export interface VideoInfo {
   bitrate: number;
   codec: string;
   framerate: number;
+  audio_streams: AudioStreamInfo[];
+  has_subtitles: boolean;
+  streams: StreamInfo[];
+  rotation?: number; // clockwise degrees players turn the picture; width/height are as stored
 }
 
 export interface ProcessingOptions {
@@ -14,10 +34,40 @@ export interface ProcessingOptions {
   output_path: string;
   resolution?: [number, number];
   bitrate?: number;
+  target_size_mb?: number; // output size in MiB; replaces bitrate/quality, best with two-pass
   framerate?: number;
+  interpolate?: boolean; // reach `framerate` with motion interpolation; very slow, off by default
   use_gpu: boolean;
   gpu_codec?: string;
   cpu_codec?: string;
+  gpu_fallback?: boolean; // retry with the software encoder if the GPU encoder fails, default true
+  hardware_decode?: boolean; // decode on the GPU, falls back to software with a warning
+  threads?: number; // encoder/decoder thread count, all cores if unset
+  convert_if_needed?: boolean; // copy inputs already in the target codec and resolution instead of encoding
+  strict?: boolean; // reject a codec the output format cannot hold instead of using the format's default
+  verify_output?: boolean; // re-open the output and check its video stream and duration, off by default
+  compute_quality?: boolean; // score the output against the input with PSNR/SSIM after encoding, off by default
+  gif_fps?: number; // GIF output only, default 15; larger values grow the file quickly
+  gif_max_width?: number; // GIF output only, default 480px
+  auto_orient?: boolean; // turn rotation-tagged (phone) videos upright, default true
+  normalize_audio?: boolean; // EBU R128 loudness normalization, applied before any volume change
+  video_stream_index?: number;
+  audio_stream_index?: number;
+}
+
+// Output scored against its source by compare_quality or a compute_quality task
+export interface QualityScore {
+  psnr: number | null; // dB; above ~40 is hard to tell from the source
+  ssim: number | null; // 0-1, 1 for identical pictures
+  vmaf: number | null; // 0-100, only in builds with the vmaf feature
+  frames: number; // frame pairs compared
+}
+
+// Part of a paused conversion already encoded, kept across restarts
+export interface ResumePoint {
+  segments: string[]; // finished segment files in input order
+  encoded_secs: number; // input time of the last encoded frame
+  output_size?: [number, number] | null; // video size of the segments, kept for the later ones
 }
 
 export enum ProcessingStatus {
@@ -35,10 +85,25 @@ export interface ProcessingTask {
   progress?: number;
 }
 
-// GPU info interface
-export interface GpuInfo {
-  name: string;
-  vendor: string;
-  isAvailable: boolean;
-  supportedCodecs: string[];
-}
\ No newline at end of file
+// FFmpeg build and codec support reported by get_ffmpeg_capabilities
+export interface CodecSupport {
+  codec: string; // e.g. "h264", "aac"
+  media_type: 'video' | 'audio';
+  encoders: string[];
+  decoders: string[];
+}
+
+export interface HardwareEncoderStatus {
+  name: string; // e.g. "h264_nvenc"
+  compiled: boolean; // FFmpeg was built with the encoder
+  opened: boolean; // the encoder opened on this machine
+  error: string | null;
+}
+
+export interface FfmpegCapabilities {
+  version: string | null;
+  configuration: string;
+  license: string;
+  codecs: CodecSupport[];
+  hardware_encoders: HardwareEncoderStatus[];
+}
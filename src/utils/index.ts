@@ -1,3 +1,6 @@
 export * from './errorUtils';
 export * from './formatUtils';
-export * from './consoleLogger';
\ No newline at end of file
+export * from './consoleLogger';
+export * from './presetValidation';
+export * from './presetOptions';
+export * from './outputPath';
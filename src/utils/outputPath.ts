@@ -0,0 +1,89 @@
+import { exists } from '@tauri-apps/plugin-fs';
+import { join } from '@tauri-apps/api/path';
+import { ProcessingOptions } from '../types/video.types';
+
+/**
+ * Template used when the user has not set one
+ */
+export const DEFAULT_OUTPUT_FILENAME_TEMPLATE = '{name}_{format}';
+
+// Characters that are not allowed in file names on Windows, macOS or Linux
+const ILLEGAL_FILENAME_CHARS = /[<>:"/\\|?*\u0000-\u001f]/g;
+
+/**
+ * Replace characters that are not allowed in file names
+ * @param name File name without directory
+ * @returns The name with illegal characters replaced by "_"
+ */
+export const sanitizeFilename = (name: string): string => {
+  const sanitized = name.replace(ILLEGAL_FILENAME_CHARS, '_').replace(/[. ]+$/, '').trim();
+  return sanitized || 'output';
+};
+
+/**
+ * Fill in an output file name template
+ *
+ * Supported tokens: {name} (input file name without extension), {format},
+ * {width}, {height}, {resolution} ("1280x720" or "original") and {counter}.
+ * @param template Template such as "{name}_{format}_{width}x{height}"
+ * @param inputPath Path of the source file
+ * @param options Processing options of the output
+ * @param counter Value for the {counter} token
+ * @returns The sanitized file name including the format's extension
+ */
+export const renderFilenameTemplate = (
+  template: string,
+  inputPath: string,
+  options: ProcessingOptions,
+  counter = 1
+): string => {
+  const fileName = inputPath.split(/[\\/]/).pop() || '';
+  const dot = fileName.lastIndexOf('.');
+  const name = dot > 0 ? fileName.slice(0, dot) : fileName;
+  const [width, height] = options.resolution ?? [];
+
+  const tokens: Record<string, string> = {
+    name,
+    format: options.output_format,
+    width: width ? String(width) : 'original',
+    height: height ? String(height) : 'original',
+    resolution: width && height ? `${width}x${height}` : 'original',
+    counter: String(counter),
+  };
+
+  const rendered = (template || DEFAULT_OUTPUT_FILENAME_TEMPLATE).replace(
+    /\{(\w+)\}/g,
+    (token, key: string) => tokens[key] ?? token
+  );
+
+  return `${sanitizeFilename(rendered)}.${options.output_format}`;
+};
+
+/**
+ * Resolve where an output file should be written
+ *
+ * With a {counter} token the counter is increased until the name is free; without
+ * it the name is returned as-is and existing files are handled by the overwrite policy.
+ * @param inputPath Path of the source file
+ * @param options Processing options of the output
+ * @param outputDir Default output directory from the preferences
+ * @param template Output file name template from the preferences
+ * @returns Full path of the output file
+ */
+export const resolveOutputPath = async (
+  inputPath: string,
+  options: ProcessingOptions,
+  outputDir: string,
+  template: string = DEFAULT_OUTPUT_FILENAME_TEMPLATE
+): Promise<string> => {
+  if (!template.includes('{counter}')) {
+    return join(outputDir, renderFilenameTemplate(template, inputPath, options));
+  }
+
+  for (let counter = 1; ; counter++) {
+    const path = await join(outputDir, renderFilenameTemplate(template, inputPath, options, counter));
+    if (!(await exists(path))) {
+      return path;
+    }
+  }
+};
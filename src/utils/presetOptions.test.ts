@@ -0,0 +1,51 @@
+import { describe, expect, it } from 'vitest';
+import { ConversionPreset, ResolutionSetting } from '../types/store.types';
+import { VideoInfo } from '../types/video.types';
+import { presetToProcessingOptions } from './presetOptions';
+
+const inputInfo: VideoInfo = {
+  path: '/videos/input.mov',
+  format: 'mov',
+  duration: 12.5,
+  width: 1920,
+  height: 1080,
+  bitrate: 8_000_000,
+  codec: 'h264',
+  framerate: 30,
+  audio_streams: [],
+  has_subtitles: false,
+  streams: [],
+};
+
+const presetWith = (resolution: ResolutionSetting): ConversionPreset => ({
+  id: 'test',
+  name: 'Test',
+  description: '',
+  output_format: 'mp4',
+  resolution,
+  bitrate: 2500,
+  codec: 'libx264',
+  use_gpu: false,
+  created_at: '2024-01-01T00:00:00.000Z',
+  updated_at: '2024-01-01T00:00:00.000Z',
+});
+
+describe('presetToProcessingOptions', () => {
+  it.each<[string, ResolutionSetting, [number, number]]>([
+    ['original', { type: 'original' }, [1920, 1080]],
+    ['preset', { type: 'preset', width: 1280, height: 720 }, [1280, 720]],
+    ['custom', { type: 'custom', width: 854, height: 480 }, [854, 480]],
+  ])('maps a %s resolution', (_type, resolution, expected) => {
+    const options = presetToProcessingOptions(presetWith(resolution), inputInfo, '/videos/out.mp4');
+
+    expect(options.resolution).toEqual(expected);
+  });
+
+  it('converts the bitrate to bps and keeps a CPU codec off the GPU', () => {
+    const options = presetToProcessingOptions(presetWith({ type: 'original' }), inputInfo, '/videos/out.mp4');
+
+    expect(options.bitrate).toBe(2_500_000);
+    expect(options.cpu_codec).toBe('libx264');
+    expect(options.gpu_codec).toBeUndefined();
+  });
+});
@@ -0,0 +1,36 @@
+import { ConversionPreset } from '../types/store.types';
+import { ProcessingOptions, VideoInfo } from '../types/video.types';
+
+// Hardware encoders are recognized by their FFmpeg name suffix
+const GPU_CODEC_PATTERN = /_(nvenc|qsv|amf|videotoolbox)$/;
+
+/**
+ * Build processing options for one input from a preset
+ * @param preset Preset to apply
+ * @param inputInfo Probed input, used for the "original" resolution
+ * @param outputPath Where the output will be written
+ * @returns Options for the backend; the preset bitrate (kbps) is converted to bps
+ */
+export const presetToProcessingOptions = (
+  preset: ConversionPreset,
+  inputInfo: VideoInfo,
+  outputPath: string
+): ProcessingOptions => {
+  const resolution: [number, number] =
+    preset.resolution.type === 'original'
+      ? [inputInfo.width, inputInfo.height]
+      : [preset.resolution.width, preset.resolution.height];
+
+  const isGpuCodec = !!preset.codec && GPU_CODEC_PATTERN.test(preset.codec);
+
+  return {
+    output_format: preset.output_format,
+    output_path: outputPath,
+    resolution,
+    bitrate: preset.bitrate !== undefined ? preset.bitrate * 1000 : undefined,
+    framerate: preset.fps,
+    use_gpu: preset.use_gpu,
+    gpu_codec: preset.use_gpu && isGpuCodec ? preset.codec : undefined,
+    cpu_codec: isGpuCodec ? undefined : preset.codec,
+  };
+};
@@ -0,0 +1,77 @@
+import { describe, expect, it } from 'vitest';
+import { ConversionPreset } from '../types/store.types';
+import { MAX_BITRATE_KBPS, MAX_DIMENSION, MAX_FRAMERATE, MIN_BITRATE_KBPS } from '../constants/formats';
+import { validatePreset } from './presetValidation';
+
+const validPreset = (overrides: Partial<ConversionPreset> = {}): ConversionPreset => ({
+  id: 'web-720p',
+  name: 'Web 720p',
+  description: 'Small H.264 for the web',
+  output_format: 'mp4',
+  resolution: { type: 'preset', width: 1280, height: 720 },
+  bitrate: 2500,
+  fps: 30,
+  codec: 'libx264',
+  use_gpu: false,
+  tags: ['Web'],
+  created_at: '2024-01-01T00:00:00.000Z',
+  updated_at: '2024-01-01T00:00:00.000Z',
+  ...overrides,
+});
+
+describe('validatePreset', () => {
+  it('accepts a valid preset', () => {
+    expect(validatePreset(validPreset())).toBeNull();
+  });
+
+  it('accepts a preset keeping the original resolution without bitrate, fps or codec', () => {
+    const preset = validPreset({ resolution: { type: 'original' } });
+    delete preset.bitrate;
+    delete preset.fps;
+    delete preset.codec;
+    delete preset.tags;
+
+    expect(validatePreset(preset)).toBeNull();
+  });
+
+  it('rejects an empty id or name', () => {
+    expect(validatePreset(validPreset({ id: '' }))).toMatch(/id/);
+    expect(validatePreset(validPreset({ name: '   ' }))).toMatch(/name/);
+  });
+
+  it('rejects an unknown output format', () => {
+    expect(validatePreset(validPreset({ output_format: 'wmv' }))).toMatch(/output format "wmv"/);
+  });
+
+  it('rejects odd, zero and oversized dimensions', () => {
+    expect(validatePreset(validPreset({ resolution: { type: 'custom', width: 1279, height: 720 } }))).toMatch(
+      /width/
+    );
+    expect(validatePreset(validPreset({ resolution: { type: 'custom', width: 1280, height: 0 } }))).toMatch(
+      /height/
+    );
+    expect(
+      validatePreset(validPreset({ resolution: { type: 'custom', width: MAX_DIMENSION + 2, height: 720 } }))
+    ).toMatch(/width/);
+  });
+
+  it('rejects bitrates outside the accepted range', () => {
+    expect(validatePreset(validPreset({ bitrate: 0 }))).toMatch(/Bitrate/);
+    expect(validatePreset(validPreset({ bitrate: MIN_BITRATE_KBPS - 1 }))).toMatch(/Bitrate/);
+    expect(validatePreset(validPreset({ bitrate: MAX_BITRATE_KBPS + 1 }))).toMatch(/Bitrate/);
+    expect(validatePreset(validPreset({ bitrate: NaN }))).toMatch(/Bitrate/);
+  });
+
+  it('rejects frame rates outside the accepted range', () => {
+    expect(validatePreset(validPreset({ fps: 0 }))).toMatch(/Frame rate/);
+    expect(validatePreset(validPreset({ fps: MAX_FRAMERATE + 1 }))).toMatch(/Frame rate/);
+  });
+
+  it('rejects empty tags', () => {
+    expect(validatePreset(validPreset({ tags: ['Web', ' '] }))).toMatch(/Tags/);
+  });
+
+  it('rejects an unknown codec', () => {
+    expect(validatePreset(validPreset({ codec: 'libfoo' }))).toMatch(/codec "libfoo"/);
+  });
+});
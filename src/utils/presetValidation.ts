@@ -0,0 +1,57 @@
+import { ConversionPreset } from '../types/store.types';
+import {
+  MAX_BITRATE_KBPS,
+  MAX_DIMENSION,
+  MAX_FRAMERATE,
+  MIN_BITRATE_KBPS,
+  OUTPUT_FORMATS,
+  VIDEO_CODECS,
+} from '../constants/formats';
+
+/**
+ * Check a preset before it is saved
+ * @param preset Preset to check
+ * @returns A description of the first problem found, or null if the preset is valid
+ */
+export const validatePreset = (preset: ConversionPreset): string | null => {
+  if (typeof preset.id !== 'string' || !preset.id.trim()) return 'Preset id must not be empty';
+  if (typeof preset.name !== 'string' || !preset.name.trim()) return 'Preset name must not be empty';
+
+  if (!OUTPUT_FORMATS.includes(preset.output_format as typeof OUTPUT_FORMATS[number])) {
+    return `Unsupported output format "${preset.output_format}" (use ${OUTPUT_FORMATS.join(', ')})`;
+  }
+
+  const resolution = preset.resolution;
+  if (!resolution || !['original', 'preset', 'custom'].includes(resolution.type)) {
+    return 'Resolution must be original, preset or custom';
+  }
+  if (resolution.type !== 'original') {
+    for (const [label, value] of [['width', resolution.width], ['height', resolution.height]] as const) {
+      if (!Number.isInteger(value) || value < 2 || value > MAX_DIMENSION || value % 2 !== 0) {
+        return `Resolution ${label} must be an even number between 2 and ${MAX_DIMENSION}, got ${value}`;
+      }
+    }
+  }
+
+  if (preset.bitrate !== undefined && preset.bitrate !== null) {
+    if (!Number.isFinite(preset.bitrate) || preset.bitrate < MIN_BITRATE_KBPS || preset.bitrate > MAX_BITRATE_KBPS) {
+      return `Bitrate must be between ${MIN_BITRATE_KBPS} and ${MAX_BITRATE_KBPS} kbps, got ${preset.bitrate}`;
+    }
+  }
+
+  if (preset.fps !== undefined && preset.fps !== null) {
+    if (!Number.isFinite(preset.fps) || preset.fps <= 0 || preset.fps > MAX_FRAMERATE) {
+      return `Frame rate must be between 0 and ${MAX_FRAMERATE}, got ${preset.fps}`;
+    }
+  }
+
+  if (preset.tags !== undefined && (!Array.isArray(preset.tags) || preset.tags.some(t => typeof t !== 'string' || !t.trim()))) {
+    return 'Tags must be non-empty strings';
+  }
+
+  if (preset.codec && !VIDEO_CODECS.includes(preset.codec as typeof VIDEO_CODECS[number])) {
+    return `Unsupported codec "${preset.codec}"`;
+  }
+
+  return null;
+};